@@ -0,0 +1,100 @@
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+mod inputs;
+use inputs::*;
+
+fn env() -> pupil::BasicEnv<'static> {
+	pupil::BasicEnv { ans: 2.5, ..Default::default() }
+}
+
+fn bench_eval(c: &mut Criterion) {
+	let env = env();
+	let variadic = variadic();
+	let nested = nested();
+	c.bench_function("eval", |b| b.iter(|| pupil::eval(&env, black_box(INPUT)).unwrap()));
+	c.bench_function("eval_short", |b| b.iter(|| pupil::eval(&env, black_box(SHORT)).unwrap()));
+	c.bench_function("eval_variables", |b| b.iter(|| pupil::eval(&env, black_box(VARIABLES)).unwrap()));
+	c.bench_function("eval_variadic", |b| b.iter(|| pupil::eval(&env, black_box(&variadic)).unwrap()));
+	c.bench_function("eval_nested", |b| b.iter(|| pupil::eval(&env, black_box(&nested)).unwrap()));
+}
+
+fn bench_tokens(c: &mut Criterion) {
+	let env = env();
+	let tokens = pupil::tokenize(INPUT).collect::<Vec<_>>();
+	c.bench_function("tokenize", |b| b.iter(|| pupil::tokenize(black_box(INPUT)).count()));
+	c.bench_function("eval_tokens", |b| b.iter(|| {
+		let mut expr = pupil::Expr::new(&env);
+		for tok in black_box(&tokens) {
+			expr.parse(tok.clone()).unwrap();
+		}
+		expr.result().unwrap()
+	}));
+	let mut expr = pupil::Expr::new(&env);
+	c.bench_function("eval_reuse", |b| b.iter(|| {
+		for tok in black_box(&tokens) {
+			expr.parse(tok.clone()).unwrap();
+		}
+		expr.result_and_clear().unwrap()
+	}));
+}
+
+fn bench_many(c: &mut Criterion) {
+	let env = env();
+	let inputs = many();
+	let inputs = inputs.iter().map(String::as_str).collect::<Vec<_>>();
+	c.bench_function("eval_loop", |b| b.iter(|| black_box(&inputs).iter().map(|input| pupil::eval(&env, input).unwrap()).sum::<f64>()));
+	c.bench_function("eval_many", |b| b.iter(|| pupil::eval_many(&env, black_box(&inputs))));
+	#[cfg(feature = "rayon")]
+	c.bench_function("par_eval_many", |b| b.iter(|| pupil::par_eval_many(&env, black_box(&inputs))));
+	let cache = pupil::CachedEvaluator::new(inputs.len());
+	c.bench_function("eval_cached", |b| b.iter(|| black_box(&inputs).iter().map(|input| cache.eval(&env, input).unwrap()).sum::<f64>()));
+}
+
+fn bench_compiled(c: &mut Criterion) {
+	use pupil::Env;
+	let env = env();
+	let names = env.builtins.iter().map(|desc| desc.name).collect::<Vec<_>>();
+	c.bench_function("function_lookup", |b| b.iter(|| black_box(&names).iter().filter(|name| env.builtin(name).is_ok()).count()));
+	let ast = pupil::parse(INPUT).unwrap();
+	c.bench_function("eval_ast", |b| b.iter(|| black_box(&ast).eval(&env).unwrap()));
+	let program = pupil::compile(&env, INPUT).unwrap();
+	c.bench_function("eval_program", |b| b.iter(|| black_box(&program).eval(&[]).unwrap()));
+}
+
+// Environment with the variable x.
+struct Column<'a>(&'a pupil::BasicEnv<'static>, pupil::Value);
+impl pupil::Env for Column<'_> {
+	fn builtin(&self, name: &str) -> Result<pupil::BuiltinFn, pupil::ErrorKind> {
+		self.0.builtin(name)
+	}
+	fn get_value(&self, name: &str) -> Result<pupil::Value, pupil::ErrorKind> {
+		match name {
+			"x" => Ok(self.1),
+			_ => self.0.get_value(name),
+		}
+	}
+	fn set_value(&mut self, _name: &str, _value: pupil::Value) -> Result<(), pupil::ErrorKind> {
+		Err(pupil::ErrorKind::EnvErrorNotFound)
+	}
+}
+
+fn bench_batch(c: &mut Criterion) {
+	const FORMULA: &str = "x^2 / 3 + sin(x) * 2 - max(x, 0.5)";
+	let env = env();
+	let xs = (0..1_000_000).map(|i| i as f64 * 1e-3).collect::<Vec<_>>();
+	let mut out = vec![0.0; xs.len()];
+	let mut group = c.benchmark_group("batch");
+	group.sample_size(10);
+	let program = pupil::compile(&env, FORMULA).unwrap();
+	group.bench_function("eval_batch", |b| b.iter(|| program.eval_batch(&[black_box(&xs)], &mut out).unwrap()));
+	group.bench_function("eval_loop", |b| b.iter(|| {
+		for (&x, y) in black_box(&xs).iter().zip(out.iter_mut()) {
+			*y = pupil::eval(&Column(&env, x), FORMULA).unwrap();
+		}
+	}));
+	group.finish();
+}
+
+criterion_group!(benches, bench_eval, bench_tokens, bench_many, bench_compiled, bench_batch);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+//! Inputs shared by the benchmarks so they measure the same expressions.
+
+/// Expression mixing operators, calls and the answer.
+pub const INPUT: &str = "2 * ans^2 + sin(ans / 3) - max(1, ans, 4) * 15% + √(ans + 1)";
+
+/// Short formula dominated by the setup of the evaluation.
+pub const SHORT: &str = "ans * 2 + 1";
+
+/// Expression looking up the answer and the builtin constants.
+pub const VARIABLES: &str = "ans * pi + ans / e - tau * ans + pi * e / ans - ans^2 + e^ans - pi / tau * ans";
+
+/// Call of a variadic builtin with 100 literals.
+pub fn variadic() -> String {
+	format!("mean({})", (1..=100).map(|i| i.to_string()).collect::<Vec<_>>().join(", "))
+}
+
+/// Literal nested in 100 parentheses.
+pub fn nested() -> String {
+	"(".repeat(100) + "ans + 1" + &")".repeat(100)
+}
+
+/// Many similar expressions.
+pub fn many() -> Vec<String> {
+	(0..100).map(|i| format!("{} * ans + max({}, 3) / 2", i, i % 7)).collect()
+}
@@ -0,0 +1,116 @@
+// Quick benchmarks printing the mean time per iteration, the eval benchmarks use criterion for statistics.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+mod inputs;
+use inputs::*;
+
+// Runs the closure repeatedly and prints the average time per iteration.
+fn bench<F: FnMut() -> Result<pupil::Value, pupil::Error>>(name: &str, mut f: F) {
+	let mut iters = 0u32;
+	let start = Instant::now();
+	while start.elapsed() < Duration::from_millis(500) {
+		for _ in 0..100 {
+			black_box(f().unwrap());
+		}
+		iters += 100;
+	}
+	println!("{:<20} {:>10.1?}/iter", name, start.elapsed() / iters);
+}
+
+fn bench_eval(env: &pupil::BasicEnv) {
+	bench("bench_eval", || pupil::eval(env, black_box(INPUT)));
+}
+
+fn bench_eval_short(env: &pupil::BasicEnv) {
+	bench("bench_eval_short", || pupil::eval(env, black_box(SHORT)));
+}
+
+fn bench_eval_variables(env: &pupil::BasicEnv) {
+	bench("bench_eval_variables", || pupil::eval(env, black_box(VARIABLES)));
+}
+
+fn bench_eval_variadic(env: &pupil::BasicEnv) {
+	let input = variadic();
+	bench("bench_eval_variadic", || pupil::eval(env, black_box(&input)));
+}
+
+fn bench_eval_nested(env: &pupil::BasicEnv) {
+	let input = nested();
+	bench("bench_eval_nested", || pupil::eval(env, black_box(&input)));
+}
+
+fn bench_tokenize() {
+	bench("bench_tokenize", || Ok(pupil::tokenize(black_box(INPUT)).count() as pupil::Value));
+}
+
+fn bench_eval_tokens(env: &pupil::BasicEnv) {
+	let tokens = pupil::tokenize(INPUT).collect::<Vec<_>>();
+	bench("bench_eval_tokens", || {
+		let mut expr = pupil::Expr::new(env);
+		for tok in black_box(&tokens) {
+			expr.parse(tok.clone())?;
+		}
+		expr.result()
+	});
+}
+
+fn bench_eval_reuse(env: &pupil::BasicEnv) {
+	let tokens = pupil::tokenize(INPUT).collect::<Vec<_>>();
+	let mut expr = pupil::Expr::new(env);
+	bench("bench_eval_reuse", || {
+		for tok in black_box(&tokens) {
+			expr.parse(tok.clone())?;
+		}
+		expr.result_and_clear()
+	});
+}
+
+fn bench_eval_many(env: &pupil::BasicEnv) {
+	let inputs = many();
+	let inputs = inputs.iter().map(String::as_str).collect::<Vec<_>>();
+	bench("bench_eval_loop", || black_box(&inputs).iter().map(|input| pupil::eval(env, input)).sum());
+	bench("bench_eval_many", || pupil::eval_many(env, black_box(&inputs)).into_iter().sum());
+	#[cfg(feature = "rayon")]
+	bench("bench_par_eval_many", || pupil::par_eval_many(env, black_box(&inputs)).into_iter().sum());
+}
+
+fn bench_eval_cached(env: &pupil::BasicEnv) {
+	let inputs = many();
+	let cache = pupil::CachedEvaluator::new(inputs.len());
+	bench("bench_eval_cached", || black_box(&inputs).iter().map(|input| cache.eval(env, input)).sum());
+}
+
+fn bench_function_lookup(env: &pupil::BasicEnv) {
+	use pupil::Env;
+	let names = env.builtins.iter().map(|desc| desc.name).collect::<Vec<_>>();
+	bench("bench_function_lookup", || Ok(black_box(&names).iter().filter(|name| env.builtin(name).is_ok()).count() as pupil::Value));
+}
+
+fn bench_eval_ast(env: &pupil::BasicEnv) {
+	let ast = pupil::parse(INPUT).unwrap();
+	bench("bench_eval_ast", || black_box(&ast).eval(env));
+}
+
+fn bench_eval_program(env: &pupil::BasicEnv) {
+	let program = pupil::compile(env, INPUT).unwrap();
+	bench("bench_eval_program", || black_box(&program).eval(&[]).map_err(|kind| pupil::Error::new(kind, 0)));
+}
+
+fn main() {
+	let env = pupil::BasicEnv { ans: 2.5, ..Default::default() };
+	bench_eval(&env);
+	bench_eval_short(&env);
+	bench_eval_variables(&env);
+	bench_eval_variadic(&env);
+	bench_eval_nested(&env);
+	bench_tokenize();
+	bench_eval_tokens(&env);
+	bench_eval_reuse(&env);
+	bench_eval_many(&env);
+	bench_eval_cached(&env);
+	bench_function_lookup(&env);
+	bench_eval_ast(&env);
+	bench_eval_program(&env);
+}
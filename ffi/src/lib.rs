@@ -0,0 +1,8 @@
+/*!
+C and WebAssembly libraries of the pupil expression evaluator.
+
+The bindings live in `pupil` behind the `capi` and `wasm` features,
+this crate links them into a `cdylib` and `staticlib` so crates depending on `pupil` don't build the libraries.
+*/
+
+pub use pupil::*;
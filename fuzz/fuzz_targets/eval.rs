@@ -0,0 +1,14 @@
+//! Run with `cargo +nightly fuzz run eval` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pupil::*;
+
+fuzz_target!(|input: &str| {
+	let env = BasicEnv { ans: 2.5, ..Default::default() };
+	if let Err(err) = eval(&env, input) {
+		assert_ne!(err.kind, ErrorKind::InternalError, "{:?}", input);
+		assert!(err.position <= input.len(), "{:?} {:?}", input, err);
+	}
+});
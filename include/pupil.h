@@ -0,0 +1,68 @@
+/* C bindings of the pupil expression evaluator, build the library of the `ffi` crate with `--features capi`. */
+
+#ifndef PUPIL_H
+#define PUPIL_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Error codes, new codes are appended. */
+#define PUPIL_OK 0
+#define PUPIL_EXPECT_OPERATOR 1
+#define PUPIL_NA_EXPRESSION 2
+#define PUPIL_DISALLOWED_UNARY 3
+#define PUPIL_INTERNAL_ERROR 4
+#define PUPIL_UNFINISHED_EXPRESSION 5
+#define PUPIL_INVALID_TOKEN 6
+#define PUPIL_UNBALANCED_PARENS 7
+#define PUPIL_MISMATCHED_PARENS 8
+#define PUPIL_MISPLACED_COMMA 9
+#define PUPIL_MISPLACED_SEMICOLON 10
+#define PUPIL_MISPLACED_ASSIGN 11
+#define PUPIL_UNBALANCED_CONDITIONAL 12
+#define PUPIL_BAD_ARGUMENT 13
+#define PUPIL_ARGUMENT_COUNT 14
+#define PUPIL_UNKNOWN_KEYWORD 15
+#define PUPIL_DUPLICATE_KEYWORD 16
+#define PUPIL_POSITIONAL_AFTER_KEYWORD 17
+#define PUPIL_LIMIT_EXCEEDED 18
+#define PUPIL_DIVISION_BY_ZERO 19
+#define PUPIL_MATH_DOMAIN 20
+#define PUPIL_OVERFLOW 21
+#define PUPIL_NOT_INTEGER 22
+#define PUPIL_DIMENSION_MISMATCH 23
+#define PUPIL_ENV_ERROR_NOT_FOUND 24
+#define PUPIL_ENV_ERROR_BUILTIN_FN 25
+#define PUPIL_CAPACITY_EXCEEDED 26
+#define PUPIL_NULL_POINTER 100
+#define PUPIL_INVALID_UTF8 101
+#define PUPIL_PANIC 102
+
+/* Environment with the default builtins and variables. */
+typedef struct PupilEnv PupilEnv;
+
+/* Error with the byte offset in the input where it went wrong. */
+typedef struct PupilError {
+	int code;
+	size_t position;
+} PupilError;
+
+/* Creates an environment, null if it failed. */
+PupilEnv *pupil_env_new(void);
+/* Frees an environment, null is ignored. */
+void pupil_env_free(PupilEnv *env);
+/* Sets a variable, returns the error code. */
+int pupil_env_set_var(PupilEnv *env, const char *name, double value);
+/* Evaluates the UTF-8 input into out and stores it in ans, returns the error code and writes the error to err if not null. */
+int pupil_eval(PupilEnv *env, const char *input, double *out, PupilError *err);
+/* Describes the error code, a static string. */
+const char *pupil_error_message(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,647 @@
+use std::{fmt, slice};
+use std::fmt::Write;
+use crate::*;
+use crate::parser::{self, Parser, Sink};
+use crate::storage::Heap;
+
+/// Node types of the expression tree.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AstKind {
+	/// Literal value.
+	Lit(Value),
+	/// Variable looked up by name.
+	Var(String),
+	/// Prefix, postfix or infix operator applied to one or two operands.
+	Op(Operator, Vec<Ast>),
+	/// Postfix percentage, eg. `15%`.
+	Percent(Box<Ast>),
+	/// Function looked up by name applied to its arguments.
+	Call(String, Vec<Ast>),
+	/// Conditional `cond ? then : else`, only the chosen branch is evaluated.
+	Cond(Box<[Ast; 3]>),
+	/// Keyword argument of a function call, eg. `base = 2` in `log(8, base = 2)`.
+	Keyword(String, Box<Ast>),
+}
+
+/// Parsed expression tree.
+///
+/// Grouping brackets are not kept in the tree.
+/// Names are resolved when evaluated allowing the same tree to be evaluated in different environments.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ast {
+	/// The node type.
+	pub kind: AstKind,
+	/// Byte offset in the input of the token which created this node.
+	pub position: usize,
+}
+
+impl Ast {
+	/// Evaluates the expression tree in the environment.
+	///
+	/// Errors are reported at the position of the node which failed.
+	///
+	/// ```
+	/// let ast = pupil::parse("2ans + 1").unwrap();
+	/// let mut env = pupil::BasicEnv::default();
+	/// env.ans = 3.0;
+	/// assert_eq!(ast.eval(&env), Ok(7.0));
+	/// env.ans = 5.0;
+	/// assert_eq!(ast.eval(&env), Ok(11.0));
+	/// ```
+	pub fn eval(&self, env: &dyn Env) -> Result<Value, Error> {
+		let mut stack = Vec::new();
+		self.eval_into(env, &mut stack)?;
+		stack.pop().ok_or(Error::new(ErrorKind::InternalError, self.position))
+	}
+	// Pushes the result on the stack, the stack is reused to hold the arguments.
+	fn eval_into(&self, env: &dyn Env, stack: &mut Vec<Value>) -> Result<(), Error> {
+		let val = match self.kind {
+			AstKind::Lit(val) => val,
+			AstKind::Var(ref name) => {
+				env.get_value(name).map_err(|kind| Error::new(kind, self.position))?
+			},
+			AstKind::Op(op, ref args) => {
+				self.call(env, stack, op.desc().pfn, args, None)?
+			},
+			AstKind::Percent(ref arg) => {
+				self.call(env, stack, crate::builtins::builtin_percent, slice::from_ref(arg), None)?
+			},
+			AstKind::Call(ref name, ref args) => {
+				let pfn = env.builtin(name).map_err(|kind| Error::new(kind, self.position))?;
+				if let Some(arity) = env.arity(name) {
+					arity.check(args.len()).map_err(|kind| Error::new(kind, self.position))?;
+				}
+				match crate::builtins::lazy(name) {
+					Some(decides) => self.call_lazy(env, stack, pfn, args, decides)?,
+					None => self.call(env, stack, pfn, args, Some(env.params(name).unwrap_or(&[])))?,
+				}
+			},
+			AstKind::Cond(ref branches) => {
+				let [cond, then, other] = &**branches;
+				let branch = if crate::builtins::truthy(cond.eval(env)?) { then } else { other };
+				return branch.eval_into(env, stack);
+			},
+			// Only valid as the argument of a call
+			AstKind::Keyword(..) => {
+				return Err(Error::new(ErrorKind::MisplacedAssign, self.position));
+			},
+		};
+		stack.push(val);
+		Ok(())
+	}
+	/// Evaluates the constant subtrees ahead of time.
+	///
+	/// Subtrees without variables calling only [pure](Env::is_pure) builtins are replaced by their value.
+	/// Constants such as `pi` are pure builtins, `ans` is a variable.
+	/// Subtrees which fail to evaluate are kept to report the error when evaluated.
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let ast = pupil::parse("(2 * pi) / 360 * ans").unwrap();
+	/// assert_eq!(ast.fold(&env).to_string(), "0.017453292519943295 * ans");
+	/// ```
+	pub fn fold(&self, env: &dyn Env) -> Ast {
+		let position = self.position;
+		let lit = |val| Ast { kind: AstKind::Lit(val), position };
+		let kind = match self.kind {
+			AstKind::Lit(val) => AstKind::Lit(val),
+			AstKind::Var(ref name) => {
+				if env.builtin(name).is_ok() && env.is_pure(name) {
+					if let Ok(val) = env.get_value(name) {
+						return lit(val);
+					}
+				}
+				AstKind::Var(name.clone())
+			},
+			AstKind::Op(op, ref args) => {
+				let args = args.iter().map(|arg| arg.fold(env)).collect::<Vec<_>>();
+				if let Some(val) = fold_call(env, op.desc().pfn, &args) {
+					return lit(val);
+				}
+				AstKind::Op(op, args)
+			},
+			AstKind::Percent(ref arg) => {
+				let arg = arg.fold(env);
+				if let Some(val) = fold_call(env, crate::builtins::builtin_percent, slice::from_ref(&arg)) {
+					return lit(val);
+				}
+				AstKind::Percent(Box::new(arg))
+			},
+			AstKind::Call(ref name, ref args) => {
+				let args = args.iter().map(|arg| arg.fold(env)).collect::<Vec<_>>();
+				if let (Ok(pfn), true) = (env.builtin(name), env.is_pure(name)) {
+					if let Some(val) = fold_call(env, pfn, &args) {
+						return lit(val);
+					}
+				}
+				AstKind::Call(name.clone(), args)
+			},
+			AstKind::Cond(ref branches) => {
+				let [cond, then, other] = &**branches;
+				let cond = cond.fold(env);
+				// Constant condition picks the branch
+				if let AstKind::Lit(val) = cond.kind {
+					return if crate::builtins::truthy(val) { then.fold(env) } else { other.fold(env) };
+				}
+				AstKind::Cond(Box::new([cond, then.fold(env), other.fold(env)]))
+			},
+			AstKind::Keyword(ref name, ref arg) => {
+				AstKind::Keyword(name.clone(), Box::new(arg.fold(env)))
+			},
+		};
+		Ast { kind, position }
+	}
+	/// Renders the expression with its constant subtrees evaluated.
+	///
+	/// See [`fold`](Self::fold).
+	pub fn simplify_to_string(&self, env: &dyn Env) -> String {
+		self.fold(env).to_string()
+	}
+	/// Renders the expression in postfix notation in order of evaluation.
+	///
+	/// Unary `-` and `+` are `neg` and `pos`, percentages are `percent`,
+	/// functions are followed by their number of arguments and conditionals are `?:`.
+	/// Keyword arguments are followed by their name and `=`.
+	///
+	/// ```
+	/// let ast = pupil::parse("-max(x, 2) * 15% + (c ? 1 : 2)").unwrap();
+	/// assert_eq!(ast.to_rpn(), "x 2 max(2) neg 15 percent * c 1 2 ?: +");
+	/// assert_eq!(pupil::parse("log(8, base = 2)").unwrap().to_rpn(), "8 2 base= log(2)");
+	/// ```
+	pub fn to_rpn(&self) -> String {
+		let mut out = String::new();
+		self.write_rpn(&mut out);
+		out
+	}
+	fn write_rpn(&self, out: &mut String) {
+		let args = match self.kind {
+			AstKind::Op(_, ref args) | AstKind::Call(_, ref args) => &args[..],
+			AstKind::Percent(ref arg) | AstKind::Keyword(_, ref arg) => slice::from_ref(&**arg),
+			AstKind::Cond(ref branches) => &branches[..],
+			AstKind::Lit(_) | AstKind::Var(_) => &[],
+		};
+		for arg in args {
+			arg.write_rpn(out);
+			out.push(' ');
+		}
+		let _ = match self.kind {
+			AstKind::Lit(val) => write!(out, "{}", TokenKind::Lit(val)),
+			AstKind::Var(ref name) => write!(out, "{}", name),
+			AstKind::Op(Operator::Sub, _) if args.len() == 1 => write!(out, "neg"),
+			AstKind::Op(Operator::Add, _) if args.len() == 1 => write!(out, "pos"),
+			AstKind::Op(op, _) => write!(out, "{}", op),
+			AstKind::Percent(_) => write!(out, "percent"),
+			AstKind::Call(ref name, _) => write!(out, "{}({})", name, args.len()),
+			AstKind::Cond(_) => write!(out, "?:"),
+			AstKind::Keyword(ref name, _) => write!(out, "{}=", name),
+		};
+	}
+	// Stops evaluating the arguments at the first one deciding the result.
+	fn call_lazy(&self, env: &dyn Env, stack: &mut Vec<Value>, pfn: BuiltinFn, args: &[Ast], decides: bool) -> Result<Value, Error> {
+		let start = stack.len();
+		for arg in args {
+			arg.eval_into(env, stack)?;
+			if stack.last().map(|&val| crate::builtins::truthy(val) == decides) == Some(true) {
+				stack.truncate(start);
+				return Ok(if decides { 1.0 } else { 0.0 });
+			}
+		}
+		let result = pfn(env, &mut stack[start..]).map_err(|kind| Error::new(kind, self.position));
+		stack.truncate(start);
+		result
+	}
+	// Calls with parameter names accept keyword arguments.
+	fn call(&self, env: &dyn Env, stack: &mut Vec<Value>, pfn: BuiltinFn, args: &[Ast], params: Option<&[&str]>) -> Result<Value, Error> {
+		let start = stack.len();
+		let mut keys = Vec::new();
+		for (index, arg) in args.iter().enumerate() {
+			match (&arg.kind, params) {
+				(AstKind::Keyword(name, value), Some(params)) => {
+					let param = parser::resolve_keyword(params, name, index, &keys).map_err(|kind| Error::new(kind, arg.position))?;
+					keys.push(param);
+					value.eval_into(env, stack)?;
+				},
+				_ if !keys.is_empty() => return Err(Error::new(ErrorKind::PositionalAfterKeyword, arg.position)),
+				_ => arg.eval_into(env, stack)?,
+			}
+		}
+		let vals = &mut stack[start..];
+		parser::place_keywords(args.len(), &mut keys, |i, j| vals.swap(i, j)).map_err(|kind| Error::new(kind, self.position))?;
+		let result = pfn(env, vals).map_err(|kind| Error::new(kind, self.position));
+		stack.truncate(start);
+		result
+	}
+}
+
+// Calls the fn if all its arguments are literals.
+fn fold_call(env: &dyn Env, pfn: BuiltinFn, args: &[Ast]) -> Option<Value> {
+	let mut vals = Vec::with_capacity(args.len());
+	for arg in args {
+		match arg.kind {
+			AstKind::Lit(val) => vals.push(val),
+			_ => return None,
+		}
+	}
+	pfn(env, &mut vals).ok()
+}
+
+// How tightly the rendered node binds, higher binds tighter.
+const COND: u8 = 1;
+const UNARY: u8 = 7;
+const POSTFIX: u8 = 8;
+const ATOM: u8 = 9;
+fn binding(op: Operator) -> u8 {
+	match op.desc().pre {
+		Order::FnBarrier | Order::Cond => COND,
+		Order::Or => 2,
+		Order::And => 3,
+		Order::AddSub => 4,
+		// Implicit multiplication is rendered as `*`
+		Order::MulDiv | Order::IMul => 5,
+		Order::Pow => 6,
+		Order::Unary => UNARY,
+	}
+}
+
+impl Ast {
+	fn binding(&self) -> u8 {
+		match self.kind {
+			AstKind::Lit(val) if val.is_sign_negative() => UNARY,
+			AstKind::Lit(_) | AstKind::Var(_) | AstKind::Call(..) => ATOM,
+			AstKind::Keyword(..) => COND,
+			AstKind::Op(Operator::Sqr | Operator::Cube, _) | AstKind::Percent(_) => POSTFIX,
+			AstKind::Op(_, ref args) if args.len() == 1 => UNARY,
+			AstKind::Op(op, _) => binding(op),
+			AstKind::Cond(_) => COND,
+		}
+	}
+	// Renders the node in parens if it binds less tightly than required.
+	fn fmt_operand(&self, f: &mut fmt::Formatter, min: u8) -> fmt::Result {
+		if self.binding() < min {
+			write!(f, "({})", self)
+		}
+		else {
+			write!(f, "{}", self)
+		}
+	}
+}
+
+/// Renders the expression with minimal parentheses and canonical spacing.
+impl fmt::Display for Ast {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.kind {
+			AstKind::Lit(val) => TokenKind::Lit(val).fmt(f),
+			AstKind::Var(ref name) => f.write_str(name),
+			AstKind::Op(op @ (Operator::Sqr | Operator::Cube), ref args) if args.len() == 1 => {
+				args[0].fmt_operand(f, POSTFIX)?;
+				// Superscripts continue an identifier
+				if let AstKind::Var(_) = args[0].kind {
+					f.write_str(" ")?;
+				}
+				op.fmt(f)
+			},
+			AstKind::Op(op, ref args) if args.len() == 1 => {
+				op.fmt(f)?;
+				args[0].fmt_operand(f, UNARY)
+			},
+			AstKind::Op(op, ref args) => {
+				let pre = binding(op);
+				let (left, right) = match op.desc().assoc {
+					Assoc::Left => (pre, pre + 1),
+					Assoc::Right => (pre + 1, pre),
+				};
+				// A `%` followed by an operator is a percentage
+				let right = if op == Operator::Rem { u8::max(right, POSTFIX) } else { right };
+				for (i, arg) in args.iter().enumerate() {
+					if i > 0 {
+						write!(f, " {} ", op)?;
+					}
+					arg.fmt_operand(f, if i == 0 { left } else { right })?;
+				}
+				Ok(())
+			},
+			AstKind::Percent(ref arg) => {
+				arg.fmt_operand(f, POSTFIX)?;
+				f.write_str("%")
+			},
+			AstKind::Call(ref name, ref args) => {
+				write!(f, "{}(", name)?;
+				for (i, arg) in args.iter().enumerate() {
+					if i > 0 {
+						f.write_str(", ")?;
+					}
+					arg.fmt(f)?;
+				}
+				f.write_str(")")
+			},
+			AstKind::Cond(ref branches) => {
+				let [cond, then, other] = &**branches;
+				cond.fmt_operand(f, COND + 1)?;
+				write!(f, " ? {} : {}", then, other)
+			},
+			AstKind::Keyword(ref name, ref arg) => {
+				write!(f, "{} = {}", name, arg)
+			},
+		}
+	}
+}
+
+/// Parses the input into an expression tree.
+///
+/// Syntax errors are reported for all branches of conditionals.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// let ast = pupil::parse("max(1, 2) * 3").unwrap();
+/// assert_eq!(ast.eval(&env), Ok(6.0));
+/// ```
+pub fn parse(input: &str) -> Result<Ast, Error> {
+	parse_with(input, LexerOptions::default())
+}
+
+/// Renders the input with minimal parentheses and canonical spacing.
+///
+/// The rendering evaluates to the same result as the input.
+///
+/// ```
+/// assert_eq!(pupil::to_canonical("2+3 * x"), Ok(String::from("2 + 3 * x")));
+/// assert_eq!(pupil::to_canonical("((2))(x)"), Ok(String::from("2 * x")));
+/// ```
+pub fn to_canonical(input: &str) -> Result<String, Error> {
+	parse(input).map(|ast| ast.to_string())
+}
+
+/// Renders the input in postfix notation, see [`Ast::to_rpn`].
+///
+/// ```
+/// assert_eq!(pupil::to_rpn("2+3 * x"), Ok(String::from("2 3 x * +")));
+/// ```
+pub fn to_rpn(input: &str) -> Result<String, Error> {
+	parse(input).map(|ast| ast.to_rpn())
+}
+
+/// Parses the input into an expression tree with custom tokenizer options.
+pub fn parse_with(input: &str, options: LexerOptions) -> Result<Ast, Error> {
+	let mut parser = Parser::new(Build { nodes: Vec::new() });
+	for tok in tokenize_with(input, options) {
+		parser.parse(tok)?;
+	}
+	parser.finish(input.len())?;
+	match parser.sink.nodes.pop() {
+		Some(ast) if parser.sink.nodes.is_empty() => Ok(ast),
+		_ => Err(parser.error(ErrorKind::UnbalancedParens)),
+	}
+}
+
+//----------------------------------------------------------------
+
+// Builds the expression tree from the parsed expression.
+struct Build {
+	nodes: Vec<Ast>,
+}
+
+enum BuildFn {
+	Op(Operator),
+	Percent,
+	// Names and positions of the keyword arguments
+	Call(String, Vec<(String, usize)>),
+	Group,
+}
+
+impl Sink for Build {
+	type Fn = BuildFn;
+	type Alloc = Heap;
+	fn lit(&mut self, val: Value, pos: usize) -> Result<(), ErrorKind> {
+		self.nodes.push(Ast { kind: AstKind::Lit(val), position: pos });
+		Ok(())
+	}
+	fn var(&mut self, name: &str, pos: usize) -> Result<(), ErrorKind> {
+		self.nodes.push(Ast { kind: AstKind::Var(name.into()), position: pos });
+		Ok(())
+	}
+	fn call(&mut self, name: &str) -> Result<BuildFn, ErrorKind> {
+		// Parentheses without a name are for grouping
+		Ok(if name.is_empty() { BuildFn::Group } else { BuildFn::Call(name.into(), Vec::new()) })
+	}
+	fn op(&mut self, op: Operator) -> BuildFn {
+		BuildFn::Op(op)
+	}
+	fn percent(&mut self) -> BuildFn {
+		BuildFn::Percent
+	}
+	fn group(&mut self) -> BuildFn {
+		BuildFn::Group
+	}
+	fn apply(&mut self, f: BuildFn, nargs: usize, pos: usize) -> Result<(), ErrorKind> {
+		if nargs > self.nodes.len() {
+			return Err(ErrorKind::InternalError);
+		}
+		let mut args = self.nodes.split_off(self.nodes.len() - nargs);
+		let kind = match f {
+			BuildFn::Op(op) => AstKind::Op(op, args),
+			BuildFn::Call(name, keywords) => {
+				// The keyword arguments follow the positional arguments
+				let named = args.split_off(nargs.checked_sub(keywords.len()).ok_or(ErrorKind::InternalError)?);
+				args.extend(named.into_iter().zip(keywords).map(|(arg, (name, position))| {
+					Ast { kind: AstKind::Keyword(name, Box::new(arg)), position }
+				}));
+				AstKind::Call(name, args)
+			},
+			BuildFn::Percent | BuildFn::Group if nargs != 1 => return Err(ErrorKind::BadArgument),
+			BuildFn::Percent => AstKind::Percent(Box::new(args.remove(0))),
+			BuildFn::Group => {
+				self.nodes.append(&mut args);
+				return Ok(());
+			},
+		};
+		self.nodes.push(Ast { kind, position: pos });
+		Ok(())
+	}
+	fn keyword(&mut self, f: &mut BuildFn, _index: usize, name: &str, pos: usize) -> Result<(), ErrorKind> {
+		// Parameter names are looked up when evaluated
+		match f {
+			BuildFn::Call(_, keywords) if keywords.iter().any(|(key, _)| key == name) => Err(ErrorKind::DuplicateKeyword),
+			BuildFn::Call(_, keywords) => {
+				keywords.push((name.into(), pos));
+				Ok(())
+			},
+			_ => Err(ErrorKind::MisplacedAssign),
+		}
+	}
+	fn next_arg(&mut self, _f: &mut BuildFn) -> Result<(), ErrorKind> {
+		Ok(())
+	}
+	fn warn(&mut self, _kind: WarningKind, _pos: usize) {}
+	fn cond(&mut self) -> Result<(), ErrorKind> {
+		Ok(())
+	}
+	fn cond_else(&mut self) -> Result<(), ErrorKind> {
+		Ok(())
+	}
+	fn cond_end(&mut self, pos: usize) -> Result<(), ErrorKind> {
+		if self.nodes.len() < 3 {
+			return Err(ErrorKind::InternalError);
+		}
+		let branches: [Ast; 3] = self.nodes.split_off(self.nodes.len() - 3).try_into().map_err(|_| ErrorKind::InternalError)?;
+		self.nodes.push(Ast { kind: AstKind::Cond(Box::new(branches)), position: pos });
+		Ok(())
+	}
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn tree() {
+	let lit = |val, position| Ast { kind: AstKind::Lit(val), position };
+	let var = |name: &str, position| Ast { kind: AstKind::Var(name.into()), position };
+	assert_eq!(parse("1 + 2x"), Ok(Ast {
+		kind: AstKind::Op(Operator::Add, vec![
+			lit(1.0, 0),
+			Ast { kind: AstKind::Op(Operator::IMul, vec![lit(2.0, 4), var("x", 5)]), position: 5 },
+		]),
+		position: 2,
+	}));
+	assert_eq!(parse("[(-a)]%"), Ok(Ast {
+		kind: AstKind::Percent(Box::new(Ast { kind: AstKind::Op(Operator::Sub, vec![var("a", 3)]), position: 2 })),
+		position: 6,
+	}));
+	assert_eq!(parse("f(x, 1) ? 2 : 3"), Ok(Ast {
+		kind: AstKind::Cond(Box::new([
+			Ast { kind: AstKind::Call("f".into(), vec![var("x", 2), lit(1.0, 5)]), position: 0 },
+			lit(2.0, 10),
+			lit(3.0, 14),
+		])),
+		position: 8,
+	}));
+}
+#[test]
+fn display() {
+	let inputs = [
+		("2+3 * x", "2 + 3 * x"),
+		("1/2x", "1 / (2 * x)"),
+		("(a/b)c", "a / b * c"),
+		("(1 - 2) - (3 - 4)", "1 - 2 - (3 - 4)"),
+		("(2^3)^2 + 2^3^2", "(2 ^ 3) ^ 2 + 2 ^ 3 ^ 2"),
+		("-x^2 + -(x^2)", "-x ^ 2 + -(x ^ 2)"),
+		("(-x)² + -x ² + (x)³ + 2²", "(-x)² + -x ² + x ³ + 2²"),
+		("√(x + 1) + 2√x", "√(x + 1) + 2 * √x"),
+		("(1 + 2)% + x% % 3 + 7 % (-2)", "(1 + 2)% + x% % 3 + 7 % (-2)"),
+		("a || b && (c || d)", "a || b && (c || d)"),
+		("max([1], {x}, c ? 1 : 2)", "max(1, x, c ? 1 : 2)"),
+		("(a ? b : c) ? d : e ? f : g", "(a ? b : c) ? d : e ? f : g"),
+		("2 * (a ? b : c)", "2 * (a ? b : c)"),
+		("log(base=2, x=a+b)", "log(base = 2, x = a + b)"),
+	];
+	let env = crate::BasicEnv::default();
+	// Evaluates with distinct values for the variables
+	let eval = |input: &str| {
+		let program = crate::compile(&env, input).unwrap();
+		let vars = program.var_names().iter().map(|name| (name.as_bytes()[0] - b'a') as Value - 2.5).collect::<Vec<_>>();
+		program.eval(&vars).map(Value::to_bits)
+	};
+	for (input, expected) in inputs {
+		let string = parse(input).unwrap().to_string();
+		assert_eq!(string, expected);
+		// Rendering preserves the meaning
+		assert_eq!(eval(&string), eval(input), "{:?}", input);
+	}
+}
+#[test]
+fn rpn() {
+	let inputs = [
+		("2+3 * x", "2 3 x * +"),
+		("2^3^2 - -x", "2 3 2 ^ ^ x neg -"),
+		("1/2x", "1 2 x * /"),
+		("√x ² + +x ³", "x ² √ x ³ pos +"),
+		("max(1, 2, 3) + pi() + 7 % 2", "1 2 3 max(3) pi(0) + 7 2 % +"),
+		("a ? b : c ? d : e", "a b c d e ?: ?:"),
+		("-1.5 && x || 50%", "1.5 neg x && 50 percent ||"),
+		("atan2(x = 1, y = -a)", "1 x= a neg y= atan2(2)"),
+	];
+	for (input, expected) in inputs {
+		assert_eq!(to_rpn(input).as_deref(), Ok(expected), "{:?}", input);
+	}
+	assert_eq!(to_rpn("1 +"), Err(Error::new(ErrorKind::UnfinishedExpression, 3)));
+	assert_eq!(to_rpn("f(a = 1, a = 2)"), Err(Error::new(ErrorKind::DuplicateKeyword, 9)));
+	assert_eq!(to_canonical("(1"), Err(Error::new(ErrorKind::UnbalancedParens, 0)));
+	// Canonical renderings evaluate to the same results
+	let env = crate::BasicEnv { ans: 1.5, ..Default::default() };
+	for input in ["2+3*ans", "(1-2)-(3-4)", "(2^3)^2", "-(ans^2)", "(1+2)%+ans%%3", "2(ans)3", "[1 ? 2 : 3] * 4", "√(9)²"] {
+		let canonical = to_canonical(input).unwrap();
+		assert_eq!(crate::eval(&env, &canonical), crate::eval(&env, input), "{:?} {:?}", input, canonical);
+		assert_eq!(to_canonical(&canonical).as_ref(), Ok(&canonical));
+	}
+}
+#[test]
+fn fold() {
+	fn count(ast: &Ast) -> usize {
+		1 + match ast.kind {
+			AstKind::Lit(_) | AstKind::Var(_) => 0,
+			AstKind::Op(_, ref args) | AstKind::Call(_, ref args) => args.iter().map(count).sum(),
+			AstKind::Percent(ref arg) | AstKind::Keyword(_, ref arg) => count(arg),
+			AstKind::Cond(ref branches) => branches.iter().map(count).sum(),
+		}
+	}
+	let mut env = crate::BasicEnv::default();
+	let inputs = [
+		("(2 * pi) / 360 * ans", "0.017453292519943295 * ans"),
+		("ans * (1 + 2) - sqrt(16) + max(ans, 3²)", "ans * 3 - 4 + max(ans, 9)"),
+		("1 ? ans : 2", "ans"),
+		("1 - 1 ? 2 : ans + 50%", "ans + 0.5"),
+		("ans ? 2 + 2 : 1", "ans ? 4 : 1"),
+		("-(2 * 3) ^ ans", "-6 ^ ans"),
+		("sqrt(1, 2) + ans", "sqrt(1, 2) + ans"),
+	];
+	for (input, expected) in inputs {
+		let ast = parse(input).unwrap();
+		let folded = ast.fold(&env);
+		assert_eq!(folded.to_string(), expected);
+		assert_eq!(ast.simplify_to_string(&env), expected);
+		assert!(count(&folded) <= count(&ast));
+		for ans in [0.0, 2.0] {
+			env.ans = ans;
+			assert_eq!(folded.eval(&env), ast.eval(&env), "{:?}", input);
+		}
+	}
+	assert_eq!(count(&parse("1 + 2 * 3 - pi / e").unwrap().fold(&env)), 1);
+	// Impure builtins are never folded
+	let builtins = [BuiltinDesc { name: "now", pfn: |_, _| Ok(42.0), pure: false, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "" }];
+	let env = crate::BasicEnv { ans: 0.0, builtins: &builtins };
+	assert_eq!(parse("now + 1").unwrap().fold(&env).to_string(), "now + 1");
+}
+#[test]
+fn eval() {
+	let mut env = crate::BasicEnv::default();
+	let inputs = [
+		"2 + 3 * 4",
+		"2^3^2 - -1",
+		"(1 + 2) * [3] - {4}",
+		"max(1, 2, 3) / 2ans",
+		"√9 + 3² + 2³ + π",
+		"120 * 15% + 7 % 4",
+		"7 // 2 && 0 || 1",
+		"0 ? 1 : ans ? 2 : 3",
+		"1 + hello",
+		"log(1)",
+		"√",
+	];
+	for ans in [0.0, 2.0] {
+		env.ans = ans;
+		for input in inputs {
+			assert_eq!(parse(input).and_then(|ast| ast.eval(&env)), crate::eval(&env, input), "{:?}", input);
+		}
+	}
+	// Names are resolved when evaluated, errors are reported at the node
+	assert_eq!(parse("1 + hello(2)").map(|_| ()), Ok(()));
+	assert_eq!(parse("1 + hello(2)").and_then(|ast| ast.eval(&env)), Err(Error::new(ErrorKind::EnvErrorNotFound, 4)));
+	// Only the chosen branch is evaluated
+	assert_eq!(parse("1 ? 2 : log(1)").and_then(|ast| ast.eval(&env)), Ok(2.0));
+	assert_eq!(parse("0 ? 2 : log(1)").and_then(|ast| ast.eval(&env)), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::exactly(2), got: 1 }, 8)));
+	// Syntax errors
+	assert_eq!(parse("0 ? 2 : log()").and_then(|ast| ast.eval(&env)), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::exactly(2), got: 0 }, 8)));
+	assert_eq!(parse("[1, 2]"), Err(Error::new(ErrorKind::BadArgument, 0)));
+	assert_eq!(parse("1 +"), Err(Error::new(ErrorKind::UnfinishedExpression, 3)));
+	assert_eq!(parse("(1"), Err(Error::new(ErrorKind::UnbalancedParens, 0)));
+}
@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+use crate::*;
+
+/// Timings of [`bench_eval`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BenchResult {
+	/// The value of the expression.
+	pub value: Value,
+	/// Number of times the expression was evaluated.
+	pub iterations: usize,
+	/// Fastest evaluation.
+	pub min: Duration,
+	/// Average evaluation.
+	pub mean: Duration,
+	/// Slowest evaluation.
+	pub max: Duration,
+}
+
+/// Tokenizes and evaluates the input the number of iterations, at least once, and measures each evaluation.
+///
+/// The first evaluation is not timed, if it fails its error is returned without looping.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// let bench = pupil::bench_eval(&env, "2 + 3", 100).unwrap();
+/// assert_eq!(bench.value, 5.0);
+/// assert!(bench.min <= bench.mean && bench.mean <= bench.max);
+/// ```
+pub fn bench_eval(env: &dyn Env, input: &str, iterations: usize) -> Result<BenchResult, Error> {
+	let value = eval(env, input)?;
+	let iterations = iterations.max(1);
+	let (mut min, mut max, mut total) = (Duration::MAX, Duration::ZERO, Duration::ZERO);
+	for _ in 0..iterations {
+		let start = Instant::now();
+		let result = eval(env, input);
+		let elapsed = start.elapsed();
+		// Impure builtins may fail later on
+		result?;
+		min = min.min(elapsed);
+		max = max.max(elapsed);
+		total += elapsed;
+	}
+	let mean = total.div_f64(iterations as f64);
+	Ok(BenchResult { value, iterations, min, mean, max })
+}
+
+#[test]
+fn bench() {
+	let env = BasicEnv::default();
+	let bench = bench_eval(&env, "sqrt(16) * 2", 10).unwrap();
+	assert_eq!((bench.value, bench.iterations), (8.0, 10));
+	assert!(bench.min <= bench.mean && bench.mean <= bench.max);
+	assert_eq!(bench_eval(&env, "1", 0).unwrap().iterations, 1);
+	// Errors are reported without looping
+	assert_eq!(bench_eval(&env, "2 +", usize::MAX), Err(Error::new(ErrorKind::UnfinishedExpression, 3)));
+}
@@ -0,0 +1,217 @@
+use std::{cell::RefCell, fmt};
+use astro_float::{BigFloat, Consts, Radix, RoundingMode};
+use crate::*;
+
+/// Arbitrary precision floating point value, see [`eval_precise`].
+///
+/// Wraps an [`astro_float::BigFloat`].
+/// Arithmetic rounds to nearest even at the working precision of the innermost [`eval_precise`] on the thread,
+/// or [`DEFAULT_PRECISION`] outside of it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BigValue(pub BigFloat);
+
+/// Working precision in bits outside of [`eval_precise`].
+pub const DEFAULT_PRECISION: usize = 128;
+
+pub(crate) const RM: RoundingMode = RoundingMode::ToEven;
+
+thread_local! {
+	// Working precision in bits and the cache of constants
+	static CONTEXT: RefCell<(usize, Consts)> = RefCell::new((DEFAULT_PRECISION, Consts::new().expect("cache of constants")));
+}
+
+// Calls the function with the working precision and the cache of constants.
+pub(crate) fn with_context<T>(f: impl FnOnce(usize, &mut Consts) -> T) -> T {
+	CONTEXT.with(|context| {
+		let (p, cc) = &mut *context.borrow_mut();
+		f(*p, cc)
+	})
+}
+
+// Restores the previous working precision when dropped.
+struct Precision(usize);
+impl Precision {
+	fn set(p: usize) -> Precision {
+		Precision(CONTEXT.with(|context| std::mem::replace(&mut context.borrow_mut().0, p)))
+	}
+}
+impl Drop for Precision {
+	fn drop(&mut self) {
+		CONTEXT.with(|context| context.borrow_mut().0 = self.0);
+	}
+}
+
+impl BigValue {
+	/// Converts at the working precision, exactly if it is at least 53 bits.
+	pub fn from_f64(val: Value) -> BigValue {
+		with_context(|p, _| BigValue(BigFloat::from_f64(val, p)))
+	}
+}
+
+impl fmt::Display for BigValue {
+	/// Formats the digits at the precision, in scientific notation for very large and small values.
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.0.is_nan() {
+			return f.write_str("NaN");
+		}
+		if self.0.is_inf() {
+			return f.write_str(if self.0.is_negative() { "-inf" } else { "inf" });
+		}
+		// Formatted as `-d.ddde+x`, zero as `0.0`
+		let s = self.0.to_string();
+		let (mantissa, exp) = match s.split_once('e') {
+			Some((mantissa, exp)) => (mantissa, exp.parse::<i64>().map_err(|_| fmt::Error)?),
+			None => (&s[..], 0),
+		};
+		let (sign, mantissa) = match mantissa.strip_prefix('-') {
+			Some(mantissa) => ("-", mantissa),
+			None => ("", mantissa),
+		};
+		let mut digits = mantissa.replace('.', "").into_bytes();
+		let mut exp = exp;
+		// Round to the significant digits of the precision, the last converted digits are not exact
+		let precision = self.0.mantissa_max_bit_len().unwrap_or(DEFAULT_PRECISION);
+		let significant = ((precision - 1) as f64 * std::f64::consts::LOG10_2) as usize;
+		if digits.len() > significant.max(1) && digits.drain(significant.max(1)..).next() >= Some(b'5') {
+			match digits.iter().rposition(|&d| d != b'9') {
+				Some(i) => {
+					digits[i] += 1;
+					digits.truncate(i + 1);
+				},
+				None => {
+					digits = vec![b'1'];
+					exp += 1;
+				},
+			}
+		}
+		let digits = std::str::from_utf8(&digits).map_err(|_| fmt::Error)?.trim_end_matches('0');
+		if digits.is_empty() {
+			return f.write_str("0");
+		}
+		f.write_str(sign)?;
+		if !(-7..40).contains(&exp) {
+			f.write_str(&digits[..1])?;
+			if digits.len() > 1 {
+				write!(f, ".{}", &digits[1..])?;
+			}
+			return write!(f, "e{}", exp);
+		}
+		// Number of digits before the decimal point
+		let point = exp + 1;
+		if point <= 0 {
+			write!(f, "0.{}{}", "0".repeat(-point as usize), digits)
+		}
+		else if point as usize >= digits.len() {
+			write!(f, "{}{}", digits, "0".repeat(point as usize - digits.len()))
+		}
+		else {
+			let (int, frac) = digits.split_at(point as usize);
+			write!(f, "{}.{}", int, frac)
+		}
+	}
+}
+
+impl Num for BigValue {
+	fn from_f64(val: f64) -> BigValue {
+		BigValue::from_f64(val)
+	}
+	fn truthy(&self) -> bool {
+		!self.0.is_zero() && !self.0.is_nan()
+	}
+	fn is_finite(&self) -> bool {
+		!self.0.is_inf() && !self.0.is_nan()
+	}
+	fn is_zero(&self) -> bool {
+		self.0.is_zero()
+	}
+	fn operator(op: Operator) -> BuiltinFn<BigValue> {
+		use crate::builtins_big::*;
+		match op {
+			Operator::Add => builtin_add,
+			Operator::Sub => builtin_sub,
+			Operator::Mul | Operator::IMul => builtin_mul,
+			Operator::Div => builtin_div,
+			Operator::Rem => builtin_rem,
+			Operator::Pow => builtin_pow,
+			Operator::And => builtin_all,
+			Operator::Or => builtin_any,
+			Operator::Sqrt => builtin_sqrt,
+			Operator::Sqr => builtin_sqr,
+			Operator::Cube => builtin_cube,
+			Operator::FloorDiv => builtin_floordiv,
+		}
+	}
+	fn percent() -> BuiltinFn<BigValue> {
+		crate::builtins_big::builtin_percent
+	}
+	fn identity() -> BuiltinFn<BigValue> {
+		crate::builtins_big::builtin_id
+	}
+	fn from_literal(text: &str) -> Result<Option<BigValue>, ErrorKind> {
+		// Hexadecimal, infinity and NaN literals are exact as f64
+		if !text.bytes().all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-')) {
+			return Ok(None);
+		}
+		let val = with_context(|p, cc| BigFloat::parse(text, Radix::Dec, p, RM, cc));
+		Ok((!val.is_nan()).then_some(BigValue(val)))
+	}
+}
+
+/// Evaluates the input at a precision in bits.
+///
+/// Literals are parsed from their text at the precision, eg. `0.1` is not rounded to `f64` first.
+///
+/// ```
+/// let env = pupil::PreciseEnv::default();
+/// let result = pupil::eval_precise(&env, "sqrt(2)", 200).unwrap();
+/// assert!(result.to_string().starts_with("1.41421356237309504880168872420969807856967187537694"));
+/// ```
+pub fn eval_precise(env: &dyn Env<BigValue>, input: &str, precision: usize) -> Result<BigValue, Error> {
+	let _precision = Precision::set(precision);
+	eval(env, input)
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn precise() {
+	let env = crate::PreciseEnv::default();
+	const PI: &str = "3.1415926535897932384626433832795028841971693993751058209749445923078164062862089986280348253421170679";
+	assert!(eval_precise(&env, "pi", 340).unwrap().to_string().starts_with(PI));
+	assert!(eval_precise(&env, "tau / 2", 340).unwrap().to_string().starts_with(PI));
+	assert!(eval_precise(&env, "4atan(1)", 340).unwrap().to_string().starts_with(PI));
+	// Lost in double precision
+	assert_eq!(eval(&crate::BasicEnv::default(), "(1 + 1e-40) - 1"), Ok(0.0));
+	let tiny = eval_precise(&env, "(1 + 1e-40) - 1", 256).unwrap();
+	assert!(!tiny.is_zero());
+	assert!(tiny.to_string().starts_with("1.00000000000000000000000000000000000") && tiny.to_string().ends_with("e-40"), "{}", tiny);
+	assert!(eval_precise(&env, "(1 + 1e-40) - 1", 64).unwrap().is_zero());
+	// Precision is restored
+	assert!(eval(&env, "(1 + 1e-40) - 1").unwrap().is_zero());
+	let e = eval_precise(&env, "e - exp(1)", 300).unwrap();
+	assert!(e.0.abs().cmp(&BigFloat::from_f64(1e-85, 64)) == Some(-1), "{}", e);
+	let sq = eval_precise(&env, "sqrt(2)^2 - 2", 300).unwrap();
+	assert!(sq.0.abs().cmp(&BigFloat::from_f64(1e-85, 64)) == Some(-1), "{}", sq);
+	// Same results as double precision where exact
+	let basic = crate::BasicEnv::default();
+	for input in ["2 + 3 * 4", "-7 // 2", "7 % -2", "50% + 1", "2^10 - 2**3", "0 ? 1 : 2 && 3", "max(1, 5, 3) - abs(-2)", "floor(-2.5) + ceil(2.5)", "log(8, 2)", "0.5 + 0.25", "1 / 0", "0x10 + 1"] {
+		let expected = eval(&basic, input).map(|val| val.to_string());
+		assert_eq!(eval_precise(&env, input, 100).map(|val| val.to_string()), expected, "{:?}", input);
+	}
+	assert!(eval_precise(&env, "0 / 0", 100).unwrap().0.is_nan());
+	assert_eq!(eval_precise(&env, "mean(1, 2)", 100), Err(Error::new(ErrorKind::EnvErrorNotFound, 0)));
+}
+#[test]
+fn display() {
+	let fmt = |input| eval_precise(&crate::PreciseEnv::default(), input, 256).unwrap().to_string();
+	assert_eq!(fmt("1234.5"), "1234.5");
+	assert_eq!(fmt("-0.00125"), "-0.00125");
+	assert_eq!(fmt("1e39"), "1000000000000000000000000000000000000000");
+	assert_eq!(fmt("1e40"), "1e40");
+	assert_eq!(fmt("-1.5e-8"), "-1.5e-8");
+	assert_eq!(fmt("0"), "0");
+	assert_eq!(fmt("-1/0"), "-inf");
+	// Significant digits at the precision
+	assert_eq!(eval_precise(&crate::PreciseEnv::default(), "0.1", 64).unwrap().to_string(), "0.1");
+	assert_eq!(eval_precise(&crate::PreciseEnv::default(), "1 / 3", 64).unwrap().to_string(), "0.333333333333333333");
+}
@@ -1,91 +1,80 @@
-use std::io::{self, Write};
-use std::env;
+use std::{env, fs, io, process};
+use std::io::IsTerminal;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 fn main() {
-	let con = unsafe { libc::isatty(0) != 0 };
-	let args = env::args();
+	let con = io::stdin().is_terminal();
 
-	if con {
+	let args = match pupil::repl::Args::parse(env::args().skip(1)) {
+		Ok(args) => args,
+		Err(msg) => {
+			eprintln!("{}", msg);
+			process::exit(pupil::repl::USAGE_ERROR);
+		},
+	};
+
+	if con && args.file.is_none() {
 		println!("Welcome to pupil, the arithmetic expression evaluator.");
-		if args.len() <= 1 {
+		if args.expr.is_empty() {
 			print!("
 Enter an expression, eg. 2 + 3, and press enter.
-Press ctrl-C to exit.
-
-Built-in functions:
-  +-*/^   : Operators with correct precedence.
-  (expr)  : Group expression with parentheses.
-  ans     : Use answer from previous expression.
-  pi, tau : Trigonometric constants.
-  e       : Euler’s number.
-  add, sub, mul, div, rem, pow, floor, ceil, round,
-  abs, sqr, cube, sqrt, cbrt, min, max, gamma, fac,
-  exp, expm1, ln, log, log2, log10, ln1p,
-  mean, median, range, var, stdev,
-  deg, rad, sin, cos, tan, asin, acos, atan, atan2,
-  sinh, cosh, tanh, asinh, acosh, atanh
-          : Use parens to provide arguments.
+Type :help for the syntax and the builtin functions, press ctrl-C to exit.
+Start with --format fixed:N, sci, eng, frac or auto to choose how results are printed,
+and --precision N to print N significant digits. Evaluate a file with --file path,
+and print JSON with --json when built with the json feature.
 
 ");
 		}
 	}
 
-	// Initialize the environment
-	let mut env = pupil::BasicEnv::default();
+	// Initialize the environment, seed the dice from the clock
+	let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u64).unwrap_or(0);
+	let mut session = match args.session(pupil::BasicEnv::default()) {
+		Ok(session) => session,
+		Err(msg) => {
+			eprintln!("{}", msg);
+			process::exit(pupil::repl::USAGE_ERROR);
+		},
+	};
+	session.set_seed(seed);
 
 	// Eval the command line args
-	if args.len() > 1 {
-		let mut expr = pupil::Expr::new(&env);
-		let result = (|| {
-			for s in args.skip(1) {
-				if let Err(e) = expr.feed(&s) {
-					return Err(e);
-				}
-			}
-			expr.result()
-		})();
-		// Print the result
-		match result {
-			Ok(val) => {
-				println!("Ok: {}", val);
-			},
+	if !args.expr.is_empty() {
+		let outcome = args.eval(&session, pupil::ColorMode::auto(io::stderr().is_terminal()));
+		print!("{}", outcome.stdout);
+		eprint!("{}", outcome.stderr);
+		process::exit(outcome.code);
+	}
+
+	let mut repl = pupil::repl::Repl::new(session, con);
+	repl.format = args.format;
+	repl.json = args.json;
+	repl.color = pupil::ColorMode::auto(io::stderr().is_terminal());
+
+	// Eval the file or piped stdin line by line
+	let failed = if let Some(path) = &args.file {
+		let file = match fs::File::open(path) {
+			Ok(file) => file,
 			Err(e) => {
-				eprintln!("Err: {}!", e);
+				eprintln!("Cannot open {}: {}", path, e);
+				process::exit(1);
 			},
-		}
+		};
+		repl.batch(&mut io::BufReader::new(file), &mut io::stdout(), &mut io::stderr())
 	}
-	// Eval from stdin
+	else if !con {
+		repl.batch(&mut io::stdin().lock(), &mut io::stdout(), &mut io::stderr())
+	}
+	// Eval from the console
 	else {
-		loop {
-			// If user is at a console, print a nice REPL
-			if con {
-				print!(">>> ");
-				io::stdout().flush().ok();
-			}
-			// Read input from stdin
-			let mut line = String::new();
-			if io::stdin().read_line(&mut line).is_err() {
-				break;
-			}
-			// Not sure how to handle ctrl-c events, Rust’s read_line is a bit weird in this regard
-			// I basically get an empty string as opposed to a newline when you just press enter.
-			if line.len() == 0 {
-				break;
-			}
-			// If you press enter without any input, just retry without evaluating.
-			let line = line.trim();
-			if line.len() > 0 {
-				// Evaluate the expression
-				match pupil::eval(&env, &line) {
-					Ok(val) => {
-						println!("{}", val);
-						env.ans = val;
-					},
-					Err(e) => {
-						eprintln!("Err: {}!", e);
-					},
-				}
-			}
-		}
+		repl.run(&mut io::stdin().lock(), &mut io::stdout(), &mut io::stderr()).map(|_| 0)
+	};
+	match failed {
+		Ok(0) => (),
+		Ok(_) => process::exit(1),
+		Err(e) => {
+			eprintln!("{}", e);
+			process::exit(1);
+		},
 	}
 }
@@ -4,119 +4,539 @@
 
 use std::f64::consts;
 use crate::*;
+use crate::math;
 
-pub fn builtin_id(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_id(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() == 1 { Ok(vals[0]) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_add(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() > 0 { Ok(vals.iter().fold(0f64, |acc, x| acc + x)) }
-	else { Err(Error::BadArgument) }
+// Neumaier's variant of Kahan summation, the lost low order bits are kept in a compensation term.
+fn sum(vals: &[Value]) -> Value {
+	let mut sum = 0f64;
+	let mut c = 0f64;
+	for &x in vals {
+		let t = sum + x;
+		if sum.abs() >= x.abs() { c += (sum - t) + x; }
+		else { c += (x - t) + sum; }
+		sum = t;
+	}
+	// The compensation is NaN once the sum is infinite
+	if sum.is_finite() { sum + c } else { sum }
+}
+/// Returns the sum of the values using compensated summation.
+pub fn builtin_add(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if !vals.is_empty() { Ok(sum(vals)) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_sub(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_sub(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	match vals.len() {
 		1 => Ok(-vals[0]),
 		2 => Ok(vals[0] - vals[1]),
-		_ => Err(Error::BadArgument),
+		_ => Err(ErrorKind::BadArgument),
 	}
 }
-pub fn builtin_mul(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_mul(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() > 1 { Ok(vals.iter().fold(1f64, |acc, x| acc * x)) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_div(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_div(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() == 2 { Ok(vals[0] / vals[1]) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_rem(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_floordiv(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok((vals[0] / vals[1]).floor()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_rem(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() == 2 { Ok(vals[0] % vals[1]) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
+}
+// Floored modulo, the result has the sign of `n`.
+fn modulo(a: Value, n: Value) -> Value {
+	let r = a % n;
+	if r != 0.0 && (r < 0.0) != (n < 0.0) {
+		let r = r + n;
+		// Tiny remainders round onto `n` which is outside the range
+		if r == n { 0.0 } else { r }
+	}
+	else {
+		r
+	}
+}
+/// Floored modulo, `mod(-1, 3)` is 2.
+///
+/// The result is within `0..n` for positive `n` and within `n..=0` for negative `n`, zero `n` is NaN like `rem`.
+pub fn builtin_mod(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(modulo(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Wraps `x` into the half open range `lo..hi`, an empty or reversed range is a bad argument.
+pub fn builtin_wrap(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x, lo, hi] if lo < hi => Ok(lo + modulo(x - lo, hi - lo)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Wraps an angle in radians into the range `(-pi, pi]`.
+pub fn builtin_wrap_angle(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() != 1 {
+		return Err(ErrorKind::BadArgument);
+	}
+	// The remainder is exact even for huge angles
+	let r = vals[0] % consts::TAU;
+	if r > consts::PI { Ok(r - consts::TAU) }
+	else if r <= -consts::PI { Ok(r + consts::TAU) }
+	else { Ok(r) }
+}
+pub fn builtin_pow(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(math::powf(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_percent(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0] / 100f64) }
+	else { Err(ErrorKind::BadArgument) }
+}
+// Applies the rounding function at the given number of decimal places.
+//
+// The decimal places must be an integer within the exponent range of f64, negative places round left of the decimal point.
+// The result is subject to the binary representation, eg. `round(1.005, 2)` is 1 because 1.005 is stored as 1.00499999...
+fn at_digits(vals: &[Value], f: fn(Value) -> Value) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x] => Ok(f(x)),
+		[x, digits] if (-308.0..=308.0).contains(&digits) && digits.fract() == 0.0 => {
+			let scale = math::powi(10 as Value, digits.abs() as i32);
+			if digits >= 0.0 {
+				let y = x * scale;
+				// Already more precise than requested
+				if !y.is_finite() { Ok(x) }
+				else { Ok(f(y) / scale) }
+			}
+			else {
+				Ok(f(x / scale) * scale)
+			}
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the `n`-th root, odd integer roots of negative values are negative.
+///
+/// Zero `n` and other roots of negative values are a bad argument.
+pub fn builtin_root(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (x, n) = match *vals {
+		[x, n] if n != 0.0 => (x, n),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	if n == 2.0 {
+		if x < 0.0 { Err(ErrorKind::BadArgument) } else { Ok(x.sqrt()) }
+	}
+	else if n == 3.0 {
+		Ok(math::cbrt(x))
+	}
+	else if x < 0.0 {
+		// Only odd integer roots of negative values are real
+		if n % 2.0 == 1.0 || n % 2.0 == -1.0 { Ok(-math::powf(-x, n.recip())) }
+		else { Err(ErrorKind::BadArgument) }
+	}
+	else {
+		Ok(math::powf(x, n.recip()))
+	}
+}
+/// Raises to an integer power by repeated squaring, a non-integer exponent is a bad argument.
+///
+/// Exact when every intermediate power is representable, as for integer results below 2^53 or `powi(10, 22)`, otherwise every step may round.
+/// Negative exponents take the reciprocal of the power, or fall back to `pow` when the power overflows or underflows.
+pub fn builtin_powi(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (x, n) = match *vals {
+		[x, n] if n.fract() == 0.0 => (x, n),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	let mut base = x;
+	let mut exp = n.abs() as u64;
+	let mut result = 1.0;
+	while exp > 0 {
+		if exp & 1 != 0 {
+			result *= base;
+		}
+		base *= base;
+		exp >>= 1;
+	}
+	if n >= 0.0 {
+		return Ok(result);
+	}
+	// Rounds once instead of for every step with the reciprocal of the base
+	if result.is_normal() || !x.is_normal() { Ok(result.recip()) }
+	else { Ok(math::powf(x, n)) }
 }
-pub fn builtin_pow(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 2 { Ok(vals[0].powf(vals[1])) }
-	else { Err(Error::BadArgument) }
+/// Rounds towards negative infinity, optionally at a number of decimal places.
+pub fn builtin_floor(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	at_digits(vals, Value::floor)
 }
-pub fn builtin_floor(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].floor()) }
-	else { Err(Error::BadArgument) }
+/// Rounds towards positive infinity, optionally at a number of decimal places.
+pub fn builtin_ceil(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	at_digits(vals, Value::ceil)
 }
-pub fn builtin_ceil(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].ceil()) }
-	else { Err(Error::BadArgument) }
+/// Rounds half away from zero, optionally at a number of decimal places.
+///
+/// For example `round(3.14159, 2)` is 3.14 and `round(1234, -2)` is 1200.
+pub fn builtin_round(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	at_digits(vals, Value::round)
 }
-pub fn builtin_round(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].round()) }
-	else { Err(Error::BadArgument) }
+/// Rounds for display to a number of decimal places, the result is the value printed with that many places.
+///
+/// Unlike `round` the exact binary value is rounded without scaling it first,
+/// eg. `tofixed(0.0055, 3)` is 0.005 because 0.0055 is stored slightly below while `round(0.0055, 3)` is 0.006.
+/// Exact ties round to even and the places must be an integer from 0 to 308.
+pub fn builtin_tofixed(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x, digits] if (0.0..=308.0).contains(&digits) && digits.fract() == 0.0 => {
+			Ok(format!("{:.*}", digits as usize, x).parse().unwrap_or(Value::NAN))
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
 }
-pub fn builtin_abs(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+/// Rounds to a number of significant figures, eg. `roundsig(98765, 2)` is 99000.
+///
+/// The figures must be a positive integer, zero, infinities and NaN are returned unchanged.
+pub fn builtin_roundsig(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (x, n) = match *vals {
+		[x, n] if n >= 1.0 && n.fract() == 0.0 => (x, n),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	// Nothing to round beyond the 17 significant digits of f64
+	if x == 0.0 || !x.is_finite() || n >= 17.0 {
+		return Ok(x);
+	}
+	let exp = math::log10(x.abs()).floor() as i32;
+	let digits = n as i32 - 1 - exp;
+	let mantissa = if digits > 300 {
+		// Subnormals are scaled in two steps to avoid overflowing the power of ten
+		(x * 1e300 * math::powi(10 as Value, digits - 300)).round()
+	}
+	else if digits >= 0 {
+		(x * math::powi(10 as Value, digits)).round()
+	}
+	else {
+		(x / math::powi(10 as Value, -digits)).round()
+	};
+	// Scaling back by an inexact power of ten is off by a few ulps, parsing the decimal is correctly rounded
+	Ok(format!("{}e{}", mantissa, -digits).parse().unwrap_or(Value::NAN))
+}
+/// Rounds to the nearest multiple of a positive step, eg. `quantize(0.8, 0.25)` is 0.75.
+pub fn builtin_quantize(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x, step] if step > 0.0 && step.is_finite() => Ok((x / step).round() * step),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_abs(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() == 1 { Ok(vals[0].abs()) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_sqr(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_sqr(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() == 1 { Ok(vals[0] * vals[0]) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_cube(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_cube(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() == 1 { Ok(vals[0] * vals[0] * vals[0]) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_sqrt(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_sqrt(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() == 1 { Ok(vals[0].sqrt()) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cbrt(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::cbrt(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the smallest argument, an empty argument list is a bad argument rather than infinity.
+pub fn builtin_min(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if !vals.is_empty() { Ok(vals.iter().fold(vals[0], |acc, &x| acc.min(x))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the largest argument, an empty argument list is a bad argument rather than negative infinity.
+pub fn builtin_max(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if !vals.is_empty() { Ok(vals.iter().fold(vals[0], |acc, &x| acc.max(x))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns `1.0` if `x >= edge` and `0.0` otherwise like GLSL’s `step(edge, x)`.
+///
+/// The single argument form `step(x)` is `step(0, x)`. NaN arguments return NaN.
+pub fn builtin_step(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (edge, x) = match *vals {
+		[x] => (0.0, x),
+		[edge, x] => (edge, x),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	if edge.is_nan() || x.is_nan() { Ok(Value::NAN) }
+	else { Ok(if x >= edge { 1.0 } else { 0.0 }) }
+}
+// Position of `x` between the edges clamped to `0.0..=1.0`, the single argument form has the edges `0` and `1`.
+// Equal edges step at the edge, edges in decreasing order are a bad argument.
+fn smooth_t(vals: &[Value]) -> Result<Value, ErrorKind> {
+	let (edge0, edge1, x) = match *vals {
+		[x] => (0.0, 1.0, x),
+		[edge0, edge1, x] => (edge0, edge1, x),
+		// The arity of the descriptor can only describe a range
+		[_, _] => return Err(ErrorKind::ArgumentCount { expected: Arity::exactly(3), got: 2 }),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	if edge0 > edge1 {
+		return Err(ErrorKind::BadArgument);
+	}
+	if edge0 == edge1 {
+		return Ok(if x.is_nan() { x } else if x < edge0 { 0.0 } else { 1.0 });
+	}
+	Ok(((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0))
+}
+/// Smooth Hermite interpolation like GLSL’s `smoothstep(edge0, edge1, x)`.
+///
+/// The single argument form `smoothstep(x)` is `smoothstep(0, 1, x)`, it only takes `x` positionally.
+/// Two arguments are an argument count error.
+/// Equal edges step like `step(edge0, x)`, edges in decreasing order are a bad argument.
+pub fn builtin_smoothstep(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let t = smooth_t(vals)?;
+	Ok(t * t * (3.0 - 2.0 * t))
+}
+/// Smoother interpolation with zero first and second derivatives at the edges, see `smoothstep`.
+pub fn builtin_smootherstep(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let t = smooth_t(vals)?;
+	Ok(t * t * t * (t * (t * 6.0 - 15.0) + 10.0))
+}
+// Exact at both ends, unlike `a + (b - a) * t` which may miss `b` at `t = 1`.
+fn lerp(a: Value, b: Value, t: Value) -> Value {
+	(1.0 - t) * a + t * b
+}
+fn invlerp(a: Value, b: Value, v: Value) -> Result<Value, ErrorKind> {
+	// An empty range has no unique parameter
+	if a == b { Err(ErrorKind::BadArgument) }
+	else { Ok((v - a) / (b - a)) }
+}
+/// Linear interpolation from `a` at `t = 0` to `b` at `t = 1`, extrapolates outside.
+pub fn builtin_lerp(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[a, b, t] => Ok(lerp(a, b, t)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Linear interpolation under its GLSL name, see `lerp`.
+pub fn builtin_mix(env: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	builtin_lerp(env, vals)
+}
+/// Clamps to `0..=1`, NaN stays NaN.
+pub fn builtin_saturate(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].clamp(0.0, 1.0)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Clamps `x` to `lo..=hi`, NaN stays NaN.
+///
+/// Swapped or NaN bounds are a bad argument rather than silently picking one of the bounds.
+pub fn builtin_clamp(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		// NaN bounds fail the comparison
+		[x, lo, hi] if lo <= hi => Ok(x.clamp(lo, hi)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Linear interpolation with `t` clamped to `0..=1`.
+pub fn builtin_lerpc(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[a, b, t] => Ok(lerp(a, b, t.clamp(0.0, 1.0))),
+		_ => Err(ErrorKind::BadArgument),
+	}
 }
-pub fn builtin_cbrt(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].cbrt()) }
-	else { Err(Error::BadArgument) }
+/// Inverse of `lerp`, returns the `t` where `lerp(a, b, t)` is `v`.
+///
+/// Equal `a` and `b` are a bad argument.
+pub fn builtin_invlerp(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[a, b, v] => invlerp(a, b, v),
+		_ => Err(ErrorKind::BadArgument),
+	}
 }
-pub fn builtin_min(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() > 0 { Ok(vals.iter().fold(vals[0], |acc, &x| acc.min(x))) }
-	else { Err(Error::BadArgument) }
+/// Maps `v` from the input range to the output range, `lerp(out_lo, out_hi, invlerp(in_lo, in_hi, v))`.
+pub fn builtin_remap(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[in_lo, in_hi, out_lo, out_hi, v] => Ok(lerp(out_lo, out_hi, invlerp(in_lo, in_hi, v)?)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Maps `v` from the input range to the output range, clamped to the output range.
+pub fn builtin_remapc(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[in_lo, in_hi, out_lo, out_hi, v] => Ok(lerp(out_lo, out_hi, invlerp(in_lo, in_hi, v)?.clamp(0.0, 1.0))),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_exp(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::exp(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_exp2(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::exp2(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns `10^x`, exact for integer powers up to `1e22`.
+pub fn builtin_exp10(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x] if x.fract() == 0.0 && x.abs() <= 22.0 => {
+			// Every power of ten up to 1e22 is exactly representable
+			let p = math::powi(10 as Value, x.abs() as i32);
+			Ok(if x < 0.0 { p.recip() } else { p })
+		},
+		[x] => Ok(math::powf(10 as Value, x)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+// Splits into a mantissa in `0.5..1` and a binary exponent like C's frexp.
+//
+// Zero, infinities and NaN return themselves with a zero exponent.
+fn frexp(x: Value) -> (Value, i32) {
+	if x == 0.0 || !x.is_finite() {
+		return (x, 0);
+	}
+	// Normalize subnormals first
+	let (x, adjust) = if x.abs() < Value::MIN_POSITIVE { (x * math::powi(2 as Value, 54), -54) } else { (x, 0) };
+	let bits = x.to_bits();
+	let exp = ((bits >> 52) & 0x7ff) as i32 - 1022;
+	let mantissa = Value::from_bits(bits & !(0x7ff << 52) | (1022 << 52));
+	(mantissa, exp + adjust)
 }
-pub fn builtin_max(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() > 0 { Ok(vals.iter().fold(vals[0], |acc, &x| acc.max(x))) }
-	else { Err(Error::BadArgument) }
+/// Returns the significand in `0.5..1`, such that `mantissa(x) * 2^exponent(x)` is `x`.
+///
+/// Zero, infinities and NaN are returned unchanged.
+pub fn builtin_mantissa(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(frexp(vals[0]).0) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_exp(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].exp()) }
-	else { Err(Error::BadArgument) }
+/// Returns the binary exponent, see `mantissa`.
+///
+/// Zero, infinities and NaN have a zero exponent.
+pub fn builtin_exponent(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(frexp(vals[0]).1 as Value) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_exp2(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].exp2()) }
-	else { Err(Error::BadArgument) }
+/// Returns the spacing from `abs(x)` to the next larger representable value.
+///
+/// The spacing of zero and subnormals is the smallest subnormal, infinities are infinite and NaN is NaN.
+/// The largest finite value uses the spacing below it.
+pub fn builtin_ulp(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let x = match *vals {
+		[x] => x.abs(),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	if !x.is_finite() {
+		Ok(x)
+	}
+	else if x == Value::MAX {
+		Ok(x - Value::from_bits(x.to_bits() - 1))
+	}
+	else {
+		Ok(Value::from_bits(x.to_bits() + 1) - x)
+	}
 }
-pub fn builtin_expm1(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].exp_m1()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_expm1(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::exp_m1(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_ln(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].ln()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_ln(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::ln(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_log(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 2 { Ok(vals[0].log(vals[1])) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_log(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(math::log(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_log2(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].log2()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_log2(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::log2(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_log10(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].log10()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_log10(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::log10(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_ln1p(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].ln_1p()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_ln1p(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::ln_1p(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_e(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 0 { Ok(consts::E) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_e(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() { Ok(consts::E) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_mean(env: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_mean(env: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	Ok(builtin_add(env, vals)? / vals.len() as Value)
 }
-pub fn builtin_median(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() > 0 {
+/// Returns the sum of squares.
+///
+/// The values are scaled by the largest magnitude while summing so the result only overflows when it is too large to represent.
+pub fn builtin_sumsq(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() {
+		return Err(ErrorKind::BadArgument);
+	}
+	let scale = vals.iter().fold(0.0, |acc: Value, val| acc.max(val.abs()));
+	if scale == 0.0 || !scale.is_finite() || vals.iter().any(|x| x.is_nan()) {
+		return Ok(vals.iter().map(|x| x * x).sum());
+	}
+	for x in vals.iter_mut() {
+		*x = (*x / scale) * (*x / scale);
+	}
+	Ok(sum(vals) * scale * scale)
+}
+/// Returns the dot product of interleaved pairs, `dot(x1, y1, x2, y2, ...)` is `x1 * y1 + x2 * y2 + ...`.
+///
+/// An odd number of arguments is a bad argument.
+pub fn builtin_dot(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() || !vals.len().is_multiple_of(2) {
+		return Err(ErrorKind::BadArgument);
+	}
+	let products = vals.chunks_exact(2).map(|pair| pair[0] * pair[1]).collect::<Vec<_>>();
+	Ok(sum(&products))
+}
+/// Returns the weighted mean of interleaved pairs, `wmean(w1, v1, w2, v2, ...)`.
+///
+/// An odd number of arguments and a total weight of zero are a bad argument.
+pub fn builtin_wmean(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() || !vals.len().is_multiple_of(2) {
+		return Err(ErrorKind::BadArgument);
+	}
+	let weights = vals.iter().step_by(2).copied().collect::<Vec<_>>();
+	let total = sum(&weights);
+	if total == 0.0 {
+		return Err(ErrorKind::BadArgument);
+	}
+	let products = vals.chunks_exact(2).map(|pair| pair[0] * pair[1]).collect::<Vec<_>>();
+	Ok(sum(&products) / total)
+}
+/// Returns the geometric mean of non-negative values.
+///
+/// Negative values are a bad argument, any zero makes the mean zero.
+pub fn builtin_geomean(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() || vals.iter().any(|&x| x < 0.0) {
+		return Err(ErrorKind::BadArgument);
+	}
+	if vals.contains(&0.0) {
+		return Ok(0.0);
+	}
+	for x in vals.iter_mut() {
+		*x = math::ln(*x);
+	}
+	Ok(math::exp(sum(vals) / vals.len() as Value))
+}
+/// Returns the harmonic mean, zero values are a bad argument.
+pub fn builtin_harmmean(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() || vals.contains(&0.0) {
+		return Err(ErrorKind::BadArgument);
+	}
+	for x in vals.iter_mut() {
+		*x = x.recip();
+	}
+	Ok(vals.len() as Value / sum(vals))
+}
+/// Returns the root mean square, scaled like `norm` so the squares do not overflow.
+pub fn builtin_rms(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	scaled_norm(vals, vals.len() as Value)
+}
+pub fn builtin_median(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if !vals.is_empty() {
 		vals.sort_by(f64::total_cmp);
 		// Pick the median value
 		let len = vals.len();
@@ -128,100 +548,1036 @@ pub fn builtin_median(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
 		}
 	}
 	else {
-		Err(Error::BadArgument)
+		Err(ErrorKind::BadArgument)
+	}
+}
+// Index of the first value preferred over all others, skipping NaN.
+fn arg_extreme(vals: &[Value], prefer: fn(Value, Value) -> bool) -> Result<Value, ErrorKind> {
+	if vals.is_empty() {
+		return Err(ErrorKind::BadArgument);
+	}
+	let mut best: Option<(usize, Value)> = None;
+	for (i, &x) in vals.iter().enumerate() {
+		if !x.is_nan() && best.is_none_or(|(_, b)| prefer(x, b)) {
+			best = Some((i, x));
+		}
+	}
+	Ok(best.map_or(Value::NAN, |(i, _)| i as Value))
+}
+/// Returns the zero based index of the smallest value, the first one on ties.
+///
+/// NaN values are skipped, the index of only NaN values is NaN.
+pub fn builtin_argmin(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	arg_extreme(vals, |x, best| x < best)
+}
+/// Returns the zero based index of the largest value, the first one on ties.
+///
+/// NaN values are skipped, the index of only NaN values is NaN.
+pub fn builtin_argmax(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	arg_extreme(vals, |x, best| x > best)
+}
+/// Returns the number of arguments.
+pub fn builtin_count(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if !vals.is_empty() { Ok(vals.len() as Value) }
+	else { Err(ErrorKind::BadArgument) }
+}
+// Sorts the data for the order statistics, empty data and NaN values are rejected.
+fn sorted(vals: &mut [Value]) -> Result<&[Value], ErrorKind> {
+	if vals.is_empty() || vals.iter().any(|x| x.is_nan()) {
+		return Err(ErrorKind::BadArgument);
+	}
+	vals.sort_by(f64::total_cmp);
+	Ok(vals)
+}
+/// Returns the `p` quantile of the values after it, `quantile(p, a, b, c, ...)`.
+///
+/// Interpolates linearly between the order statistics, `p` must be within `0..=1`.
+/// Empty data and NaN values are a bad argument.
+pub fn builtin_quantile(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (&mut p, data) = vals.split_first_mut().ok_or(ErrorKind::BadArgument)?;
+	if !(0.0..=1.0).contains(&p) {
+		return Err(ErrorKind::BadArgument);
+	}
+	let data = sorted(data)?;
+	let h = (data.len() - 1) as Value * p;
+	let lo = h.floor() as usize;
+	match data.get(lo + 1) {
+		Some(&hi) => Ok(data[lo] + (h - lo as Value) * (hi - data[lo])),
+		None => Ok(data[lo]),
 	}
 }
-pub fn builtin_range(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() > 0 {
-		let (mut min, mut max) = (vals[0], vals[0]);
+/// Returns the most frequent value, ties resolve to the smallest.
+///
+/// Empty data and NaN values are a bad argument.
+pub fn builtin_mode(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let data = sorted(vals)?;
+	let mut best = (data[0], 0);
+	for run in data.chunk_by(|a, b| a == b) {
+		if run.len() > best.1 {
+			best = (run[0], run.len());
+		}
+	}
+	Ok(best.0)
+}
+/// Returns the `k`-th smallest of the values after it, `kth(k, a, b, c, ...)`.
+///
+/// The `k` is one based and must be an integer within the number of values.
+/// NaN values are a bad argument.
+pub fn builtin_kth(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (&mut k, data) = vals.split_first_mut().ok_or(ErrorKind::BadArgument)?;
+	if !(k >= 1.0 && k <= data.len() as Value && k.fract() == 0.0) {
+		return Err(ErrorKind::BadArgument);
+	}
+	let data = sorted(data)?;
+	Ok(data[k as usize - 1])
+}
+/// Returns the difference between the largest and the smallest value.
+///
+/// NaN values are ignored like in `min` and `max`, the range of only NaN values is NaN.
+pub fn builtin_range(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if !vals.is_empty() {
+		let (mut min, mut max) = (Value::NAN, Value::NAN);
 		for &val in vals.iter() {
-			if !(val >= min) {
-				min = val;
-			}
-			else if !(val <= max) {
-				max = val;
-			}
+			min = min.min(val);
+			max = max.max(val);
 		}
 		Ok(max - min)
 	}
 	else {
-		Err(Error::BadArgument)
+		Err(ErrorKind::BadArgument)
+	}
+}
+// Sum of squared differences from the mean using Welford's online algorithm.
+fn sum_sq_diff(vals: &[Value]) -> Value {
+	let mut mean = 0f64;
+	let mut m2 = 0f64;
+	for (i, &x) in vals.iter().enumerate() {
+		let delta = x - mean;
+		mean += delta / (i + 1) as Value;
+		m2 += delta * (x - mean);
 	}
+	m2
 }
-pub fn builtin_var(env: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	let mean = builtin_mean(env, vals)?;
-	Ok(vals.iter().fold(0f64, |acc, &x| acc + (x - mean) * (x - mean)) / vals.len() as Value)
+/// Returns the population variance, dividing by `n`.
+pub fn builtin_var(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() { Err(ErrorKind::BadArgument) }
+	else { Ok(sum_sq_diff(vals) / vals.len() as Value) }
 }
-pub fn builtin_stdev(env: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+/// Returns the population standard deviation.
+pub fn builtin_stdev(env: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	Ok(builtin_var(env, vals)?.sqrt())
 }
-pub fn builtin_deg(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+/// Returns the sample variance, dividing by `n - 1`.
+///
+/// Matches the spreadsheet `VAR.S`, fewer than two values is a bad argument.
+pub fn builtin_svar(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() < 2 { Err(ErrorKind::BadArgument) }
+	else { Ok(sum_sq_diff(vals) / (vals.len() - 1) as Value) }
+}
+/// Returns the sample standard deviation.
+pub fn builtin_sstdev(env: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	Ok(builtin_svar(env, vals)?.sqrt())
+}
+/// Returns the standard error of the mean, the sample standard deviation over `sqrt(n)`.
+pub fn builtin_sem(env: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	Ok((builtin_svar(env, vals)? / vals.len() as Value).sqrt())
+}
+// Central moment sums `(n, M2, M3, M4)` accumulated in one pass without raw powers.
+fn moments(vals: &[Value]) -> (Value, Value, Value, Value) {
+	let (mut n, mut mean, mut m2, mut m3, mut m4) = (0f64, 0f64, 0f64, 0f64, 0f64);
+	for &x in vals {
+		let n1 = n;
+		n += 1.0;
+		let delta = x - mean;
+		let delta_n = delta / n;
+		let delta_n2 = delta_n * delta_n;
+		let term = delta * delta_n * n1;
+		mean += delta_n;
+		m4 += term * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * m2 - 4.0 * delta_n * m3;
+		m3 += term * delta_n * (n - 2.0) - 3.0 * delta_n * m2;
+		m2 += term;
+	}
+	(n, m2, m3, m4)
+}
+/// Returns the population skewness.
+///
+/// Fewer than three values is a bad argument, constant values have zero skewness.
+pub fn builtin_skew(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() < 3 {
+		return Err(ErrorKind::BadArgument);
+	}
+	let (n, m2, m3, _) = moments(vals);
+	if m2 == 0.0 { Ok(0.0) }
+	else { Ok(n.sqrt() * m3 / math::powf(m2, 1.5)) }
+}
+/// Returns the population excess kurtosis, zero for the normal distribution.
+///
+/// Fewer than four values is a bad argument, constant values have zero excess kurtosis.
+pub fn builtin_kurt(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() < 4 {
+		return Err(ErrorKind::BadArgument);
+	}
+	let (n, m2, _, m4) = moments(vals);
+	if m2 == 0.0 { Ok(0.0) }
+	else { Ok(n * m4 / (m2 * m2) - 3.0) }
+}
+/// Returns -1, 0 or 1 when `a` is less than, equal to or greater than `b`.
+///
+/// Zero and negative zero compare equal, NaN is ordered like `total_cmp`:
+/// positive NaN is greater and negative NaN is less than any number.
+pub fn builtin_cmp(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[a, b] => {
+			let ord = a.partial_cmp(&b).unwrap_or_else(|| a.total_cmp(&b));
+			Ok(ord as i8 as Value)
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns true if `lo <= x <= hi`, or `lo < x < hi` when the optional fourth argument is truthy.
+///
+/// Reversed bounds are an empty range and NaN in any of the first three arguments is false.
+pub fn builtin_between(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (x, lo, hi, open) = match *vals {
+		[x, lo, hi] => (x, lo, hi, false),
+		[x, lo, hi, open] => (x, lo, hi, truthy(open)),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	let result = if open { lo < x && x < hi } else { lo <= x && x <= hi };
+	Ok(if result { 1.0 } else { 0.0 })
+}
+/// Returns true if `a` and `b` are approximately equal, `approx(a, b, rel, abs)`.
+///
+/// The test is `abs(a - b) <= max(abs, rel * max(abs(a), abs(b)))` with a default relative tolerance of `1e-9` and absolute tolerance of zero.
+/// Infinities are only equal to themselves and NaN is never equal, negative tolerances are a bad argument.
+pub fn builtin_approx(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (a, b, rel, abs) = match *vals {
+		[a, b] => (a, b, 1e-9, 0.0),
+		[a, b, rel] => (a, b, rel, 0.0),
+		[a, b, rel, abs] => (a, b, rel, abs),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	if !(rel >= 0.0 && abs >= 0.0) {
+		return Err(ErrorKind::BadArgument);
+	}
+	let result = if a == b { true }
+		else if a.is_infinite() || b.is_infinite() { false }
+		else { (a - b).abs() <= abs.max(rel * a.abs().max(b.abs())) };
+	Ok(if result { 1.0 } else { 0.0 })
+}
+/// Returns true if all values are truthy.
+///
+/// Any non-zero value is truthy, zero and NaN are falsy.
+/// The result is `1.0` for true and `0.0` for false.
+pub fn builtin_all(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if !vals.is_empty() { Ok(if vals.iter().all(|&x| truthy(x)) { 1.0 } else { 0.0 }) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns true if any value is truthy.
+///
+/// Any non-zero value is truthy, zero and NaN are falsy.
+/// The result is `1.0` for true and `0.0` for false.
+pub fn builtin_any(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if !vals.is_empty() { Ok(if vals.iter().any(|&x| truthy(x)) { 1.0 } else { 0.0 }) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns true if the value is falsy, NaN is falsy so `not(NaN)` is `1.0`.
+pub fn builtin_not(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(if truthy(vals[0]) { 0.0 } else { 1.0 }) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the argument after the index chosen by the first argument, `select(i, a, b, ...)`.
+///
+/// The index is zero based and negative indices count from the end like Python.
+/// An index which is not an integer or out of range is a bad argument.
+pub fn builtin_select(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let Some((&index, choices)) = vals.split_first() else {
+		return Err(ErrorKind::BadArgument);
+	};
+	let len = choices.len() as Value;
+	if index != index.trunc() || !(-len..len).contains(&index) {
+		return Err(ErrorKind::BadArgument);
+	}
+	let index = if index < 0.0 { index + len } else { index };
+	Ok(choices[index as usize])
+}
+/// Returns the argument after the index chosen by the first argument, `selectc(i, a, b, ...)`.
+///
+/// The index is floored and clamped to the first or last choice, a NaN index is a bad argument.
+pub fn builtin_selectc(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let Some((&index, choices)) = vals.split_first() else {
+		return Err(ErrorKind::BadArgument);
+	};
+	if choices.is_empty() || index.is_nan() {
+		return Err(ErrorKind::BadArgument);
+	}
+	// Float to int casts saturate
+	let index = (index.floor().max(0.0) as usize).min(choices.len() - 1);
+	Ok(choices[index])
+}
+// Lazy builtins stop evaluating their arguments at the first one deciding the result.
+// Returns whether a truthy or falsy argument decides the result, which is then `1.0` or `0.0` respectively.
+pub(crate) fn lazy(name: &str) -> Option<bool> {
+	match name {
+		"and" => Some(false),
+		"or" => Some(true),
+		_ => None,
+	}
+}
+#[inline]
+pub(crate) fn truthy(val: Value) -> bool {
+	// NaN compares unequal to everything, explicitly reject it
+	val != 0.0 && !val.is_nan()
+}
+// Lanczos approximation with g = 7 and 9 coefficients.
+const LANCZOS_G: Value = 7.0;
+const LANCZOS: [Value; 9] = [
+	0.999_999_999_999_809_9,
+	676.520_368_121_885_1,
+	-1_259.139_216_722_402_8,
+	771.323_428_777_653_1,
+	-176.615_029_162_140_6,
+	12.507_343_278_686_905,
+	-0.138_571_095_265_720_12,
+	9.984_369_578_019_572e-6,
+	1.505_632_735_149_311_6e-7,
+];
+// Returns the Lanczos sum and `t` for `x >= 0.5`.
+fn lanczos(x: Value) -> (Value, Value) {
+	let x = x - 1.0;
+	let sum = LANCZOS[1..].iter().enumerate().fold(LANCZOS[0], |acc, (i, &p)| acc + p / (x + (i + 1) as Value));
+	(sum, x + LANCZOS_G + 0.5)
+}
+// Factorial of a non-negative integer, overflows to infinity past 170.
+fn factorial(n: Value) -> Value {
+	if n > 170.0 { Value::INFINITY }
+	else { (2..=n as u32).fold(1.0, |acc, i| acc * i as Value) }
+}
+// Sine of pi times x with the argument reduced exactly, accurate next to the integers unlike `sin(PI * x)`.
+fn sinpi(x: Value) -> Value {
+	let n = x.round();
+	let s = math::sin(consts::PI * (x - n));
+	if n % 2.0 == 0.0 { s } else { -s }
+}
+fn gamma(x: Value) -> Value {
+	if x.is_nan() || x == Value::INFINITY {
+		x
+	}
+	else if x <= 0.0 && x.fract() == 0.0 {
+		// Poles at zero and the negative integers
+		Value::NAN
+	}
+	else if x.fract() == 0.0 {
+		// Exact for the positive integers
+		factorial(x - 1.0)
+	}
+	else if x < 0.5 {
+		// Reflection formula
+		consts::PI / (sinpi(x) * gamma(1.0 - x))
+	}
+	else {
+		let (sum, t) = lanczos(x);
+		// Split the power to avoid overflowing before multiplying with the tiny exponential
+		let half = math::powf(t, (x - 0.5) / 2.0);
+		(2.0 * consts::PI).sqrt() * half * (math::exp(-t) * half) * sum
+	}
+}
+fn lgamma(x: Value) -> Value {
+	if x.is_nan() {
+		x
+	}
+	else if x.is_infinite() || (x <= 0.0 && x.fract() == 0.0) {
+		Value::INFINITY
+	}
+	else if x.fract() == 0.0 && x <= 171.0 {
+		// Exact for the positive integers where the factorial is finite
+		math::ln(factorial(x - 1.0))
+	}
+	else if x < 0.5 {
+		// Reflection formula
+		math::ln(consts::PI / sinpi(x).abs()) - lgamma(1.0 - x)
+	}
+	else {
+		let (sum, t) = lanczos(x);
+		0.5 * math::ln(2.0 * consts::PI) + (x - 0.5) * math::ln(t) - t + math::ln(sum)
+	}
+}
+/// Returns the gamma function, `gamma(n)` is `fac(n - 1)` for positive integers.
+///
+/// Uses the Lanczos approximation with a relative error below `1e-14` within ±10,
+/// growing to `2e-13` towards the overflow past ±171, also next to the poles.
+/// The poles at zero and the negative integers are NaN.
+pub fn builtin_gamma(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(gamma(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the natural logarithm of the absolute value of the gamma function.
+///
+/// Finite for large arguments where `gamma` overflows, the poles are infinite.
+pub fn builtin_lgamma(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(lgamma(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the factorial of a non-negative integer.
+///
+/// Other arguments are a bad argument, use `gamma(x + 1)` instead. Overflows to infinity past `fac(170)`.
+pub fn builtin_fac(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[n] if n >= 0.0 && n.fract() == 0.0 => Ok(factorial(n)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+// Series expansion of erf for `0 <= x < 2`, all terms are positive so nothing cancels.
+fn erf_series(x: Value) -> Value {
+	let x2 = x * x;
+	let mut term = x;
+	let mut sum = x;
+	let mut n = 0.0;
+	while term > sum * Value::EPSILON {
+		n += 1.0;
+		term *= 2.0 * x2 / (2.0 * n + 1.0);
+		sum += term;
+	}
+	sum * consts::FRAC_2_SQRT_PI * math::exp(-x2)
+}
+// Continued fraction of erfc for `x >= 2`, evaluated with the modified Lentz method.
+fn erfc_fraction(x: Value) -> Value {
+	// Underflows to zero, also avoids infinity arithmetic
+	if x > 27.0 {
+		return 0.0;
+	}
+	let mut f = x;
+	let mut c = x;
+	let mut d = 0.0;
+	for n in 1..500 {
+		let a = n as Value * 0.5;
+		d = 1.0 / (x + a * d);
+		c = x + a / c;
+		let delta = c * d;
+		f *= delta;
+		if (delta - 1.0).abs() <= Value::EPSILON {
+			break;
+		}
+	}
+	math::exp(-x * x) / (consts::PI.sqrt() * f)
+}
+fn erf(x: Value) -> Value {
+	let a = x.abs();
+	let y = if a < 2.0 { erf_series(a) } else { 1.0 - erfc_fraction(a) };
+	if x.is_nan() { x } else { y.copysign(x) }
+}
+fn erfc(x: Value) -> Value {
+	if x >= 2.0 { erfc_fraction(x) }
+	else if x <= -2.0 { 2.0 - erfc_fraction(-x) }
+	else { 1.0 - erf(x) }
+}
+/// Returns the error function.
+///
+/// Uses a series expansion near zero and a continued fraction in the tails, the relative error is below `1e-13`.
+pub fn builtin_erf(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(erf(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the complementary error function `1 - erf(x)`.
+///
+/// Computed directly for large arguments to keep precision in the tail.
+pub fn builtin_erfc(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(erfc(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the cumulative distribution function of the standard normal distribution.
+pub fn builtin_normcdf(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(0.5 * erfc(-vals[0] * consts::FRAC_1_SQRT_2)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+// Integers up to 2^53 are exactly representable.
+fn to_uint(val: Value) -> Result<u64, ErrorKind> {
+	if (0.0..=9007199254740992.0).contains(&val) && val.fract() == 0.0 { Ok(val as u64) }
+	else { Err(ErrorKind::BadArgument) }
+}
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+	while b != 0 {
+		let r = a % b;
+		a = b;
+		b = r;
+	}
+	a
+}
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+	(a as u128 * b as u128 % m as u128) as u64
+}
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+	let mut result = 1;
+	base %= m;
+	while exp > 0 {
+		if exp & 1 != 0 {
+			result = mul_mod(result, base, m);
+		}
+		base = mul_mod(base, base, m);
+		exp >>= 1;
+	}
+	result
+}
+// Miller-Rabin with bases that are deterministic for all 64-bit integers.
+fn is_prime(n: u64) -> bool {
+	const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+	if n < 2 {
+		return false;
+	}
+	for &p in &BASES {
+		if n.is_multiple_of(p) {
+			return n == p;
+		}
+	}
+	let s = (n - 1).trailing_zeros();
+	let d = (n - 1) >> s;
+	BASES.iter().all(|&a| {
+		let mut x = pow_mod(a, d, n);
+		if x == 1 || x == n - 1 {
+			return true;
+		}
+		for _ in 1..s {
+			x = mul_mod(x, x, n);
+			if x == n - 1 {
+				return true;
+			}
+		}
+		false
+	})
+}
+// Binary operation on two integers up to 2^53.
+fn bitwise(vals: &[Value], f: fn(u64, u64) -> u64) -> Result<Value, ErrorKind> {
+	match *vals {
+		[a, b] => Ok(f(to_uint(a)?, to_uint(b)?) as Value),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Bitwise and of non-negative integers up to 2^53.
+pub fn builtin_band(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	bitwise(vals, |a, b| a & b)
+}
+/// Bitwise or of non-negative integers up to 2^53.
+pub fn builtin_bor(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	bitwise(vals, |a, b| a | b)
+}
+/// Bitwise exclusive or of non-negative integers up to 2^53.
+pub fn builtin_bxor(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	bitwise(vals, |a, b| a ^ b)
+}
+/// Bitwise not within a width of 1 to 53 bits, `bnot(a, width)` with a default width of 32.
+///
+/// Values which do not fit the width are a bad argument.
+pub fn builtin_bnot(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (a, width) = match *vals {
+		[a] => (to_uint(a)?, 32),
+		[a, width] => (to_uint(a)?, to_uint(width)?),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	if !(1..=53).contains(&width) || a >> width != 0 {
+		return Err(ErrorKind::BadArgument);
+	}
+	Ok((!a & ((1 << width) - 1)) as Value)
+}
+/// Shifts left by `n` bits, results beyond 2^53 are a bad argument.
+pub fn builtin_shl(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (a, n) = match *vals {
+		[a, n] => (to_uint(a)?, to_uint(n)?),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	if a == 0 {
+		return Ok(0.0);
+	}
+	// The shifted value must remain exactly representable
+	if n >= 54 || a > (1 << 53) >> n {
+		return Err(ErrorKind::BadArgument);
+	}
+	Ok((a << n) as Value)
+}
+/// Shifts right by `n` bits, shifting past the width is zero.
+pub fn builtin_shr(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[a, n] => Ok(to_uint(a)?.checked_shr(to_uint(n)?.try_into().unwrap_or(u32::MAX)).unwrap_or(0) as Value),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the number of set bits.
+pub fn builtin_popcount(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[a] => Ok(to_uint(a)?.count_ones() as Value),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns bit `i` counting from the least significant bit, bits past the width are zero.
+pub fn builtin_bit(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[a, i] => {
+			let (a, i) = (to_uint(a)?, to_uint(i)?);
+			Ok(if i < 64 { (a >> i & 1) as Value } else { 0.0 })
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+// Returns `(F(n), F(n + 1))` by fast doubling.
+fn fib(n: u64) -> (u64, u64) {
+	if n == 0 {
+		return (0, 1);
+	}
+	let (a, b) = fib(n / 2);
+	let c = a * (2 * b - a);
+	let d = a * a + b * b;
+	if n.is_multiple_of(2) { (c, d) } else { (d, c + d) }
+}
+/// Returns the `n`-th Fibonacci number.
+///
+/// Only defined up to `fib(78)`, the largest which is exactly representable, other arguments are a bad argument.
+pub fn builtin_fib(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[n] => match to_uint(n)? {
+			n @ 0..=78 => Ok(fib(n).0 as Value),
+			_ => Err(ErrorKind::BadArgument),
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the `n`-th triangular number `n(n + 1) / 2`.
+pub fn builtin_tri(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[n] => {
+			let n = to_uint(n)? as u128;
+			Ok((n * (n + 1) / 2) as Value)
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the `n`-th square pyramidal number `n(n + 1)(2n + 1) / 6`, the sum of the first `n` squares.
+pub fn builtin_sqrpyr(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[n] => {
+			let n = to_uint(n)? as u128;
+			// Exact unless the product overflows, which is far beyond the exact range of f64 anyway
+			match (n * (n + 1)).checked_mul(2 * n + 1) {
+				Some(p) => Ok((p / 6) as Value),
+				None => {
+					let n = n as Value;
+					Ok(n * (n + 1.0) * (2.0 * n + 1.0) / 6.0)
+				},
+			}
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the greatest common divisor of non-negative integers.
+pub fn builtin_gcd(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() {
+		return Err(ErrorKind::BadArgument);
+	}
+	let mut acc = 0;
+	for &val in vals.iter() {
+		acc = gcd(acc, to_uint(val)?);
+	}
+	Ok(acc as Value)
+}
+/// Returns the least common multiple of non-negative integers, overflows to infinity.
+pub fn builtin_lcm(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() {
+		return Err(ErrorKind::BadArgument);
+	}
+	let mut acc = Some(1u64);
+	for &val in vals.iter() {
+		let n = to_uint(val)?;
+		acc = acc.and_then(|acc| match n {
+			0 => Some(0),
+			_ => (acc / gcd(acc, n).max(1)).checked_mul(n),
+		});
+	}
+	Ok(acc.map_or(Value::INFINITY, |acc| acc as Value))
+}
+/// Returns 1 if the non-negative integer is prime, 0 otherwise.
+pub fn builtin_isprime(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[n] => Ok(if is_prime(to_uint(n)?) { 1.0 } else { 0.0 }),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the magnitude of the first argument with the sign of the second.
+pub fn builtin_copysign(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(vals[0].copysign(vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns 1 if the sign bit is set, including `-0` and negative NaN, 0 otherwise.
+pub fn builtin_signbit(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(if vals[0].is_sign_negative() { 1.0 } else { 0.0 }) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the next representable value after `x` in the direction of `toward`.
+pub fn builtin_nextafter(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (x, toward) = match *vals {
+		[x, toward] => (x, toward),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	if x.is_nan() || toward.is_nan() {
+		Ok(Value::NAN)
+	}
+	else if x == toward {
+		Ok(toward)
+	}
+	else if x == 0.0 {
+		// Smallest subnormal with the sign of the direction
+		Ok(Value::from_bits(1).copysign(toward))
+	}
+	else {
+		// Adjacent bit patterns are adjacent values of the same sign
+		let bits = x.to_bits();
+		let bits = if (toward > x) == (x > 0.0) { bits + 1 } else { bits - 1 };
+		Ok(Value::from_bits(bits))
+	}
+}
+/// Returns the unnormalized sinc function `sin(x) / x`, which is 1 at zero.
+pub fn builtin_sinc(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x] => Ok(if x == 0.0 { 1.0 } else if x.is_infinite() { 0.0 } else { math::sin(x) / x }),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the logistic function `1 / (1 + exp(-x))`.
+///
+/// The exponential is only taken of non-positive values so it never overflows.
+pub fn builtin_sigmoid(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x] if x >= 0.0 => Ok(1.0 / (1.0 + math::exp(-x))),
+		[x] => {
+			let e = math::exp(x);
+			Ok(e / (1.0 + e))
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the softplus function `ln(1 + exp(x))`, computed as `max(x, 0) + ln1p(exp(-abs(x)))`.
+pub fn builtin_softplus(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x] => Ok(x.max(0.0) + math::ln_1p(math::exp(-x.abs()))),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+// Growth factor `(1 + rate)^nper` and `(1 + rate)^nper - 1`, accurate for small rates.
+fn growth(rate: Value, nper: Value) -> (Value, Value) {
+	let x = nper * math::ln_1p(rate);
+	(math::exp(x), math::exp_m1(x))
+}
+// Optional trailing argument with a default of zero.
+fn fin_args(vals: &[Value]) -> Result<(Value, Value, Value, Value), ErrorKind> {
+	match *vals {
+		[a, b, c] => Ok((a, b, c, 0.0)),
+		[a, b, c, d] => Ok((a, b, c, d)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the future value of an investment, `fv(rate, nper, pmt, pv)`.
+///
+/// Follows the spreadsheet sign convention: money paid out is negative and money received is positive.
+/// Payments are made at the end of each period and the present value defaults to zero.
+pub fn builtin_fv(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (rate, nper, pmt, pv) = fin_args(vals)?;
+	if rate == 0.0 {
+		return Ok(-(pv + pmt * nper));
+	}
+	let (factor, gain) = growth(rate, nper);
+	Ok(-(pv * factor + pmt * gain / rate))
+}
+/// Returns the present value of an investment, `pv(rate, nper, pmt, fv)`.
+///
+/// Uses the sign convention of `fv`, the future value defaults to zero.
+pub fn builtin_pv(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (rate, nper, pmt, fv) = fin_args(vals)?;
+	if rate == 0.0 {
+		return Ok(-(fv + pmt * nper));
+	}
+	let (factor, gain) = growth(rate, nper);
+	Ok(-(fv + pmt * gain / rate) / factor)
+}
+/// Returns the payment per period of a loan, `pmt(rate, nper, pv, fv)`.
+///
+/// Uses the sign convention of `fv`, the future value defaults to zero.
+/// Zero periods is a bad argument.
+pub fn builtin_pmt(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (rate, nper, pv, fv) = fin_args(vals)?;
+	if nper == 0.0 {
+		return Err(ErrorKind::BadArgument);
+	}
+	if rate == 0.0 {
+		return Ok(-(pv + fv) / nper);
+	}
+	let (factor, gain) = growth(rate, nper);
+	Ok(-(pv * factor + fv) * rate / gain)
+}
+/// Returns the net present value of cash flows at the end of each period, `npv(rate, cf1, cf2, ...)`.
+///
+/// A rate of -1 is a bad argument.
+pub fn builtin_npv(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (&mut rate, flows) = vals.split_first_mut().ok_or(ErrorKind::BadArgument)?;
+	if flows.is_empty() || rate == -1.0 {
+		return Err(ErrorKind::BadArgument);
+	}
+	let mut discount = 1.0;
+	for cf in flows.iter_mut() {
+		discount /= 1.0 + rate;
+		*cf *= discount;
+	}
+	Ok(sum(flows))
+}
+// Rolls a die with the given number of sides using the environment's random number generator.
+fn die(env: &dyn Env, sides: u64) -> Result<Value, ErrorKind> {
+	let r = env.random().ok_or(ErrorKind::EnvErrorNotFound)?;
+	Ok(1.0 + (r * sides as Value).floor())
+}
+/// Rolls a single die, `d(6)` returns 1 to 6.
+///
+/// The sides must be a positive integer, requires an environment with a random number generator.
+/// The name only resolves when called, a variable named `d` is still a variable.
+pub fn builtin_d(env: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[sides] => match to_uint(sides)? {
+			0 => Err(ErrorKind::BadArgument),
+			sides => die(env, sides),
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Returns the sum of `n` dice rolls, `roll(3, 6)` returns 3 to 18.
+///
+/// At most a million dice are rolled at once, see `d`.
+pub fn builtin_roll(env: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let (n, sides) = match *vals {
+		[n, sides] => (to_uint(n)?, to_uint(sides)?),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	if sides == 0 || n > 1_000_000 {
+		return Err(ErrorKind::BadArgument);
+	}
+	let mut total = 0.0;
+	for _ in 0..n {
+		total += die(env, sides)?;
+	}
+	Ok(total)
+}
+/// Returns 1 with probability `p` and 0 otherwise, `p` must be within `0..=1`.
+pub fn builtin_chance(env: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[p] if (0.0..=1.0).contains(&p) => {
+			let r = env.random().ok_or(ErrorKind::EnvErrorNotFound)?;
+			Ok(if r < p { 1.0 } else { 0.0 })
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_deg(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() == 1 { Ok(vals[0] * (180f64 / consts::PI)) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_rad(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
+pub fn builtin_rad(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
 	if vals.len() == 1 { Ok(vals[0] * (consts::PI / 180f64)) }
-	else { Err(Error::BadArgument) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_pi(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() { Ok(consts::PI) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_tau(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.is_empty() { Ok(consts::PI + consts::PI) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sin(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::sin(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cos(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::cos(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_tan(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::tan(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_asin(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::asin(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_acos(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::acos(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_atan(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::atan(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_atan2(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(math::atan2(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cot(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::tan(vals[0]).recip()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sec(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::cos(vals[0]).recip()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_csc(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::sin(vals[0]).recip()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+// Sine and cosine of an angle in degrees.
+//
+// The angle is reduced modulo 360 and then to within 45 degrees of a quadrant before converting to radians.
+// This keeps huge angles accurate and the multiples of 90 degrees exact.
+fn sincosd(x: Value) -> (Value, Value) {
+	let r = x % 360.0;
+	let q = (r / 90.0).round();
+	let t = (r - q * 90.0).to_radians();
+	let (sin, cos) = math::sin_cos(t);
+	// Subtract from zero to avoid negative zeros
+	match q as i32 & 3 {
+		0 => (sin, cos),
+		1 => (cos, 0.0 - sin),
+		2 => (0.0 - sin, 0.0 - cos),
+		_ => (0.0 - cos, sin),
+	}
+}
+pub fn builtin_sind(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(sincosd(vals[0]).0) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_pi(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 0 { Ok(consts::PI) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_cosd(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(sincosd(vals[0]).1) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_tau(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 0 { Ok(consts::PI + consts::PI) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_tand(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 {
+		let (sin, cos) = sincosd(vals[0]);
+		Ok(sin / cos)
+	}
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_asind(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::asin(vals[0]).to_degrees()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_acosd(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::acos(vals[0]).to_degrees()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_atand(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::atan(vals[0]).to_degrees()) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_sin(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].sin()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_atan2d(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(math::atan2(vals[0], vals[1]).to_degrees()) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_cos(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].cos()) }
-	else { Err(Error::BadArgument) }
+/// Returns the length of the hypotenuse without intermediate overflow.
+pub fn builtin_hypot(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(math::hypot(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the radius of the point `(x, y)`.
+pub fn builtin_polar_r(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(math::hypot(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Returns the angle of the point `(x, y)` in (-π, π].
+///
+/// The origin has angle 0 and the negative x axis has angle π regardless of the sign of zero.
+pub fn builtin_polar_theta(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x, y] => Ok(polar_angle(x, y)),
+		_ => Err(ErrorKind::BadArgument),
+	}
 }
-pub fn builtin_tan(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].tan()) }
-	else { Err(Error::BadArgument) }
+// Angle of `(x, y)` in (-π, π] with zeros normalized.
+fn polar_angle(x: Value, y: Value) -> Value {
+	// Adding zero turns -0.0 into +0.0
+	math::atan2(y + 0.0, x + 0.0)
 }
-pub fn builtin_asin(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].asin()) }
-	else { Err(Error::BadArgument) }
+/// Returns the x coordinate of the polar point `(r, theta)`.
+pub fn builtin_cart_x(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(vals[0] * math::cos(vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_acos(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].acos()) }
-	else { Err(Error::BadArgument) }
+/// Returns the y coordinate of the polar point `(r, theta)`.
+pub fn builtin_cart_y(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 2 { Ok(vals[0] * math::sin(vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_atan(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].atan()) }
-	else { Err(Error::BadArgument) }
+/// Returns the signed angle from vector `(x1, y1)` to vector `(x2, y2)` in (-π, π].
+///
+/// Counterclockwise is positive, opposite vectors give π and a zero vector is a bad argument.
+pub fn builtin_angle_between(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	let [x1, y1, x2, y2] = *vals else {
+		return Err(ErrorKind::BadArgument);
+	};
+	if x1 == 0.0 && y1 == 0.0 || x2 == 0.0 && y2 == 0.0 {
+		return Err(ErrorKind::BadArgument);
+	}
+	Ok(polar_angle(x1 * x2 + y1 * y2, x1 * y2 - y1 * x2))
+}
+/// Returns the Euclidean length of the arguments.
+///
+/// The components are scaled by the largest magnitude so the squares do not overflow or underflow.
+/// Any infinite component makes the length infinite, even when another is NaN.
+pub fn builtin_norm(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	scaled_norm(vals, 1.0)
+}
+// Square root of the sum of squares divided by `div`, scaled by the largest magnitude.
+fn scaled_norm(vals: &[Value], div: Value) -> Result<Value, ErrorKind> {
+	if vals.is_empty() {
+		return Err(ErrorKind::BadArgument);
+	}
+	if vals.iter().any(|val| val.is_infinite()) {
+		return Ok(Value::INFINITY);
+	}
+	if vals.iter().any(|val| val.is_nan()) {
+		return Ok(Value::NAN);
+	}
+	let scale = vals.iter().fold(0.0, |acc: Value, val| acc.max(val.abs()));
+	if scale == 0.0 {
+		return Ok(0.0);
+	}
+	let sum = vals.iter().fold(0.0, |acc, val| {
+		let x = val / scale;
+		acc + x * x
+	});
+	Ok((sum / div).sqrt() * scale)
 }
-pub fn builtin_atan2(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 2 { Ok(vals[0].atan2(vals[1])) }
-	else { Err(Error::BadArgument) }
+/// Returns the distance between the points `(x1, y1)` and `(x2, y2)`.
+pub fn builtin_dist(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	match *vals {
+		[x1, y1, x2, y2] => Ok(math::hypot(x2 - x1, y2 - y1)),
+		_ => Err(ErrorKind::BadArgument),
+	}
 }
-pub fn builtin_sinh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].sinh()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_sinh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::sinh(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_cosh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].cosh()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_cosh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::cosh(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_tanh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].tanh()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_tanh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::tanh(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_asinh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].asinh()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_asinh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::asinh(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_acosh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].acosh()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_acosh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::acosh(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
-pub fn builtin_atanh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, Error> {
-	if vals.len() == 1 { Ok(vals[0].atanh()) }
-	else { Err(Error::BadArgument) }
+pub fn builtin_atanh(_: &dyn Env, vals: &mut [Value]) -> Result<Value, ErrorKind> {
+	if vals.len() == 1 { Ok(math::atanh(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
 }
 
 #[test]
@@ -231,6 +1587,979 @@ fn stats() {
 	assert_eq!(builtin_median(&env, &mut [2.0, 1.0, 4.0]), Ok(2.0));
 	assert_eq!(builtin_median(&env, &mut [8.0, 4.0]), Ok(6.0));
 	assert_eq!(builtin_range(&env, &mut [1.0, 7.0, 4.5]), Ok(6.0));
+	assert_eq!(builtin_range(&env, &mut [3.0, 1.0, 2.0]), Ok(2.0));
+	assert_eq!(builtin_range(&env, &mut [1.0, 3.0, 2.0, -4.0]), Ok(7.0));
+	assert_eq!(builtin_range(&env, &mut [5.0]), Ok(0.0));
+	assert_eq!(builtin_range(&env, &mut [f64::NAN, 2.0, f64::NAN, -1.0]), Ok(3.0));
+	assert!(builtin_range(&env, &mut [f64::NAN, f64::NAN]).unwrap().is_nan());
+	assert_eq!(builtin_range(&env, &mut []), Err(ErrorKind::BadArgument));
 	assert_eq!(builtin_var(&env, &mut [3.0, 4.0, 7.0, 10.0]), Ok(7.5));
 	assert_eq!(builtin_stdev(&env, &mut [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), Ok(2.0));
+	assert_eq!(builtin_svar(&env, &mut [3.0, 4.0, 7.0, 10.0]), Ok(10.0));
+	let close = |result: Result<Value, ErrorKind>, expected: Value| (result.unwrap() - expected).abs() < 1e-15;
+	assert!(close(builtin_svar(&env, &mut [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), 4.571428571428571));
+	assert!(close(builtin_sstdev(&env, &mut [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), 2.138089935299395));
+	assert!(close(builtin_sem(&env, &mut [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), 0.7559289460184544));
+	assert_eq!(builtin_svar(&env, &mut [3.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_sstdev(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_sem(&env, &mut [1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(crate::eval(&env, "svar(1)").unwrap_err().to_string(), "function expects at least 2 arguments, got 1 at position 0");
+}
+#[test]
+fn shape() {
+	let env = crate::BasicEnv::default();
+	let close = |result: Result<Value, ErrorKind>, expected: Value, eps: Value| {
+		let val = result.unwrap();
+		assert!((val - expected).abs() <= eps, "{} != {}", val, expected);
+	};
+	close(builtin_skew(&env, &mut [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), 0.65625, 1e-14);
+	close(builtin_kurt(&env, &mut [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), -0.21875, 1e-14);
+	close(builtin_skew(&env, &mut [1.0, 2.0, 3.0, 4.0, 10.0]), 1.1384199576606167, 1e-14);
+	close(builtin_kurt(&env, &mut [1.0, 2.0, 3.0, 4.0, 10.0]), -0.212, 1e-14);
+	close(builtin_skew(&env, &mut [1.0, 2.0, 3.0]), 0.0, 1e-15);
+	close(builtin_kurt(&env, &mut [-1.0, 1.0, -1.0, 1.0]), -2.0, 1e-15);
+	// Raw power sums lose all precision here
+	close(builtin_skew(&env, &mut [1e9 + 1.0, 1e9 + 2.0, 1e9 + 3.0, 1e9 + 4.0, 1e9 + 10.0]), 1.1384199576606167, 1e-9);
+	close(builtin_kurt(&env, &mut [1e9 + 1.0, 1e9 + 2.0, 1e9 + 3.0, 1e9 + 4.0, 1e9 + 10.0]), -0.212, 1e-9);
+	assert_eq!(builtin_skew(&env, &mut [4.0, 4.0, 4.0]), Ok(0.0));
+	assert_eq!(builtin_kurt(&env, &mut [4.0, 4.0, 4.0, 4.0]), Ok(0.0));
+	assert_eq!(builtin_skew(&env, &mut [1.0, 2.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_kurt(&env, &mut [1.0, 2.0, 3.0]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn means() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_geomean(&env, &mut [2.0, 8.0]), Ok(4.0));
+	assert_eq!(builtin_geomean(&env, &mut [3.0, 0.0, 5.0]), Ok(0.0));
+	assert_eq!(builtin_geomean(&env, &mut [3.0, -1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_geomean(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_harmmean(&env, &mut [1.0, 4.0, 4.0]), Ok(2.0));
+	assert_eq!(builtin_harmmean(&env, &mut [1.0, 0.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_harmmean(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_rms(&env, &mut [1.0, 7.0]), Ok(5.0));
+	assert_eq!(builtin_rms(&env, &mut [-3.0]), Ok(3.0));
+	assert_eq!(builtin_rms(&env, &mut [f64::MAX, f64::MAX]), Ok(f64::MAX));
+	assert_eq!(builtin_rms(&env, &mut []), Err(ErrorKind::BadArgument));
+
+	let data = [1.5, 2.0, 7.25, 3.0, 11.0, 0.5];
+	let get = |f: fn(&dyn Env, &mut [Value]) -> Result<Value, ErrorKind>| f(&env, &mut data.clone()).unwrap();
+	let (harm, geo, mean, rms) = (get(builtin_harmmean), get(builtin_geomean), get(builtin_mean), get(builtin_rms));
+	assert!(harm <= geo && geo <= mean && mean <= rms, "{} {} {} {}", harm, geo, mean, rms);
+}
+#[test]
+fn args() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_argmin(&env, &mut [3.0, 1.0, 2.0]), Ok(1.0));
+	assert_eq!(builtin_argmax(&env, &mut [3.0, 1.0, 2.0]), Ok(0.0));
+	assert_eq!(builtin_argmin(&env, &mut [2.0, 1.0, 5.0, 1.0]), Ok(1.0));
+	assert_eq!(builtin_argmax(&env, &mut [2.0, 5.0, 1.0, 5.0]), Ok(1.0));
+	assert_eq!(builtin_argmin(&env, &mut [f64::NAN, 4.0, f64::NAN, 3.0]), Ok(3.0));
+	assert_eq!(builtin_argmax(&env, &mut [f64::NAN, 4.0, f64::NAN, 3.0]), Ok(1.0));
+	assert!(builtin_argmin(&env, &mut [f64::NAN, f64::NAN]).unwrap().is_nan());
+	assert!(builtin_argmax(&env, &mut [f64::NAN]).unwrap().is_nan());
+	assert_eq!(builtin_argmin(&env, &mut [7.0]), Ok(0.0));
+	assert_eq!(builtin_argmax(&env, &mut [7.0]), Ok(0.0));
+	assert_eq!(builtin_argmax(&env, &mut [f64::NEG_INFINITY, f64::NEG_INFINITY]), Ok(0.0));
+	assert_eq!(builtin_argmin(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_argmax(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_count(&env, &mut [7.0]), Ok(1.0));
+	assert_eq!(builtin_count(&env, &mut [f64::NAN, 1.0, 2.0]), Ok(3.0));
+	assert_eq!(builtin_count(&env, &mut []), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn pairs() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_sumsq(&env, &mut [1.0, 2.0, 3.0]), Ok(14.0));
+	assert_eq!(builtin_sumsq(&env, &mut [-3.0]), Ok(9.0));
+	assert_eq!(builtin_sumsq(&env, &mut [0.0, 0.0]), Ok(0.0));
+	assert!((builtin_sumsq(&env, &mut [3e150, 4e150]).unwrap() - 2.5e301).abs() <= 2.5e301 * 1e-15);
+	assert_eq!(builtin_sumsq(&env, &mut [1e200, 1.0]), Ok(f64::INFINITY));
+	assert_eq!(builtin_sumsq(&env, &mut [f64::NEG_INFINITY, 1.0]), Ok(f64::INFINITY));
+	assert!(builtin_sumsq(&env, &mut [f64::NAN, 1.0]).unwrap().is_nan());
+	assert_eq!(builtin_sumsq(&env, &mut []), Err(ErrorKind::BadArgument));
+
+	// Interleaved as x1, y1, x2, y2 and not all x then all y
+	assert_eq!(builtin_dot(&env, &mut [1.0, 2.0, 3.0, 4.0]), Ok(14.0));
+	assert_eq!(builtin_dot(&env, &mut [2.0, 5.0]), Ok(10.0));
+	assert_eq!(builtin_dot(&env, &mut [1e16, 1.0, 1.0, 1.0, -1e16, 1.0]), Ok(1.0));
+	assert_eq!(builtin_dot(&env, &mut [1.0, 2.0, 3.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_dot(&env, &mut []), Err(ErrorKind::BadArgument));
+
+	// Interleaved as w1, v1, w2, v2
+	assert_eq!(builtin_wmean(&env, &mut [1.0, 10.0, 3.0, 20.0]), Ok(17.5));
+	assert_eq!(builtin_wmean(&env, &mut [2.0, 4.0]), Ok(4.0));
+	assert_eq!(builtin_wmean(&env, &mut [0.0, 1.0, 1.0, 5.0]), Ok(5.0));
+	assert_eq!(builtin_wmean(&env, &mut [1.0, 1.0, -1.0, 5.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_wmean(&env, &mut [0.0, 1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_wmean(&env, &mut [1.0, 2.0, 3.0]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn order() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_quantile(&env, &mut [0.25, 5.0, 1.0, 4.0, 2.0, 3.0]), Ok(2.0));
+	assert_eq!(builtin_quantile(&env, &mut [0.25, 4.0, 3.0, 2.0, 1.0]), Ok(1.75));
+	assert_eq!(builtin_quantile(&env, &mut [0.5, 4.0, 3.0, 2.0, 1.0]), Ok(2.5));
+	assert_eq!(builtin_quantile(&env, &mut [0.0, 7.0, -2.0, 3.0]), Ok(-2.0));
+	assert_eq!(builtin_quantile(&env, &mut [1.0, 7.0, -2.0, 3.0]), Ok(7.0));
+	assert_eq!(builtin_quantile(&env, &mut [0.9, 6.0]), Ok(6.0));
+	assert_eq!(builtin_quantile(&env, &mut [0.5]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_quantile(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_quantile(&env, &mut [1.5, 1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_quantile(&env, &mut [f64::NAN, 1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_quantile(&env, &mut [0.5, 1.0, f64::NAN]), Err(ErrorKind::BadArgument));
+
+	assert_eq!(builtin_mode(&env, &mut [3.0, 1.0, 3.0, 2.0, 1.0, 3.0]), Ok(3.0));
+	assert_eq!(builtin_mode(&env, &mut [4.0, 2.0, 4.0, 2.0, 5.0]), Ok(2.0));
+	assert_eq!(builtin_mode(&env, &mut [9.0, 8.0, 7.0]), Ok(7.0));
+	assert_eq!(builtin_mode(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_mode(&env, &mut [1.0, f64::NAN]), Err(ErrorKind::BadArgument));
+
+	assert_eq!(builtin_kth(&env, &mut [1.0, 5.0, 1.0, 4.0]), Ok(1.0));
+	assert_eq!(builtin_kth(&env, &mut [2.0, 5.0, 1.0, 4.0]), Ok(4.0));
+	assert_eq!(builtin_kth(&env, &mut [3.0, 5.0, 1.0, 4.0]), Ok(5.0));
+	assert_eq!(builtin_kth(&env, &mut [0.0, 5.0, 1.0, 4.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_kth(&env, &mut [4.0, 5.0, 1.0, 4.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_kth(&env, &mut [1.5, 5.0, 1.0, 4.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_kth(&env, &mut [1.0, f64::NAN]), Err(ErrorKind::BadArgument));
+	assert_eq!(crate::eval(&env, "kth(2, 8, 6, 7) + quantile(0.5, 1, 3) + mode(1, 1, 2)"), Ok(10.0));
+}
+#[test]
+fn rounding() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_round(&env, &mut [2.5]), Ok(3.0));
+	assert_eq!(builtin_round(&env, &mut [-2.5]), Ok(-3.0));
+	assert_eq!(builtin_floor(&env, &mut [-1.5]), Ok(-2.0));
+	assert_eq!(builtin_ceil(&env, &mut [1.25]), Ok(2.0));
+	assert_eq!(builtin_round(&env, &mut [1.23456, 2.0]), Ok(1.23));
+	assert_eq!(builtin_floor(&env, &mut [1.23456, 3.0]), Ok(1.234));
+	assert_eq!(builtin_ceil(&env, &mut [1.23456, 1.0]), Ok(1.3));
+	assert_eq!(builtin_round(&env, &mut [1234.0, -2.0]), Ok(1200.0));
+	assert_eq!(builtin_floor(&env, &mut [-1234.0, -2.0]), Ok(-1300.0));
+	assert_eq!(builtin_ceil(&env, &mut [1234.0, -3.0]), Ok(2000.0));
+	assert_eq!(builtin_round(&env, &mut [7.5, 0.0]), Ok(8.0));
+	// 1.005 is slightly below in binary
+	assert_eq!(builtin_round(&env, &mut [1.005, 2.0]), Ok(1.0));
+	assert_eq!(builtin_round(&env, &mut [1e300, 20.0]), Ok(1e300));
+	assert_eq!(builtin_round(&env, &mut [1e300, -308.0]), Ok(0.0));
+	assert_eq!(builtin_round(&env, &mut [1.0, 1.5]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_round(&env, &mut [1.0, 400.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_round(&env, &mut [1.0, f64::NAN]), Err(ErrorKind::BadArgument));
+
+	assert_eq!(builtin_tofixed(&env, &mut [1.23456, 2.0]), Ok(1.23));
+	assert_eq!(builtin_tofixed(&env, &mut [-2.5, 0.0]), Ok(-2.0));
+	assert_eq!(builtin_tofixed(&env, &mut [3.5, 0.0]), Ok(4.0));
+	assert_eq!(builtin_tofixed(&env, &mut [0.125, 2.0]), Ok(0.12));
+	assert_eq!(builtin_tofixed(&env, &mut [0.0055, 3.0]), Ok(0.005));
+	assert_eq!(builtin_round(&env, &mut [0.0055, 3.0]), Ok(0.006));
+	assert_eq!(builtin_tofixed(&env, &mut [0.1 + 0.2, 17.0]), Ok(0.1 + 0.2));
+	assert_eq!(builtin_tofixed(&env, &mut [5e-324, 308.0]), Ok(0.0));
+	assert_eq!(builtin_tofixed(&env, &mut [f64::INFINITY, 2.0]), Ok(f64::INFINITY));
+	assert!(builtin_tofixed(&env, &mut [f64::NAN, 2.0]).unwrap().is_nan());
+	assert_eq!(builtin_tofixed(&env, &mut [1.0, -1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_tofixed(&env, &mut [1.0, 1.5]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_tofixed(&env, &mut [1.0, 309.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_tofixed(&env, &mut [1.0]), Err(ErrorKind::BadArgument));
+
+	assert_eq!(builtin_quantize(&env, &mut [0.8, 0.25]), Ok(0.75));
+	assert_eq!(builtin_quantize(&env, &mut [-0.9, 0.25]), Ok(-1.0));
+	assert_eq!(builtin_quantize(&env, &mut [17.0, 5.0]), Ok(15.0));
+	assert_eq!(builtin_quantize(&env, &mut [1.0, 0.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_quantize(&env, &mut [1.0, -1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(crate::eval(&env, "round(x = 1.55, digits = 1) + floor(2.9)"), Ok(3.6));
+
+	let table = [
+		(0.00123456, 3.0, 0.00123),
+		(98765.0, 2.0, 99000.0),
+		(-98765.0, 2.0, -99000.0),
+		(123.456, 1.0, 100.0),
+		(999.96, 4.0, 1000.0),
+		(999.94, 4.0, 999.9),
+		(1000.0, 1.0, 1000.0),
+		(0.001, 2.0, 0.001),
+		(0.000999, 2.0, 0.001),
+		(1e-5, 1.0, 1e-5),
+		(1.234e308, 2.0, 1.2e308),
+		(1.7976931348623157e308, 3.0, f64::INFINITY),
+		(0.1 + 0.2, 17.0, 0.1 + 0.2),
+		(1.23456e-310, 3.0, 1.23e-310),
+		(5e-324, 1.0, 5e-324),
+	];
+	for &(x, n, expected) in &table {
+		assert_eq!(builtin_roundsig(&env, &mut [x, n]), Ok(expected), "roundsig({}, {})", x, n);
+	}
+	assert_eq!(builtin_roundsig(&env, &mut [0.0, 3.0]), Ok(0.0));
+	assert_eq!(builtin_roundsig(&env, &mut [f64::NEG_INFINITY, 3.0]), Ok(f64::NEG_INFINITY));
+	assert!(builtin_roundsig(&env, &mut [f64::NAN, 3.0]).unwrap().is_nan());
+	assert_eq!(builtin_roundsig(&env, &mut [1.0, 0.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_roundsig(&env, &mut [1.0, 2.5]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_roundsig(&env, &mut [1.0]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn step() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_step(&env, &mut [1.0, 0.5]), Ok(0.0));
+	assert_eq!(builtin_step(&env, &mut [1.0, 1.0]), Ok(1.0));
+	assert_eq!(builtin_step(&env, &mut [1.0, 2.0]), Ok(1.0));
+	assert_eq!(builtin_step(&env, &mut [-0.5]), Ok(0.0));
+	assert_eq!(builtin_step(&env, &mut [0.0]), Ok(1.0));
+	assert_eq!(builtin_step(&env, &mut [-0.0]), Ok(1.0));
+	assert_eq!(builtin_step(&env, &mut [3.0]), builtin_step(&env, &mut [0.0, 3.0]));
+	assert!(builtin_step(&env, &mut [f64::NAN]).unwrap().is_nan());
+	assert!(builtin_step(&env, &mut [f64::NAN, 1.0]).unwrap().is_nan());
+	assert!(builtin_step(&env, &mut [1.0, f64::NAN]).unwrap().is_nan());
+	assert_eq!(builtin_step(&env, &mut [f64::NEG_INFINITY, f64::NEG_INFINITY]), Ok(1.0));
+	assert_eq!(builtin_step(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(crate::eval(&env, "step(2, 2) + step(edge = 3, x = 2) + step(-1)"), Ok(1.0));
+}
+#[test]
+fn smoothstep() {
+	let env = crate::BasicEnv::default();
+	for pfn in [builtin_smoothstep, builtin_smootherstep] {
+		assert_eq!(pfn(&env, &mut [-1.0]), Ok(0.0));
+		assert_eq!(pfn(&env, &mut [0.0]), Ok(0.0));
+		assert_eq!(pfn(&env, &mut [0.5]), Ok(0.5));
+		assert_eq!(pfn(&env, &mut [1.0]), Ok(1.0));
+		assert_eq!(pfn(&env, &mut [2.0]), Ok(1.0));
+		// Edges and midpoint of the three argument form
+		assert_eq!(pfn(&env, &mut [2.0, 4.0, 2.0]), Ok(0.0));
+		assert_eq!(pfn(&env, &mut [2.0, 4.0, 3.0]), Ok(0.5));
+		assert_eq!(pfn(&env, &mut [2.0, 4.0, 4.0]), Ok(1.0));
+		assert_eq!(pfn(&env, &mut [2.0, 4.0, 5.0]), Ok(1.0));
+		assert_eq!(pfn(&env, &mut [0.0, 1.0, 0.25]), pfn(&env, &mut [0.25]));
+		// Equal edges step at the edge
+		assert_eq!(pfn(&env, &mut [1.0, 1.0, 0.5]), Ok(0.0));
+		assert_eq!(pfn(&env, &mut [1.0, 1.0, 1.0]), Ok(1.0));
+		assert!(pfn(&env, &mut [1.0, 1.0, f64::NAN]).unwrap().is_nan());
+		assert!(pfn(&env, &mut [f64::NAN]).unwrap().is_nan());
+		assert_eq!(pfn(&env, &mut [4.0, 2.0, 3.0]), Err(ErrorKind::BadArgument));
+		assert_eq!(pfn(&env, &mut [0.0, 1.0]), Err(ErrorKind::ArgumentCount { expected: Arity::exactly(3), got: 2 }));
+	}
+	// Keywords name the parameters of the three argument form
+	assert_eq!(crate::eval(&env, "smoothstep(x = 3, edge0 = 2, edge1 = 4)"), Ok(0.5));
+	assert_eq!(crate::eval(&env, "smoothstep(2, 4, x = 3)"), Ok(0.5));
+	assert_eq!(crate::eval(&env, "smoothstep(0, 1)").map_err(|e| e.kind), Err(ErrorKind::ArgumentCount { expected: Arity::exactly(3), got: 2 }));
+	assert_eq!(builtin_smoothstep(&env, &mut [0.0, 4.0, 1.0]), Ok(0.15625));
+	assert_eq!(builtin_smootherstep(&env, &mut [0.0, 4.0, 1.0]), Ok(0.103515625));
+}
+#[test]
+fn gammas() {
+	let env = crate::BasicEnv::default();
+	let close = |result: Result<Value, ErrorKind>, expected: Value| {
+		let val = result.unwrap();
+		assert!((val - expected).abs() <= expected.abs() * 1e-13, "{} != {}", val, expected);
+	};
+	close(builtin_gamma(&env, &mut [0.5]), consts::PI.sqrt());
+	close(builtin_gamma(&env, &mut [1.5]), consts::PI.sqrt() / 2.0);
+	close(builtin_gamma(&env, &mut [-0.5]), -2.0 * consts::PI.sqrt());
+	close(builtin_gamma(&env, &mut [-2.5]), -8.0 / 15.0 * consts::PI.sqrt());
+	close(builtin_gamma(&env, &mut [4.2]), 7.756_689_535_793_181);
+	close(builtin_gamma(&env, &mut [170.5]), 5.562_092_414_559_434e305);
+	assert_eq!(builtin_gamma(&env, &mut [11.0]), Ok(3628800.0));
+	assert_eq!(builtin_gamma(&env, &mut [1.0]), Ok(1.0));
+	assert!(builtin_gamma(&env, &mut [0.0]).unwrap().is_nan());
+	assert!(builtin_gamma(&env, &mut [-3.0]).unwrap().is_nan());
+	assert_eq!(builtin_gamma(&env, &mut [172.0]), Ok(f64::INFINITY));
+	assert_eq!(builtin_gamma(&env, &mut [f64::INFINITY]), Ok(f64::INFINITY));
+
+	close(builtin_lgamma(&env, &mut [100.0]), 359.134_205_369_575_4);
+	close(builtin_lgamma(&env, &mut [0.5]), consts::PI.sqrt().ln());
+	close(builtin_lgamma(&env, &mut [-0.5]), (2.0 * consts::PI.sqrt()).ln());
+	close(builtin_lgamma(&env, &mut [1000.5]), 5_908.674_175_848_678);
+	assert_eq!(builtin_lgamma(&env, &mut [1.0]), Ok(0.0));
+	assert_eq!(builtin_lgamma(&env, &mut [2.0]), Ok(0.0));
+	assert_eq!(builtin_lgamma(&env, &mut [-1.0]), Ok(f64::INFINITY));
+	// Next to the poles
+	close(builtin_gamma(&env, &mut [-3.0000001]), 1_666_666.460_041_391_7);
+	close(builtin_gamma(&env, &mut [-0.999999]), -1_000_000.422_756_991_2);
+	close(builtin_gamma(&env, &mut [-10.000001]), -0.275_572_544_367_331_9);
+	close(builtin_gamma(&env, &mut [-20.0000001]), -4.110_316_333_747_547e-12);
+	close(builtin_lgamma(&env, &mut [-10.000001]), -1.288_904_366_113_830_6);
+
+	assert_eq!(builtin_fac(&env, &mut [0.0]), Ok(1.0));
+	assert_eq!(builtin_fac(&env, &mut [10.0]), Ok(3628800.0));
+	assert_eq!(builtin_fac(&env, &mut [20.0]), Ok(2432902008176640000.0));
+	assert!(builtin_fac(&env, &mut [170.0]).unwrap().is_finite());
+	assert_eq!(builtin_fac(&env, &mut [171.0]), Ok(f64::INFINITY));
+	assert_eq!(builtin_fac(&env, &mut [2.5]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_fac(&env, &mut [-1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_fac(&env, &mut [f64::NAN]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_fac(&env, &mut [f64::INFINITY]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn erfs() {
+	let env = crate::BasicEnv::default();
+	let close = |result: Result<Value, ErrorKind>, expected: Value| {
+		let val = result.unwrap();
+		assert!((val - expected).abs() <= expected.abs() * 1e-13, "{} != {}", val, expected);
+	};
+	let table = [
+		(0.1, 0.112_462_916_018_284_9, 0.887_537_083_981_715_2),
+		(0.5, 0.520_499_877_813_046_5, 0.479_500_122_186_953_5),
+		(1.0, 0.842_700_792_949_714_9, 0.157_299_207_050_285_13),
+		(1.5, 0.966_105_146_475_310_8, 0.033_894_853_524_689_274),
+		(2.0, 0.995_322_265_018_952_7, 0.004_677_734_981_047_265),
+		(2.5, 0.999_593_047_982_555, 0.000_406_952_017_444_958_9),
+		(3.0, 0.999_977_909_503_001_4, 2.209_049_699_858_543_8e-5),
+		(5.0, 0.999_999_999_998_462_6, 1.537_459_794_428_035_1e-12),
+	];
+	for &(x, erf, erfc) in &table {
+		close(builtin_erf(&env, &mut [x]), erf);
+		close(builtin_erfc(&env, &mut [x]), erfc);
+		assert_eq!(builtin_erf(&env, &mut [-x]), Ok(-builtin_erf(&env, &mut [x]).unwrap()));
+		close(builtin_erfc(&env, &mut [-x]), 2.0 - erfc);
+	}
+	assert_eq!(builtin_erf(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_erfc(&env, &mut [0.0]), Ok(1.0));
+	close(builtin_erfc(&env, &mut [10.0]), 2.088_487_583_762_545e-45);
+	close(builtin_erfc(&env, &mut [26.0]), 5.663_192_408_856_143e-296);
+	assert_eq!(builtin_erfc(&env, &mut [f64::INFINITY]), Ok(0.0));
+	assert_eq!(builtin_erf(&env, &mut [f64::NEG_INFINITY]), Ok(-1.0));
+	assert!(builtin_erf(&env, &mut [f64::NAN]).unwrap().is_nan());
+
+	assert_eq!(builtin_normcdf(&env, &mut [0.0]), Ok(0.5));
+	close(builtin_normcdf(&env, &mut [1.96]), 0.975_002_104_851_779_5);
+	close(builtin_normcdf(&env, &mut [-10.0]), 7.619_853_024_160_593e-24);
+}
+#[test]
+fn norms() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_hypot(&env, &mut [3.0, 4.0]), Ok(5.0));
+	assert_eq!(builtin_hypot(&env, &mut [3.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_norm(&env, &mut [3.0, 4.0, 12.0]), Ok(13.0));
+	assert_eq!(builtin_norm(&env, &mut [-2.0]), Ok(2.0));
+	assert_eq!(builtin_norm(&env, &mut [0.0, 0.0]), Ok(0.0));
+	assert_eq!(builtin_norm(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_norm(&env, &mut [f64::NAN, f64::NEG_INFINITY]), Ok(f64::INFINITY));
+	assert!(builtin_norm(&env, &mut [f64::NAN, 1.0]).unwrap().is_nan());
+	assert_eq!(builtin_dist(&env, &mut [1.0, 2.0, 4.0, 6.0]), Ok(5.0));
+	assert_eq!(builtin_dist(&env, &mut [1.0, 2.0, 4.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(crate::eval(&env, "norm()").unwrap_err().to_string(), "function expects at least 1 argument, got 0 at position 0");
+	assert_eq!(crate::eval(&env, "dist(1, 2, 3)").unwrap_err().to_string(), "function expects 4 arguments, got 3 at position 0");
+	assert_eq!(crate::eval(&env, "dist(x2 = 4, y2 = 6, x1 = 1, y1 = 2)"), Ok(5.0));
+
+	// Naive sum of squares overflows or underflows here
+	let big = f64::MAX / 2.0;
+	let expected = big * 2f64.sqrt();
+	assert!((builtin_hypot(&env, &mut [big, big]).unwrap() - expected).abs() <= expected * 1e-15);
+	assert!((builtin_norm(&env, &mut [big, big]).unwrap() - expected).abs() <= expected * 1e-15);
+	assert!((builtin_dist(&env, &mut [0.0, 0.0, big, -big]).unwrap() - expected).abs() <= expected * 1e-15);
+	assert_eq!(builtin_norm(&env, &mut [f64::MAX, 0.0]), Ok(f64::MAX));
+	assert_eq!(builtin_norm(&env, &mut [3e-200, 4e-200]), Ok(5e-200));
+}
+#[test]
+fn lerps() {
+	let env = crate::BasicEnv::default();
+	let lerp = |t| builtin_lerp(&env, &mut [2.0, 6.0, t]);
+	assert_eq!(lerp(0.0), Ok(2.0));
+	assert_eq!(lerp(0.5), Ok(4.0));
+	assert_eq!(lerp(1.0), Ok(6.0));
+	assert_eq!(lerp(-0.5), Ok(0.0));
+	assert_eq!(lerp(1.5), Ok(8.0));
+	// Exact at the end where the naive form rounds
+	assert_eq!(builtin_lerp(&env, &mut [1e16, 1.0, 1.0]), Ok(1.0));
+	assert_eq!(builtin_lerp(&env, &mut [2.0, 6.0]), Err(ErrorKind::BadArgument));
+
+	let lerpc = |t| builtin_lerpc(&env, &mut [2.0, 6.0, t]);
+	assert_eq!(lerpc(0.0), Ok(2.0));
+	assert_eq!(lerpc(0.5), Ok(4.0));
+	assert_eq!(lerpc(1.0), Ok(6.0));
+	assert_eq!(lerpc(-0.5), Ok(2.0));
+	assert_eq!(lerpc(1.5), Ok(6.0));
+
+	assert_eq!(builtin_mix(&env, &mut [2.0, 6.0, 0.25]), Ok(3.0));
+	assert_eq!(builtin_mix(&env, &mut [2.0, 6.0, 1.0]), Ok(6.0));
+	assert_eq!(builtin_mix(&env, &mut [2.0, 6.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(crate::eval(&env, "mix(1, 2)").unwrap_err().to_string(), "function expects 3 arguments, got 2 at position 0");
+	assert_eq!(builtin_saturate(&env, &mut [0.25]), Ok(0.25));
+	assert_eq!(builtin_saturate(&env, &mut [-3.0]), Ok(0.0));
+	assert_eq!(builtin_saturate(&env, &mut [3.0]), Ok(1.0));
+	assert_eq!(builtin_saturate(&env, &mut [f64::INFINITY]), Ok(1.0));
+	assert!(builtin_saturate(&env, &mut [f64::NAN]).unwrap().is_nan());
+	assert_eq!(builtin_saturate(&env, &mut [1.0, 2.0]), Err(ErrorKind::BadArgument));
+
+	assert_eq!(builtin_clamp(&env, &mut [3.0, 1.0, 5.0]), Ok(3.0));
+	assert_eq!(builtin_clamp(&env, &mut [-3.0, 1.0, 5.0]), Ok(1.0));
+	assert_eq!(builtin_clamp(&env, &mut [7.0, 1.0, 5.0]), Ok(5.0));
+	assert_eq!(builtin_clamp(&env, &mut [f64::NEG_INFINITY, 1.0, 5.0]), Ok(1.0));
+	assert_eq!(builtin_clamp(&env, &mut [7.0, 2.0, 2.0]), Ok(2.0));
+	assert_eq!(builtin_clamp(&env, &mut [-7.0, 2.0, 2.0]), Ok(2.0));
+	assert!(builtin_clamp(&env, &mut [f64::NAN, 1.0, 5.0]).unwrap().is_nan());
+	assert_eq!(builtin_clamp(&env, &mut [3.0, 5.0, 1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_clamp(&env, &mut [3.0, f64::NAN, 5.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_clamp(&env, &mut [3.0, 1.0, f64::NAN]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_clamp(&env, &mut [3.0, f64::NAN, f64::NAN]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_clamp(&env, &mut [3.0, 1.0]), Err(ErrorKind::BadArgument));
+
+	let invlerp = |v| builtin_invlerp(&env, &mut [2.0, 6.0, v]);
+	assert_eq!(invlerp(2.0), Ok(0.0));
+	assert_eq!(invlerp(4.0), Ok(0.5));
+	assert_eq!(invlerp(6.0), Ok(1.0));
+	assert_eq!(invlerp(0.0), Ok(-0.5));
+	assert_eq!(invlerp(8.0), Ok(1.5));
+	assert_eq!(builtin_invlerp(&env, &mut [3.0, 3.0, 3.0]), Err(ErrorKind::BadArgument));
+
+	let remap = |v| builtin_remap(&env, &mut [2.0, 6.0, 10.0, 0.0, v]);
+	assert_eq!(remap(2.0), Ok(10.0));
+	assert_eq!(remap(4.0), Ok(5.0));
+	assert_eq!(remap(6.0), Ok(0.0));
+	assert_eq!(remap(0.0), Ok(15.0));
+	assert_eq!(remap(8.0), Ok(-5.0));
+	assert_eq!(builtin_remap(&env, &mut [1.0, 1.0, 0.0, 1.0, 1.0]), Err(ErrorKind::BadArgument));
+
+	let remapc = |v| builtin_remapc(&env, &mut [2.0, 6.0, 10.0, 0.0, v]);
+	assert_eq!(remapc(2.0), Ok(10.0));
+	assert_eq!(remapc(4.0), Ok(5.0));
+	assert_eq!(remapc(6.0), Ok(0.0));
+	assert_eq!(remapc(0.0), Ok(10.0));
+	assert_eq!(remapc(8.0), Ok(0.0));
+	assert_eq!(builtin_remapc(&env, &mut [1.0, 1.0, 0.0, 1.0, 1.0]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn integers() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_gcd(&env, &mut [1071.0, 462.0]), Ok(21.0));
+	assert_eq!(builtin_gcd(&env, &mut [12.0, 18.0, 8.0]), Ok(2.0));
+	assert_eq!(builtin_gcd(&env, &mut [0.0, 5.0]), Ok(5.0));
+	assert_eq!(builtin_gcd(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_lcm(&env, &mut [4.0, 6.0]), Ok(12.0));
+	assert_eq!(builtin_lcm(&env, &mut [2.0, 3.0, 4.0, 5.0]), Ok(60.0));
+	assert_eq!(builtin_lcm(&env, &mut [0.0, 5.0]), Ok(0.0));
+	assert_eq!(builtin_lcm(&env, &mut [7.0]), Ok(7.0));
+	// Divides before multiplying, the overflowing result is infinite
+	assert_eq!(builtin_lcm(&env, &mut [9007199254740992.0, 4.0]), Ok(9007199254740992.0));
+	assert_eq!(builtin_lcm(&env, &mut [9007199254740991.0, 9007199254740990.0]), Ok(f64::INFINITY));
+
+	assert_eq!(builtin_isprime(&env, &mut [97.0]), Ok(1.0));
+	assert_eq!(builtin_isprime(&env, &mut [2.0]), Ok(1.0));
+	assert_eq!(builtin_isprime(&env, &mut [1.0]), Ok(0.0));
+	assert_eq!(builtin_isprime(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_isprime(&env, &mut [91.0]), Ok(0.0));
+	assert_eq!(builtin_isprime(&env, &mut [3215031751.0]), Ok(0.0));
+	assert_eq!(builtin_isprime(&env, &mut [9007199254740881.0]), Ok(1.0));
+	// 2^61 - 1 is prime but beyond 2^53 so not exactly representable
+	assert_eq!(builtin_isprime(&env, &mut [2305843009213693951.0]), Err(ErrorKind::BadArgument));
+	assert!(is_prime(2305843009213693951));
+	assert!(!is_prime(2305843009213693953));
+
+	assert_eq!(builtin_fib(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_fib(&env, &mut [1.0]), Ok(1.0));
+	assert_eq!(builtin_fib(&env, &mut [2.0]), Ok(1.0));
+	assert_eq!(builtin_fib(&env, &mut [10.0]), Ok(55.0));
+	assert_eq!(builtin_fib(&env, &mut [78.0]), Ok(8944394323791464.0));
+	assert_eq!(builtin_fib(&env, &mut [79.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_tri(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_tri(&env, &mut [4.0]), Ok(10.0));
+	assert_eq!(builtin_tri(&env, &mut [100.0]), Ok(5050.0));
+	assert_eq!(builtin_sqrpyr(&env, &mut [1.0]), Ok(1.0));
+	assert_eq!(builtin_sqrpyr(&env, &mut [4.0]), Ok(30.0));
+	assert_eq!(builtin_sqrpyr(&env, &mut [9007199254740992.0]).map(Value::is_finite), Ok(true));
+
+	for bad in [-1.0, 2.5, f64::NAN, f64::INFINITY, 9007199254740994.0] {
+		assert_eq!(builtin_fib(&env, &mut [bad]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_tri(&env, &mut [bad]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_sqrpyr(&env, &mut [bad]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_gcd(&env, &mut [bad, 2.0]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_lcm(&env, &mut [2.0, bad]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_isprime(&env, &mut [bad]), Err(ErrorKind::BadArgument));
+	}
+}
+#[test]
+fn compensated() {
+	let env = crate::BasicEnv::default();
+	// The ones are lost entirely with a naive fold
+	let mut vals = vec![1.0; 1001];
+	vals[0] = 1e16;
+	assert_eq!(builtin_add(&env, &mut vals), Ok(1e16 + 1000.0));
+	assert_eq!(builtin_add(&env, &mut [1.0, 1e100, 1.0, -1e100]), Ok(2.0));
+	assert_eq!(builtin_mean(&env, &mut [1e16, 1.0, 1.0, -1e16]), Ok(0.5));
+	assert_eq!(builtin_add(&env, &mut [f64::INFINITY, 1.0]), Ok(f64::INFINITY));
+	assert!(builtin_add(&env, &mut [f64::INFINITY, f64::NEG_INFINITY]).unwrap().is_nan());
+
+	// Clustered around 1e9, the naive sum of squares formula is far off
+	let mut vals = [1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0];
+	assert_eq!(builtin_var(&env, &mut vals), Ok(22.5));
+	let mut vals = [1e9 + 1.0, 1e9 + 2.0, 1e9 + 3.0];
+	let var = builtin_var(&env, &mut vals).unwrap();
+	assert!((var - 2.0 / 3.0).abs() < 1e-9, "{}", var);
+	assert_eq!(builtin_stdev(&env, &mut [1e12 + 2.0, 1e12 + 4.0]), Ok(1.0));
+	assert_eq!(builtin_var(&env, &mut [5.0]), Ok(0.0));
+	assert_eq!(builtin_var(&env, &mut []), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn signs() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_copysign(&env, &mut [3.0, -0.0]), Ok(-3.0));
+	assert_eq!(builtin_copysign(&env, &mut [-3.0, 1.0]), Ok(3.0));
+	assert_eq!(builtin_copysign(&env, &mut [f64::INFINITY, -1.0]), Ok(f64::NEG_INFINITY));
+	assert_eq!(builtin_signbit(&env, &mut [-0.0]), Ok(1.0));
+	assert_eq!(builtin_signbit(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_signbit(&env, &mut [-2.0]), Ok(1.0));
+	assert_eq!(builtin_signbit(&env, &mut [f64::NEG_INFINITY]), Ok(1.0));
+	assert_eq!(builtin_signbit(&env, &mut [f64::INFINITY]), Ok(0.0));
+	assert_eq!(builtin_signbit(&env, &mut [-f64::NAN]), Ok(1.0));
+	assert_eq!(builtin_signbit(&env, &mut [f64::NAN]), Ok(0.0));
+
+	let next = |x, toward| builtin_nextafter(&env, &mut [x, toward]).unwrap();
+	assert_eq!(next(1.0, 2.0) - 1.0, f64::EPSILON);
+	assert_eq!(1.0 - next(1.0, 0.0), f64::EPSILON / 2.0);
+	assert_eq!(next(-1.0, -2.0), -1.0 - f64::EPSILON);
+	assert_eq!(next(0.0, 1.0), 5e-324);
+	assert_eq!(next(0.0, -1.0), -5e-324);
+	assert_eq!(next(-0.0, 1.0), 5e-324);
+	assert_eq!(next(5e-324, -1.0), 0.0);
+	assert_eq!(next(-5e-324, 1.0), -0.0);
+	assert_eq!(next(f64::MAX, f64::INFINITY), f64::INFINITY);
+	assert_eq!(next(f64::INFINITY, 0.0), f64::MAX);
+	assert_eq!(next(f64::NEG_INFINITY, 0.0), f64::MIN);
+	assert_eq!(next(2.0, 2.0), 2.0);
+	assert!(next(f64::NAN, 1.0).is_nan());
+	assert!(next(1.0, f64::NAN).is_nan());
+}
+#[test]
+fn modulos() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_mod(&env, &mut [-1.0, 3.0]), Ok(2.0));
+	assert_eq!(builtin_mod(&env, &mut [7.0, 3.0]), Ok(1.0));
+	assert_eq!(builtin_mod(&env, &mut [3.0, 3.0]), Ok(0.0));
+	assert_eq!(builtin_mod(&env, &mut [-3.0, 3.0]), Ok(0.0));
+	assert_eq!(builtin_mod(&env, &mut [5.5, 2.0]), Ok(1.5));
+	assert_eq!(builtin_mod(&env, &mut [1.0, -3.0]), Ok(-2.0));
+	assert_eq!(builtin_mod(&env, &mut [-1.0, -3.0]), Ok(-1.0));
+	assert_eq!(builtin_mod(&env, &mut [-1e-20, 3.0]), Ok(0.0));
+	assert_eq!(builtin_mod(&env, &mut [1e20, 3.0]), Ok(1.0));
+	assert_eq!(builtin_mod(&env, &mut [-1e20, 3.0]), Ok(2.0));
+	assert!(builtin_mod(&env, &mut [1.0, 0.0]).unwrap().is_nan());
+
+	assert_eq!(builtin_wrap(&env, &mut [370.0, 0.0, 360.0]), Ok(10.0));
+	assert_eq!(builtin_wrap(&env, &mut [-10.0, 0.0, 360.0]), Ok(350.0));
+	assert_eq!(builtin_wrap(&env, &mut [360.0, 0.0, 360.0]), Ok(0.0));
+	assert_eq!(builtin_wrap(&env, &mut [0.0, 0.0, 360.0]), Ok(0.0));
+	assert_eq!(builtin_wrap(&env, &mut [6.0, -2.0, 3.0]), Ok(1.0));
+	assert_eq!(builtin_wrap(&env, &mut [-7.0, -2.0, 3.0]), Ok(-2.0));
+	assert_eq!(builtin_wrap(&env, &mut [1e20, 0.0, 3.0]), Ok(1.0));
+	assert_eq!(builtin_wrap(&env, &mut [1.0, 2.0, 2.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_wrap(&env, &mut [1.0, 3.0, 2.0]), Err(ErrorKind::BadArgument));
+
+	let wrap = |x| builtin_wrap_angle(&env, &mut [x]).unwrap();
+	assert_eq!(wrap(0.5), 0.5);
+	assert_eq!(wrap(consts::PI), consts::PI);
+	assert_eq!(wrap(-consts::PI), consts::PI);
+	assert_eq!(wrap(3.0 * consts::PI), consts::PI);
+	assert_eq!(wrap(7.0), 0.7168146928204138);
+	assert_eq!(wrap(-7.0), -0.7168146928204138);
+	assert_eq!(wrap(1e10), -0.5092306823485515);
+	assert_eq!(wrap(-1e10), 0.5092306823485515);
+	assert_eq!(crate::eval(&env, "wrap_angle(2pi) + mod(-7, 4)"), Ok(1.0));
+}
+#[test]
+fn activations() {
+	let env = crate::BasicEnv::default();
+	let sinc = |x| builtin_sinc(&env, &mut [x]).unwrap();
+	assert_eq!(sinc(0.0), 1.0);
+	assert_eq!(sinc(-0.0), 1.0);
+	assert_eq!(sinc(1e-300), 1.0);
+	assert_eq!(sinc(consts::PI / 2.0), 2.0 / consts::PI);
+	assert!(sinc(consts::PI).abs() < 1e-16);
+	assert_eq!(sinc(f64::INFINITY), 0.0);
+	assert!(sinc(f64::NAN).is_nan());
+
+	let sigmoid = |x| builtin_sigmoid(&env, &mut [x]).unwrap();
+	assert_eq!(sigmoid(0.0), 0.5);
+	assert_eq!(sigmoid(40.0), 1.0);
+	assert!((sigmoid(-40.0) - (-40f64).exp()).abs() < 1e-30);
+	assert_eq!(sigmoid(1000.0), 1.0);
+	assert_eq!(sigmoid(-1000.0), 0.0);
+	assert_eq!(sigmoid(f64::INFINITY), 1.0);
+	assert_eq!(sigmoid(f64::NEG_INFINITY), 0.0);
+	assert!((sigmoid(2.0) + sigmoid(-2.0) - 1.0).abs() < 1e-15);
+
+	let softplus = |x| builtin_softplus(&env, &mut [x]).unwrap();
+	assert_eq!(softplus(0.0), consts::LN_2);
+	assert_eq!(softplus(1000.0), 1000.0);
+	assert_eq!(softplus(-1000.0), 0.0);
+	assert!((softplus(-40.0) - (-40f64).exp()).abs() < 1e-30);
+	let xs = [-1e-3, -1e-8, -1e-15, 0.0, 1e-15, 1e-8, 1e-3];
+	for pair in xs.windows(2) {
+		assert!(softplus(pair[0]) < softplus(pair[1]), "softplus({}) >= softplus({})", pair[0], pair[1]);
+	}
+}
+#[test]
+fn trig() {
+	let env = crate::BasicEnv::default();
+	let close = |val: Value, expected: Value| assert!((val - expected).abs() <= 1e-14, "{} != {}", val, expected);
+	close(builtin_cot(&env, &mut [consts::FRAC_PI_4]).unwrap(), 1.0);
+	close(builtin_sec(&env, &mut [consts::FRAC_PI_3]).unwrap(), 2.0);
+	close(builtin_csc(&env, &mut [consts::FRAC_PI_6]).unwrap(), 2.0);
+	assert_eq!(builtin_csc(&env, &mut [0.0]), Ok(f64::INFINITY));
+	assert_eq!(builtin_cot(&env, &mut []), Err(ErrorKind::BadArgument));
+
+	let sind = |x| builtin_sind(&env, &mut [x]).unwrap();
+	let cosd = |x| builtin_cosd(&env, &mut [x]).unwrap();
+	let tand = |x| builtin_tand(&env, &mut [x]).unwrap();
+	for (x, sin, cos) in [(0.0, 0.0, 1.0), (90.0, 1.0, 0.0), (180.0, 0.0, -1.0), (270.0, -1.0, 0.0), (-90.0, -1.0, 0.0), (720.0, 0.0, 1.0)] {
+		assert_eq!(sind(x), sin, "sind({})", x);
+		assert_eq!(cosd(x), cos, "cosd({})", x);
+	}
+	close(sind(30.0), 0.5);
+	close(cosd(60.0), 0.5);
+	close(sind(45.0), consts::FRAC_1_SQRT_2);
+	close(cosd(135.0), -consts::FRAC_1_SQRT_2);
+	close(sind(-150.0), -0.5);
+	close(tand(45.0), 1.0);
+	close(tand(-45.0), -1.0);
+	assert_eq!(tand(180.0), 0.0);
+	assert_eq!(tand(90.0), f64::INFINITY);
+	// Reduced before converting to radians
+	close(sind(1e9 * 360.0 + 30.0), 0.5);
+	close(cosd(-1e12 * 360.0 + 60.0), 0.5);
+	assert!(sind(f64::INFINITY).is_nan());
+
+	close(builtin_asind(&env, &mut [0.5]).unwrap(), 30.0);
+	close(builtin_acosd(&env, &mut [-1.0]).unwrap(), 180.0);
+	close(builtin_atand(&env, &mut [1.0]).unwrap(), 45.0);
+	close(builtin_atan2d(&env, &mut [1.0, -1.0]).unwrap(), 135.0);
+	assert_eq!(builtin_atan2d(&env, &mut [1.0]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn powers() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_root(&env, &mut [-8.0, 3.0]), Ok(-2.0));
+	assert_eq!(builtin_root(&env, &mut [16.0, 4.0]), Ok(2.0));
+	assert_eq!(builtin_root(&env, &mut [9.0, 2.0]), Ok(3.0));
+	assert_eq!(builtin_root(&env, &mut [-32.0, 5.0]), Ok(-2.0));
+	assert_eq!(builtin_root(&env, &mut [-0.125, -3.0]), Ok(-2.0));
+	assert_eq!(builtin_root(&env, &mut [4.0, -2.0]), Ok(0.5));
+	assert_eq!(builtin_root(&env, &mut [8.0, 0.5]), Ok(64.0));
+	assert_eq!(builtin_root(&env, &mut [-16.0, 4.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_root(&env, &mut [-4.0, 2.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_root(&env, &mut [-8.0, 1.5]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_root(&env, &mut [8.0, 0.0]), Err(ErrorKind::BadArgument));
+
+	assert_eq!(builtin_powi(&env, &mut [2.0, 60.0]), Ok(1152921504606846976.0));
+	assert_eq!(builtin_powi(&env, &mut [10.0, 15.0]), Ok(1e15));
+	assert_eq!(builtin_powi(&env, &mut [10.0, 22.0]), Ok(1e22));
+	assert_eq!(builtin_powi(&env, &mut [10.0, -3.0]), Ok(0.001));
+	assert_eq!(builtin_powi(&env, &mut [-2.0, 3.0]), Ok(-8.0));
+	assert_eq!(builtin_powi(&env, &mut [-2.0, 4.0]), Ok(16.0));
+	assert_eq!(builtin_powi(&env, &mut [2.0, -2.0]), Ok(0.25));
+	assert_eq!(builtin_powi(&env, &mut [f64::NAN, 0.0]), Ok(1.0));
+	assert_eq!(builtin_powi(&env, &mut [1.0000001, 1e12]), Ok(f64::INFINITY));
+	// The reciprocal of an overflowed power
+	assert_eq!(builtin_powi(&env, &mut [10.0, -323.0]), Ok(math::powf(10.0, -323.0)));
+	assert!(builtin_powi(&env, &mut [10.0, -323.0]).unwrap() > 0.0);
+	assert_eq!(builtin_powi(&env, &mut [-10.0, -309.0]), Ok(math::powf(-10.0, -309.0)));
+	assert_eq!(builtin_powi(&env, &mut [0.1, -330.0]), Ok(f64::INFINITY));
+	assert_eq!(builtin_powi(&env, &mut [0.0, -1.0]), Ok(f64::INFINITY));
+	assert_eq!(builtin_powi(&env, &mut [2.0, 0.5]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_powi(&env, &mut [2.0, f64::NAN]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn decompose() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_exp10(&env, &mut [3.0]), Ok(1000.0));
+	assert_eq!(builtin_exp10(&env, &mut [22.0]), Ok(1e22));
+	assert_eq!(builtin_exp10(&env, &mut [-5.0]), Ok(1e-5));
+	assert_eq!(builtin_exp10(&env, &mut [0.0]), Ok(1.0));
+	assert!((builtin_exp10(&env, &mut [0.5]).unwrap() - 10f64.sqrt()).abs() < 1e-15);
+	assert_eq!(builtin_exp10(&env, &mut [400.0]), Ok(f64::INFINITY));
+	assert_eq!(builtin_exp10(&env, &mut [f64::NEG_INFINITY]), Ok(0.0));
+
+	let samples = [1.0, -1.0, 0.75, 3.0, -1e300, 1e-300, 123.456, f64::MAX, f64::MIN_POSITIVE, 5e-324, -2.5e-320, 1e-310];
+	for &x in &samples {
+		let m = builtin_mantissa(&env, &mut [x]).unwrap();
+		let e = builtin_exponent(&env, &mut [x]).unwrap();
+		assert!((0.5..1.0).contains(&m.abs()), "mantissa({}) = {}", x, m);
+		// Scale in two halves so neither power of two overflows
+		let half = (e / 2.0).trunc();
+		assert_eq!(m * 2f64.powf(half) * 2f64.powf(e - half), x, "mantissa({}) * 2^exponent({})", x, x);
+	}
+	assert_eq!(builtin_mantissa(&env, &mut [8.0]), Ok(0.5));
+	assert_eq!(builtin_exponent(&env, &mut [8.0]), Ok(4.0));
+	assert_eq!(builtin_exponent(&env, &mut [5e-324]), Ok(-1073.0));
+	assert_eq!(builtin_mantissa(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_exponent(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_mantissa(&env, &mut [f64::NEG_INFINITY]), Ok(f64::NEG_INFINITY));
+	assert_eq!(builtin_exponent(&env, &mut [f64::INFINITY]), Ok(0.0));
+	assert!(builtin_mantissa(&env, &mut [f64::NAN]).unwrap().is_nan());
+	assert_eq!(builtin_exponent(&env, &mut [f64::NAN]), Ok(0.0));
+
+	assert_eq!(builtin_ulp(&env, &mut [1.0]), Ok(f64::EPSILON));
+	assert_eq!(builtin_ulp(&env, &mut [-1.0]), Ok(f64::EPSILON));
+	assert_eq!(builtin_ulp(&env, &mut [0.0]), Ok(5e-324));
+	assert_eq!(builtin_ulp(&env, &mut [1e-310]), Ok(5e-324));
+	assert_eq!(builtin_ulp(&env, &mut [1e16]), Ok(2.0));
+	assert_eq!(builtin_ulp(&env, &mut [f64::MAX]), Ok(2f64.powi(971)));
+	assert_eq!(builtin_ulp(&env, &mut [f64::INFINITY]), Ok(f64::INFINITY));
+	assert!(builtin_ulp(&env, &mut [f64::NAN]).unwrap().is_nan());
+}
+#[test]
+fn compare() {
+	let env = crate::BasicEnv::default();
+	let cmp = |a, b| builtin_cmp(&env, &mut [a, b]).unwrap();
+	assert_eq!(cmp(1.0, 2.0), -1.0);
+	assert_eq!(cmp(2.0, 1.0), 1.0);
+	assert_eq!(cmp(2.0, 2.0), 0.0);
+	assert_eq!(cmp(-0.0, 0.0), 0.0);
+	assert_eq!(cmp(f64::NEG_INFINITY, f64::INFINITY), -1.0);
+	assert_eq!(cmp(f64::NAN, f64::INFINITY), 1.0);
+	assert_eq!(cmp(1.0, f64::NAN), -1.0);
+	assert_eq!(cmp(-f64::NAN, f64::NEG_INFINITY), -1.0);
+	assert_eq!(cmp(f64::NAN, f64::NAN), 0.0);
+	assert_eq!(builtin_cmp(&env, &mut [1.0]), Err(ErrorKind::BadArgument));
+
+	let between = |x, lo, hi| builtin_between(&env, &mut [x, lo, hi]).unwrap();
+	let open = |x, lo, hi| builtin_between(&env, &mut [x, lo, hi, 1.0]).unwrap();
+	// Below, at the lower bound, inside, at the upper bound and above
+	let table = [(0.0, 0.0, 0.0), (1.0, 1.0, 0.0), (2.0, 1.0, 1.0), (3.0, 1.0, 0.0), (4.0, 0.0, 0.0)];
+	for &(x, closed_expected, open_expected) in &table {
+		assert_eq!(between(x, 1.0, 3.0), closed_expected, "between({}, 1, 3)", x);
+		assert_eq!(open(x, 1.0, 3.0), open_expected, "between({}, 1, 3, 1)", x);
+	}
+	assert_eq!(builtin_between(&env, &mut [1.0, 1.0, 3.0, 0.0]), Ok(1.0));
+	assert_eq!(between(2.0, 2.0, 2.0), 1.0);
+	assert_eq!(open(2.0, 2.0, 2.0), 0.0);
+	assert_eq!(between(2.0, 3.0, 1.0), 0.0);
+	assert_eq!(between(-0.0, 0.0, 1.0), 1.0);
+	assert_eq!(between(0.0, -1.0, -0.0), 1.0);
+	assert_eq!(between(f64::NAN, 0.0, 1.0), 0.0);
+	assert_eq!(between(0.5, f64::NAN, 1.0), 0.0);
+	assert_eq!(between(0.5, 0.0, f64::NAN), 0.0);
+	assert_eq!(between(f64::INFINITY, 0.0, f64::INFINITY), 1.0);
+	assert_eq!(builtin_between(&env, &mut [1.0, 2.0]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn approx() {
+	let env = crate::BasicEnv::default();
+	let approx = |a, b| builtin_approx(&env, &mut [a, b]).unwrap();
+	// An absolute tolerance gets both of these wrong
+	let absolute = |a: Value, b: Value| (a - b).abs() <= 1e-9;
+	assert!(!absolute(1e12, 1e12 + 1.0));
+	assert_eq!(approx(1e12, 1e12 + 1.0), 1.0);
+	assert!(absolute(1e-12, 2e-12));
+	assert_eq!(approx(1e-12, 2e-12), 0.0);
+
+	assert_eq!(approx(1.0, 1.0 + 1e-10), 1.0);
+	assert_eq!(approx(1.0, 1.0 + 1e-8), 0.0);
+	assert_eq!(approx(0.1 + 0.2, 0.3), 1.0);
+	assert_eq!(approx(0.0, 1e-300), 0.0);
+	assert_eq!(approx(-0.0, 0.0), 1.0);
+	assert_eq!(approx(f64::INFINITY, f64::INFINITY), 1.0);
+	assert_eq!(approx(f64::INFINITY, f64::NEG_INFINITY), 0.0);
+	assert_eq!(approx(f64::INFINITY, f64::MAX), 0.0);
+	assert_eq!(approx(f64::NAN, f64::NAN), 0.0);
+	assert_eq!(approx(1.0, f64::NAN), 0.0);
+
+	assert_eq!(builtin_approx(&env, &mut [100.0, 101.0, 0.01]), Ok(1.0));
+	assert_eq!(builtin_approx(&env, &mut [100.0, 102.0, 0.01]), Ok(0.0));
+	assert_eq!(builtin_approx(&env, &mut [0.0, 1e-300, 1e-9, 1e-12]), Ok(1.0));
+	assert_eq!(builtin_approx(&env, &mut [1.0, 2.0, 0.0, 1.0]), Ok(1.0));
+	assert_eq!(builtin_approx(&env, &mut [1.0, 1.0, -1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_approx(&env, &mut [1.0, 1.0, 0.0, f64::NAN]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_approx(&env, &mut [1.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(crate::eval(&env, "approx(1e12, 1e12 + 1, abs = 0, rel = 1e-9)"), Ok(1.0));
+}
+#[test]
+fn finance() {
+	let env = crate::BasicEnv::default();
+	let close = |result: Result<Value, ErrorKind>, expected: Value| {
+		let val = result.unwrap();
+		assert!((val - expected).abs() <= expected.abs() * 1e-12, "{} != {}", val, expected);
+	};
+	// Reference values from a spreadsheet
+	close(builtin_fv(&env, &mut [0.06 / 12.0, 10.0, -200.0, -500.0]), 2571.175347651979);
+	close(builtin_pmt(&env, &mut [0.08 / 12.0, 10.0, 10000.0]), -1037.0320893591636);
+	close(builtin_pv(&env, &mut [0.08 / 12.0, 240.0, 500.0]), -59777.14585118777);
+	close(builtin_npv(&env, &mut [0.1, -10000.0, 3000.0, 4200.0, 6800.0]), 1188.4434123352216);
+	// Zero rate
+	assert_eq!(builtin_fv(&env, &mut [0.0, 10.0, -100.0, -1000.0]), Ok(2000.0));
+	assert_eq!(builtin_pv(&env, &mut [0.0, 10.0, -100.0, 500.0]), Ok(500.0));
+	assert_eq!(builtin_pmt(&env, &mut [0.0, 12.0, 1200.0]), Ok(-100.0));
+	assert_eq!(builtin_npv(&env, &mut [0.0, 1.0, 2.0, 3.0]), Ok(6.0));
+	// Negative rate
+	close(builtin_fv(&env, &mut [-0.02, 5.0, -100.0, -1000.0]), 1384.3168128000002);
+	close(builtin_pmt(&env, &mut [-0.02, 5.0, 1000.0]), -188.16159308032215);
+	close(builtin_pv(&env, &mut [-0.02, 5.0, 100.0]), -531.4580853772435);
+	close(builtin_npv(&env, &mut [-0.05, 100.0, 100.0]), 216.06648199445985);
+	// The functions invert each other
+	let pmt = builtin_pmt(&env, &mut [0.01, 36.0, 5000.0, -1000.0]).unwrap();
+	close(builtin_pv(&env, &mut [0.01, 36.0, pmt, -1000.0]), 5000.0);
+	close(builtin_fv(&env, &mut [0.01, 36.0, pmt, 5000.0]), -1000.0);
+
+	assert_eq!(builtin_pmt(&env, &mut [0.0, 0.0, 1000.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_npv(&env, &mut [-1.0, 100.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_npv(&env, &mut [0.1]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_fv(&env, &mut [0.1, 1.0]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn dice() {
+	let mut env = crate::VarEnv::<Value>::default();
+	env.set_seed(42);
+	let rolls = (0..10).map(|_| builtin_d(&env, &mut [6.0]).unwrap()).collect::<Vec<_>>();
+	assert_eq!(rolls, [5.0, 1.0, 2.0, 3.0, 1.0, 6.0, 2.0, 5.0, 3.0, 4.0]);
+	let mut other = crate::VarEnv::<Value>::default();
+	other.set_seed(42);
+	assert_eq!(builtin_roll(&other, &mut [10.0, 6.0]), Ok(32.0));
+
+	// The mean of many rolls is close to the expected value
+	let total = builtin_roll(&env, &mut [10000.0, 6.0]).unwrap();
+	assert!((total / 10000.0 - 3.5).abs() < 0.05, "{}", total / 10000.0);
+	let hits = (0..10000).map(|_| builtin_chance(&env, &mut [0.25]).unwrap()).sum::<Value>();
+	assert!((hits / 10000.0 - 0.25).abs() < 0.02, "{}", hits / 10000.0);
+	assert_eq!(builtin_chance(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_chance(&env, &mut [1.0]), Ok(1.0));
+	assert_eq!(builtin_d(&env, &mut [1.0]), Ok(1.0));
+	assert_eq!(builtin_roll(&env, &mut [0.0, 6.0]), Ok(0.0));
+
+	assert_eq!(builtin_d(&env, &mut [0.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_d(&env, &mut [2.5]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_roll(&env, &mut [-1.0, 6.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_roll(&env, &mut [1e9, 6.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_chance(&env, &mut [1.5]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_chance(&env, &mut [f64::NAN]), Err(ErrorKind::BadArgument));
+
+	// Environments without a random number generator
+	struct NoRng;
+	impl Env for NoRng {
+		fn builtin(&self, _: &str) -> Result<crate::BuiltinFn, ErrorKind> { Err(ErrorKind::EnvErrorNotFound) }
+		fn get_value(&self, _: &str) -> Result<Value, ErrorKind> { Err(ErrorKind::EnvErrorNotFound) }
+		fn set_value(&mut self, _: &str, _: Value) -> Result<(), ErrorKind> { Err(ErrorKind::EnvErrorNotFound) }
+	}
+	assert_eq!(builtin_d(&NoRng, &mut [6.0]), Err(ErrorKind::EnvErrorNotFound));
+	assert_eq!(builtin_roll(&NoRng, &mut [2.0, 6.0]), Err(ErrorKind::EnvErrorNotFound));
+	assert_eq!(builtin_chance(&NoRng, &mut [0.5]), Err(ErrorKind::EnvErrorNotFound));
+}
+#[test]
+fn polar() {
+	use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+	let env = crate::BasicEnv::default();
+	let close = |a: Result<Value, ErrorKind>, b: Value| (a.unwrap() - b).abs() < 1e-12;
+	// Quadrants and axes
+	for (x, y, theta) in [
+		(1.0, 0.0, 0.0), (1.0, 1.0, FRAC_PI_4), (0.0, 1.0, FRAC_PI_2), (-1.0, 1.0, 3.0 * FRAC_PI_4),
+		(-1.0, 0.0, PI), (-1.0, -1.0, -3.0 * FRAC_PI_4), (0.0, -1.0, -FRAC_PI_2), (1.0, -1.0, -FRAC_PI_4),
+	] {
+		let r = Value::hypot(x, y);
+		assert_eq!(builtin_polar_r(&env, &mut [x, y]), Ok(r));
+		assert_eq!(builtin_polar_theta(&env, &mut [x, y]), Ok(theta));
+		assert!(close(builtin_cart_x(&env, &mut [r, theta]), x));
+		assert!(close(builtin_cart_y(&env, &mut [r, theta]), y));
+	}
+	// Branch cut and origin
+	assert_eq!(builtin_polar_theta(&env, &mut [-1.0, -0.0]), Ok(PI));
+	assert_eq!(builtin_polar_theta(&env, &mut [0.0, 0.0]), Ok(0.0));
+	assert_eq!(builtin_polar_theta(&env, &mut [-0.0, -0.0]), Ok(0.0));
+	assert_eq!(builtin_polar_r(&env, &mut [0.0, 0.0]), Ok(0.0));
+	assert_eq!(builtin_cart_x(&env, &mut [0.0, 2.0]), Ok(0.0));
+	assert_eq!(builtin_cart_y(&env, &mut [0.0, 2.0]), Ok(0.0));
+	assert_eq!(builtin_polar_r(&env, &mut [3.0]), Err(ErrorKind::BadArgument));
+
+	// Angle between vectors
+	assert_eq!(builtin_angle_between(&env, &mut [1.0, 0.0, 0.0, 1.0]), Ok(FRAC_PI_2));
+	assert_eq!(builtin_angle_between(&env, &mut [0.0, 1.0, 1.0, 0.0]), Ok(-FRAC_PI_2));
+	assert_eq!(builtin_angle_between(&env, &mut [1.0, 0.0, -1.0, 0.0]), Ok(PI));
+	assert_eq!(builtin_angle_between(&env, &mut [-1.0, 0.0, 1.0, 0.0]), Ok(PI));
+	assert_eq!(builtin_angle_between(&env, &mut [2.0, 2.0, 1.0, 1.0]), Ok(0.0));
+	assert!(close(builtin_angle_between(&env, &mut [-1.0, 1.0, -1.0, -1.0]), FRAC_PI_2));
+	assert!(close(builtin_angle_between(&env, &mut [-1.0, -1.0, 1.0, -1.0]), FRAC_PI_2));
+	assert!(close(builtin_angle_between(&env, &mut [1.0, -1.0, -1.0, 1.0]), PI));
+	assert!(close(builtin_angle_between(&env, &mut [1.0, 1.0, -1.0, 0.0]), 3.0 * FRAC_PI_4));
+	assert_eq!(builtin_angle_between(&env, &mut [0.0, 0.0, 1.0, 0.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_angle_between(&env, &mut [1.0, 0.0, -0.0, 0.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_angle_between(&env, &mut [1.0, 0.0, 1.0]), Err(ErrorKind::BadArgument));
+}
+#[test]
+fn bits() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_band(&env, &mut [12.0, 10.0]), Ok(8.0));
+	assert_eq!(builtin_bor(&env, &mut [12.0, 10.0]), Ok(14.0));
+	assert_eq!(builtin_bxor(&env, &mut [12.0, 10.0]), Ok(6.0));
+	assert_eq!(builtin_band(&env, &mut [9007199254740991.0, 255.0]), Ok(255.0));
+
+	assert_eq!(builtin_bnot(&env, &mut [0.0]), Ok(4294967295.0));
+	assert_eq!(builtin_bnot(&env, &mut [5.0, 4.0]), Ok(10.0));
+	assert_eq!(builtin_bnot(&env, &mut [0.0, 53.0]), Ok(9007199254740991.0));
+	assert_eq!(builtin_bnot(&env, &mut [16.0, 4.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_bnot(&env, &mut [1.0, 0.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_bnot(&env, &mut [1.0, 54.0]), Err(ErrorKind::BadArgument));
+
+	assert_eq!(builtin_shl(&env, &mut [3.0, 4.0]), Ok(48.0));
+	assert_eq!(builtin_shl(&env, &mut [1.0, 53.0]), Ok(9007199254740992.0));
+	assert_eq!(builtin_shl(&env, &mut [0.0, 100.0]), Ok(0.0));
+	assert_eq!(builtin_shl(&env, &mut [3.0, 52.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_shl(&env, &mut [1.0, 54.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_shl(&env, &mut [1.0, 64.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_shr(&env, &mut [48.0, 4.0]), Ok(3.0));
+	assert_eq!(builtin_shr(&env, &mut [7.0, 1.0]), Ok(3.0));
+	assert_eq!(builtin_shr(&env, &mut [9007199254740992.0, 53.0]), Ok(1.0));
+	assert_eq!(builtin_shr(&env, &mut [255.0, 64.0]), Ok(0.0));
+	assert_eq!(builtin_shr(&env, &mut [255.0, 1000.0]), Ok(0.0));
+
+	assert_eq!(builtin_popcount(&env, &mut [0.0]), Ok(0.0));
+	assert_eq!(builtin_popcount(&env, &mut [255.0]), Ok(8.0));
+	assert_eq!(builtin_popcount(&env, &mut [170.0]), Ok(4.0));
+	assert_eq!(builtin_popcount(&env, &mut [9007199254740991.0]), Ok(53.0));
+	assert_eq!(builtin_bit(&env, &mut [5.0, 0.0]), Ok(1.0));
+	assert_eq!(builtin_bit(&env, &mut [5.0, 1.0]), Ok(0.0));
+	assert_eq!(builtin_bit(&env, &mut [5.0, 2.0]), Ok(1.0));
+	assert_eq!(builtin_bit(&env, &mut [9007199254740992.0, 53.0]), Ok(1.0));
+	assert_eq!(builtin_bit(&env, &mut [5.0, 100.0]), Ok(0.0));
+
+	for bad in [-1.0, 2.5, f64::NAN, 9007199254740994.0] {
+		assert_eq!(builtin_band(&env, &mut [bad, 1.0]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_bor(&env, &mut [1.0, bad]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_bxor(&env, &mut [bad, 1.0]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_bnot(&env, &mut [bad]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_shl(&env, &mut [1.0, bad]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_shr(&env, &mut [bad, 1.0]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_popcount(&env, &mut [bad]), Err(ErrorKind::BadArgument));
+		assert_eq!(builtin_bit(&env, &mut [1.0, bad]), Err(ErrorKind::BadArgument));
+	}
+}
+#[test]
+fn logic() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(builtin_all(&env, &mut [1.0, -2.0, 0.5]), Ok(1.0));
+	assert_eq!(builtin_all(&env, &mut [1.0, 0.0]), Ok(0.0));
+	assert_eq!(builtin_all(&env, &mut [f64::NAN]), Ok(0.0));
+	assert_eq!(builtin_any(&env, &mut [0.0, -0.0, 3.0]), Ok(1.0));
+	assert_eq!(builtin_any(&env, &mut [0.0, f64::NAN]), Ok(0.0));
+	assert_eq!(builtin_all(&env, &mut []), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_any(&env, &mut []), Err(ErrorKind::BadArgument));
+
+	// Truth table with NaN as falsy
+	let nan = f64::NAN;
+	for (a, b, all, any) in [
+		(0.0, 0.0, 0.0, 0.0), (0.0, 1.0, 0.0, 1.0), (1.0, 0.0, 0.0, 1.0), (1.0, 1.0, 1.0, 1.0),
+		(nan, 0.0, 0.0, 0.0), (nan, 1.0, 0.0, 1.0), (1.0, nan, 0.0, 1.0), (nan, nan, 0.0, 0.0),
+	] {
+		assert_eq!(builtin_all(&env, &mut [a, b]), Ok(all));
+		assert_eq!(builtin_any(&env, &mut [a, b]), Ok(any));
+	}
+	for (a, not) in [(0.0, 1.0), (-0.0, 1.0), (nan, 1.0), (1.0, 0.0), (-2.5, 0.0), (f64::INFINITY, 0.0)] {
+		assert_eq!(builtin_not(&env, &mut [a]), Ok(not));
+	}
+	assert_eq!(builtin_not(&env, &mut [1.0, 2.0]), Err(ErrorKind::BadArgument));
+
+	assert_eq!(builtin_select(&env, &mut [0.0, nan]).map(f64::is_nan), Ok(true));
+	assert_eq!(builtin_select(&env, &mut [0.0]), Err(ErrorKind::BadArgument));
+	assert_eq!(builtin_selectc(&env, &mut [0.0]), Err(ErrorKind::BadArgument));
+	// Indices -1, 0, len - 1, len, NaN and 1e300 against both selects
+	for (index, select, selectc) in [
+		(-1.0, Ok(30.0), Ok(10.0)),
+		(-3.0, Ok(10.0), Ok(10.0)),
+		(-4.0, Err(ErrorKind::BadArgument), Ok(10.0)),
+		(0.0, Ok(10.0), Ok(10.0)),
+		(-0.0, Ok(10.0), Ok(10.0)),
+		(1.0, Ok(20.0), Ok(20.0)),
+		(1.9, Err(ErrorKind::BadArgument), Ok(20.0)),
+		(2.0, Ok(30.0), Ok(30.0)),
+		(3.0, Err(ErrorKind::BadArgument), Ok(30.0)),
+		(nan, Err(ErrorKind::BadArgument), Err(ErrorKind::BadArgument)),
+		(1e300, Err(ErrorKind::BadArgument), Ok(30.0)),
+		(-1e300, Err(ErrorKind::BadArgument), Ok(10.0)),
+		(f64::INFINITY, Err(ErrorKind::BadArgument), Ok(30.0)),
+	] {
+		assert_eq!(builtin_select(&env, &mut [index, 10.0, 20.0, 30.0]), select, "select({})", index);
+		assert_eq!(builtin_selectc(&env, &mut [index, 10.0, 20.0, 30.0]), selectc, "selectc({})", index);
+	}
 }
@@ -0,0 +1,219 @@
+//! Single precision builtins.
+//!
+//! The subset of the [`builtins`] available in a [`BasicEnv32`], evaluated in `f32`.
+
+use std::f32::consts;
+use crate::*;
+use crate::math::single as math;
+
+pub fn builtin_id(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+// Neumaier's variant of Kahan summation, see the double precision version.
+fn sum(vals: &[f32]) -> f32 {
+	let mut sum = 0f32;
+	let mut c = 0f32;
+	for &x in vals {
+		let t = sum + x;
+		if sum.abs() >= x.abs() { c += (sum - t) + x; }
+		else { c += (x - t) + sum; }
+		sum = t;
+	}
+	if sum.is_finite() { sum + c } else { sum }
+}
+/// Returns the sum of the values using compensated summation.
+pub fn builtin_add(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if !vals.is_empty() { Ok(sum(vals)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sub(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	match vals.len() {
+		1 => Ok(-vals[0]),
+		2 => Ok(vals[0] - vals[1]),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_mul(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() > 1 { Ok(vals.iter().fold(1f32, |acc, x| acc * x)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_div(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 2 { Ok(vals[0] / vals[1]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_floordiv(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 2 { Ok((vals[0] / vals[1]).floor()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_rem(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 2 { Ok(vals[0] % vals[1]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Floored modulo, `mod(-1, 3)` is 2.
+pub fn builtin_mod(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	let (a, n) = match *vals {
+		[a, n] => (a, n),
+		_ => return Err(ErrorKind::BadArgument),
+	};
+	let r = a % n;
+	if r != 0.0 && (r < 0.0) != (n < 0.0) {
+		let r = r + n;
+		// Tiny remainders round onto `n` which is outside the range
+		Ok(if r == n { 0.0 } else { r })
+	}
+	else {
+		Ok(r)
+	}
+}
+pub fn builtin_pow(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 2 { Ok(math::powf(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_percent(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0] / 100f32) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_min(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if !vals.is_empty() { Ok(vals.iter().fold(vals[0], |acc, &x| acc.min(x))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_max(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if !vals.is_empty() { Ok(vals.iter().fold(vals[0], |acc, &x| acc.max(x))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_clamp(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	match *vals {
+		// NaN bounds fail the comparison
+		[x, lo, hi] if lo <= hi => Ok(x.clamp(lo, hi)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_e(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.is_empty() { Ok(consts::E) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_pi(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.is_empty() { Ok(consts::PI) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_tau(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.is_empty() { Ok(consts::TAU) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_deg(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].to_degrees()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_rad(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].to_radians()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_atan2(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 2 { Ok(math::atan2(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_hypot(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 2 { Ok(math::hypot(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_log(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 2 { Ok(math::log(vals[0], vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sqr(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0] * vals[0]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cube(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0] * vals[0] * vals[0]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_abs(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].abs()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sqrt(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].sqrt()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cbrt(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::cbrt(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_floor(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].floor()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_ceil(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].ceil()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_round(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].round()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_exp(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::exp(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_ln(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::ln(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_log2(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::log2(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_log10(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::log10(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sin(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::sin(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cos(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::cos(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_tan(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::tan(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_asin(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::asin(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_acos(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::acos(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_atan(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::atan(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sinh(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::sinh(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cosh(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::cosh(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_tanh(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(math::tanh(vals[0])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_all(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if !vals.is_empty() { Ok(if vals.iter().all(|&x| x.truthy()) { 1.0 } else { 0.0 }) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_any(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if !vals.is_empty() { Ok(if vals.iter().any(|&x| x.truthy()) { 1.0 } else { 0.0 }) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_not(_: &dyn Env<f32>, vals: &mut [f32]) -> Result<f32, ErrorKind> {
+	if vals.len() == 1 { Ok(if vals[0].truthy() { 0.0 } else { 1.0 }) }
+	else { Err(ErrorKind::BadArgument) }
+}
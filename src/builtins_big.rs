@@ -0,0 +1,161 @@
+//! Arbitrary precision builtins.
+//!
+//! The builtins available in a [`PreciseEnv`](crate::PreciseEnv), evaluated at the working precision of [`eval_precise`](crate::eval_precise).
+
+use astro_float::{BigFloat, Consts};
+use crate::*;
+use crate::bigfloat::{with_context, RM};
+
+// Applies to a single argument at the working precision.
+fn unary(vals: &[BigValue], f: impl FnOnce(&BigFloat, usize, &mut Consts) -> BigFloat) -> Result<BigValue, ErrorKind> {
+	match vals {
+		[x] => Ok(BigValue(with_context(|p, cc| f(&x.0, p, cc)))),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+// Applies to two arguments at the working precision.
+fn binary(vals: &[BigValue], f: impl FnOnce(&BigFloat, &BigFloat, usize, &mut Consts) -> BigFloat) -> Result<BigValue, ErrorKind> {
+	match vals {
+		[x, y] => Ok(BigValue(with_context(|p, cc| f(&x.0, &y.0, p, cc)))),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+// Computes a constant at the working precision.
+fn constant(vals: &[BigValue], f: impl FnOnce(usize, &mut Consts) -> BigFloat) -> Result<BigValue, ErrorKind> {
+	if vals.is_empty() { Ok(BigValue(with_context(f))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+fn boolean(val: bool) -> BigValue {
+	BigValue::from_f64(if val { 1.0 } else { 0.0 })
+}
+
+pub fn builtin_id(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, _, _| x.clone())
+}
+pub fn builtin_add(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	let (first, rest) = vals.split_first().ok_or(ErrorKind::BadArgument)?;
+	Ok(BigValue(with_context(|p, _| rest.iter().fold(first.0.clone(), |acc, x| acc.add(&x.0, p, RM)))))
+}
+pub fn builtin_sub(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	match vals.len() {
+		1 => unary(vals, |x, _, _| x.neg()),
+		_ => binary(vals, |x, y, p, _| x.sub(y, p, RM)),
+	}
+}
+pub fn builtin_mul(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	match vals {
+		[first, rest @ ..] if !rest.is_empty() => Ok(BigValue(with_context(|p, _| rest.iter().fold(first.0.clone(), |acc, x| acc.mul(&x.0, p, RM))))),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_div(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	binary(vals, |x, y, p, _| x.div(y, p, RM))
+}
+pub fn builtin_floordiv(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	binary(vals, |x, y, p, _| x.div(y, p, RM).floor())
+}
+/// Truncated remainder with the sign of x.
+pub fn builtin_rem(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	binary(vals, |x, y, _, _| x.rem(y))
+}
+pub fn builtin_pow(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	binary(vals, |x, y, p, cc| x.pow(y, p, RM, cc))
+}
+pub fn builtin_percent(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, _| x.div(&BigFloat::from_f64(100.0, p), p, RM))
+}
+pub fn builtin_sqrt(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, _| x.sqrt(p, RM))
+}
+pub fn builtin_cbrt(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, _| x.cbrt(p, RM))
+}
+pub fn builtin_sqr(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, _| x.mul(x, p, RM))
+}
+pub fn builtin_cube(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, _| x.mul(x, p, RM).mul(x, p, RM))
+}
+pub fn builtin_abs(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, _, _| x.abs())
+}
+pub fn builtin_floor(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, _, _| x.floor())
+}
+pub fn builtin_ceil(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, _, _| x.ceil())
+}
+pub fn builtin_min(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	let (first, rest) = vals.split_first().ok_or(ErrorKind::BadArgument)?;
+	Ok(BigValue(rest.iter().fold(first.0.clone(), |acc, x| acc.min(&x.0))))
+}
+pub fn builtin_max(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	let (first, rest) = vals.split_first().ok_or(ErrorKind::BadArgument)?;
+	Ok(BigValue(rest.iter().fold(first.0.clone(), |acc, x| acc.max(&x.0))))
+}
+pub fn builtin_e(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	constant(vals, |p, cc| cc.e(p, RM))
+}
+pub fn builtin_pi(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	constant(vals, |p, cc| cc.pi(p, RM))
+}
+pub fn builtin_tau(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	// Doubling is exact
+	constant(vals, |p, cc| { let pi = cc.pi(p, RM); pi.add(&pi, p, RM) })
+}
+pub fn builtin_exp(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.exp(p, RM, cc))
+}
+pub fn builtin_ln(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.ln(p, RM, cc))
+}
+pub fn builtin_log(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	binary(vals, |x, base, p, cc| x.log(base, p, RM, cc))
+}
+pub fn builtin_log2(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.log2(p, RM, cc))
+}
+pub fn builtin_log10(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.log10(p, RM, cc))
+}
+pub fn builtin_sin(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.sin(p, RM, cc))
+}
+pub fn builtin_cos(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.cos(p, RM, cc))
+}
+pub fn builtin_tan(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.tan(p, RM, cc))
+}
+pub fn builtin_asin(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.asin(p, RM, cc))
+}
+pub fn builtin_acos(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.acos(p, RM, cc))
+}
+pub fn builtin_atan(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.atan(p, RM, cc))
+}
+pub fn builtin_sinh(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.sinh(p, RM, cc))
+}
+pub fn builtin_cosh(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.cosh(p, RM, cc))
+}
+pub fn builtin_tanh(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	unary(vals, |x, p, cc| x.tanh(p, RM, cc))
+}
+pub fn builtin_all(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	if !vals.is_empty() { Ok(boolean(vals.iter().all(|x| x.truthy()))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_any(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	if !vals.is_empty() { Ok(boolean(vals.iter().any(|x| x.truthy()))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_not(_: &dyn Env<BigValue>, vals: &mut [BigValue]) -> Result<BigValue, ErrorKind> {
+	match vals {
+		[x] => Ok(boolean(!x.truthy())),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
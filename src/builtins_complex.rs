@@ -0,0 +1,163 @@
+//! Complex builtins.
+//!
+//! The builtins available in a [`ComplexEnv`](crate::ComplexEnv).
+//! Functions only defined for real numbers, such as `rem` and `floordiv`, are a bad argument for non-real arguments.
+
+use std::f64::consts;
+use crate::*;
+
+// Real parts of real arguments.
+fn real<const N: usize>(vals: &[Complex]) -> Result<[Value; N], ErrorKind> {
+	let vals: &[Complex; N] = vals.try_into().map_err(|_| ErrorKind::BadArgument)?;
+	if vals.iter().all(|val| val.is_real()) { Ok(vals.map(|val| val.re)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+
+pub fn builtin_id(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_add(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if !vals.is_empty() { Ok(vals.iter().fold(Complex::default(), |acc, &x| acc + x)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sub(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	match vals.len() {
+		1 => Ok(-vals[0]),
+		2 => Ok(vals[0] - vals[1]),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_mul(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() > 1 { Ok(vals[1..].iter().fold(vals[0], |acc, &x| acc * x)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_div(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 2 { Ok(vals[0] / vals[1]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_floordiv(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	let [x, y] = real(vals)?;
+	Ok(Complex::from((x / y).floor()))
+}
+pub fn builtin_rem(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	let [x, y] = real(vals)?;
+	Ok(Complex::from(x % y))
+}
+pub fn builtin_pow(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 2 { Ok(vals[0].pow(vals[1])) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_percent(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0] / Complex::from(100.0)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sqrt(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].sqrt()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_sqr(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0] * vals[0]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cube(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0] * vals[0] * vals[0]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_e(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.is_empty() { Ok(Complex::from(consts::E)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_i(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.is_empty() { Ok(Complex::I) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_pi(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.is_empty() { Ok(Complex::from(consts::PI)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_tau(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.is_empty() { Ok(Complex::from(consts::TAU)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_re(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(Complex::from(vals[0].re)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_im(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(Complex::from(vals[0].im)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_abs(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(Complex::from(vals[0].abs())) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_arg(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(Complex::from(vals[0].arg())) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_conj(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].conj()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_exp(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].exp()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Principal natural logarithm, real for positive real arguments.
+pub fn builtin_ln(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	match *vals {
+		[x] if x.is_real() && x.re >= 0.0 => Ok(Complex::from(x.re.ln())),
+		[x] => Ok(x.ln()),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_log(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	match *vals {
+		[x, base] if x.is_real() && base.is_real() && x.re >= 0.0 && base.re >= 0.0 => Ok(Complex::from(x.re.log(base.re))),
+		[x, base] => Ok(x.ln() / base.ln()),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_sin(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].sin()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cos(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].cos()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_tan(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	match *vals {
+		[x] if x.is_real() => Ok(Complex::from(x.re.tan())),
+		[x] => Ok(x.sin() / x.cos()),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_sinh(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].sinh()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_cosh(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0].cosh()) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_tanh(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	match *vals {
+		[x] if x.is_real() => Ok(Complex::from(x.re.tanh())),
+		[x] => Ok(x.sinh() / x.cosh()),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_all(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if !vals.is_empty() { Ok(Complex::from(if vals.iter().all(|&x| x.truthy()) { 1.0 } else { 0.0 })) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_any(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if !vals.is_empty() { Ok(Complex::from(if vals.iter().any(|&x| x.truthy()) { 1.0 } else { 0.0 })) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_not(_: &dyn Env<Complex>, vals: &mut [Complex]) -> Result<Complex, ErrorKind> {
+	if vals.len() == 1 { Ok(Complex::from(if vals[0].truthy() { 0.0 } else { 1.0 })) }
+	else { Err(ErrorKind::BadArgument) }
+}
@@ -0,0 +1,184 @@
+//! Integer builtins.
+//!
+//! The builtins available in an [`IntEnv`].
+//! Results which overflow fail with [`ErrorKind::Overflow`] and division follows the [`IntDivision`] of [`eval_int_with`].
+
+use crate::*;
+
+// Divides with the division of the evaluation.
+fn divide(x: i64, y: i64) -> Result<i64, ErrorKind> {
+	match crate::int::division() {
+		IntDivision::Exact => {
+			let rem = x.checked_rem(y).ok_or(if y == 0 { ErrorKind::DivisionByZero } else { ErrorKind::Overflow })?;
+			if rem != 0 {
+				return Err(ErrorKind::NotInteger);
+			}
+			x.checked_div(y).ok_or(ErrorKind::Overflow)
+		},
+		IntDivision::Floor => floor_div(x, y),
+	}
+}
+fn floor_div(x: i64, y: i64) -> Result<i64, ErrorKind> {
+	if y == 0 {
+		return Err(ErrorKind::DivisionByZero);
+	}
+	let q = x.checked_div(y).ok_or(ErrorKind::Overflow)?;
+	Ok(if x % y != 0 && (x < 0) != (y < 0) { q - 1 } else { q })
+}
+fn boolean(val: bool) -> i64 {
+	if val { 1 } else { 0 }
+}
+
+pub fn builtin_id(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_add(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	if vals.is_empty() {
+		return Err(ErrorKind::BadArgument);
+	}
+	vals.iter().try_fold(0i64, |acc, &x| acc.checked_add(x)).ok_or(ErrorKind::Overflow)
+}
+pub fn builtin_sub(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[x] => x.checked_neg().ok_or(ErrorKind::Overflow),
+		[x, y] => x.checked_sub(y).ok_or(ErrorKind::Overflow),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_mul(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	if vals.len() < 2 {
+		return Err(ErrorKind::BadArgument);
+	}
+	vals[1..].iter().try_fold(vals[0], |acc, &x| acc.checked_mul(x)).ok_or(ErrorKind::Overflow)
+}
+/// Divides x by y, see [`IntDivision`].
+pub fn builtin_div(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[x, y] => divide(x, y),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_floordiv(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[x, y] => floor_div(x, y),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Truncated remainder with the sign of x.
+pub fn builtin_rem(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[_, 0] => Err(ErrorKind::DivisionByZero),
+		[x, y] => Ok(x.wrapping_rem(y)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Floored modulo with the sign of n.
+pub fn builtin_mod(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[_, 0] => Err(ErrorKind::DivisionByZero),
+		[a, n] => {
+			let r = a.wrapping_rem(n);
+			Ok(if r != 0 && (r < 0) != (n < 0) { r + n } else { r })
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Raises base to a power, negative powers divide one by the result.
+pub fn builtin_pow(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	let [base, exp] = *vals else {
+		return Err(ErrorKind::BadArgument);
+	};
+	match base {
+		0 if exp < 0 => return Err(ErrorKind::DivisionByZero),
+		1 => return Ok(1),
+		-1 => return Ok(if exp % 2 == 0 { 1 } else { -1 }),
+		_ => (),
+	}
+	if exp < 0 {
+		// The magnitude of the result is below one, round like dividing one by a larger integer
+		let negative = base < 0 && exp % 2 != 0;
+		return divide(if negative { -1 } else { 1 }, i64::MAX);
+	}
+	let exp = u32::try_from(exp).map_err(|_| ErrorKind::Overflow)?;
+	base.checked_pow(exp).ok_or(ErrorKind::Overflow)
+}
+pub fn builtin_percent(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[x] => divide(x, 100),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Square root, a perfect square unless flooring, see [`IntDivision`].
+pub fn builtin_sqrt(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	let [x] = *vals else {
+		return Err(ErrorKind::BadArgument);
+	};
+	if x < 0 {
+		return Err(ErrorKind::MathDomain);
+	}
+	// Correct the estimate in double precision
+	let mut r = (x as f64).sqrt() as i64;
+	while r.checked_mul(r).is_none_or(|sq| sq > x) {
+		r -= 1;
+	}
+	while (r + 1).checked_mul(r + 1).is_some_and(|sq| sq <= x) {
+		r += 1;
+	}
+	if r * r != x && crate::int::division() == IntDivision::Exact {
+		return Err(ErrorKind::NotInteger);
+	}
+	Ok(r)
+}
+pub fn builtin_sqr(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[x] => x.checked_mul(x).ok_or(ErrorKind::Overflow),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_cube(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[x] => x.checked_mul(x).and_then(|sq| sq.checked_mul(x)).ok_or(ErrorKind::Overflow),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_abs(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[x] => x.checked_abs().ok_or(ErrorKind::Overflow),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_min(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	vals.iter().copied().min().ok_or(ErrorKind::BadArgument)
+}
+pub fn builtin_max(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	vals.iter().copied().max().ok_or(ErrorKind::BadArgument)
+}
+/// Greatest common divisor of the magnitudes.
+pub fn builtin_gcd(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	if vals.is_empty() {
+		return Err(ErrorKind::BadArgument);
+	}
+	let mut acc = 0u64;
+	for &val in vals.iter() {
+		let mut b = val.unsigned_abs();
+		while b != 0 {
+			(acc, b) = (b, acc % b);
+		}
+	}
+	i64::try_from(acc).map_err(|_| ErrorKind::Overflow)
+}
+pub fn builtin_all(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	if !vals.is_empty() { Ok(boolean(vals.iter().all(|&x| x != 0))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_any(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	if !vals.is_empty() { Ok(boolean(vals.iter().any(|&x| x != 0))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_not(_: &dyn Env<i64>, vals: &mut [i64]) -> Result<i64, ErrorKind> {
+	match *vals {
+		[x] => Ok(boolean(x == 0)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
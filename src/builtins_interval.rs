@@ -0,0 +1,227 @@
+//! Interval builtins.
+//!
+//! The builtins available in an [`IntervalEnv`].
+//! Monotone functions map the bounds, periodic functions find their extremes within the interval.
+
+use std::f64::consts;
+use crate::*;
+
+fn args<const N: usize>(vals: &[Interval]) -> Result<[Interval; N], ErrorKind> {
+	let vals: &[Interval; N] = vals.try_into().map_err(|_| ErrorKind::BadArgument)?;
+	Ok(*vals)
+}
+// Library functions are accurate to an ulp, widen by another for their rounding.
+fn approx(lo: Value, hi: Value) -> Interval {
+	let val = Interval::outward(lo, hi);
+	Interval::outward(val.lo, val.hi)
+}
+fn boolean(val: bool) -> Interval {
+	Interval::from(if val { 1.0 } else { 0.0 })
+}
+fn div(x: Interval, y: Interval) -> Interval {
+	if x.is_nan() || y.is_nan() { Interval::NAN }
+	else if y.contains(0.0) { Interval::ENTIRE }
+	else { x.corners(y, |a, b| a / b) }
+}
+// Cosine shifted by the phase, the maxima are at the phase plus multiples of tau.
+fn cosine(x: Interval, phase: Value, f: fn(Value) -> Value) -> Interval {
+	if x.is_nan() {
+		return Interval::NAN;
+	}
+	if !x.is_finite() || x.width() >= consts::TAU {
+		return Interval::new(-1.0, 1.0);
+	}
+	// Tolerate the rounding of finding the extremes by including close ones
+	let within = |at: Value| {
+		let k = ((x.lo - at) / consts::TAU - 1e-9).ceil();
+		at + k * consts::TAU <= x.hi + 1e-9
+	};
+	let (a, b) = (f(x.lo), f(x.hi));
+	let val = approx(a.min(b), a.max(b));
+	let lo = if within(phase + consts::PI) { -1.0 } else { val.lo.max(-1.0) };
+	let hi = if within(phase) { 1.0 } else { val.hi.min(1.0) };
+	Interval { lo, hi }
+}
+
+// Integer powers of negative bases are defined, other powers of the non-negative part of the base.
+fn pow(base: Interval, exp: Interval) -> Interval {
+	if base.is_nan() || exp.is_nan() {
+		return Interval::NAN;
+	}
+	if exp.is_point() && exp.lo.fract() == 0.0 {
+		let n = exp.lo;
+		if n < 0.0 {
+			return div(Interval::from(1.0), pow(base, Interval::from(-n)));
+		}
+		if n % 2.0 != 0.0 {
+			return approx(base.lo.powf(n), base.hi.powf(n));
+		}
+		// Even powers of the magnitude
+		let (lo, hi) = (base.lo.abs(), base.hi.abs());
+		let val = approx(lo.min(hi).powf(n), lo.max(hi).powf(n));
+		return if base.contains(0.0) { Interval { lo: 0.0, hi: val.hi } } else { val };
+	}
+	if base.hi < 0.0 {
+		return Interval::NAN;
+	}
+	let val = Interval { lo: base.lo.max(0.0), hi: base.hi }.corners(exp, Value::powf);
+	Interval::outward(val.lo, val.hi)
+}
+
+pub fn builtin_id(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(x)
+}
+pub fn builtin_add(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let (first, rest) = vals.split_first().ok_or(ErrorKind::BadArgument)?;
+	Ok(rest.iter().fold(*first, |acc, x| Interval::outward(acc.lo + x.lo, acc.hi + x.hi)))
+}
+pub fn builtin_sub(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	match *vals {
+		[x] => Ok(Interval { lo: -x.hi, hi: -x.lo }),
+		[x, y] => Ok(Interval::outward(x.lo - y.hi, x.hi - y.lo)),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_mul(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	match vals {
+		[first, rest @ ..] if !rest.is_empty() => Ok(rest.iter().fold(*first, |acc, &x| acc.corners(x, |a, b| a * b))),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Divides x by y, all values if y contains zero.
+pub fn builtin_div(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x, y] = args(vals)?;
+	Ok(div(x, y))
+}
+pub fn builtin_floordiv(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x, y] = args(vals)?;
+	let q = div(x, y);
+	Ok(Interval { lo: q.lo.floor(), hi: q.hi.floor() })
+}
+/// Truncated remainder, within the magnitudes of both arguments with the sign of x.
+pub fn builtin_rem(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x, y] = args(vals)?;
+	if x.is_nan() || y.is_nan() {
+		return Ok(Interval::NAN);
+	}
+	let m = y.lo.abs().max(y.hi.abs()).min(x.lo.abs().max(x.hi.abs()));
+	Ok(Interval { lo: if x.lo < 0.0 { -m } else { 0.0 }, hi: if x.hi > 0.0 { m } else { 0.0 } })
+}
+/// Raises base to a power, integer powers of negative bases are exact.
+pub fn builtin_pow(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [base, exp] = args(vals)?;
+	Ok(pow(base, exp))
+}
+pub fn builtin_percent(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(div(x, Interval::from(100.0)))
+}
+pub fn builtin_sqrt(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	if x.is_nan() || x.hi < 0.0 {
+		return Ok(Interval::NAN);
+	}
+	Ok(Interval::outward(x.lo.max(0.0).sqrt(), x.hi.sqrt()))
+}
+pub fn builtin_sqr(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(pow(x, Interval::from(2.0)))
+}
+pub fn builtin_cube(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(pow(x, Interval::from(3.0)))
+}
+pub fn builtin_cbrt(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(approx(x.lo.cbrt(), x.hi.cbrt()))
+}
+pub fn builtin_abs(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	let (lo, hi) = (x.lo.abs(), x.hi.abs());
+	Ok(if x.contains(0.0) { Interval { lo: 0.0, hi: lo.max(hi) } } else { Interval::new(lo, hi) })
+}
+pub fn builtin_min(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let (first, rest) = vals.split_first().ok_or(ErrorKind::BadArgument)?;
+	Ok(rest.iter().fold(*first, |acc, x| Interval { lo: acc.lo.min(x.lo), hi: acc.hi.min(x.hi) }))
+}
+pub fn builtin_max(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let (first, rest) = vals.split_first().ok_or(ErrorKind::BadArgument)?;
+	Ok(rest.iter().fold(*first, |acc, x| Interval { lo: acc.lo.max(x.lo), hi: acc.hi.max(x.hi) }))
+}
+/// Smallest interval containing both arguments, `interval(lo, hi)`.
+pub fn builtin_interval(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [a, b] = args(vals)?;
+	Ok(a.hull(b))
+}
+pub fn builtin_lo(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(Interval::from(x.lo))
+}
+pub fn builtin_hi(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(Interval::from(x.hi))
+}
+pub fn builtin_e(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [] = args(vals)?;
+	Ok(Interval::outward(consts::E, consts::E))
+}
+pub fn builtin_pi(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [] = args(vals)?;
+	Ok(Interval::outward(consts::PI, consts::PI))
+}
+pub fn builtin_tau(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [] = args(vals)?;
+	Ok(Interval::outward(consts::TAU, consts::TAU))
+}
+pub fn builtin_exp(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(approx(x.lo.exp(), x.hi.exp()))
+}
+// Logarithm of the non-negative part.
+fn log(x: Interval, f: fn(Value) -> Value) -> Interval {
+	if x.is_nan() || x.hi < 0.0 {
+		return Interval::NAN;
+	}
+	approx(f(x.lo.max(0.0)), f(x.hi))
+}
+pub fn builtin_ln(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(log(x, Value::ln))
+}
+pub fn builtin_log2(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(log(x, Value::log2))
+}
+pub fn builtin_log10(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(log(x, Value::log10))
+}
+pub fn builtin_sin(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(cosine(x, consts::FRAC_PI_2, Value::sin))
+}
+pub fn builtin_cos(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(cosine(x, 0.0, Value::cos))
+}
+pub fn builtin_atan(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(approx(x.lo.atan(), x.hi.atan()))
+}
+pub fn builtin_tanh(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(approx(x.lo.tanh(), x.hi.tanh()))
+}
+pub fn builtin_all(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	if !vals.is_empty() { Ok(boolean(vals.iter().all(|x| x.truthy()))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_any(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	if !vals.is_empty() { Ok(boolean(vals.iter().any(|x| x.truthy()))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_not(_: &dyn Env<Interval>, vals: &mut [Interval]) -> Result<Interval, ErrorKind> {
+	let [x] = args(vals)?;
+	Ok(boolean(!x.truthy()))
+}
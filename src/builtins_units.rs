@@ -0,0 +1,212 @@
+//! Units of measure builtins.
+//!
+//! The builtins available in a [`UnitEnv`].
+//! Combining quantities of different dimensions fails with [`ErrorKind::DimensionMismatch`], transcendental functions require dimensionless arguments.
+
+use crate::*;
+
+// Dimension shared by all the arguments.
+fn same(vals: &[Quantity]) -> Result<Dimension, ErrorKind> {
+	let dim = vals.first().ok_or(ErrorKind::BadArgument)?.dim;
+	if vals.iter().all(|x| x.dim == dim) { Ok(dim) }
+	else { Err(ErrorKind::DimensionMismatch) }
+}
+// Applies to a single dimensionless argument.
+fn scalar(vals: &[Quantity], f: fn(Value) -> Value) -> Result<Quantity, ErrorKind> {
+	match vals {
+		[x] if x.dim.is_none() => Ok(Quantity::from(f(x.value))),
+		[_] => Err(ErrorKind::DimensionMismatch),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+// Plain numbers don't change the unit of the other operand, eg. `3 km` stays in km.
+fn unit(x: &Quantity, y: &Quantity) -> Option<&'static Unit> {
+	let plain = |q: &Quantity| q.dim.is_none() && q.unit.is_none();
+	if plain(y) { x.unit }
+	else if plain(x) { y.unit }
+	else { None }
+}
+fn quotient(x: &Quantity, y: &Quantity) -> Result<Quantity, ErrorKind> {
+	let dim = x.dim.checked_div(y.dim).ok_or(ErrorKind::DimensionMismatch)?;
+	let unit = if y.dim.is_none() && y.unit.is_none() { x.unit } else { None };
+	Ok(Quantity { value: x.value / y.value, dim, unit })
+}
+fn root(vals: &[Quantity], n: i8, f: fn(Value) -> Value) -> Result<Quantity, ErrorKind> {
+	let [x] = vals else {
+		return Err(ErrorKind::BadArgument);
+	};
+	let dim = x.dim.root(n).ok_or(ErrorKind::DimensionMismatch)?;
+	Ok(Quantity { value: f(x.value), dim, unit: None })
+}
+fn power(x: &Quantity, exp: Value) -> Result<Quantity, ErrorKind> {
+	let dim = x.dim.pow(exp).ok_or(ErrorKind::DimensionMismatch)?;
+	Ok(Quantity { value: x.value.powf(exp), dim, unit: None })
+}
+fn boolean(val: bool) -> Quantity {
+	Quantity::from(if val { 1.0 } else { 0.0 })
+}
+
+pub fn builtin_id(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	if vals.len() == 1 { Ok(vals[0]) }
+	else { Err(ErrorKind::BadArgument) }
+}
+/// Sum of quantities of the same dimension, in the unit of the first argument with one.
+pub fn builtin_add(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	let dim = same(vals)?;
+	let unit = vals.iter().find_map(|x| x.unit);
+	Ok(Quantity { value: vals.iter().map(|x| x.value).sum(), dim, unit })
+}
+pub fn builtin_sub(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match *vals {
+		[x] => Ok(Quantity { value: -x.value, ..x }),
+		[x, y] => {
+			let dim = same(&[x, y])?;
+			Ok(Quantity { value: x.value - y.value, dim, unit: x.unit.or(y.unit) })
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_mul(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	if vals.len() < 2 {
+		return Err(ErrorKind::BadArgument);
+	}
+	vals[1..].iter().try_fold(vals[0], |acc, x| {
+		let dim = acc.dim.checked_mul(x.dim).ok_or(ErrorKind::DimensionMismatch)?;
+		Ok(Quantity { value: acc.value * x.value, dim, unit: unit(&acc, x) })
+	})
+}
+pub fn builtin_div(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match vals {
+		[x, y] => quotient(x, y),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_floordiv(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match vals {
+		[x, y] => quotient(x, y).map(|q| Quantity { value: q.value.floor(), ..q }),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Truncated remainder of quantities of the same dimension.
+pub fn builtin_rem(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match *vals {
+		[x, y] => {
+			same(&[x, y])?;
+			Ok(Quantity { value: x.value % y.value, ..x })
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Raises base to a dimensionless power, the exponents of the dimension must stay integers.
+pub fn builtin_pow(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match vals {
+		[base, exp] if exp.dim.is_none() => power(base, exp.value),
+		[_, _] => Err(ErrorKind::DimensionMismatch),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_percent(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match *vals {
+		[x] => Ok(Quantity { value: x.value / 100.0, ..x }),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+/// Square root, halves the exponents of the dimension.
+pub fn builtin_sqrt(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	root(vals, 2, Value::sqrt)
+}
+/// Cube root, divides the exponents of the dimension by three.
+pub fn builtin_cbrt(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	root(vals, 3, Value::cbrt)
+}
+pub fn builtin_sqr(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match vals {
+		[x] => power(x, 2.0),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_cube(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match vals {
+		[x] => power(x, 3.0),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_abs(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match *vals {
+		[x] => Ok(Quantity { value: x.value.abs(), ..x }),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_min(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	same(vals)?;
+	Ok(vals.iter().copied().reduce(|acc, x| if x.value < acc.value { x } else { acc }).unwrap())
+}
+pub fn builtin_max(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	same(vals)?;
+	Ok(vals.iter().copied().reduce(|acc, x| if x.value > acc.value { x } else { acc }).unwrap())
+}
+/// Displays x in a unit of the same dimension, eg. `to(1 mi, "ft")`.
+pub fn builtin_to(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match *vals {
+		[x, Quantity { dim, unit: Some(unit), .. }] => {
+			if x.dim != dim {
+				return Err(ErrorKind::DimensionMismatch);
+			}
+			Ok(Quantity { unit: Some(unit), ..x })
+		},
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
+pub fn builtin_e(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	if vals.is_empty() { Ok(Quantity::from(std::f64::consts::E)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_pi(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	if vals.is_empty() { Ok(Quantity::from(std::f64::consts::PI)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_tau(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	if vals.is_empty() { Ok(Quantity::from(std::f64::consts::TAU)) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_exp(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	scalar(vals, Value::exp)
+}
+pub fn builtin_ln(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	scalar(vals, Value::ln)
+}
+pub fn builtin_log10(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	scalar(vals, Value::log10)
+}
+pub fn builtin_sin(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	scalar(vals, Value::sin)
+}
+pub fn builtin_cos(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	scalar(vals, Value::cos)
+}
+pub fn builtin_tan(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	scalar(vals, Value::tan)
+}
+pub fn builtin_asin(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	scalar(vals, Value::asin)
+}
+pub fn builtin_acos(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	scalar(vals, Value::acos)
+}
+pub fn builtin_atan(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	scalar(vals, Value::atan)
+}
+pub fn builtin_all(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	if !vals.is_empty() { Ok(boolean(vals.iter().all(|x| x.truthy()))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_any(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	if !vals.is_empty() { Ok(boolean(vals.iter().any(|x| x.truthy()))) }
+	else { Err(ErrorKind::BadArgument) }
+}
+pub fn builtin_not(_: &dyn Env<Quantity>, vals: &mut [Quantity]) -> Result<Quantity, ErrorKind> {
+	match vals {
+		[x] => Ok(boolean(!x.truthy())),
+		_ => Err(ErrorKind::BadArgument),
+	}
+}
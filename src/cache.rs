@@ -0,0 +1,180 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use crate::*;
+
+// Parsed expressions with the tick they were last used, and their inputs by that tick from the least recently used.
+#[derive(Default)]
+struct Lru {
+	map: HashMap<Arc<str>, (Arc<Ast>, u64)>,
+	order: BTreeMap<u64, Arc<str>>,
+	tick: u64,
+}
+
+impl Lru {
+	// Marks the cached input as used, returning its parsed expression.
+	fn touch(&mut self, input: &str) -> Option<Arc<Ast>> {
+		self.tick += 1;
+		let (ast, used) = self.map.get_mut(input)?;
+		let key = self.order.remove(used)?;
+		*used = self.tick;
+		self.order.insert(self.tick, key);
+		Some(ast.clone())
+	}
+	// Caches the parsed input, evicting the least recently used when full.
+	fn insert(&mut self, input: &str, ast: Arc<Ast>, capacity: usize) {
+		if self.touch(input).is_some() {
+			return;
+		}
+		if self.map.len() >= capacity {
+			if let Some((_, oldest)) = self.order.pop_first() {
+				self.map.remove(&oldest);
+			}
+		}
+		let key: Arc<str> = input.into();
+		self.map.insert(key.clone(), (ast, self.tick));
+		self.order.insert(self.tick, key);
+	}
+}
+
+/// Evaluator caching the parsed expressions by their text.
+///
+/// Holds up to its capacity of parsed expressions, evicting the least recently used to make room.
+/// The cache is locked only to look up and insert, so it can be shared between threads and evaluated in parallel.
+///
+/// ```
+/// let cache = pupil::CachedEvaluator::new(64);
+/// let mut env = pupil::BasicEnv::default();
+/// for i in 0..10 {
+///     env.ans = i as f64;
+///     assert_eq!(cache.eval(&env, "ans * 2 + 1"), Ok(i as f64 * 2.0 + 1.0));
+/// }
+/// assert_eq!((cache.hits(), cache.misses()), (9, 1));
+/// ```
+pub struct CachedEvaluator {
+	capacity: usize,
+	lru: Mutex<Lru>,
+	hits: AtomicU64,
+	misses: AtomicU64,
+}
+
+impl CachedEvaluator {
+	/// Creates an empty cache holding up to the capacity of expressions.
+	pub fn new(capacity: usize) -> CachedEvaluator {
+		CachedEvaluator {
+			capacity,
+			lru: Mutex::default(),
+			hits: AtomicU64::new(0),
+			misses: AtomicU64::new(0),
+		}
+	}
+	/// Evaluates the input like [`eval`], parsing it only if it isn't cached.
+	///
+	/// Inputs which fail to parse are not cached.
+	pub fn eval(&self, env: &dyn Env, input: &str) -> Result<Value, Error> {
+		self.get(input)?.eval(env)
+	}
+	/// Returns the parsed input, parsing and caching it if it isn't cached.
+	pub fn get(&self, input: &str) -> Result<Arc<Ast>, Error> {
+		if let Some(ast) = self.lookup(input) {
+			self.hits.fetch_add(1, Ordering::Relaxed);
+			return Ok(ast);
+		}
+		self.misses.fetch_add(1, Ordering::Relaxed);
+		// Parse without holding the lock, another thread may race to insert the same input
+		let ast = Arc::new(parse(input)?);
+		if self.capacity > 0 {
+			self.lock().insert(input, ast.clone(), self.capacity);
+		}
+		Ok(ast)
+	}
+	fn lookup(&self, input: &str) -> Option<Arc<Ast>> {
+		self.lock().touch(input)
+	}
+	fn lock(&self) -> std::sync::MutexGuard<'_, Lru> {
+		// The cache is consistent between operations, a panic elsewhere doesn't corrupt it
+		self.lru.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+	/// Number of evaluations which found the input cached.
+	pub fn hits(&self) -> u64 {
+		self.hits.load(Ordering::Relaxed)
+	}
+	/// Number of evaluations which parsed the input.
+	pub fn misses(&self) -> u64 {
+		self.misses.load(Ordering::Relaxed)
+	}
+	/// Number of cached expressions.
+	pub fn len(&self) -> usize {
+		self.lock().map.len()
+	}
+	/// Whether no expressions are cached.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+	/// Maximum number of cached expressions.
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+	/// Removes the cached expressions and resets the statistics.
+	pub fn clear(&self) {
+		*self.lock() = Lru::default();
+		self.hits.store(0, Ordering::Relaxed);
+		self.misses.store(0, Ordering::Relaxed);
+	}
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn lru() {
+	let cache = CachedEvaluator::new(2);
+	let env = BasicEnv::default();
+	assert_eq!(cache.eval(&env, "1 + 1"), Ok(2.0));
+	assert_eq!(cache.eval(&env, "2 + 2"), Ok(4.0));
+	assert_eq!(cache.eval(&env, "1 + 1"), Ok(2.0));
+	// Evicts the least recently used
+	assert_eq!(cache.eval(&env, "3 + 3"), Ok(6.0));
+	assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 3, 2));
+	assert_eq!(cache.eval(&env, "1 + 1"), Ok(2.0));
+	assert_eq!(cache.eval(&env, "2 + 2"), Ok(4.0));
+	assert_eq!((cache.hits(), cache.misses()), (2, 4));
+	// Errors keep their positions and parse errors aren't cached
+	assert_eq!(cache.eval(&env, "1 + x"), Err(Error::new(ErrorKind::EnvErrorNotFound, 4)));
+	assert_eq!(cache.eval(&env, "1 + x"), Err(Error::new(ErrorKind::EnvErrorNotFound, 4)));
+	assert_eq!(cache.eval(&env, "(1"), Err(Error::new(ErrorKind::UnbalancedParens, 0)));
+	assert_eq!(cache.eval(&env, "(1"), Err(Error::new(ErrorKind::UnbalancedParens, 0)));
+	assert_eq!((cache.hits(), cache.misses()), (3, 7));
+	cache.clear();
+	assert!(cache.is_empty() && cache.hits() == 0);
+	// Without capacity nothing is cached
+	let cache = CachedEvaluator::new(0);
+	assert_eq!(cache.eval(&env, "1 + 1").and(cache.eval(&env, "1 + 1")), Ok(2.0));
+	assert_eq!((cache.hits(), cache.misses(), cache.len()), (0, 2, 0));
+	// Evicts in order of last use
+	let cache = CachedEvaluator::new(3);
+	for input in ["1", "2", "3", "1", "4", "2", "1", "3"] {
+		cache.eval(&env, input).unwrap();
+	}
+	assert_eq!((cache.hits(), cache.misses(), cache.len()), (2, 6, 3));
+}
+#[test]
+fn shared() {
+	let cache = CachedEvaluator::new(16);
+	let inputs = ["ans * 2", "ans + 1", "max(ans, 3)", "sqrt(ans)"];
+	std::thread::scope(|scope| {
+		for i in 0..4 {
+			let cache = &cache;
+			scope.spawn(move || {
+				let env = BasicEnv { ans: i as f64, ..Default::default() };
+				for _ in 0..100 {
+					for input in inputs {
+						assert_eq!(cache.eval(&env, input), eval(&env, input));
+					}
+				}
+			});
+		}
+	});
+	assert_eq!(cache.hits() + cache.misses(), 1600);
+	assert!(cache.misses() >= 4 && cache.misses() <= 16);
+	assert_eq!(cache.len(), 4);
+}
@@ -0,0 +1,243 @@
+/*!
+C bindings.
+
+The declarations are in `include/pupil.h`, link against the `cdylib` or `staticlib` of the `ffi` crate built with `cargo build --manifest-path ffi/Cargo.toml --features capi`:
+
+```c
+#include <stdio.h>
+#include "pupil.h"
+
+int main(void) {
+    PupilEnv *env = pupil_env_new();
+    pupil_env_set_var(env, "r", 2.0);
+    double result;
+    PupilError err;
+    if (pupil_eval(env, "pi * r^2", &result, &err) == PUPIL_OK) {
+        printf("%g\n", result);
+    }
+    else {
+        printf("%s at %zu\n", pupil_error_message(err.code), err.position);
+    }
+    pupil_env_free(env);
+}
+```
+
+Every function catches panics and reports them as [`PUPIL_PANIC`].
+*/
+
+use std::ffi::{c_char, c_int, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use crate::*;
+
+/// Success.
+pub const PUPIL_OK: c_int = 0;
+/// A pointer argument was null.
+pub const PUPIL_NULL_POINTER: c_int = 100;
+/// A string argument isn't UTF-8, the position is the first invalid byte.
+pub const PUPIL_INVALID_UTF8: c_int = 101;
+/// Evaluation panicked.
+pub const PUPIL_PANIC: c_int = 102;
+
+/// Stable error code of the kind, the codes of new kinds are appended.
+pub fn error_code(kind: ErrorKind) -> c_int {
+	match kind {
+		ErrorKind::ExpectOperator => 1,
+		ErrorKind::NaExpression => 2,
+		ErrorKind::DisallowedUnary => 3,
+		ErrorKind::InternalError => 4,
+		ErrorKind::UnfinishedExpression => 5,
+		ErrorKind::InvalidToken => 6,
+		ErrorKind::UnbalancedParens => 7,
+		ErrorKind::MismatchedParens => 8,
+		ErrorKind::MisplacedComma => 9,
+		ErrorKind::MisplacedSemicolon => 10,
+		ErrorKind::MisplacedAssign => 11,
+		ErrorKind::UnbalancedConditional => 12,
+		ErrorKind::BadArgument => 13,
+		ErrorKind::ArgumentCount { .. } => 14,
+		ErrorKind::UnknownKeyword => 15,
+		ErrorKind::DuplicateKeyword => 16,
+		ErrorKind::PositionalAfterKeyword => 17,
+		ErrorKind::LimitExceeded => 18,
+		ErrorKind::DivisionByZero => 19,
+		ErrorKind::MathDomain => 20,
+		ErrorKind::Overflow => 21,
+		ErrorKind::NotInteger => 22,
+		ErrorKind::DimensionMismatch => 23,
+		ErrorKind::EnvErrorNotFound => 24,
+		ErrorKind::EnvErrorBuiltinFn => 25,
+		ErrorKind::CapacityExceeded => 26,
+	}
+}
+
+/// Error reported to C.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PupilError {
+	/// Error code, see [`error_code`].
+	pub code: c_int,
+	/// Byte offset in the input.
+	pub position: usize,
+}
+
+/// Environment with the default builtins and variables, opaque to C.
+pub type PupilEnv = VarEnv<'static, Value>;
+
+// Borrows a C string as UTF-8.
+unsafe fn utf8<'a>(ptr: *const c_char) -> Result<&'a str, PupilError> {
+	if ptr.is_null() {
+		return Err(PupilError { code: PUPIL_NULL_POINTER, position: 0 });
+	}
+	CStr::from_ptr(ptr).to_str().map_err(|err| PupilError { code: PUPIL_INVALID_UTF8, position: err.valid_up_to() })
+}
+
+// Runs the body, reporting its error and panics through the error pointer.
+unsafe fn guard(err: *mut PupilError, body: impl FnOnce() -> Result<(), PupilError>) -> c_int {
+	let result = catch_unwind(AssertUnwindSafe(body)).unwrap_or(Err(PupilError { code: PUPIL_PANIC, position: 0 }));
+	let error = result.err().unwrap_or_default();
+	if !err.is_null() {
+		*err = error;
+	}
+	error.code
+}
+
+/// Creates an environment, free it with [`pupil_env_free`].
+///
+/// Returns null if it panicked.
+#[no_mangle]
+pub extern "C" fn pupil_env_new() -> *mut PupilEnv {
+	catch_unwind(|| Box::into_raw(Box::<PupilEnv>::default())).unwrap_or(std::ptr::null_mut())
+}
+
+/// Frees an environment, null is ignored.
+///
+/// # Safety
+///
+/// The environment must come from [`pupil_env_new`] and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pupil_env_free(env: *mut PupilEnv) {
+	if !env.is_null() {
+		let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(env))));
+	}
+}
+
+/// Sets a variable, returns the error code.
+///
+/// # Safety
+///
+/// The environment must come from [`pupil_env_new`] and the name must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pupil_env_set_var(env: *mut PupilEnv, name: *const c_char, value: f64) -> c_int {
+	guard(std::ptr::null_mut(), || {
+		let env = env.as_mut().ok_or(PupilError { code: PUPIL_NULL_POINTER, position: 0 })?;
+		let name = utf8(name)?;
+		env.set_value(name, value).map_err(|kind| PupilError { code: error_code(kind), position: 0 })
+	})
+}
+
+/// Evaluates the input and stores the result in `ans`, returns the error code.
+///
+/// The result is written to `out` on success and the error to `err` if it isn't null.
+///
+/// # Safety
+///
+/// The environment must come from [`pupil_env_new`], the input must be a NUL-terminated string and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn pupil_eval(env: *mut PupilEnv, input: *const c_char, out: *mut f64, err: *mut PupilError) -> c_int {
+	guard(err, || {
+		let env = env.as_mut().ok_or(PupilError { code: PUPIL_NULL_POINTER, position: 0 })?;
+		let out = out.as_mut().ok_or(PupilError { code: PUPIL_NULL_POINTER, position: 0 })?;
+		let input = utf8(input)?;
+		let result = eval(env, input).map_err(|err| PupilError { code: error_code(err.kind), position: err.position })?;
+		let _ = env.set_value("ans", result);
+		*out = result;
+		Ok(())
+	})
+}
+
+/// Describes the error code, a static NUL-terminated string.
+#[no_mangle]
+pub extern "C" fn pupil_error_message(code: c_int) -> *const c_char {
+	let msg = match code {
+		PUPIL_OK => c"ok",
+		1 => c"expected an operator",
+		2 => c"not an expression",
+		3 => c"not an unary operator",
+		4 => c"internal corruption",
+		5 => c"unfinished expression",
+		6 => c"invalid token",
+		7 => c"unbalanced parens",
+		8 => c"mismatched parens",
+		9 => c"misplaced comma",
+		10 => c"misplaced semicolon",
+		11 => c"misplaced assignment",
+		12 => c"unbalanced conditional",
+		13 => c"bad argument",
+		14 => c"wrong number of arguments",
+		15 => c"unknown keyword",
+		16 => c"duplicate keyword",
+		17 => c"positional argument after keyword",
+		18 => c"limit exceeded",
+		19 => c"division by zero",
+		20 => c"math domain error",
+		21 => c"integer overflow",
+		22 => c"not an integer",
+		23 => c"dimension mismatch",
+		24 => c"env error not found",
+		25 => c"env error builtin",
+		26 => c"capacity exceeded",
+		PUPIL_NULL_POINTER => c"null pointer",
+		PUPIL_INVALID_UTF8 => c"invalid utf-8",
+		PUPIL_PANIC => c"panicked",
+		_ => c"unknown error",
+	};
+	msg.as_ptr()
+}
+
+//----------------------------------------------------------------
+
+#[cfg(test)]
+fn message(code: c_int) -> &'static str {
+	unsafe { CStr::from_ptr(pupil_error_message(code)) }.to_str().unwrap()
+}
+
+#[test]
+fn ffi() {
+	unsafe {
+		let env = pupil_env_new();
+		assert_eq!(pupil_env_set_var(env, c"r".as_ptr(), 2.0), PUPIL_OK);
+		let mut out = 0.0;
+		let mut err = PupilError::default();
+		assert_eq!(pupil_eval(env, c"r * 3".as_ptr(), &mut out, &mut err), PUPIL_OK);
+		assert_eq!((out, err), (6.0, PupilError { code: PUPIL_OK, position: 0 }));
+		assert_eq!(pupil_eval(env, c"ans + 1".as_ptr(), &mut out, std::ptr::null_mut()), PUPIL_OK);
+		assert_eq!(out, 7.0);
+		// Errors with their positions
+		let code = pupil_eval(env, c"2 + (3".as_ptr(), &mut out, &mut err);
+		assert_eq!((code, err), (7, PupilError { code: 7, position: 4 }));
+		assert_eq!(message(code), "unbalanced parens");
+		pupil_eval(env, c"1 + x".as_ptr(), &mut out, &mut err);
+		assert_eq!(err, PupilError { code: error_code(ErrorKind::EnvErrorNotFound), position: 4 });
+		assert_eq!(pupil_env_set_var(env, c"sin".as_ptr(), 1.0), error_code(ErrorKind::EnvErrorBuiltinFn));
+		assert_eq!(pupil_env_set_var(env, c"d".as_ptr(), 1.0), PUPIL_OK);
+		// Invalid arguments
+		pupil_eval(env, c"1 + \xff".as_ptr(), &mut out, &mut err);
+		assert_eq!(err, PupilError { code: PUPIL_INVALID_UTF8, position: 4 });
+		assert_eq!(pupil_eval(env, std::ptr::null(), &mut out, &mut err), PUPIL_NULL_POINTER);
+		assert_eq!(pupil_eval(std::ptr::null_mut(), c"1".as_ptr(), &mut out, &mut err), PUPIL_NULL_POINTER);
+		assert_eq!(pupil_eval(env, c"1".as_ptr(), std::ptr::null_mut(), &mut err), PUPIL_NULL_POINTER);
+		pupil_env_free(env);
+		pupil_env_free(std::ptr::null_mut());
+	}
+}
+#[test]
+fn error_messages() {
+	// The messages match the error kinds
+	let kinds = [ErrorKind::ExpectOperator, ErrorKind::InvalidToken, ErrorKind::DivisionByZero, ErrorKind::DimensionMismatch, ErrorKind::EnvErrorBuiltinFn, ErrorKind::CapacityExceeded];
+	for kind in kinds {
+		assert_eq!(message(error_code(kind)), kind.to_string());
+	}
+	assert!((1..=26).all(|code| message(code) != "unknown error"));
+	assert_eq!(message(PUPIL_PANIC), "panicked");
+	assert_eq!(message(-1), "unknown error");
+}
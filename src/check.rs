@@ -0,0 +1,323 @@
+use crate::*;
+use crate::parser::{self, Parser, Sink};
+use crate::storage::Heap;
+
+/// Parses the expression and looks up all the names without evaluating it.
+///
+/// Reports the same syntax and name errors as [`eval`] at the same positions,
+/// errors returned by the functions themselves are only found when evaluated.
+/// Unlike evaluation both branches of conditionals are checked.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// assert_eq!(pupil::check(&env, "sqrt(-1) * pi"), Ok(()));
+/// assert_eq!(pupil::check(&env, "1 ? 2 : x").map_err(|e| e.kind), Err(pupil::ErrorKind::EnvErrorNotFound));
+/// ```
+pub fn check(env: &dyn Env, input: &str) -> Result<(), Error> {
+	run(Check { env: Some(env), keys: Vec::new() }, input)
+}
+
+/// Checks the expression like [`check`] but reports all errors instead of stopping at the first.
+///
+/// After an error the tokens are skipped up to the next comma or closing bracket of the enclosing brackets where checking resumes.
+/// Errors outside of brackets end the checking. At most one error is reported per position.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// let errors = pupil::lint(&env, "max(1 2, hello(3), 4 +) * pi");
+/// assert_eq!(errors.iter().map(|e| e.position).collect::<Vec<_>>(), [6, 9, 22]);
+/// assert_eq!(pupil::lint(&env, "max(1, 2)"), []);
+/// ```
+pub fn lint(env: &dyn Env, input: &str) -> Vec<Error> {
+	let mut parser = Parser::new(Check { env: Some(env), keys: Vec::new() });
+	let mut errors = Vec::new();
+	let mut report = |error: Error| {
+		if !errors.iter().any(|known: &Error| known.position == error.position) {
+			errors.push(error);
+		}
+	};
+	// Number of brackets opened while skipping the tokens after an error
+	let mut skip = None;
+	for tok in tokenize_checked(input) {
+		let tok = match tok {
+			Ok(tok) => tok,
+			Err(error) => {
+				// The variable before the bad token is still checked
+				if skip.is_none() {
+					if let Err(error) = parser.flush_key() {
+						report(error);
+					}
+				}
+				report(error.into());
+				skip.get_or_insert(0);
+				continue;
+			},
+		};
+		let brackets = parser.brackets();
+		let result = match (skip, &tok.kind) {
+			(None, _) => parser.parse(tok.clone()),
+			(Some(depth), TokenKind::Open(_) | TokenKind::Group(_)) => {
+				skip = Some(depth + 1);
+				continue;
+			},
+			(Some(0), TokenKind::Comma | TokenKind::Close(_)) => {
+				match parser.resync(&tok, brackets) {
+					Ok(resumed) => if resumed { skip = None },
+					Err(error) => report(error),
+				}
+				continue;
+			},
+			(Some(depth), TokenKind::Close(_)) => {
+				skip = Some(depth - 1);
+				continue;
+			},
+			(Some(_), _) => continue,
+		};
+		if let Err(error) = result {
+			report(error);
+			skip = match tok.kind {
+				// The offending bracket was never opened
+				TokenKind::Open(_) | TokenKind::Group(_) => Some(1),
+				// The offending token may resume right away, eg. `max(1,,2)`
+				TokenKind::Comma | TokenKind::Close(_) if parser.resync(&tok, brackets).unwrap_or(false) => None,
+				_ => Some(0),
+			};
+		}
+	}
+	// Unfinished after skipping the remaining tokens
+	if skip.is_none() {
+		if let Err(error) = parser.finish(input.len()) {
+			report(error);
+		}
+	}
+	errors
+}
+
+/// Returns the distinct variable names used by the expression in order of first appearance.
+///
+/// Syntax errors are reported but no names are looked up.
+///
+/// ```
+/// assert_eq!(pupil::variables("2x y + max(x, z)"), Ok(vec!["x", "y", "z"]));
+/// ```
+pub fn variables(input: &str) -> Result<Vec<&str>, Error> {
+	distinct(input, |kind| match kind {
+		TokenKind::Var(name) => Some(name),
+		_ => None,
+	})
+}
+
+/// Returns the distinct function names called by the expression in order of first appearance.
+///
+/// Grouping parentheses are not a function call.
+/// Syntax errors are reported but no names are looked up.
+///
+/// ```
+/// assert_eq!(pupil::functions("max(1, sin(x)) * (2 + max(3, 4))"), Ok(vec!["max", "sin"]));
+/// ```
+pub fn functions(input: &str) -> Result<Vec<&str>, Error> {
+	distinct(input, |kind| match kind {
+		TokenKind::Open(name) if !name.is_empty() => Some(name),
+		_ => None,
+	})
+}
+
+//----------------------------------------------------------------
+
+// Collects the distinct names selected from the tokens after checking the syntax.
+fn distinct<'a>(input: &'a str, select: fn(TokenKind<'a>) -> Option<&'a str>) -> Result<Vec<&'a str>, Error> {
+	run(Check { env: None, keys: Vec::new() }, input)?;
+	let mut names = Vec::new();
+	for name in tokenize(input).filter_map(|tok| select(tok.kind)) {
+		if !names.contains(&name) {
+			names.push(name);
+		}
+	}
+	Ok(names)
+}
+
+// Parses the input without evaluating it.
+fn run(check: Check, input: &str) -> Result<(), Error> {
+	let mut parser = Parser::new(check);
+	for tok in tokenize(input) {
+		parser.parse(tok)?;
+	}
+	parser.finish(input.len())
+}
+
+// Checks the syntax and looks up the names if there is an env.
+struct Check<'a> {
+	env: Option<&'a dyn Env>,
+	// Parameters named by the keyword arguments of the pending fns
+	keys: Vec<usize>,
+}
+
+struct CheckFn<'a> {
+	// Whether the fn is a grouping bracket
+	group: bool,
+	// Parameter names of calls if there is an env
+	params: Option<&'a [&'a str]>,
+	// Number of keyword arguments
+	keywords: usize,
+}
+
+impl<'a> CheckFn<'a> {
+	fn new(group: bool) -> CheckFn<'a> {
+		CheckFn { group, params: None, keywords: 0 }
+	}
+}
+
+impl<'a> Sink for Check<'a> {
+	type Fn = CheckFn<'a>;
+	type Alloc = Heap;
+	fn lit(&mut self, _val: Value, _pos: usize) -> Result<(), ErrorKind> {
+		Ok(())
+	}
+	fn var(&mut self, name: &str, _pos: usize) -> Result<(), ErrorKind> {
+		match self.env {
+			Some(env) => env.get_value(name).map(|_| ()),
+			None => Ok(()),
+		}
+	}
+	fn call(&mut self, name: &str) -> Result<CheckFn<'a>, ErrorKind> {
+		let mut f = CheckFn::new(name.is_empty());
+		if let Some(env) = self.env {
+			env.builtin(name)?;
+			f.params = Some(env.params(name).unwrap_or(&[]));
+		}
+		Ok(f)
+	}
+	fn op(&mut self, _op: Operator) -> CheckFn<'a> {
+		CheckFn::new(false)
+	}
+	fn percent(&mut self) -> CheckFn<'a> {
+		CheckFn::new(false)
+	}
+	fn group(&mut self) -> CheckFn<'a> {
+		CheckFn::new(true)
+	}
+	fn apply(&mut self, f: CheckFn<'a>, nargs: usize, _pos: usize) -> Result<(), ErrorKind> {
+		// Grouping brackets contain a single value
+		if f.group && nargs != 1 {
+			return Err(ErrorKind::BadArgument);
+		}
+		let keys = self.keys.len().checked_sub(f.keywords).ok_or(ErrorKind::InternalError)?;
+		self.keys.truncate(keys);
+		Ok(())
+	}
+	fn keyword(&mut self, f: &mut CheckFn<'a>, index: usize, name: &str, _pos: usize) -> Result<(), ErrorKind> {
+		if f.group {
+			return Err(ErrorKind::MisplacedAssign);
+		}
+		let keys = self.keys.len().checked_sub(f.keywords).ok_or(ErrorKind::InternalError)?;
+		let param = match f.params {
+			Some(params) => parser::resolve_keyword(params, name, index, &self.keys[keys..])?,
+			None => index,
+		};
+		self.keys.push(param);
+		f.keywords += 1;
+		Ok(())
+	}
+	fn next_arg(&mut self, _f: &mut CheckFn<'a>) -> Result<(), ErrorKind> {
+		Ok(())
+	}
+	fn warn(&mut self, _kind: WarningKind, _pos: usize) {}
+	fn cond(&mut self) -> Result<(), ErrorKind> {
+		Ok(())
+	}
+	fn cond_else(&mut self) -> Result<(), ErrorKind> {
+		Ok(())
+	}
+	fn cond_end(&mut self, _pos: usize) -> Result<(), ErrorKind> {
+		Ok(())
+	}
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn checks() {
+	let env = crate::BasicEnv::default();
+	// Same errors as evaluating at the same positions
+	let inputs = [
+		"",
+		"12 5",
+		",",
+		")",
+		"*2",
+		"2 +",
+		"!&",
+		"1 + (2",
+		"(3))",
+		"2,",
+		"[]",
+		"mean",
+		"hello(5)",
+		"2 * hi",
+		"[1 + 2)",
+		"1; 2",
+		"1 ? 2",
+		"(1 ? 2) : 3",
+		"max(1, 2]",
+		"log(8, bass = 2)",
+		"log(x = 8, base = 2, x = 1)",
+		"log(x = 8, 2)",
+		"(x = 2)",
+	];
+	for input in inputs {
+		assert!(crate::eval(&env, input).is_err(), "{:?}", input);
+		assert_eq!(check(&env, input), crate::eval(&env, input).map(|_| ()), "{:?}", input);
+	}
+	// Functions are never called
+	assert_eq!(check(&env, "sqrt(-1)"), Ok(()));
+	assert_eq!(check(&env, "sqrt(1, 2) + log(1)"), Ok(()));
+	// Both branches are checked
+	assert_eq!(check(&env, "1 ? 2 : hello"), Err(Error::new(ErrorKind::EnvErrorNotFound, 8)));
+	assert_eq!(check(&env, "0 ? hello(1) : 2"), Err(Error::new(ErrorKind::EnvErrorNotFound, 4)));
+}
+#[test]
+fn lints() {
+	let env = crate::BasicEnv::default();
+	let inputs: [(&str, &[(ErrorKind, usize)]); 11] = [
+		("max(1, 2) + pi", &[]),
+		("max(1 2, 3 +, 4)", &[(ErrorKind::ExpectOperator, 6), (ErrorKind::NaExpression, 12)]),
+		("max(1,,2) * min(hello, 3)", &[(ErrorKind::NaExpression, 6), (ErrorKind::EnvErrorNotFound, 16)]),
+		("[1 2] + (x)", &[(ErrorKind::ExpectOperator, 3), (ErrorKind::EnvErrorNotFound, 9)]),
+		("f(1, 2) + max(3 $ 4)", &[(ErrorKind::EnvErrorNotFound, 0), (ErrorKind::InvalidToken, 16)]),
+		("max(y $)", &[(ErrorKind::EnvErrorNotFound, 4), (ErrorKind::InvalidToken, 6)]),
+		("max((1, 2), log(8, bass = 2))", &[(ErrorKind::BadArgument, 4), (ErrorKind::UnknownKeyword, 19)]),
+		("max(1 (2 3), 4 5)", &[(ErrorKind::ExpectOperator, 9), (ErrorKind::ExpectOperator, 15)]),
+		("max(1 2] + (3 4)", &[(ErrorKind::ExpectOperator, 6), (ErrorKind::ExpectOperator, 14)]),
+		// Errors outside of brackets end the checking
+		("1 2 + hello", &[(ErrorKind::ExpectOperator, 2)]),
+		("max(1 +, (2", &[(ErrorKind::NaExpression, 7), (ErrorKind::UnbalancedParens, 9)]),
+	];
+	for (input, expected) in inputs {
+		let errors = lint(&env, input).into_iter().map(|e| (e.kind, e.position)).collect::<Vec<_>>();
+		assert_eq!(errors, expected, "{:?}", input);
+		// The first error is the one reported by check
+		assert_eq!(check(&env, input).err(), lint(&env, input).first().copied(), "{:?}", input);
+	}
+}
+#[test]
+fn names() {
+	assert_eq!(variables("a + b * a - c"), Ok(vec!["a", "b", "c"]));
+	assert_eq!(variables("2x y"), Ok(vec!["x", "y"]));
+	assert_eq!(variables("2π r"), Ok(vec!["pi", "r"]));
+	assert_eq!(variables("1 + 2"), Ok(vec![]));
+	// Function names are not variables, even when shadowing one
+	assert_eq!(variables("f(x) + f * g(2)"), Ok(vec!["x", "f"]));
+	assert_eq!(variables("c ? [a] : {b}"), Ok(vec!["c", "a", "b"]));
+	// Syntax errors without looking up any names
+	assert_eq!(variables("x +"), Err(Error::new(ErrorKind::UnfinishedExpression, 3)));
+	assert_eq!(variables("x y)"), Err(Error::new(ErrorKind::UnbalancedParens, 3)));
+	assert_eq!(variables("f() + x"), Ok(vec!["x"]));
+	assert_eq!(variables("(x, y)"), Err(Error::new(ErrorKind::BadArgument, 0)));
+
+	assert_eq!(functions("f(g(1), f(2)) + (h(3)) * g(4)"), Ok(vec!["f", "g", "h"]));
+	assert_eq!(functions("((1) + [2]) * x"), Ok(vec![]));
+	// Variable names are not functions, even when shadowing one
+	assert_eq!(functions("f + f(x) + x"), Ok(vec!["f"]));
+	assert_eq!(functions("c ? sin(1) : cos(2)"), Ok(vec!["sin", "cos"]));
+	assert_eq!(functions("f(1"), Err(Error::new(ErrorKind::UnbalancedParens, 0)));
+}
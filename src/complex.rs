@@ -0,0 +1,288 @@
+use std::{fmt, ops};
+use crate::*;
+
+/// Complex number, see [`ComplexEnv`].
+///
+/// Real arithmetic gives the same results as [`Value`], eg. dividing by a real zero is infinite.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Complex {
+	/// Real part.
+	pub re: Value,
+	/// Imaginary part.
+	pub im: Value,
+}
+
+impl Complex {
+	/// The imaginary unit.
+	pub const I: Complex = Complex { re: 0.0, im: 1.0 };
+
+	/// Creates a complex number from its parts.
+	pub const fn new(re: Value, im: Value) -> Complex {
+		Complex { re, im }
+	}
+	/// Whether the imaginary part is zero.
+	pub fn is_real(self) -> bool {
+		self.im == 0.0
+	}
+	/// Absolute value, the distance to the origin.
+	pub fn abs(self) -> Value {
+		self.re.hypot(self.im)
+	}
+	/// Argument in radians within `-pi..=pi`, the angle to the positive real axis.
+	pub fn arg(self) -> Value {
+		self.im.atan2(self.re)
+	}
+	/// Complex conjugate.
+	pub fn conj(self) -> Complex {
+		Complex { re: self.re, im: 0.0 - self.im }
+	}
+	/// Natural exponential.
+	pub fn exp(self) -> Complex {
+		let r = self.re.exp();
+		if self.im == 0.0 {
+			return Complex::from(r);
+		}
+		Complex { re: r * self.im.cos(), im: r * self.im.sin() }
+	}
+	/// Principal natural logarithm, the imaginary part is the argument.
+	pub fn ln(self) -> Complex {
+		Complex { re: self.abs().ln(), im: self.arg() }
+	}
+	/// Principal square root, `sqrt(-1)` is `i`.
+	pub fn sqrt(self) -> Complex {
+		if self.im == 0.0 && self.re >= 0.0 {
+			return Complex::from(self.re.sqrt());
+		}
+		let r = self.abs();
+		Complex {
+			re: ((r + self.re) * 0.5).sqrt(),
+			im: ((r - self.re) * 0.5).sqrt().copysign(self.im),
+		}
+	}
+	/// Principal value of raising to a complex power.
+	///
+	/// Exact for small integer exponents, eg. `i^2` is `-1`.
+	pub fn pow(self, exp: Complex) -> Complex {
+		if exp.im == 0.0 {
+			if self.im == 0.0 && (self.re >= 0.0 || exp.re.fract() == 0.0) {
+				return Complex::from(self.re.powf(exp.re));
+			}
+			// Repeated squaring for integer exponents
+			if exp.re.fract() == 0.0 && exp.re.abs() <= 64.0 {
+				let mut base = self;
+				let mut n = exp.re.abs() as u32;
+				let mut result = Complex::from(1.0);
+				while n > 0 {
+					if n & 1 != 0 {
+						result = result * base;
+					}
+					base = base * base;
+					n >>= 1;
+				}
+				return if exp.re < 0.0 { Complex::from(1.0) / result } else { result };
+			}
+		}
+		if self == Complex::default() {
+			// Zero to a power with a positive real part
+			return if exp.re > 0.0 { self } else { Complex::new(Value::NAN, Value::NAN) };
+		}
+		(exp * self.ln()).exp()
+	}
+	/// Sine of an angle in radians.
+	pub fn sin(self) -> Complex {
+		Complex { re: self.re.sin() * self.im.cosh(), im: self.re.cos() * self.im.sinh() }
+	}
+	/// Cosine of an angle in radians.
+	pub fn cos(self) -> Complex {
+		Complex { re: self.re.cos() * self.im.cosh(), im: -self.re.sin() * self.im.sinh() }
+	}
+	/// Hyperbolic sine.
+	pub fn sinh(self) -> Complex {
+		Complex { re: self.re.sinh() * self.im.cos(), im: self.re.cosh() * self.im.sin() }
+	}
+	/// Hyperbolic cosine.
+	pub fn cosh(self) -> Complex {
+		Complex { re: self.re.cosh() * self.im.cos(), im: self.re.sinh() * self.im.sin() }
+	}
+}
+
+impl From<Value> for Complex {
+	fn from(re: Value) -> Complex {
+		Complex { re, im: 0.0 }
+	}
+}
+
+impl ops::Add for Complex {
+	type Output = Complex;
+	fn add(self, rhs: Complex) -> Complex {
+		Complex { re: self.re + rhs.re, im: self.im + rhs.im }
+	}
+}
+impl ops::Sub for Complex {
+	type Output = Complex;
+	fn sub(self, rhs: Complex) -> Complex {
+		Complex { re: self.re - rhs.re, im: self.im - rhs.im }
+	}
+}
+impl ops::Mul for Complex {
+	type Output = Complex;
+	fn mul(self, rhs: Complex) -> Complex {
+		// Keep real arithmetic free of NaN from infinities times zero
+		if self.im == 0.0 && rhs.im == 0.0 {
+			return Complex::from(self.re * rhs.re);
+		}
+		Complex {
+			re: self.re * rhs.re - self.im * rhs.im,
+			im: self.re * rhs.im + self.im * rhs.re,
+		}
+	}
+}
+impl ops::Div for Complex {
+	type Output = Complex;
+	fn div(self, rhs: Complex) -> Complex {
+		if rhs.im == 0.0 {
+			return Complex { re: self.re / rhs.re, im: if self.im == 0.0 { 0.0 } else { self.im / rhs.re } };
+		}
+		// Smith's algorithm avoids overflow in the denominator
+		if rhs.re.abs() >= rhs.im.abs() {
+			let r = rhs.im / rhs.re;
+			let d = rhs.re + rhs.im * r;
+			Complex { re: (self.re + self.im * r) / d, im: (self.im - self.re * r) / d }
+		}
+		else {
+			let r = rhs.re / rhs.im;
+			let d = rhs.re * r + rhs.im;
+			Complex { re: (self.re * r + self.im) / d, im: (self.im * r - self.re) / d }
+		}
+	}
+}
+impl ops::Neg for Complex {
+	type Output = Complex;
+	fn neg(self) -> Complex {
+		// Negated real numbers stay above the branch cut, eg. `arg(-1)` is pi
+		Complex { re: -self.re, im: 0.0 - self.im }
+	}
+}
+
+impl fmt::Display for Complex {
+	/// Formats like the input syntax, eg. `3+4i`, `-2i` or `1.5`.
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.im == 0.0 {
+			return self.re.fmt(f);
+		}
+		if self.re != 0.0 {
+			self.re.fmt(f)?;
+			if !self.im.is_sign_negative() {
+				f.write_str("+")?;
+			}
+		}
+		write!(f, "{}i", self.im)
+	}
+}
+
+impl Num for Complex {
+	#[inline]
+	fn from_f64(val: f64) -> Complex {
+		Complex::from(val)
+	}
+	#[inline]
+	fn truthy(&self) -> bool {
+		*self != Complex::default() && !self.re.is_nan() && !self.im.is_nan()
+	}
+	#[inline]
+	fn is_finite(&self) -> bool {
+		self.re.is_finite() && self.im.is_finite()
+	}
+	#[inline]
+	fn is_zero(&self) -> bool {
+		*self == Complex::default()
+	}
+	fn operator(op: Operator) -> BuiltinFn<Complex> {
+		use crate::builtins_complex::*;
+		match op {
+			Operator::Add => builtin_add,
+			Operator::Sub => builtin_sub,
+			Operator::Mul | Operator::IMul => builtin_mul,
+			Operator::Div => builtin_div,
+			Operator::Rem => builtin_rem,
+			Operator::Pow => builtin_pow,
+			Operator::And => builtin_all,
+			Operator::Or => builtin_any,
+			Operator::Sqrt => builtin_sqrt,
+			Operator::Sqr => builtin_sqr,
+			Operator::Cube => builtin_cube,
+			Operator::FloorDiv => builtin_floordiv,
+		}
+	}
+	fn percent() -> BuiltinFn<Complex> {
+		crate::builtins_complex::builtin_percent
+	}
+	fn identity() -> BuiltinFn<Complex> {
+		crate::builtins_complex::builtin_id
+	}
+	#[inline]
+	fn imaginary(val: f64) -> Option<Complex> {
+		Some(Complex { re: 0.0, im: val })
+	}
+}
+
+/// Evaluates the input with complex numbers in a default [`ComplexEnv`].
+///
+/// ```
+/// use pupil::Complex;
+/// assert_eq!(pupil::eval_complex("(1+2i)*(3-1i)"), Ok(Complex::new(5.0, 5.0)));
+/// assert_eq!(pupil::eval_complex("sqrt(-4)"), Ok(Complex::new(0.0, 2.0)));
+/// ```
+pub fn eval_complex(input: &str) -> Result<Complex, Error> {
+	eval(&ComplexEnv::default(), input)
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn arithmetic() {
+	let c = Complex::new;
+	assert_eq!(eval_complex("(1+2i)*(3-1i)"), Ok(c(5.0, 5.0)));
+	assert_eq!(eval_complex("(5+5i) / (1+2i)"), Ok(c(3.0, -1.0)));
+	assert_eq!(eval_complex("i*i"), Ok(c(-1.0, 0.0)));
+	assert_eq!(eval_complex("i^2 + i^-1"), Ok(c(-1.0, -1.0)));
+	assert_eq!(eval_complex("2^3i"), eval_complex("2^(3i)"));
+	assert_eq!(eval_complex("-4i"), Ok(c(0.0, -4.0)));
+	assert_eq!(eval_complex("2.5e1i - 1"), Ok(c(-1.0, 25.0)));
+	assert_eq!(eval_complex("abs(3+4i)"), Ok(c(5.0, 0.0)));
+	assert_eq!(eval_complex("re(3+4i) + im(3+4i)i"), Ok(c(3.0, 4.0)));
+	assert_eq!(eval_complex("conj(3+4i)"), Ok(c(3.0, -4.0)));
+	assert_eq!(eval_complex("arg(-1)"), Ok(c(std::f64::consts::PI, 0.0)));
+	assert_eq!(eval_complex("sqrt(-1)"), Ok(Complex::I));
+	assert_eq!(eval_complex("√-9 + 2²"), Ok(c(4.0, 3.0)));
+	// Real arithmetic is unchanged
+	let env = crate::BasicEnv::default();
+	for input in ["2 + 3 * 4", "1 / 0", "7 % 3 + 50%", "-7 // 2", "ln(e) + exp(0)", "sin(1) * cos(2) / tan(0.5)", "2^0.5 + 1 && 0 || 3", "0 ? 1 : 2"] {
+		assert_eq!(eval_complex(input), eval(&env, input).map(Complex::from), "{:?}", input);
+	}
+	// Euler's identity
+	let z = eval_complex("exp(i*pi)").unwrap();
+	assert!((z.re + 1.0).abs() < 1e-15 && z.im.abs() < 1e-15, "{}", z);
+	let z = eval_complex("ln(-1) / i").unwrap();
+	assert_eq!(z, c(std::f64::consts::PI, 0.0));
+	let z = eval_complex("sin(1+i)^2 + cos(1+i)^2").unwrap();
+	assert!((z.re - 1.0).abs() < 1e-15 && z.im.abs() < 1e-15, "{}", z);
+	// Suffix only on literals
+	assert_eq!(eval_complex("2in"), Err(Error::new(ErrorKind::EnvErrorNotFound, 1)));
+	assert_eq!(eval_complex("(2)i"), Ok(c(0.0, 2.0)));
+	assert_eq!(eval_complex("2i 3"), Err(Error::new(ErrorKind::ExpectOperator, 3)));
+	assert_eq!(eval_complex("(1+i) % 2"), Err(Error::new(ErrorKind::BadArgument, 6)));
+	assert_eq!(eval(&env, "2i"), Err(Error::new(ErrorKind::EnvErrorNotFound, 1)));
+}
+#[test]
+fn display() {
+	let c = Complex::new;
+	assert_eq!(c(3.0, 4.0).to_string(), "3+4i");
+	assert_eq!(c(3.0, -4.0).to_string(), "3-4i");
+	assert_eq!(c(0.0, -2.0).to_string(), "-2i");
+	assert_eq!(c(1.5, 0.0).to_string(), "1.5");
+	assert_eq!(c(0.0, 0.0).to_string(), "0");
+	let tokens = crate::lexer::tokenize_num("1+2.5i", LexerOptions::default(), true, false).collect::<Vec<_>>();
+	assert_eq!(tokens[2].kind, TokenKind::Imag(2.5));
+	assert_eq!(detokenize(&tokens), "1+2.5i");
+}
@@ -0,0 +1,536 @@
+use crate::*;
+
+// Powers of ten which fit a limb of the big integers.
+const POW10: [u64; 10] = [1, 10, 100, 1000, 10000, 100000, 1000000, 10000000, 100000000, 1000000000];
+// Limbs of the big integers scaling the literals, enough for the numerator of the smallest literals.
+const LIMBS: usize = 40;
+// Operator symbols without an ASCII equivalent, `√`, `²` and `³`.
+const UNSUPPORTED: u8 = 0x7f;
+
+macro_rules! tri {
+	($e:expr) => {
+		match $e {
+			Ok(val) => val,
+			Err(err) => return Err(err),
+		}
+	};
+}
+
+// Recursive descent with the precedence of the parser.
+struct ConstParser<'a> {
+	input: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> ConstParser<'a> {
+	// Skips whitespace and returns the next byte, zero at the end.
+	const fn peek(&mut self) -> u8 {
+		while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+			self.pos += 1;
+		}
+		self.symbol(self.pos).0
+	}
+	// Moves past the operator returned by peek.
+	const fn bump(&mut self) {
+		self.pos += self.symbol(self.pos).1;
+	}
+	const fn at(&self, pos: usize) -> u8 {
+		if pos < self.input.len() { self.input[pos] } else { 0 }
+	}
+	// Byte at the position with the unicode math symbols of the lexer replaced by their ASCII operator, and its length.
+	const fn symbol(&self, pos: usize) -> (u8, usize) {
+		match (self.at(pos), self.at(pos + 1), self.at(pos + 2)) {
+			(0xc3, 0x97, _) => (b'*', 2),
+			(0xc3, 0xb7, _) => (b'/', 2),
+			(0xe2, 0x88, 0x92) => (b'-', 3),
+			(0xe2, 0x88, 0x9a) | (0xc2, 0xb2 | 0xb3, _) => (UNSUPPORTED, 1),
+			(c, _, _) => (c, 1),
+		}
+	}
+	const fn error<T>(&self, kind: ErrorKind) -> Result<T, Error> {
+		Err(Error::new(kind, self.pos))
+	}
+	// Whether an operand starts at the position after whitespace, a `%` before it is a remainder instead of a percentage.
+	const fn operand_at(&self, mut pos: usize) -> bool {
+		while self.at(pos).is_ascii_whitespace() {
+			pos += 1;
+		}
+		let c = self.symbol(pos).0;
+		c.is_ascii_alphanumeric() || c == b'.' || c == b'(' || c == b'_' || c >= 0x80
+	}
+	// Error for what follows an operand, implicit multiplication is not supported.
+	const fn unexpected<T>(&self) -> Result<T, Error> {
+		self.error(if self.operand_at(self.pos) { ErrorKind::ExpectOperator } else { ErrorKind::InvalidToken })
+	}
+	const fn sum(&mut self) -> Result<Value, Error> {
+		let mut acc = tri!(self.product());
+		loop {
+			match self.peek() {
+				b'+' => { self.bump(); acc += tri!(self.product()); },
+				b'-' => { self.bump(); acc -= tri!(self.product()); },
+				_ => return Ok(acc),
+			}
+		}
+	}
+	const fn product(&mut self) -> Result<Value, Error> {
+		let mut acc = tri!(self.power());
+		loop {
+			let c = self.peek();
+			let next = self.at(self.pos + 1);
+			if c == b'*' && next != b'*' {
+				self.bump();
+				acc *= tri!(self.power());
+			}
+			else if c == b'/' && next == b'/' {
+				self.pos += 2;
+				acc = floor(acc / tri!(self.power()));
+			}
+			else if c == b'/' {
+				self.bump();
+				acc /= tri!(self.power());
+			}
+			else if c == b'%' {
+				self.pos += 1;
+				acc %= tri!(self.power());
+			}
+			else {
+				return Ok(acc);
+			}
+		}
+	}
+	// Right associative, binds looser than the sign like `-2^2` is 4.
+	const fn power(&mut self) -> Result<Value, Error> {
+		let base = tri!(self.unary());
+		let c = self.peek();
+		let len = if c == b'^' { 1 } else if c == b'*' && self.at(self.pos + 1) == b'*' { 2 } else { 0 };
+		if len == 0 {
+			return Ok(base);
+		}
+		let pos = self.pos;
+		self.pos += len;
+		let exp = tri!(self.power());
+		if exp as i64 as Value != exp {
+			return Err(Error::new(ErrorKind::NotInteger, pos));
+		}
+		Ok(powi(base, exp as i64))
+	}
+	const fn unary(&mut self) -> Result<Value, Error> {
+		match self.peek() {
+			b'-' => { self.bump(); Ok(-tri!(self.unary())) },
+			b'+' => { self.bump(); self.unary() },
+			_ => self.percent(),
+		}
+	}
+	const fn percent(&mut self) -> Result<Value, Error> {
+		let mut val = tri!(self.operand());
+		while self.peek() == b'%' && !self.operand_at(self.pos + 1) {
+			self.pos += 1;
+			val /= 100.0;
+		}
+		Ok(val)
+	}
+	const fn operand(&mut self) -> Result<Value, Error> {
+		let c = self.peek();
+		if c == b'(' {
+			let open = self.pos;
+			self.pos += 1;
+			let val = tri!(self.sum());
+			return match self.peek() {
+				b')' => { self.pos += 1; Ok(val) },
+				0 => Err(Error::new(ErrorKind::UnbalancedParens, open)),
+				_ => self.unexpected(),
+			};
+		}
+		if c.is_ascii_digit() || c == b'.' {
+			return self.literal();
+		}
+		if c.is_ascii_alphabetic() || c == b'_' || c >= 0x80 {
+			return self.error(ErrorKind::EnvErrorNotFound);
+		}
+		match c {
+			0 | b')' => self.error(ErrorKind::UnfinishedExpression),
+			b'*' | b'/' | b'%' | b'^' => self.error(ErrorKind::DisallowedUnary),
+			_ => self.error(ErrorKind::InvalidToken),
+		}
+	}
+	// Decimal literal correctly rounded to double precision, overflowing literals are invalid.
+	const fn literal(&mut self) -> Result<Value, Error> {
+		let start = self.pos;
+		let (mut mantissa, mut exp, mut digits, mut inexact) = (0u64, 0i32, 0, false);
+		let mut point = false;
+		loop {
+			let c = self.at(self.pos);
+			if c.is_ascii_digit() {
+				// Digits beyond the mantissa only shift it unless they're significant
+				if mantissa < 1_000_000_000_000_000_000 {
+					mantissa = mantissa * 10 + (c - b'0') as u64;
+					if point {
+						exp -= 1;
+					}
+				}
+				else {
+					inexact |= c != b'0';
+					if !point {
+						exp += 1;
+					}
+				}
+				digits += 1;
+			}
+			else if c == b'.' && !point {
+				point = true;
+			}
+			else {
+				break;
+			}
+			self.pos += 1;
+		}
+		if digits == 0 {
+			return Err(Error::new(ErrorKind::InvalidToken, start));
+		}
+		// The exponent only if digits follow, otherwise `e` is a name
+		let c = self.at(self.pos);
+		if c == b'e' || c == b'E' {
+			let sign = self.at(self.pos + 1);
+			let skip = if sign == b'-' || sign == b'+' { 2 } else { 1 };
+			if self.at(self.pos + skip).is_ascii_digit() {
+				self.pos += skip;
+				let mut e = 0i32;
+				while self.at(self.pos).is_ascii_digit() {
+					if e < 10000 {
+						e = e * 10 + (self.at(self.pos) - b'0') as i32;
+					}
+					self.pos += 1;
+				}
+				exp += if sign == b'-' { -e } else { e };
+			}
+		}
+		if mantissa == 0 {
+			return Ok(0.0);
+		}
+		// Rounding the truncated digits could round differently than the whole literal
+		if inexact {
+			return Err(Error::new(ErrorKind::InvalidToken, start));
+		}
+		let mut len = 0;
+		let mut rest = mantissa;
+		while rest != 0 {
+			rest /= 10;
+			len += 1;
+		}
+		// At least 1e309 overflows, below 1e-325 rounds to zero
+		if exp + len > 309 {
+			return Err(Error::new(ErrorKind::InvalidToken, start));
+		}
+		if exp + len < -324 {
+			return Ok(0.0);
+		}
+		let mut num = Big::from_u64(mantissa);
+		if exp >= 0 {
+			num.mul_pow10(exp as u32);
+			let bits = num.bits();
+			let shift = bits.saturating_sub(128);
+			let val = round(num.top(shift), num.any_below(shift), -(shift as i32));
+			return if val.is_finite() { Ok(val) } else { Err(Error::new(ErrorKind::InvalidToken, start)) };
+		}
+		// Divides with enough quotient bits to round
+		let mut den = Big::from_u64(1);
+		den.mul_pow10(-exp as u32);
+		let shift = den.bits() + 66 - num.bits();
+		num = num.shl(shift);
+		let top = num.bits() - den.bits();
+		let mut sub = den.shl(top);
+		let mut quot = 0u128;
+		let mut i = top + 1;
+		while i > 0 {
+			i -= 1;
+			if !num.less(&sub) {
+				num.sub(&sub);
+				quot |= 1 << i;
+			}
+			sub.shr1();
+		}
+		Ok(round(quot, num.bits() != 0, shift as i32))
+	}
+}
+
+// Little endian big integer to correctly round the literals.
+struct Big([u32; LIMBS]);
+
+impl Big {
+	const fn from_u64(x: u64) -> Big {
+		let mut limbs = [0; LIMBS];
+		limbs[0] = x as u32;
+		limbs[1] = (x >> 32) as u32;
+		Big(limbs)
+	}
+	const fn mul_pow10(&mut self, mut exp: u32) {
+		while exp > 0 {
+			let n = if exp > 9 { 9 } else { exp };
+			let factor = POW10[n as usize];
+			let mut carry = 0u64;
+			let mut i = 0;
+			while i < LIMBS {
+				let t = self.0[i] as u64 * factor + carry;
+				self.0[i] = t as u32;
+				carry = t >> 32;
+				i += 1;
+			}
+			exp -= n;
+		}
+	}
+	// Number of significant bits.
+	const fn bits(&self) -> u32 {
+		let mut i = LIMBS;
+		while i > 0 {
+			i -= 1;
+			if self.0[i] != 0 {
+				return i as u32 * 32 + 32 - self.0[i].leading_zeros();
+			}
+		}
+		0
+	}
+	const fn bit(&self, i: u32) -> bool {
+		let limb = (i / 32) as usize;
+		limb < LIMBS && self.0[limb] >> (i % 32) & 1 != 0
+	}
+	// The 128 bits starting at the bit.
+	const fn top(&self, shift: u32) -> u128 {
+		let mut x = 0u128;
+		let mut i = 0;
+		while i < 128 {
+			if self.bit(shift + i) {
+				x |= 1 << i;
+			}
+			i += 1;
+		}
+		x
+	}
+	// Whether any bit below the bit is set.
+	const fn any_below(&self, shift: u32) -> bool {
+		let mut i = 0;
+		while i < shift {
+			if self.bit(i) {
+				return true;
+			}
+			i += 1;
+		}
+		false
+	}
+	const fn shl(&self, shift: u32) -> Big {
+		let mut limbs = [0; LIMBS];
+		let (words, bits) = ((shift / 32) as usize, shift % 32);
+		let mut i = LIMBS;
+		while i > words {
+			i -= 1;
+			limbs[i] = self.0[i - words] << bits;
+			if bits != 0 && i > words {
+				limbs[i] |= self.0[i - words - 1] >> (32 - bits);
+			}
+		}
+		Big(limbs)
+	}
+	const fn shr1(&mut self) {
+		let mut i = 0;
+		while i < LIMBS {
+			self.0[i] >>= 1;
+			if i + 1 < LIMBS {
+				self.0[i] |= self.0[i + 1] << 31;
+			}
+			i += 1;
+		}
+	}
+	const fn less(&self, other: &Big) -> bool {
+		let mut i = LIMBS;
+		while i > 0 {
+			i -= 1;
+			if self.0[i] != other.0[i] {
+				return self.0[i] < other.0[i];
+			}
+		}
+		false
+	}
+	const fn sub(&mut self, other: &Big) {
+		let mut borrow = 0u64;
+		let mut i = 0;
+		while i < LIMBS {
+			let t = (self.0[i] as u64).wrapping_sub(other.0[i] as u64 + borrow);
+			self.0[i] = t as u32;
+			borrow = t >> 63;
+			i += 1;
+		}
+	}
+}
+
+// Rounds `x * 2^-shift` to nearest even, the sticky bit tells if the exact value is above `x`.
+const fn round(x: u128, sticky: bool, shift: i32) -> Value {
+	let bits = 128 - x.leading_zeros() as i32;
+	// Keeps 53 bits, or less for subnormals which have their last bit at 2^-1074
+	let drop = if bits - 1 - shift < -1022 { shift - 1074 } else { bits - 53 };
+	if drop <= 0 {
+		return scale(x as Value, -shift);
+	}
+	if drop > 128 {
+		return 0.0;
+	}
+	let mut m = if drop == 128 { 0 } else { x >> drop };
+	let half = x >> (drop - 1) & 1 != 0;
+	let below = sticky || x & ((1 << (drop - 1)) - 1) != 0;
+	if half && (below || m & 1 != 0) {
+		m += 1;
+	}
+	scale(m as Value, drop - shift)
+}
+
+// Multiplies by a power of two in two steps for exponents beyond the normal range.
+const fn scale(x: Value, exp: i32) -> Value {
+	let half = exp / 2;
+	x * powi(2.0, half as i64) * powi(2.0, (exp - half) as i64)
+}
+
+const fn floor(x: Value) -> Value {
+	// Larger magnitudes and NaN are integers already
+	if !(x > -4503599627370496.0 && x < 4503599627370496.0) {
+		return x;
+	}
+	let t = x as i64 as Value;
+	if t > x { t - 1.0 } else { t }
+}
+
+// Exponentiation by squaring.
+const fn powi(base: Value, exp: i64) -> Value {
+	let mut n = exp.unsigned_abs();
+	let (mut acc, mut sq) = (1.0, base);
+	while n != 0 {
+		if n & 1 != 0 {
+			acc *= sq;
+		}
+		sq *= sq;
+		n >>= 1;
+	}
+	if exp < 0 { 1.0 / acc } else { acc }
+}
+
+/// Evaluates an expression of literals and operators in a const context, see [`expr!`](crate::expr!).
+///
+/// Supports the arithmetic operators `+ - * / // % ^ **`, percentages and parentheses with the precedence of [`eval`].
+/// Names and implicit multiplication are not supported and fail with [`ErrorKind::EnvErrorNotFound`] and [`ErrorKind::ExpectOperator`].
+///
+/// Literals are correctly rounded like [`eval`], literals with more than 19 significant digits or overflowing to infinity are an [`ErrorKind::InvalidToken`].
+/// The math symbols `×`, `÷` and `−` are supported, `√`, `²` and `³` are an [`ErrorKind::InvalidToken`].
+/// Powers must be integers and are computed by repeated squaring, which may differ in the last digit from `pow`.
+///
+/// ```
+/// const AREA: Result<f64, pupil::Error> = pupil::eval_const("(2 + 3) * 4^2 / 10");
+/// assert_eq!(AREA, Ok(8.0));
+/// ```
+pub const fn eval_const(input: &str) -> Result<Value, Error> {
+	let mut parser = ConstParser { input: input.as_bytes(), pos: 0 };
+	let val = tri!(parser.sum());
+	match parser.peek() {
+		0 => Ok(val),
+		b')' => parser.error(ErrorKind::UnbalancedParens),
+		_ => parser.unexpected(),
+	}
+}
+
+/// Evaluates like [`eval_const`] and panics on errors, which fails the compilation of [`expr!`](crate::expr!).
+#[doc(hidden)]
+pub const fn eval_const_or_panic(input: &str) -> Value {
+	match eval_const(input) {
+		Ok(val) => val,
+		Err(err) => panic!("{}", match err.kind {
+			ErrorKind::EnvErrorNotFound => "names are not supported in const expressions",
+			ErrorKind::ExpectOperator => "expected an operator",
+			ErrorKind::NotInteger => "powers must be integers in const expressions",
+			ErrorKind::InvalidToken if input.as_bytes()[err.position].is_ascii_digit() || input.as_bytes()[err.position] == b'.' => "literal out of range or with too many digits",
+			ErrorKind::InvalidToken => "invalid or unsupported token in const expressions",
+			ErrorKind::UnbalancedParens => "unbalanced parens",
+			ErrorKind::DisallowedUnary => "not an unary operator",
+			_ => "unfinished expression",
+		}),
+	}
+}
+
+/// Evaluates an expression of literals and operators at compile time, see [`eval_const`].
+///
+/// ```
+/// const SPEED: f64 = pupil::expr!("340.29 * 2");
+/// assert_eq!(SPEED, 680.58);
+/// ```
+///
+/// Names, functions and syntax errors fail to compile:
+///
+/// ```compile_fail
+/// const SPEED: f64 = pupil::expr!("340.29 * mach");
+/// ```
+///
+/// ```compile_fail
+/// const ANGLE: f64 = pupil::expr!("sin(2)");
+/// ```
+///
+/// ```compile_fail
+/// const SUM: f64 = pupil::expr!("(1 + 2");
+/// ```
+#[macro_export]
+macro_rules! expr {
+	($input:expr) => {{
+		const VALUE: $crate::Value = $crate::eval_const_or_panic($input);
+		VALUE
+	}};
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn agrees() {
+	let env = BasicEnv::default();
+	let inputs = [
+		"340.29 * 2", "1 + 2 * 3 - 4 / 5", "-2^2", "2^-1", "2^3^2", "2**10", "(1 + 2) * (3 - 4)",
+		"-3 % 2", "7%-3", "50% * 2", "5 % 3", "-7 // 2", ".5 + 5.", "1e3 - 2.5E-3", "0.1 + 0.2",
+		"123456789012345", "1e22 * 1e-22", "3e30", "--2", "+1", "1.5e+2",
+		"2 × 3 ÷ 4 − 1", "1e-30 * 1e30", "3.14159265358979323",
+	];
+	for input in inputs {
+		let expected = eval(&env, input).unwrap();
+		assert_eq!(eval_const(input).map(Value::to_bits), Ok(expected.to_bits()), "{}", input);
+	}
+	const SPEED: Value = crate::expr!("340.29 * 2");
+	assert_eq!(SPEED, 680.58);
+}
+#[test]
+fn errors() {
+	let env = BasicEnv::default();
+	// Same errors as the evaluator
+	for input in ["2)", "(2", "2 3", "", "2 +", "x", "2 + * 3", "1.5.5", "3!", "2 ^ ^ 3", "("] {
+		assert_eq!(eval_const(input), eval(&env, input), "{}", input);
+	}
+	assert_eq!(eval_const("1 + sin(1)"), Err(Error::new(ErrorKind::EnvErrorNotFound, 4)));
+	assert_eq!(eval_const("2(3)"), Err(Error::new(ErrorKind::ExpectOperator, 1)));
+	assert_eq!(eval_const("2^0.5"), Err(Error::new(ErrorKind::NotInteger, 1)));
+	assert_eq!(eval_const("0.12345678901234567891"), Err(Error::new(ErrorKind::InvalidToken, 0)));
+	assert_eq!(eval_const("1e400"), Err(Error::new(ErrorKind::InvalidToken, 0)));
+	// Math symbols without an ASCII operator
+	assert_eq!(eval_const("√4"), Err(Error::new(ErrorKind::InvalidToken, 0)));
+	assert_eq!(eval_const("2 + 3²"), Err(Error::new(ErrorKind::InvalidToken, 5)));
+	assert_eq!(eval_const("2π"), Err(Error::new(ErrorKind::ExpectOperator, 1)));
+	assert_eq!(eval_const("π"), Err(Error::new(ErrorKind::EnvErrorNotFound, 0)));
+}
+#[test]
+fn literals() {
+	// Correctly rounded at any exponent
+	let inputs = [
+		"1e-23", "1e-30", "1.7976931348623157e308", "2.2250738585072014e-308", "4.9e-324", "2.4703282292062328e-324",
+		"1e-400", "123456789012345678e-5", "9007199254740993", "0.30000000000000004", "1e23", "8.98846567431158e307",
+		"6.02214076e23", "1.602176634e-19", "2.5e-324", "5e-324", "1e308", "1.7976931348623158e308",
+	];
+	for input in inputs {
+		let expected: Value = input.parse().unwrap();
+		assert_eq!(eval_const(input).map(Value::to_bits), Ok(expected.to_bits()), "{}", input);
+	}
+	const TINY: Value = crate::expr!("1e-30 + 4.9e-324");
+	assert_eq!(TINY, 1e-30);
+	const HUGE: Value = crate::expr!("1234567890123456789e289");
+	assert_eq!(HUGE, "1234567890123456789e289".parse::<Value>().unwrap());
+	// Overflows
+	assert_eq!(eval_const("1.7976931348623159e308"), Err(Error::new(ErrorKind::InvalidToken, 0)));
+}
@@ -1,173 +1,984 @@
-use std::{error, fmt};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::{ErrorKind, Num};
 
 //----------------------------------------------------------------
 
-/// Things that can go wrong.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-#[non_exhaustive]
-pub enum Error {
-	/// Expected an operator-like thing.
+/// Underlying type used for arithmetic.
+pub type Value = f64;
+
+/// Signature for builtins.
+pub type BuiltinFn<V = Value> = fn(env: &dyn Env<V>, vals: &mut [V]) -> Result<V, ErrorKind>;
+
+/// Builtin function descriptor.
+#[derive(Copy, Clone, Debug)]
+pub struct BuiltinDesc<'a, V: Num = Value> {
+	/// Name used to call the builtin.
+	pub name: &'a str,
+	/// The function implementation.
+	pub pfn: BuiltinFn<V>,
+	/// Whether the builtin always returns the same result for the same arguments.
 	///
-	/// Eg. `12 5`. Expected an operator instead of `5`.
-	ExpectOperator,
-	/// Expected a value-like thing.
-	NaExpression,
-	/// Disallowed unary operator.
+	/// Only pure builtins are evaluated ahead of time, see [`Ast::fold`](crate::Ast::fold).
+	pub pure: bool,
+	/// Whether the builtin divides by its last argument.
 	///
-	/// Only `+` and `-` are allowed as unary operators.
-	DisallowedUnary,
-	/// Something went wrong unexpectedly.
+	/// Strict evaluation reports a non-finite result as [`ErrorKind::DivisionByZero`] when that argument is zero.
+	pub divides: bool,
+	/// Number of arguments accepted by the builtin.
+	pub arity: Arity,
+	/// Names of the arguments for keyword arguments, empty if not accepted.
+	pub params: &'a [&'a str],
+	/// Category the builtin is listed under in help.
+	pub category: Category,
+	/// One line description for help.
+	pub desc: &'a str,
+}
+
+impl<'a, V: Num> BuiltinDesc<'a, V> {
+	/// Returns how the builtin is called, eg. `round(x[, digits])`, `mean(x, ...)` or `mul(x1, x2, ...)`.
 	///
-	/// This is a bug.
-	InternalError,
-	/// Expression isn’t finished, cannot end with an operator.
+	/// Optional arguments are in brackets, builtins taking no arguments are constants written without parentheses.
 	///
-	/// Eg. `2 +`.
-	UnfinishedExpression,
-	/// Tokenization failed to lex a token.
-	InvalidToken,
-	/// Different number of `(` and `)`.
-	UnbalancedParens,
-	/// Misplaced a comma token outside of a function application.
-	MisplacedComma,
-	/// Bad number of arguments.
-	BadArgument,
-	/// A variable or function symbol wasn’t found.
-	EnvErrorNotFound,
-	/// Expected a variable name, found a builtin symbol instead.
-	EnvErrorBuiltinFn,
-}
-impl fmt::Display for Error {
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let sig = |name| env.builtins.iter().find(|desc| desc.name == name).unwrap().signature();
+	/// assert_eq!(sig("log"), "log(x, base)");
+	/// assert_eq!(sig("approx"), "approx(a, b[, rel][, abs])");
+	/// assert_eq!(sig("mean"), "mean(x, ...)");
+	/// assert_eq!(sig("kurt"), "kurt(x1, x2, x3, x4, ...)");
+	/// assert_eq!(sig("pi"), "pi");
+	/// ```
+	pub fn signature(&self) -> String {
+		if self.arity.max == 0 {
+			return self.name.to_string();
+		}
+		let mut sig = format!("{}(", self.name);
+		if self.params.is_empty() {
+			// Variadic builtins name their arguments x, numbered when more than one is required
+			if self.arity.min <= 1 {
+				sig.push('x');
+			}
+			else {
+				let names: Vec<String> = (1..=self.arity.min).map(|i| format!("x{}", i)).collect();
+				sig.push_str(&names.join(", "));
+			}
+		}
+		else {
+			for (i, param) in self.params.iter().enumerate() {
+				if i < self.arity.min {
+					if i > 0 { sig.push_str(", "); }
+					sig.push_str(param);
+				}
+				else if i < self.arity.max {
+					sig.push_str(if i > 0 { "[, " } else { "[" });
+					sig.push_str(param);
+					sig.push(']');
+				}
+			}
+		}
+		if self.arity.max == usize::MAX {
+			sig.push_str(", ...");
+		}
+		sig.push(')');
+		sig
+	}
+}
+
+/// Categories of builtins, in the order they are listed in help.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Category {
+	/// Builtins without arguments, eg. `pi`.
+	Constant,
+	/// Basic operations, eg. `add` or `sqrt`.
+	Arithmetic,
+	/// Rounding and floating point representation, eg. `round` or `ulp`.
+	Rounding,
+	/// Exponentials and logarithms, eg. `exp` or `log`.
+	Exponential,
+	/// Trigonometry and geometry, eg. `sin` or `hypot`.
+	Trigonometry,
+	/// Interpolation and easing, eg. `lerp` or `smoothstep`.
+	Interpolation,
+	/// Special functions, eg. `gamma` or `erf`.
+	Special,
+	/// Statistics over the arguments, eg. `mean` or `stdev`.
+	Statistics,
+	/// Integer and bitwise functions, eg. `gcd` or `band`.
+	Integer,
+	/// Logic and comparison, eg. `and` or `approx`.
+	Logic,
+	/// Financial functions, eg. `pmt`.
+	Finance,
+	/// Random numbers, eg. `roll`.
+	Random,
+}
+
+impl Category {
+	/// All categories in order.
+	pub const ALL: [Category; 12] = [
+		Category::Constant, Category::Arithmetic, Category::Rounding, Category::Exponential,
+		Category::Trigonometry, Category::Interpolation, Category::Special, Category::Statistics,
+		Category::Integer, Category::Logic, Category::Finance, Category::Random,
+	];
+}
+
+impl fmt::Display for Category {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		let desc = match self {
-			Error::ExpectOperator => "expected an operator",
-			Error::NaExpression => "not an expression",
-			Error::DisallowedUnary => "not an unary operator",
-			Error::InternalError => "internal corruption",
-			Error::UnfinishedExpression => "unfinished expression",
-			Error::InvalidToken => "invalid token",
-			Error::UnbalancedParens => "unbalanced parens",
-			Error::MisplacedComma => "misplaced comma",
-			Error::BadArgument => "bad argument",
-			Error::EnvErrorNotFound => "env error not found",
-			Error::EnvErrorBuiltinFn => "env error builtin",
+		let name = match self {
+			Category::Constant => "Constants",
+			Category::Arithmetic => "Arithmetic",
+			Category::Rounding => "Rounding",
+			Category::Exponential => "Exponentials and logarithms",
+			Category::Trigonometry => "Trigonometry and geometry",
+			Category::Interpolation => "Interpolation",
+			Category::Special => "Special functions",
+			Category::Statistics => "Statistics",
+			Category::Integer => "Integers and bits",
+			Category::Logic => "Logic and comparison",
+			Category::Finance => "Finance",
+			Category::Random => "Random",
 		};
-		desc.fmt(f)
+		f.write_str(name)
 	}
 }
-impl error::Error for Error {}
 
-//----------------------------------------------------------------
+/// Number of arguments accepted by a builtin.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arity {
+	/// Minimum number of arguments.
+	pub min: usize,
+	/// Maximum number of arguments, `usize::MAX` if variadic.
+	pub max: usize,
+}
 
-/// Underlying type used for arithmetic.
-pub type Value = f64;
+impl Arity {
+	/// Accepts exactly this many arguments.
+	pub const fn exactly(n: usize) -> Arity {
+		Arity { min: n, max: n }
+	}
+	/// Accepts at least this many arguments.
+	pub const fn at_least(min: usize) -> Arity {
+		Arity { min, max: usize::MAX }
+	}
+	/// Accepts between min and max arguments inclusive.
+	pub const fn between(min: usize, max: usize) -> Arity {
+		Arity { min, max }
+	}
+	/// Returns whether this many arguments are accepted.
+	pub const fn accepts(self, nargs: usize) -> bool {
+		nargs >= self.min && nargs <= self.max
+	}
+	/// Checks the number of arguments.
+	///
+	/// ```
+	/// use pupil::{Arity, ErrorKind};
+	/// assert_eq!(Arity::exactly(3).check(3), Ok(()));
+	/// assert_eq!(Arity::exactly(3).check(2), Err(ErrorKind::ArgumentCount { expected: Arity::exactly(3), got: 2 }));
+	/// ```
+	pub const fn check(self, nargs: usize) -> Result<(), ErrorKind> {
+		if self.accepts(nargs) { Ok(()) }
+		else { Err(ErrorKind::ArgumentCount { expected: self, got: nargs }) }
+	}
+}
 
-/// Signature for builtins.
-pub type BuiltinFn = fn(env: &dyn Env, vals: &mut [Value]) -> Result<Value, Error>;
+impl fmt::Display for Arity {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let plural = |n: usize| if n == 1 { "argument" } else { "arguments" };
+		match (self.min, self.max) {
+			(0, 0) => f.write_str("no arguments"),
+			(min, usize::MAX) => write!(f, "at least {} {}", min, plural(min)),
+			(min, max) if min == max => write!(f, "{} {}", min, plural(min)),
+			(min, max) => write!(f, "{} to {} arguments", min, max),
+		}
+	}
+}
 
-static DEFAULT_BUILTINS: [(&str, BuiltinFn); 48] = {
+/// The default builtins sorted by name.
+///
+/// One letter builtins which take arguments, like `d(sides)`, only resolve when called,
+/// the name is otherwise left to a variable.
+static DEFAULT_BUILTINS: [BuiltinDesc; 149] = {
 use crate::builtins::*;
 [
-	("", builtin_id),
-	("abs", builtin_abs),
-	("acos", builtin_cos),
-	("acosh", builtin_acosh),
-	("add", builtin_add),
-	("asin", builtin_sin),
-	("asinh", builtin_asinh),
-	("atan", builtin_tan),
-	("atan2", builtin_atan2),
-	("atanh", builtin_atanh),
-	("cbrt", builtin_cbrt),
-	("ceil", builtin_ceil),
-	("cos", builtin_cos),
-	("cosh", builtin_cosh),
-	("cube", builtin_cube),
-	("deg", builtin_deg),
-	("div", builtin_div),
-	("e", builtin_e),
-	("exp", builtin_exp),
-	("exp2", builtin_exp2),
-	("expm1", builtin_expm1),
-	("floor", builtin_floor),
-	("ln", builtin_ln),
-	("ln1p", builtin_ln1p),
-	("log", builtin_log),
-	("log10", builtin_log10),
-	("log2", builtin_log2),
-	("max", builtin_max),
-	("mean", builtin_mean),
-	("median", builtin_median),
-	("min", builtin_min),
-	("mul", builtin_mul),
-	("pi", builtin_pi),
-	("pow", builtin_pow),
-	("rad", builtin_rad),
-	("range", builtin_range),
-	("rem", builtin_rem),
-	("round", builtin_round),
-	("sin", builtin_sin),
-	("sinh", builtin_sinh),
-	("sqr", builtin_sqr),
-	("sqrt", builtin_sqrt),
-	("stdev", builtin_stdev),
-	("sub", builtin_sub),
-	("tan", builtin_tan),
-	("tanh", builtin_tanh),
-	("tau", builtin_tau),
-	("var", builtin_var),
+	BuiltinDesc { name: "", pfn: builtin_id, pure: true, divides: false, arity: Arity::exactly(1), params: &[], category: Category::Arithmetic, desc: "Identity, used for parenthesized groups." },
+	BuiltinDesc { name: "abs", pfn: builtin_abs, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Absolute value." },
+	BuiltinDesc { name: "acos", pfn: builtin_acos, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc cosine in radians." },
+	BuiltinDesc { name: "acosd", pfn: builtin_acosd, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc cosine in degrees." },
+	BuiltinDesc { name: "acosh", pfn: builtin_acosh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Inverse hyperbolic cosine." },
+	BuiltinDesc { name: "add", pfn: builtin_add, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Sum of the arguments with compensated summation." },
+	BuiltinDesc { name: "all", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if all arguments are truthy, NaN is falsy." },
+	BuiltinDesc { name: "and", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting all, stops at the first falsy argument." },
+	BuiltinDesc { name: "angle_between", pfn: builtin_angle_between, pure: true, divides: false, arity: Arity::exactly(4), params: &["x1", "y1", "x2", "y2"], category: Category::Trigonometry, desc: "Signed angle from vector 1 to vector 2 in (-pi, pi]." },
+	BuiltinDesc { name: "any", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if any argument is truthy, NaN is falsy." },
+	BuiltinDesc { name: "approx", pfn: builtin_approx, pure: true, divides: false, arity: Arity::between(2, 4), params: &["a", "b", "rel", "abs"], category: Category::Logic, desc: "1 if a and b are equal within a relative and absolute tolerance." },
+	BuiltinDesc { name: "argmax", pfn: builtin_argmax, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Zero based index of the largest argument, NaN is skipped." },
+	BuiltinDesc { name: "argmin", pfn: builtin_argmin, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Zero based index of the smallest argument, NaN is skipped." },
+	BuiltinDesc { name: "asin", pfn: builtin_asin, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc sine in radians." },
+	BuiltinDesc { name: "asind", pfn: builtin_asind, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc sine in degrees." },
+	BuiltinDesc { name: "asinh", pfn: builtin_asinh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Inverse hyperbolic sine." },
+	BuiltinDesc { name: "atan", pfn: builtin_atan, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc tangent in radians." },
+	BuiltinDesc { name: "atan2", pfn: builtin_atan2, pure: true, divides: false, arity: Arity::exactly(2), params: &["y", "x"], category: Category::Trigonometry, desc: "Angle of the point (x, y) in radians." },
+	BuiltinDesc { name: "atan2d", pfn: builtin_atan2d, pure: true, divides: false, arity: Arity::exactly(2), params: &["y", "x"], category: Category::Trigonometry, desc: "Angle of the point (x, y) in degrees." },
+	BuiltinDesc { name: "atand", pfn: builtin_atand, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc tangent in degrees." },
+	BuiltinDesc { name: "atanh", pfn: builtin_atanh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Inverse hyperbolic tangent." },
+	BuiltinDesc { name: "band", pfn: builtin_band, pure: true, divides: false, arity: Arity::exactly(2), params: &["a", "b"], category: Category::Integer, desc: "Bitwise and of integers up to 2^53." },
+	BuiltinDesc { name: "between", pfn: builtin_between, pure: true, divides: false, arity: Arity::between(3, 4), params: &["x", "lo", "hi", "open"], category: Category::Logic, desc: "1 if lo <= x <= hi, exclusive when open is truthy." },
+	BuiltinDesc { name: "bit", pfn: builtin_bit, pure: true, divides: false, arity: Arity::exactly(2), params: &["a", "i"], category: Category::Integer, desc: "Bit i of an integer counting from the least significant bit." },
+	BuiltinDesc { name: "bnot", pfn: builtin_bnot, pure: true, divides: false, arity: Arity::between(1, 2), params: &["a", "width"], category: Category::Integer, desc: "Bitwise not within a width of 1 to 53 bits, 32 by default." },
+	BuiltinDesc { name: "bor", pfn: builtin_bor, pure: true, divides: false, arity: Arity::exactly(2), params: &["a", "b"], category: Category::Integer, desc: "Bitwise or of integers up to 2^53." },
+	BuiltinDesc { name: "bxor", pfn: builtin_bxor, pure: true, divides: false, arity: Arity::exactly(2), params: &["a", "b"], category: Category::Integer, desc: "Bitwise exclusive or of integers up to 2^53." },
+	BuiltinDesc { name: "cart_x", pfn: builtin_cart_x, pure: true, divides: false, arity: Arity::exactly(2), params: &["r", "theta"], category: Category::Trigonometry, desc: "X coordinate of the polar point (r, theta)." },
+	BuiltinDesc { name: "cart_y", pfn: builtin_cart_y, pure: true, divides: false, arity: Arity::exactly(2), params: &["r", "theta"], category: Category::Trigonometry, desc: "Y coordinate of the polar point (r, theta)." },
+	BuiltinDesc { name: "cbrt", pfn: builtin_cbrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube root." },
+	BuiltinDesc { name: "ceil", pfn: builtin_ceil, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "digits"], category: Category::Rounding, desc: "Rounds towards positive infinity, optionally at decimal places." },
+	BuiltinDesc { name: "chance", pfn: builtin_chance, pure: false, divides: false, arity: Arity::exactly(1), params: &["p"], category: Category::Random, desc: "1 with probability p, 0 otherwise." },
+	BuiltinDesc { name: "clamp", pfn: builtin_clamp, pure: true, divides: false, arity: Arity::exactly(3), params: &["x", "lo", "hi"], category: Category::Arithmetic, desc: "Clamps x to lo..=hi, swapped bounds are an error." },
+	BuiltinDesc { name: "cmp", pfn: builtin_cmp, pure: true, divides: false, arity: Arity::exactly(2), params: &["a", "b"], category: Category::Logic, desc: "Compares a and b, returns -1, 0 or 1." },
+	BuiltinDesc { name: "copysign", pfn: builtin_copysign, pure: true, divides: false, arity: Arity::exactly(2), params: &["mag", "sign"], category: Category::Arithmetic, desc: "Magnitude of mag with the sign of sign." },
+	BuiltinDesc { name: "cos", pfn: builtin_cos, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Cosine of an angle in radians." },
+	BuiltinDesc { name: "cosd", pfn: builtin_cosd, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Cosine of an angle in degrees." },
+	BuiltinDesc { name: "cosh", pfn: builtin_cosh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic cosine." },
+	BuiltinDesc { name: "cot", pfn: builtin_cot, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Cotangent of an angle in radians." },
+	BuiltinDesc { name: "count", pfn: builtin_count, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Number of arguments." },
+	BuiltinDesc { name: "csc", pfn: builtin_csc, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Cosecant of an angle in radians." },
+	BuiltinDesc { name: "cube", pfn: builtin_cube, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube of the argument." },
+	BuiltinDesc { name: "d", pfn: builtin_d, pure: false, divides: false, arity: Arity::exactly(1), params: &["sides"], category: Category::Random, desc: "Rolls a die with a number of sides." },
+	BuiltinDesc { name: "deg", pfn: builtin_deg, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Converts radians to degrees." },
+	BuiltinDesc { name: "dist", pfn: builtin_dist, pure: true, divides: false, arity: Arity::exactly(4), params: &["x1", "y1", "x2", "y2"], category: Category::Trigonometry, desc: "Distance between the points (x1, y1) and (x2, y2)." },
+	BuiltinDesc { name: "div", pfn: builtin_div, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Divides x by y." },
+	BuiltinDesc { name: "dot", pfn: builtin_dot, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Statistics, desc: "Dot product of interleaved pairs." },
+	BuiltinDesc { name: "e", pfn: builtin_e, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Euler's number." },
+	BuiltinDesc { name: "erf", pfn: builtin_erf, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Special, desc: "Error function." },
+	BuiltinDesc { name: "erfc", pfn: builtin_erfc, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Special, desc: "Complementary error function." },
+	BuiltinDesc { name: "exp", pfn: builtin_exp, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural exponential." },
+	BuiltinDesc { name: "exp10", pfn: builtin_exp10, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Ten raised to the power." },
+	BuiltinDesc { name: "exp2", pfn: builtin_exp2, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Two raised to the power." },
+	BuiltinDesc { name: "expm1", pfn: builtin_expm1, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "exp(x) - 1 accurate near zero." },
+	BuiltinDesc { name: "exponent", pfn: builtin_exponent, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Rounding, desc: "Binary exponent, x is mantissa(x) * 2^exponent(x)." },
+	BuiltinDesc { name: "fac", pfn: builtin_fac, pure: true, divides: false, arity: Arity::exactly(1), params: &["n"], category: Category::Special, desc: "Factorial of an integer." },
+	BuiltinDesc { name: "fib", pfn: builtin_fib, pure: true, divides: false, arity: Arity::exactly(1), params: &["n"], category: Category::Integer, desc: "Fibonacci number, n up to 78." },
+	BuiltinDesc { name: "floor", pfn: builtin_floor, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "digits"], category: Category::Rounding, desc: "Rounds towards negative infinity, optionally at decimal places." },
+	BuiltinDesc { name: "fv", pfn: builtin_fv, pure: true, divides: false, arity: Arity::between(3, 4), params: &["rate", "nper", "pmt", "pv"], category: Category::Finance, desc: "Future value of periodic payments." },
+	BuiltinDesc { name: "gamma", pfn: builtin_gamma, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Special, desc: "Gamma function." },
+	BuiltinDesc { name: "gcd", pfn: builtin_gcd, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Integer, desc: "Greatest common divisor." },
+	BuiltinDesc { name: "geomean", pfn: builtin_geomean, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Geometric mean." },
+	BuiltinDesc { name: "harmmean", pfn: builtin_harmmean, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Harmonic mean." },
+	BuiltinDesc { name: "hypot", pfn: builtin_hypot, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Trigonometry, desc: "Length of the hypotenuse without overflow." },
+	BuiltinDesc { name: "invlerp", pfn: builtin_invlerp, pure: true, divides: false, arity: Arity::exactly(3), params: &["a", "b", "v"], category: Category::Interpolation, desc: "Inverse of lerp, the t where lerp(a, b, t) is v." },
+	BuiltinDesc { name: "isprime", pfn: builtin_isprime, pure: true, divides: false, arity: Arity::exactly(1), params: &["n"], category: Category::Integer, desc: "1 if the integer is prime." },
+	BuiltinDesc { name: "kth", pfn: builtin_kth, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Statistics, desc: "The k-th smallest value, 1 based." },
+	BuiltinDesc { name: "kurt", pfn: builtin_kurt, pure: true, divides: false, arity: Arity::at_least(4), params: &[], category: Category::Statistics, desc: "Population excess kurtosis." },
+	BuiltinDesc { name: "lcm", pfn: builtin_lcm, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Integer, desc: "Least common multiple." },
+	BuiltinDesc { name: "lerp", pfn: builtin_lerp, pure: true, divides: false, arity: Arity::exactly(3), params: &["a", "b", "t"], category: Category::Interpolation, desc: "Linear interpolation from a to b." },
+	BuiltinDesc { name: "lerpc", pfn: builtin_lerpc, pure: true, divides: false, arity: Arity::exactly(3), params: &["a", "b", "t"], category: Category::Interpolation, desc: "Linear interpolation with t clamped to 0..=1." },
+	BuiltinDesc { name: "lgamma", pfn: builtin_lgamma, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Special, desc: "Natural logarithm of the absolute gamma function." },
+	BuiltinDesc { name: "ln", pfn: builtin_ln, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural logarithm." },
+	BuiltinDesc { name: "ln1p", pfn: builtin_ln1p, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "ln(1 + x) accurate near zero." },
+	BuiltinDesc { name: "log", pfn: builtin_log, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "base"], category: Category::Exponential, desc: "Logarithm in a base." },
+	BuiltinDesc { name: "log10", pfn: builtin_log10, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Base 10 logarithm." },
+	BuiltinDesc { name: "log2", pfn: builtin_log2, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Base 2 logarithm." },
+	BuiltinDesc { name: "mantissa", pfn: builtin_mantissa, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Rounding, desc: "Binary mantissa in 0.5..1 with the sign of x." },
+	BuiltinDesc { name: "max", pfn: builtin_max, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Largest argument." },
+	BuiltinDesc { name: "mean", pfn: builtin_mean, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Arithmetic mean." },
+	BuiltinDesc { name: "median", pfn: builtin_median, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Median." },
+	BuiltinDesc { name: "min", pfn: builtin_min, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Smallest argument." },
+	BuiltinDesc { name: "mix", pfn: builtin_mix, pure: true, divides: false, arity: Arity::exactly(3), params: &["a", "b", "t"], category: Category::Interpolation, desc: "Linear interpolation, same as lerp." },
+	BuiltinDesc { name: "mod", pfn: builtin_mod, pure: true, divides: true, arity: Arity::exactly(2), params: &["a", "n"], category: Category::Arithmetic, desc: "Floored modulo with the sign of n." },
+	BuiltinDesc { name: "mode", pfn: builtin_mode, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Most frequent value, ties go to the smallest." },
+	BuiltinDesc { name: "mul", pfn: builtin_mul, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Arithmetic, desc: "Product of the arguments." },
+	BuiltinDesc { name: "nextafter", pfn: builtin_nextafter, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "toward"], category: Category::Rounding, desc: "Next representable value from x toward another value." },
+	BuiltinDesc { name: "norm", pfn: builtin_norm, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Euclidean length of the arguments." },
+	BuiltinDesc { name: "normcdf", pfn: builtin_normcdf, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Special, desc: "Standard normal cumulative distribution." },
+	BuiltinDesc { name: "not", pfn: builtin_not, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Logic, desc: "1 if the argument is falsy, NaN is falsy." },
+	BuiltinDesc { name: "npv", pfn: builtin_npv, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Finance, desc: "Net present value of cash flows at a rate." },
+	BuiltinDesc { name: "or", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting any, stops at the first truthy argument." },
+	BuiltinDesc { name: "pi", pfn: builtin_pi, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its diameter." },
+	BuiltinDesc { name: "pmt", pfn: builtin_pmt, pure: true, divides: false, arity: Arity::between(3, 4), params: &["rate", "nper", "pv", "fv"], category: Category::Finance, desc: "Periodic payment of a loan." },
+	BuiltinDesc { name: "polar_r", pfn: builtin_polar_r, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Trigonometry, desc: "Radius of the point (x, y)." },
+	BuiltinDesc { name: "polar_theta", pfn: builtin_polar_theta, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Trigonometry, desc: "Angle of the point (x, y) in (-pi, pi]." },
+	BuiltinDesc { name: "popcount", pfn: builtin_popcount, pure: true, divides: false, arity: Arity::exactly(1), params: &["a"], category: Category::Integer, desc: "Number of set bits." },
+	BuiltinDesc { name: "pow", pfn: builtin_pow, pure: true, divides: false, arity: Arity::exactly(2), params: &["base", "exp"], category: Category::Arithmetic, desc: "Raises base to a power." },
+	BuiltinDesc { name: "powi", pfn: builtin_powi, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "n"], category: Category::Arithmetic, desc: "Raises to an integer power exactly." },
+	BuiltinDesc { name: "pv", pfn: builtin_pv, pure: true, divides: false, arity: Arity::between(3, 4), params: &["rate", "nper", "pmt", "fv"], category: Category::Finance, desc: "Present value of periodic payments." },
+	BuiltinDesc { name: "quantile", pfn: builtin_quantile, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Statistics, desc: "Quantile p in 0..=1 with linear interpolation." },
+	BuiltinDesc { name: "quantize", pfn: builtin_quantize, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "step"], category: Category::Rounding, desc: "Rounds to the nearest multiple of step." },
+	BuiltinDesc { name: "rad", pfn: builtin_rad, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Converts degrees to radians." },
+	BuiltinDesc { name: "range", pfn: builtin_range, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Difference between the largest and smallest value." },
+	BuiltinDesc { name: "rem", pfn: builtin_rem, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Truncated remainder with the sign of x." },
+	BuiltinDesc { name: "remap", pfn: builtin_remap, pure: true, divides: false, arity: Arity::exactly(5), params: &["in_lo", "in_hi", "out_lo", "out_hi", "v"], category: Category::Interpolation, desc: "Maps v from the input range to the output range." },
+	BuiltinDesc { name: "remapc", pfn: builtin_remapc, pure: true, divides: false, arity: Arity::exactly(5), params: &["in_lo", "in_hi", "out_lo", "out_hi", "v"], category: Category::Interpolation, desc: "Maps v from the input range to the output range, clamped." },
+	BuiltinDesc { name: "rms", pfn: builtin_rms, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Root mean square." },
+	BuiltinDesc { name: "roll", pfn: builtin_roll, pure: false, divides: false, arity: Arity::exactly(2), params: &["n", "sides"], category: Category::Random, desc: "Sum of n dice with a number of sides." },
+	BuiltinDesc { name: "root", pfn: builtin_root, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "n"], category: Category::Arithmetic, desc: "The n-th root." },
+	BuiltinDesc { name: "round", pfn: builtin_round, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "digits"], category: Category::Rounding, desc: "Rounds half away from zero, optionally at decimal places." },
+	BuiltinDesc { name: "roundsig", pfn: builtin_roundsig, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "n"], category: Category::Rounding, desc: "Rounds to n significant figures." },
+	BuiltinDesc { name: "saturate", pfn: builtin_saturate, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Interpolation, desc: "Clamps to 0..=1." },
+	BuiltinDesc { name: "sec", pfn: builtin_sec, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Secant of an angle in radians." },
+	BuiltinDesc { name: "select", pfn: builtin_select, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Logic, desc: "Argument at the zero based index i, negative counts from the end." },
+	BuiltinDesc { name: "selectc", pfn: builtin_selectc, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Logic, desc: "Argument at the index i clamped to the first or last." },
+	BuiltinDesc { name: "sem", pfn: builtin_sem, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Statistics, desc: "Standard error of the mean." },
+	BuiltinDesc { name: "shl", pfn: builtin_shl, pure: true, divides: false, arity: Arity::exactly(2), params: &["a", "n"], category: Category::Integer, desc: "Shifts left by n bits." },
+	BuiltinDesc { name: "shr", pfn: builtin_shr, pure: true, divides: false, arity: Arity::exactly(2), params: &["a", "n"], category: Category::Integer, desc: "Shifts right by n bits." },
+	BuiltinDesc { name: "sigmoid", pfn: builtin_sigmoid, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Special, desc: "Logistic function." },
+	BuiltinDesc { name: "signbit", pfn: builtin_signbit, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "1 if the sign bit is set, also for -0." },
+	BuiltinDesc { name: "sin", pfn: builtin_sin, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Sine of an angle in radians." },
+	BuiltinDesc { name: "sinc", pfn: builtin_sinc, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Special, desc: "Unnormalized sinc, sin(x) / x." },
+	BuiltinDesc { name: "sind", pfn: builtin_sind, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Sine of an angle in degrees." },
+	BuiltinDesc { name: "sinh", pfn: builtin_sinh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic sine." },
+	BuiltinDesc { name: "skew", pfn: builtin_skew, pure: true, divides: false, arity: Arity::at_least(3), params: &[], category: Category::Statistics, desc: "Population skewness." },
+	BuiltinDesc { name: "smootherstep", pfn: builtin_smootherstep, pure: true, divides: false, arity: Arity::between(1, 3), params: &["edge0", "edge1", "x"], category: Category::Interpolation, desc: "Smoother Hermite interpolation between the edges, takes 3 arguments or only x with edges 0 and 1." },
+	BuiltinDesc { name: "smoothstep", pfn: builtin_smoothstep, pure: true, divides: false, arity: Arity::between(1, 3), params: &["edge0", "edge1", "x"], category: Category::Interpolation, desc: "Hermite interpolation between the edges, takes 3 arguments or only x with edges 0 and 1." },
+	BuiltinDesc { name: "softplus", pfn: builtin_softplus, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Special, desc: "Smooth approximation of max(0, x)." },
+	BuiltinDesc { name: "sqr", pfn: builtin_sqr, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square of the argument." },
+	BuiltinDesc { name: "sqrpyr", pfn: builtin_sqrpyr, pure: true, divides: false, arity: Arity::exactly(1), params: &["n"], category: Category::Integer, desc: "Square pyramidal number." },
+	BuiltinDesc { name: "sqrt", pfn: builtin_sqrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square root." },
+	BuiltinDesc { name: "sstdev", pfn: builtin_sstdev, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Statistics, desc: "Sample standard deviation." },
+	BuiltinDesc { name: "stdev", pfn: builtin_stdev, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Population standard deviation." },
+	BuiltinDesc { name: "step", pfn: builtin_step, pure: true, divides: false, arity: Arity::between(1, 2), params: &["edge", "x"], category: Category::Interpolation, desc: "1 if x >= edge, 0 otherwise, step(x) has edge 0." },
+	BuiltinDesc { name: "sub", pfn: builtin_sub, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "y"], category: Category::Arithmetic, desc: "Subtracts y from x, negates a single argument." },
+	BuiltinDesc { name: "sumsq", pfn: builtin_sumsq, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Sum of squares." },
+	BuiltinDesc { name: "svar", pfn: builtin_svar, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Statistics, desc: "Sample variance." },
+	BuiltinDesc { name: "tan", pfn: builtin_tan, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Tangent of an angle in radians." },
+	BuiltinDesc { name: "tand", pfn: builtin_tand, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Tangent of an angle in degrees." },
+	BuiltinDesc { name: "tanh", pfn: builtin_tanh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic tangent." },
+	BuiltinDesc { name: "tau", pfn: builtin_tau, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its radius." },
+	BuiltinDesc { name: "tofixed", pfn: builtin_tofixed, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "digits"], category: Category::Rounding, desc: "Rounds for display at decimal places." },
+	BuiltinDesc { name: "tri", pfn: builtin_tri, pure: true, divides: false, arity: Arity::exactly(1), params: &["n"], category: Category::Integer, desc: "Triangular number." },
+	BuiltinDesc { name: "ulp", pfn: builtin_ulp, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Rounding, desc: "Distance to the next larger representable value." },
+	BuiltinDesc { name: "var", pfn: builtin_var, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Statistics, desc: "Population variance." },
+	BuiltinDesc { name: "wmean", pfn: builtin_wmean, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Statistics, desc: "Weighted mean of interleaved value and weight pairs." },
+	BuiltinDesc { name: "wrap", pfn: builtin_wrap, pure: true, divides: false, arity: Arity::exactly(3), params: &["x", "lo", "hi"], category: Category::Interpolation, desc: "Wraps x into lo..hi." },
+	BuiltinDesc { name: "wrap_angle", pfn: builtin_wrap_angle, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Wraps an angle into (-pi, pi]." },
+]
+};
+
+static F32_BUILTINS: [BuiltinDesc<f32>; 45] = {
+use crate::builtins32::*;
+[
+	BuiltinDesc { name: "", pfn: builtin_id, pure: true, divides: false, arity: Arity::exactly(1), params: &[], category: Category::Arithmetic, desc: "Identity, used for parenthesized groups." },
+	BuiltinDesc { name: "abs", pfn: builtin_abs, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Absolute value." },
+	BuiltinDesc { name: "acos", pfn: builtin_acos, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc cosine in radians." },
+	BuiltinDesc { name: "add", pfn: builtin_add, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Sum of the arguments with compensated summation." },
+	BuiltinDesc { name: "all", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if all arguments are truthy, NaN is falsy." },
+	BuiltinDesc { name: "and", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting all, stops at the first falsy argument." },
+	BuiltinDesc { name: "any", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if any argument is truthy, NaN is falsy." },
+	BuiltinDesc { name: "asin", pfn: builtin_asin, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc sine in radians." },
+	BuiltinDesc { name: "atan", pfn: builtin_atan, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc tangent in radians." },
+	BuiltinDesc { name: "atan2", pfn: builtin_atan2, pure: true, divides: false, arity: Arity::exactly(2), params: &["y", "x"], category: Category::Trigonometry, desc: "Angle of the point (x, y) in radians." },
+	BuiltinDesc { name: "cbrt", pfn: builtin_cbrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube root." },
+	BuiltinDesc { name: "ceil", pfn: builtin_ceil, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Rounding, desc: "Rounds towards positive infinity." },
+	BuiltinDesc { name: "clamp", pfn: builtin_clamp, pure: true, divides: false, arity: Arity::exactly(3), params: &["x", "lo", "hi"], category: Category::Arithmetic, desc: "Clamps x to lo..=hi, swapped bounds are an error." },
+	BuiltinDesc { name: "cos", pfn: builtin_cos, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Cosine of an angle in radians." },
+	BuiltinDesc { name: "cosh", pfn: builtin_cosh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic cosine." },
+	BuiltinDesc { name: "cube", pfn: builtin_cube, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube of the argument." },
+	BuiltinDesc { name: "deg", pfn: builtin_deg, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Converts radians to degrees." },
+	BuiltinDesc { name: "div", pfn: builtin_div, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Divides x by y." },
+	BuiltinDesc { name: "e", pfn: builtin_e, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Euler's number." },
+	BuiltinDesc { name: "exp", pfn: builtin_exp, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural exponential." },
+	BuiltinDesc { name: "floor", pfn: builtin_floor, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Rounding, desc: "Rounds towards negative infinity." },
+	BuiltinDesc { name: "hypot", pfn: builtin_hypot, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Trigonometry, desc: "Length of the hypotenuse without overflow." },
+	BuiltinDesc { name: "ln", pfn: builtin_ln, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural logarithm." },
+	BuiltinDesc { name: "log", pfn: builtin_log, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "base"], category: Category::Exponential, desc: "Logarithm in a base." },
+	BuiltinDesc { name: "log10", pfn: builtin_log10, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Base 10 logarithm." },
+	BuiltinDesc { name: "log2", pfn: builtin_log2, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Base 2 logarithm." },
+	BuiltinDesc { name: "max", pfn: builtin_max, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Largest argument." },
+	BuiltinDesc { name: "min", pfn: builtin_min, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Smallest argument." },
+	BuiltinDesc { name: "mod", pfn: builtin_mod, pure: true, divides: true, arity: Arity::exactly(2), params: &["a", "n"], category: Category::Arithmetic, desc: "Floored modulo with the sign of n." },
+	BuiltinDesc { name: "mul", pfn: builtin_mul, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Arithmetic, desc: "Product of the arguments." },
+	BuiltinDesc { name: "not", pfn: builtin_not, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Logic, desc: "1 if the argument is falsy, NaN is falsy." },
+	BuiltinDesc { name: "or", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting any, stops at the first truthy argument." },
+	BuiltinDesc { name: "pi", pfn: builtin_pi, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its diameter." },
+	BuiltinDesc { name: "pow", pfn: builtin_pow, pure: true, divides: false, arity: Arity::exactly(2), params: &["base", "exp"], category: Category::Arithmetic, desc: "Raises base to a power." },
+	BuiltinDesc { name: "rad", pfn: builtin_rad, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Converts degrees to radians." },
+	BuiltinDesc { name: "rem", pfn: builtin_rem, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Truncated remainder with the sign of x." },
+	BuiltinDesc { name: "round", pfn: builtin_round, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Rounding, desc: "Rounds half away from zero." },
+	BuiltinDesc { name: "sin", pfn: builtin_sin, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Sine of an angle in radians." },
+	BuiltinDesc { name: "sinh", pfn: builtin_sinh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic sine." },
+	BuiltinDesc { name: "sqr", pfn: builtin_sqr, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square of the argument." },
+	BuiltinDesc { name: "sqrt", pfn: builtin_sqrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square root." },
+	BuiltinDesc { name: "sub", pfn: builtin_sub, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "y"], category: Category::Arithmetic, desc: "Subtracts y from x, negates a single argument." },
+	BuiltinDesc { name: "tan", pfn: builtin_tan, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Tangent of an angle in radians." },
+	BuiltinDesc { name: "tanh", pfn: builtin_tanh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic tangent." },
+	BuiltinDesc { name: "tau", pfn: builtin_tau, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its radius." },
+]
+};
+
+static INT_BUILTINS: [BuiltinDesc<i64>; 18] = {
+use crate::builtins_int::*;
+[
+	BuiltinDesc { name: "", pfn: builtin_id, pure: true, divides: false, arity: Arity::exactly(1), params: &[], category: Category::Arithmetic, desc: "Identity, used for parenthesized groups." },
+	BuiltinDesc { name: "abs", pfn: builtin_abs, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Absolute value." },
+	BuiltinDesc { name: "add", pfn: builtin_add, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Sum of the arguments." },
+	BuiltinDesc { name: "all", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if all arguments are truthy." },
+	BuiltinDesc { name: "and", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting all, stops at the first falsy argument." },
+	BuiltinDesc { name: "any", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if any argument is truthy." },
+	BuiltinDesc { name: "div", pfn: builtin_div, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Divides x by y, fails or floors with a remainder." },
+	BuiltinDesc { name: "gcd", pfn: builtin_gcd, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Integer, desc: "Greatest common divisor of the magnitudes." },
+	BuiltinDesc { name: "max", pfn: builtin_max, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Largest argument." },
+	BuiltinDesc { name: "min", pfn: builtin_min, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Smallest argument." },
+	BuiltinDesc { name: "mod", pfn: builtin_mod, pure: true, divides: true, arity: Arity::exactly(2), params: &["a", "n"], category: Category::Arithmetic, desc: "Floored modulo with the sign of n." },
+	BuiltinDesc { name: "mul", pfn: builtin_mul, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Arithmetic, desc: "Product of the arguments." },
+	BuiltinDesc { name: "not", pfn: builtin_not, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Logic, desc: "1 if the argument is falsy." },
+	BuiltinDesc { name: "or", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting any, stops at the first truthy argument." },
+	BuiltinDesc { name: "pow", pfn: builtin_pow, pure: true, divides: false, arity: Arity::exactly(2), params: &["base", "exp"], category: Category::Arithmetic, desc: "Raises base to a power, overflow is an error." },
+	BuiltinDesc { name: "rem", pfn: builtin_rem, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Truncated remainder with the sign of x." },
+	BuiltinDesc { name: "sqrt", pfn: builtin_sqrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square root of a perfect square." },
+	BuiltinDesc { name: "sub", pfn: builtin_sub, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "y"], category: Category::Arithmetic, desc: "Subtracts y from x, negates a single argument." },
+]
+};
+
+static INTERVAL_BUILTINS: [BuiltinDesc<crate::Interval>; 33] = {
+use crate::builtins_interval::*;
+[
+	BuiltinDesc { name: "", pfn: builtin_id, pure: true, divides: false, arity: Arity::exactly(1), params: &[], category: Category::Arithmetic, desc: "Identity, used for parenthesized groups." },
+	BuiltinDesc { name: "abs", pfn: builtin_abs, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Absolute value." },
+	BuiltinDesc { name: "add", pfn: builtin_add, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Sum of the arguments." },
+	BuiltinDesc { name: "all", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if all arguments are truthy, intervals containing zero are falsy." },
+	BuiltinDesc { name: "and", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting all, stops at the first falsy argument." },
+	BuiltinDesc { name: "any", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if any argument is truthy, intervals containing zero are falsy." },
+	BuiltinDesc { name: "atan", pfn: builtin_atan, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc tangent in radians." },
+	BuiltinDesc { name: "cbrt", pfn: builtin_cbrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube root." },
+	BuiltinDesc { name: "cos", pfn: builtin_cos, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Cosine of an angle in radians." },
+	BuiltinDesc { name: "cube", pfn: builtin_cube, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube of the argument." },
+	BuiltinDesc { name: "div", pfn: builtin_div, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Divides x by y, all values if y contains zero." },
+	BuiltinDesc { name: "e", pfn: builtin_e, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Euler's number." },
+	BuiltinDesc { name: "exp", pfn: builtin_exp, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural exponential." },
+	BuiltinDesc { name: "hi", pfn: builtin_hi, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Upper bound." },
+	BuiltinDesc { name: "interval", pfn: builtin_interval, pure: true, divides: false, arity: Arity::exactly(2), params: &["lo", "hi"], category: Category::Arithmetic, desc: "Interval between the arguments." },
+	BuiltinDesc { name: "ln", pfn: builtin_ln, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural logarithm." },
+	BuiltinDesc { name: "lo", pfn: builtin_lo, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Lower bound." },
+	BuiltinDesc { name: "log10", pfn: builtin_log10, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Base 10 logarithm." },
+	BuiltinDesc { name: "log2", pfn: builtin_log2, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Base 2 logarithm." },
+	BuiltinDesc { name: "max", pfn: builtin_max, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Largest argument." },
+	BuiltinDesc { name: "min", pfn: builtin_min, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Smallest argument." },
+	BuiltinDesc { name: "mul", pfn: builtin_mul, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Arithmetic, desc: "Product of the arguments." },
+	BuiltinDesc { name: "not", pfn: builtin_not, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Logic, desc: "1 if the argument is falsy, intervals containing zero are falsy." },
+	BuiltinDesc { name: "or", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting any, stops at the first truthy argument." },
+	BuiltinDesc { name: "pi", pfn: builtin_pi, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its diameter." },
+	BuiltinDesc { name: "pow", pfn: builtin_pow, pure: true, divides: false, arity: Arity::exactly(2), params: &["base", "exp"], category: Category::Arithmetic, desc: "Raises base to a power." },
+	BuiltinDesc { name: "rem", pfn: builtin_rem, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Bounds of the truncated remainder with the sign of x." },
+	BuiltinDesc { name: "sin", pfn: builtin_sin, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Sine of an angle in radians." },
+	BuiltinDesc { name: "sqr", pfn: builtin_sqr, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square of the argument." },
+	BuiltinDesc { name: "sqrt", pfn: builtin_sqrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square root." },
+	BuiltinDesc { name: "sub", pfn: builtin_sub, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "y"], category: Category::Arithmetic, desc: "Subtracts y from x, negates a single argument." },
+	BuiltinDesc { name: "tanh", pfn: builtin_tanh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic tangent." },
+	BuiltinDesc { name: "tau", pfn: builtin_tau, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its radius." },
+]
+};
+
+#[cfg(feature = "complex")]
+static COMPLEX_BUILTINS: [BuiltinDesc<crate::Complex>; 33] = {
+use crate::builtins_complex::*;
+[
+	BuiltinDesc { name: "", pfn: builtin_id, pure: true, divides: false, arity: Arity::exactly(1), params: &[], category: Category::Arithmetic, desc: "Identity, used for parenthesized groups." },
+	BuiltinDesc { name: "abs", pfn: builtin_abs, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Arithmetic, desc: "Absolute value, the distance to the origin." },
+	BuiltinDesc { name: "add", pfn: builtin_add, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Sum of the arguments." },
+	BuiltinDesc { name: "all", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if all arguments are truthy, NaN is falsy." },
+	BuiltinDesc { name: "and", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting all, stops at the first falsy argument." },
+	BuiltinDesc { name: "any", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if any argument is truthy, NaN is falsy." },
+	BuiltinDesc { name: "arg", pfn: builtin_arg, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Trigonometry, desc: "Angle to the positive real axis in radians." },
+	BuiltinDesc { name: "conj", pfn: builtin_conj, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Arithmetic, desc: "Complex conjugate." },
+	BuiltinDesc { name: "cos", pfn: builtin_cos, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Trigonometry, desc: "Cosine of an angle in radians." },
+	BuiltinDesc { name: "cosh", pfn: builtin_cosh, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Trigonometry, desc: "Hyperbolic cosine." },
+	BuiltinDesc { name: "cube", pfn: builtin_cube, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Arithmetic, desc: "Cube of the argument." },
+	BuiltinDesc { name: "div", pfn: builtin_div, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Divides x by y." },
+	BuiltinDesc { name: "e", pfn: builtin_e, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Euler's number." },
+	BuiltinDesc { name: "exp", pfn: builtin_exp, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Exponential, desc: "Natural exponential." },
+	BuiltinDesc { name: "i", pfn: builtin_i, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "The imaginary unit." },
+	BuiltinDesc { name: "im", pfn: builtin_im, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Arithmetic, desc: "Imaginary part." },
+	BuiltinDesc { name: "ln", pfn: builtin_ln, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Exponential, desc: "Principal natural logarithm." },
+	BuiltinDesc { name: "log", pfn: builtin_log, pure: true, divides: false, arity: Arity::exactly(2), params: &["z", "base"], category: Category::Exponential, desc: "Principal logarithm in a base." },
+	BuiltinDesc { name: "mul", pfn: builtin_mul, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Arithmetic, desc: "Product of the arguments." },
+	BuiltinDesc { name: "not", pfn: builtin_not, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Logic, desc: "1 if the argument is falsy, NaN is falsy." },
+	BuiltinDesc { name: "or", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting any, stops at the first truthy argument." },
+	BuiltinDesc { name: "pi", pfn: builtin_pi, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its diameter." },
+	BuiltinDesc { name: "pow", pfn: builtin_pow, pure: true, divides: false, arity: Arity::exactly(2), params: &["base", "exp"], category: Category::Arithmetic, desc: "Principal value of raising base to a power." },
+	BuiltinDesc { name: "re", pfn: builtin_re, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Arithmetic, desc: "Real part." },
+	BuiltinDesc { name: "rem", pfn: builtin_rem, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Truncated remainder of real arguments." },
+	BuiltinDesc { name: "sin", pfn: builtin_sin, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Trigonometry, desc: "Sine of an angle in radians." },
+	BuiltinDesc { name: "sinh", pfn: builtin_sinh, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Trigonometry, desc: "Hyperbolic sine." },
+	BuiltinDesc { name: "sqr", pfn: builtin_sqr, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Arithmetic, desc: "Square of the argument." },
+	BuiltinDesc { name: "sqrt", pfn: builtin_sqrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Arithmetic, desc: "Principal square root, sqrt(-1) is i." },
+	BuiltinDesc { name: "sub", pfn: builtin_sub, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "y"], category: Category::Arithmetic, desc: "Subtracts y from x, negates a single argument." },
+	BuiltinDesc { name: "tan", pfn: builtin_tan, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Trigonometry, desc: "Tangent of an angle in radians." },
+	BuiltinDesc { name: "tanh", pfn: builtin_tanh, pure: true, divides: false, arity: Arity::exactly(1), params: &["z"], category: Category::Trigonometry, desc: "Hyperbolic tangent." },
+	BuiltinDesc { name: "tau", pfn: builtin_tau, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its radius." },
+]
+};
+
+#[cfg(feature = "bigfloat")]
+static BIG_BUILTINS: [BuiltinDesc<crate::BigValue>; 38] = {
+use crate::builtins_big::*;
+[
+	BuiltinDesc { name: "", pfn: builtin_id, pure: true, divides: false, arity: Arity::exactly(1), params: &[], category: Category::Arithmetic, desc: "Identity, used for parenthesized groups." },
+	BuiltinDesc { name: "abs", pfn: builtin_abs, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Absolute value." },
+	BuiltinDesc { name: "acos", pfn: builtin_acos, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc cosine in radians." },
+	BuiltinDesc { name: "add", pfn: builtin_add, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Sum of the arguments." },
+	BuiltinDesc { name: "all", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if all arguments are truthy, NaN is falsy." },
+	BuiltinDesc { name: "and", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting all, stops at the first falsy argument." },
+	BuiltinDesc { name: "any", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if any argument is truthy, NaN is falsy." },
+	BuiltinDesc { name: "asin", pfn: builtin_asin, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc sine in radians." },
+	BuiltinDesc { name: "atan", pfn: builtin_atan, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc tangent in radians." },
+	BuiltinDesc { name: "cbrt", pfn: builtin_cbrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube root." },
+	BuiltinDesc { name: "ceil", pfn: builtin_ceil, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Rounding, desc: "Rounds towards positive infinity." },
+	BuiltinDesc { name: "cos", pfn: builtin_cos, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Cosine of an angle in radians." },
+	BuiltinDesc { name: "cosh", pfn: builtin_cosh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic cosine." },
+	BuiltinDesc { name: "cube", pfn: builtin_cube, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube of the argument." },
+	BuiltinDesc { name: "div", pfn: builtin_div, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Divides x by y." },
+	BuiltinDesc { name: "e", pfn: builtin_e, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Euler's number." },
+	BuiltinDesc { name: "exp", pfn: builtin_exp, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural exponential." },
+	BuiltinDesc { name: "floor", pfn: builtin_floor, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Rounding, desc: "Rounds towards negative infinity." },
+	BuiltinDesc { name: "ln", pfn: builtin_ln, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural logarithm." },
+	BuiltinDesc { name: "log", pfn: builtin_log, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "base"], category: Category::Exponential, desc: "Logarithm in a base." },
+	BuiltinDesc { name: "log10", pfn: builtin_log10, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Base 10 logarithm." },
+	BuiltinDesc { name: "log2", pfn: builtin_log2, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Base 2 logarithm." },
+	BuiltinDesc { name: "max", pfn: builtin_max, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Largest argument." },
+	BuiltinDesc { name: "min", pfn: builtin_min, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Smallest argument." },
+	BuiltinDesc { name: "mul", pfn: builtin_mul, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Arithmetic, desc: "Product of the arguments." },
+	BuiltinDesc { name: "not", pfn: builtin_not, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Logic, desc: "1 if the argument is falsy, NaN is falsy." },
+	BuiltinDesc { name: "or", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting any, stops at the first truthy argument." },
+	BuiltinDesc { name: "pi", pfn: builtin_pi, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its diameter." },
+	BuiltinDesc { name: "pow", pfn: builtin_pow, pure: true, divides: false, arity: Arity::exactly(2), params: &["base", "exp"], category: Category::Arithmetic, desc: "Raises base to a power." },
+	BuiltinDesc { name: "rem", pfn: builtin_rem, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Truncated remainder with the sign of x." },
+	BuiltinDesc { name: "sin", pfn: builtin_sin, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Sine of an angle in radians." },
+	BuiltinDesc { name: "sinh", pfn: builtin_sinh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic sine." },
+	BuiltinDesc { name: "sqr", pfn: builtin_sqr, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square of the argument." },
+	BuiltinDesc { name: "sqrt", pfn: builtin_sqrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square root." },
+	BuiltinDesc { name: "sub", pfn: builtin_sub, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "y"], category: Category::Arithmetic, desc: "Subtracts y from x, negates a single argument." },
+	BuiltinDesc { name: "tan", pfn: builtin_tan, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Tangent of an angle in radians." },
+	BuiltinDesc { name: "tanh", pfn: builtin_tanh, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Hyperbolic tangent." },
+	BuiltinDesc { name: "tau", pfn: builtin_tau, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its radius." },
+]
+};
+
+#[cfg(feature = "units")]
+static UNIT_BUILTINS: [BuiltinDesc<crate::Quantity>; 32] = {
+use crate::builtins_units::*;
+[
+	BuiltinDesc { name: "", pfn: builtin_id, pure: true, divides: false, arity: Arity::exactly(1), params: &[], category: Category::Arithmetic, desc: "Identity, used for parenthesized groups." },
+	BuiltinDesc { name: "abs", pfn: builtin_abs, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Absolute value." },
+	BuiltinDesc { name: "acos", pfn: builtin_acos, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc cosine of a dimensionless value in radians." },
+	BuiltinDesc { name: "add", pfn: builtin_add, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Sum of quantities of the same dimension." },
+	BuiltinDesc { name: "all", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if all arguments are truthy, NaN is falsy." },
+	BuiltinDesc { name: "and", pfn: builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting all, stops at the first falsy argument." },
+	BuiltinDesc { name: "any", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "1 if any argument is truthy, NaN is falsy." },
+	BuiltinDesc { name: "asin", pfn: builtin_asin, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc sine of a dimensionless value in radians." },
+	BuiltinDesc { name: "atan", pfn: builtin_atan, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Arc tangent of a dimensionless value in radians." },
+	BuiltinDesc { name: "cbrt", pfn: builtin_cbrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube root, divides the exponents of the dimension by three." },
+	BuiltinDesc { name: "cos", pfn: builtin_cos, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Cosine of an angle in radians." },
+	BuiltinDesc { name: "cube", pfn: builtin_cube, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Cube of the argument." },
+	BuiltinDesc { name: "div", pfn: builtin_div, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Divides x by y." },
+	BuiltinDesc { name: "e", pfn: builtin_e, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Euler's number." },
+	BuiltinDesc { name: "exp", pfn: builtin_exp, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural exponential of a dimensionless value." },
+	BuiltinDesc { name: "ln", pfn: builtin_ln, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Natural logarithm of a dimensionless value." },
+	BuiltinDesc { name: "log10", pfn: builtin_log10, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Exponential, desc: "Base 10 logarithm of a dimensionless value." },
+	BuiltinDesc { name: "max", pfn: builtin_max, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Largest of quantities of the same dimension." },
+	BuiltinDesc { name: "min", pfn: builtin_min, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "Smallest of quantities of the same dimension." },
+	BuiltinDesc { name: "mul", pfn: builtin_mul, pure: true, divides: false, arity: Arity::at_least(2), params: &[], category: Category::Arithmetic, desc: "Product of the arguments." },
+	BuiltinDesc { name: "not", pfn: builtin_not, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Logic, desc: "1 if the argument is falsy, NaN is falsy." },
+	BuiltinDesc { name: "or", pfn: builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "Short-circuiting any, stops at the first truthy argument." },
+	BuiltinDesc { name: "pi", pfn: builtin_pi, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its diameter." },
+	BuiltinDesc { name: "pow", pfn: builtin_pow, pure: true, divides: false, arity: Arity::exactly(2), params: &["base", "exp"], category: Category::Arithmetic, desc: "Raises base to a dimensionless power." },
+	BuiltinDesc { name: "rem", pfn: builtin_rem, pure: true, divides: true, arity: Arity::exactly(2), params: &["x", "y"], category: Category::Arithmetic, desc: "Truncated remainder with the sign of x." },
+	BuiltinDesc { name: "sin", pfn: builtin_sin, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Sine of an angle in radians." },
+	BuiltinDesc { name: "sqr", pfn: builtin_sqr, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square of the argument." },
+	BuiltinDesc { name: "sqrt", pfn: builtin_sqrt, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Arithmetic, desc: "Square root, halves the exponents of the dimension." },
+	BuiltinDesc { name: "sub", pfn: builtin_sub, pure: true, divides: false, arity: Arity::between(1, 2), params: &["x", "y"], category: Category::Arithmetic, desc: "Subtracts y from x, negates a single argument." },
+	BuiltinDesc { name: "tan", pfn: builtin_tan, pure: true, divides: false, arity: Arity::exactly(1), params: &["x"], category: Category::Trigonometry, desc: "Tangent of an angle in radians." },
+	BuiltinDesc { name: "tau", pfn: builtin_tau, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "Ratio of a circle's circumference to its radius." },
+	BuiltinDesc { name: "to", pfn: builtin_to, pure: true, divides: false, arity: Arity::exactly(2), params: &["x", "unit"], category: Category::Arithmetic, desc: "Displays x in a unit of the same dimension, eg. to(1 mi, \"ft\")." },
 ]
 };
 
+/// Seedable random number generator.
+///
+/// Implements splitmix64 with an atomic state so environments can be shared between threads.
+#[derive(Debug, Default)]
+pub struct Rng {
+	state: AtomicU64,
+}
+impl Rng {
+	/// Creates a generator with the given seed, the same seed always produces the same sequence.
+	pub const fn new(seed: u64) -> Rng {
+		Rng { state: AtomicU64::new(seed) }
+	}
+	/// Returns the next random 64-bit integer.
+	pub fn next_u64(&self) -> u64 {
+		const GAMMA: u64 = 0x9e3779b97f4a7c15;
+		let mut z = self.state.fetch_add(GAMMA, Ordering::Relaxed).wrapping_add(GAMMA);
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+		z ^ (z >> 31)
+	}
+	/// Returns the next random value uniformly distributed in `0..1`.
+	pub fn next_value(&self) -> Value {
+		(self.next_u64() >> 11) as Value * (1.0 / (1u64 << 53) as Value)
+	}
+}
+impl Clone for Rng {
+	fn clone(&self) -> Rng {
+		Rng::new(self.state.load(Ordering::Relaxed))
+	}
+}
+
 /// The environment.
 ///
-/// Stores the builtin functions and variables available to expressions.
-pub trait Env {
+/// Stores the builtin functions and variables available to expressions evaluated in the [`Num`] type.
+pub trait Env<V: Num = Value> {
 	/// Lookup a builtin function.
-	fn builtin(&self, name: &str) -> Result<BuiltinFn, Error>;
+	fn builtin(&self, name: &str) -> Result<BuiltinFn<V>, ErrorKind>;
 	/// Gets a variable’s value.
-	fn get_value(&self, name: &str) -> Result<Value, Error>;
+	fn get_value(&self, name: &str) -> Result<V, ErrorKind>;
 	/// Sets a variable’s value.
-	fn set_value(&mut self, name: &str, value: Value) -> Result<(), Error>;
+	fn set_value(&mut self, name: &str, value: V) -> Result<(), ErrorKind>;
+	/// Returns whether the builtin always returns the same result for the same arguments.
+	///
+	/// Only pure builtins are evaluated ahead of time, see [`Ast::fold`](crate::Ast::fold).
+	fn is_pure(&self, _name: &str) -> bool {
+		false
+	}
+	/// Returns whether the builtin divides by its last argument, see [`BuiltinDesc::divides`].
+	fn divides(&self, _name: &str) -> bool {
+		false
+	}
+	/// Returns the number of arguments accepted by the builtin if known.
+	///
+	/// Calls with a different number of arguments fail with [`ErrorKind::ArgumentCount`] without calling the builtin.
+	fn arity(&self, _name: &str) -> Option<Arity> {
+		None
+	}
+	/// Returns the names of the builtin’s arguments if it accepts keyword arguments.
+	///
+	/// Keyword arguments follow the positional arguments in any order and are passed to the builtin at the position of their name.
+	fn params(&self, _name: &str) -> Option<&[&str]> {
+		None
+	}
+	/// Returns a random value uniformly distributed in `0..1`.
+	///
+	/// Returns `None` if the environment has no random number generator, the random builtins then fail with [`ErrorKind::EnvErrorNotFound`].
+	fn random(&self) -> Option<Value> {
+		None
+	}
 }
 
-/// Basic environment.
+/// Basic environment in any [`Num`] type, see [`BasicEnv`] and [`BasicEnv32`].
 ///
-/// Supports just the default builtins and saves the last answer.
+/// Supports just the builtins and saves the last answer.
+/// Without a random number generator the random builtins fail, see [`VarEnv`].
 #[derive(Clone)]
-pub struct BasicEnv<'a> {
-	pub ans: Value,
-	pub builtins: &'a [(&'a str, BuiltinFn)],
+pub struct NumEnv<'a, V: Num> {
+	pub ans: V,
+	/// Sorted by name.
+	pub builtins: &'a [BuiltinDesc<'a, V>],
 }
-impl<'a> Default for BasicEnv<'a> {
+
+/// Basic environment with the default builtins.
+pub type BasicEnv<'a> = NumEnv<'a, Value>;
+
+/// Basic environment evaluating in single precision with the [`builtins32`](crate::builtins32).
+///
+/// ```
+/// let env = pupil::BasicEnv32::default();
+/// assert_eq!(pupil::eval(&env, "0.1 + 0.2"), Ok(0.1f32 + 0.2f32));
+/// ```
+pub type BasicEnv32<'a> = NumEnv<'a, f32>;
+
+/// Basic environment evaluating in integers with the [`builtins_int`](crate::builtins_int), see [`eval_int`](crate::eval_int).
+///
+/// ```
+/// let env = pupil::IntEnv::default();
+/// assert_eq!(pupil::eval_int(&env, "gcd(12, 18) * 2"), Ok(12));
+/// ```
+pub type IntEnv<'a> = NumEnv<'a, i64>;
+
+/// Basic environment evaluating complex numbers with the [`builtins_complex`](crate::builtins_complex).
+///
+/// The imaginary unit is the constant `i` and literals accept an `i` suffix.
+///
+/// ```
+/// let env = pupil::ComplexEnv::default();
+/// assert_eq!(pupil::eval(&env, "abs(3+4i)"), Ok(pupil::Complex::from(5.0)));
+/// ```
+#[cfg(feature = "complex")]
+pub type ComplexEnv<'a> = NumEnv<'a, crate::Complex>;
+
+/// Basic environment evaluating in arbitrary precision with the [`builtins_big`](crate::builtins_big), see [`eval_precise`](crate::eval_precise).
+///
+/// ```
+/// let env = pupil::PreciseEnv::default();
+/// assert_eq!(pupil::eval_precise(&env, "0.1 + 0.2", 128).unwrap().to_string(), "0.3");
+/// ```
+#[cfg(feature = "bigfloat")]
+pub type PreciseEnv<'a> = NumEnv<'a, crate::BigValue>;
+
+impl<'a> Default for NumEnv<'a, f64> {
 	fn default() -> BasicEnv<'a> {
-		BasicEnv {
+		NumEnv {
 			ans: 0.0f64,
 			builtins: &DEFAULT_BUILTINS,
 		}
 	}
 }
-impl<'a> Env for BasicEnv<'a> {
-	fn builtin(&self, name: &str) -> Result<BuiltinFn, Error> {
-		match self.builtins.binary_search_by_key(&name, |it| it.0) {
-			Ok(index) => Ok(self.builtins[index].1),
-			Err(_) => Err(Error::EnvErrorNotFound),
+impl<'a> Default for NumEnv<'a, f32> {
+	fn default() -> BasicEnv32<'a> {
+		NumEnv {
+			ans: 0.0f32,
+			builtins: &F32_BUILTINS,
 		}
 	}
-	fn get_value(&self, name: &str) -> Result<Value, Error> {
+}
+impl<'a> Default for NumEnv<'a, i64> {
+	fn default() -> IntEnv<'a> {
+		NumEnv {
+			ans: 0,
+			builtins: &INT_BUILTINS,
+		}
+	}
+}
+impl<'a> Default for NumEnv<'a, crate::Interval> {
+	fn default() -> NumEnv<'a, crate::Interval> {
+		NumEnv {
+			ans: crate::Interval::from(0.0),
+			builtins: &INTERVAL_BUILTINS,
+		}
+	}
+}
+#[cfg(feature = "complex")]
+impl<'a> Default for NumEnv<'a, crate::Complex> {
+	fn default() -> ComplexEnv<'a> {
+		NumEnv {
+			ans: crate::Complex::default(),
+			builtins: &COMPLEX_BUILTINS,
+		}
+	}
+}
+#[cfg(feature = "bigfloat")]
+impl<'a> Default for NumEnv<'a, crate::BigValue> {
+	fn default() -> PreciseEnv<'a> {
+		NumEnv {
+			ans: crate::BigValue::from_f64(0.0),
+			builtins: &BIG_BUILTINS,
+		}
+	}
+}
+#[cfg(feature = "units")]
+impl<'a> Default for NumEnv<'a, crate::Quantity> {
+	fn default() -> NumEnv<'a, crate::Quantity> {
+		NumEnv {
+			ans: crate::Quantity::from(0.0),
+			builtins: &UNIT_BUILTINS,
+		}
+	}
+}
+const BUCKETS: usize = 256;
+
+// Hashes the first and last byte and the length, spreads the default builtins in buckets of at most three.
+const fn bucket(name: &str) -> usize {
+	match name.as_bytes() {
+		[] => 0,
+		&[first, ref rest @ ..] => {
+			let last = match rest { [.., last] => *last, [] => first };
+			(first as usize * 31 + last as usize * 24 + name.len()) % BUCKETS
+		},
+	}
+}
+
+// Positions of the builtins of a table grouped by the bucket of their name.
+struct BuiltinIndex<O: ?Sized = [u16]> {
+	// Start of each bucket in the order, followed by the end of the last
+	starts: [u16; BUCKETS + 1],
+	order: O,
+}
+impl<const N: usize> BuiltinIndex<[u16; N]> {
+	const fn new<V: Num>(builtins: &[BuiltinDesc<V>; N]) -> BuiltinIndex<[u16; N]> {
+		// Counting sort by bucket
+		let mut starts = [0u16; BUCKETS + 1];
+		let mut i = 0;
+		while i < N {
+			starts[bucket(builtins[i].name) + 1] += 1;
+			i += 1;
+		}
+		let mut b = 0;
+		while b < BUCKETS {
+			starts[b + 1] += starts[b];
+			b += 1;
+		}
+		let mut next = starts;
+		let mut order = [0u16; N];
+		let mut i = 0;
+		while i < N {
+			let b = bucket(builtins[i].name);
+			order[next[b] as usize] = i as u16;
+			next[b] += 1;
+			i += 1;
+		}
+		BuiltinIndex { starts, order }
+	}
+}
+impl BuiltinIndex {
+	fn find<'b, 'a, V: Num>(&self, builtins: &'b [BuiltinDesc<'a, V>], name: &str) -> Option<&'b BuiltinDesc<'a, V>> {
+		let b = bucket(name);
+		let bucket = &self.order[self.starts[b] as usize..self.starts[b + 1] as usize];
+		bucket.iter().map(|&i| &builtins[i as usize]).find(|desc| desc.name == name)
+	}
+}
+
+static DEFAULT_INDEX: BuiltinIndex<[u16; 149]> = BuiltinIndex::new(&DEFAULT_BUILTINS);
+static F32_INDEX: BuiltinIndex<[u16; 45]> = BuiltinIndex::new(&F32_BUILTINS);
+static INT_INDEX: BuiltinIndex<[u16; 18]> = BuiltinIndex::new(&INT_BUILTINS);
+static INTERVAL_INDEX: BuiltinIndex<[u16; 33]> = BuiltinIndex::new(&INTERVAL_BUILTINS);
+#[cfg(feature = "complex")]
+static COMPLEX_INDEX: BuiltinIndex<[u16; 33]> = BuiltinIndex::new(&COMPLEX_BUILTINS);
+#[cfg(feature = "bigfloat")]
+static BIG_INDEX: BuiltinIndex<[u16; 38]> = BuiltinIndex::new(&BIG_BUILTINS);
+#[cfg(feature = "units")]
+static UNIT_INDEX: BuiltinIndex<[u16; 32]> = BuiltinIndex::new(&UNIT_BUILTINS);
+
+// Index of the static tables, the builtins may be replaced by any table.
+fn index<V: Num>(builtins: &[BuiltinDesc<V>]) -> Option<&'static BuiltinIndex> {
+	fn same<T, U>(a: &[T], b: &[U]) -> bool {
+		a.as_ptr().cast::<()>() == b.as_ptr().cast::<()>() && a.len() == b.len()
+	}
+	if same(builtins, &DEFAULT_BUILTINS) { return Some(&DEFAULT_INDEX); }
+	if same(builtins, &F32_BUILTINS) { return Some(&F32_INDEX); }
+	if same(builtins, &INT_BUILTINS) { return Some(&INT_INDEX); }
+	if same(builtins, &INTERVAL_BUILTINS) { return Some(&INTERVAL_INDEX); }
+	#[cfg(feature = "complex")]
+	if same(builtins, &COMPLEX_BUILTINS) { return Some(&COMPLEX_INDEX); }
+	#[cfg(feature = "bigfloat")]
+	if same(builtins, &BIG_BUILTINS) { return Some(&BIG_INDEX); }
+	#[cfg(feature = "units")]
+	if same(builtins, &UNIT_BUILTINS) { return Some(&UNIT_INDEX); }
+	None
+}
+
+// Binary searches the builtins sorted by name.
+fn search<'b, 'a, V: Num>(builtins: &'b [BuiltinDesc<'a, V>], name: &str) -> Option<&'b BuiltinDesc<'a, V>> {
+	let index = builtins.binary_search_by_key(&name, |it| it.name).ok()?;
+	Some(&builtins[index])
+}
+
+impl<'a, V: Num> NumEnv<'a, V> {
+	fn desc(&self, name: &str) -> Option<&BuiltinDesc<'a, V>> {
+		match index(self.builtins) {
+			Some(index) => index.find(self.builtins, name),
+			None => search(self.builtins, name),
+		}
+	}
+	// Returns whether the name is a builtin which can't be used as a variable.
+	pub(crate) fn reserves(&self, name: &str) -> bool {
+		match self.desc(name) {
+			// One letter builtins like `d(sides)` leave the name to a variable
+			Some(desc) => name.len() != 1 || desc.arity.accepts(0),
+			None => false,
+		}
+	}
+}
+impl<'a, V: Num> Env<V> for NumEnv<'a, V> {
+	fn builtin(&self, name: &str) -> Result<BuiltinFn<V>, ErrorKind> {
+		self.desc(name).map(|desc| desc.pfn).ok_or(ErrorKind::EnvErrorNotFound)
+	}
+	fn get_value(&self, name: &str) -> Result<V, ErrorKind> {
 		match name {
-			"ans" => Ok(self.ans),
+			"ans" => Ok(self.ans.clone()),
+			_ if !self.reserves(name) => Err(ErrorKind::EnvErrorNotFound),
 			// Builtins which take zero arguments are treated as constants
-			_ => self.builtin(name)?(self, &mut []).map_err(|_| Error::EnvErrorBuiltinFn),
+			_ => self.builtin(name)?(self, &mut []).map_err(|_| ErrorKind::EnvErrorBuiltinFn),
 		}
 	}
-	fn set_value(&mut self, name: &str, value: Value) -> Result<(), Error> {
+	fn set_value(&mut self, name: &str, value: V) -> Result<(), ErrorKind> {
 		match name {
 			"ans" => self.ans = value,
-			_ => return Err(Error::EnvErrorNotFound),
+			_ => return Err(ErrorKind::EnvErrorNotFound),
 		}
 		Ok(())
 	}
+	fn is_pure(&self, name: &str) -> bool {
+		self.desc(name).map(|desc| desc.pure).unwrap_or(false)
+	}
+	fn divides(&self, name: &str) -> bool {
+		self.desc(name).map(|desc| desc.divides).unwrap_or(false)
+	}
+	fn arity(&self, name: &str) -> Option<Arity> {
+		self.desc(name).map(|desc| desc.arity)
+	}
+	fn params(&self, name: &str) -> Option<&[&str]> {
+		self.desc(name).map(|desc| desc.params)
+	}
+}
+
+/// Environment of variables assigned by the user over a [`NumEnv`].
+///
+/// The names of builtins cannot be assigned, except for one letter builtins which take arguments.
+/// The random number generator is seeded with zero, see [`set_seed`](Self::set_seed).
+///
+/// ```
+/// use pupil::{Env, VarEnv};
+/// let mut env = VarEnv::<f64>::default();
+/// env.set_value("r", 2.0).unwrap();
+/// assert_eq!(pupil::eval(&env, "r * d(1)"), Ok(2.0));
+/// assert_eq!(env.set_value("pi", 3.0), Err(pupil::ErrorKind::EnvErrorBuiltinFn));
+/// ```
+#[derive(Clone)]
+pub struct VarEnv<'a, V: Num> {
+	pub env: NumEnv<'a, V>,
+	pub vars: BTreeMap<String, V>,
+	rng: Rng,
+}
+
+impl<'a, V: Num> VarEnv<'a, V> {
+	/// Creates an environment without variables.
+	pub fn new(env: NumEnv<'a, V>) -> VarEnv<'a, V> {
+		VarEnv { env, vars: BTreeMap::new(), rng: Rng::default() }
+	}
+	/// Reseeds the random number generator, the same seed rolls the same dice.
+	pub fn set_seed(&mut self, seed: u64) {
+		self.rng = Rng::new(seed);
+	}
+}
+impl<'a, V: Num> Default for VarEnv<'a, V> where NumEnv<'a, V>: Default {
+	fn default() -> VarEnv<'a, V> {
+		VarEnv::new(NumEnv::default())
+	}
+}
+impl<'a, V: Num> Env<V> for VarEnv<'a, V> {
+	fn builtin(&self, name: &str) -> Result<BuiltinFn<V>, ErrorKind> {
+		self.env.builtin(name)
+	}
+	fn get_value(&self, name: &str) -> Result<V, ErrorKind> {
+		match self.vars.get(name) {
+			Some(val) => Ok(val.clone()),
+			None => self.env.get_value(name),
+		}
+	}
+	fn set_value(&mut self, name: &str, value: V) -> Result<(), ErrorKind> {
+		if name == "ans" {
+			return self.env.set_value(name, value);
+		}
+		if self.env.reserves(name) {
+			return Err(ErrorKind::EnvErrorBuiltinFn);
+		}
+		self.vars.insert(name.to_string(), value);
+		Ok(())
+	}
+	fn is_pure(&self, name: &str) -> bool {
+		self.env.is_pure(name)
+	}
+	fn divides(&self, name: &str) -> bool {
+		self.env.divides(name)
+	}
+	fn arity(&self, name: &str) -> Option<Arity> {
+		self.env.arity(name)
+	}
+	fn params(&self, name: &str) -> Option<&[&str]> {
+		self.env.params(name)
+	}
+	fn random(&self) -> Option<Value> {
+		Some(self.rng.next_value())
+	}
 }
 
 //----------------------------------------------------------------
@@ -178,13 +989,140 @@ fn var() {
 	env.set_value("ans", 12.4).unwrap();
 	assert_eq!(env.get_value("ans"), Ok(12.4));
 	assert_eq!(env.get_value("pi"), Ok(std::f64::consts::PI));
-	assert_eq!(env.get_value("unknown"), Err(Error::EnvErrorNotFound));
-	assert_eq!(env.get_value("mean"), Err(Error::EnvErrorBuiltinFn));
+	assert_eq!(env.get_value("unknown"), Err(ErrorKind::EnvErrorNotFound));
+	assert_eq!(env.get_value("mean"), Err(ErrorKind::EnvErrorBuiltinFn));
+	// One letter builtins taking arguments are only called
+	assert_eq!(env.get_value("d"), Err(ErrorKind::EnvErrorNotFound));
+	assert!(env.builtin("d").is_ok());
+	let program = crate::compile(&env, "c + d").unwrap();
+	assert_eq!(program.var_names(), ["c", "d"]);
+	assert_eq!(program.eval(&[1.0, 2.0]), Ok(3.0));
+	assert_eq!(crate::eval(&VarEnv::new(env.clone()), "d(1) + 1"), Ok(2.0));
+	assert_eq!(crate::eval(&env, "d(1)"), Err(crate::Error::new(ErrorKind::EnvErrorNotFound, 0)));
 	
+	// Random builtins are not pure
+	assert!(!env.is_pure("roll"));
+	assert!(env.is_pure("sin"));
+
+	// Inverse trigonometry is registered under the right names
+	assert_eq!(crate::eval(&env, "acos(1) + asin(0) + atan(0)"), Ok(0.0));
+
 	// Assert the default builtins are sorted
 	let mut copy = DEFAULT_BUILTINS;
-	copy.sort_by_key(|builtin| builtin.0);
-	for ((left, _), (right, _)) in Iterator::zip(copy.iter(), DEFAULT_BUILTINS.iter()) {
-		assert_eq!(left, right);
+	copy.sort_by_key(|builtin| builtin.name);
+	for (left, right) in Iterator::zip(copy.iter(), DEFAULT_BUILTINS.iter()) {
+		assert_eq!(left.name, right.name);
+	}
+}
+#[test]
+fn builtins32() {
+	let mut env = BasicEnv32::default();
+	env.set_value("ans", 12.4).unwrap();
+	assert_eq!(env.get_value("ans"), Ok(12.4f32));
+	assert_eq!(env.get_value("pi"), Ok(std::f32::consts::PI));
+	assert_eq!(env.get_value("mean"), Err(ErrorKind::EnvErrorNotFound));
+
+	// Sorted and a subset of the default builtins accepting the same arguments
+	assert!(F32_BUILTINS.windows(2).all(|pair| pair[0].name < pair[1].name));
+	for desc in F32_BUILTINS.iter() {
+		let index = DEFAULT_BUILTINS.binary_search_by_key(&desc.name, |it| it.name).unwrap();
+		let desc64 = &DEFAULT_BUILTINS[index];
+		assert_eq!((desc.pure, desc.category), (desc64.pure, desc64.category), "{}", desc.name);
+		assert_eq!(desc.params, &desc64.params[..desc.params.len()], "{}", desc.name);
+		assert!(desc.arity.min == desc64.arity.min && desc.arity.max <= desc64.arity.max, "{}", desc.name);
+	}
+}
+#[test]
+fn builtins_int() {
+	assert!(INT_BUILTINS.windows(2).all(|pair| pair[0].name < pair[1].name));
+	let env = IntEnv::default();
+	assert_eq!(env.arity("gcd"), Some(Arity::at_least(1)));
+	assert_eq!(env.get_value("pi"), Err(ErrorKind::EnvErrorNotFound));
+}
+#[test]
+fn builtins_interval() {
+	assert!(INTERVAL_BUILTINS.windows(2).all(|pair| pair[0].name < pair[1].name));
+	let env = NumEnv::<crate::Interval>::default();
+	assert!(env.get_value("tau").unwrap().contains(std::f64::consts::TAU));
+}
+#[cfg(feature = "complex")]
+#[test]
+fn builtins_complex() {
+	assert!(COMPLEX_BUILTINS.windows(2).all(|pair| pair[0].name < pair[1].name));
+	let env = ComplexEnv::default();
+	assert_eq!(env.get_value("i"), Ok(crate::Complex::I));
+	assert_eq!(env.get_value("re"), Err(ErrorKind::EnvErrorBuiltinFn));
+}
+#[cfg(feature = "bigfloat")]
+#[test]
+fn builtins_big() {
+	assert!(BIG_BUILTINS.windows(2).all(|pair| pair[0].name < pair[1].name));
+	let env = PreciseEnv::default();
+	assert_eq!(env.get_value("pi").map(|pi| pi.to_string()), Ok("3.1415926535897932384626433832795028842".to_string()));
+	assert_eq!(env.get_value("sin"), Err(ErrorKind::EnvErrorBuiltinFn));
+}
+#[cfg(feature = "units")]
+#[test]
+fn builtins_units() {
+	assert!(UNIT_BUILTINS.windows(2).all(|pair| pair[0].name < pair[1].name));
+	let env = NumEnv::<crate::Quantity>::default();
+	assert_eq!(env.arity("to"), Some(Arity::exactly(2)));
+	assert_eq!(env.get_value("m"), Err(ErrorKind::EnvErrorNotFound));
+}
+#[cfg(test)]
+fn agrees<V: Num>(builtins: &[BuiltinDesc<V>], index: &BuiltinIndex) {
+	let mut names = builtins.iter().map(|desc| desc.name.to_string()).collect::<Vec<_>>();
+	// Prefixes, extensions and other names which aren't builtins
+	names.extend(builtins.iter().flat_map(|desc| [format!("{}x", desc.name), desc.name.chars().skip(1).collect()]));
+	names.extend(["ans", "unknown", "SIN", "√"].map(String::from));
+	for name in &names {
+		let found = index.find(builtins, name).map(|desc| desc as *const _);
+		assert_eq!(found, search(builtins, name).map(|desc| desc as *const _), "{}", name);
+	}
+}
+#[test]
+fn builtin_index() {
+	agrees(&DEFAULT_BUILTINS, &DEFAULT_INDEX);
+	agrees(&F32_BUILTINS, &F32_INDEX);
+	agrees(&INT_BUILTINS, &INT_INDEX);
+	agrees(&INTERVAL_BUILTINS, &INTERVAL_INDEX);
+	#[cfg(feature = "complex")]
+	agrees(&COMPLEX_BUILTINS, &COMPLEX_INDEX);
+	#[cfg(feature = "bigfloat")]
+	agrees(&BIG_BUILTINS, &BIG_INDEX);
+	#[cfg(feature = "units")]
+	agrees(&UNIT_BUILTINS, &UNIT_INDEX);
+	assert!((0..BUCKETS).all(|b| DEFAULT_INDEX.starts[b + 1] - DEFAULT_INDEX.starts[b] <= 3));
+	// Other tables are binary searched
+	let builtins = &DEFAULT_BUILTINS[1..];
+	assert!(index(builtins).is_none());
+	let env = BasicEnv { builtins, ..Default::default() };
+	assert!(env.builtin("sin").is_ok() && env.builtin("").is_err());
+}
+#[test]
+fn rng() {
+	// Reference values of splitmix64 seeded with zero
+	let rng = Rng::new(0);
+	assert_eq!(rng.next_u64(), 0xe220a8397b1dcdaf);
+	assert_eq!(rng.next_u64(), 0x6e789e6aa1b965f4);
+	let copy = rng.clone();
+	assert_eq!(rng.next_u64(), copy.next_u64());
+	for _ in 0..1000 {
+		let val = rng.next_value();
+		assert!((0.0..1.0).contains(&val));
+	}
+}
+#[test]
+fn empty_args() {
+	// Only the constants accept an empty argument list, everything else must reject it
+	let env = BasicEnv::default();
+	let constants = [("e", std::f64::consts::E), ("pi", std::f64::consts::PI), ("tau", std::f64::consts::TAU)];
+	for desc in DEFAULT_BUILTINS.iter() {
+		let expected = match constants.iter().find(|&&(name, _)| name == desc.name) {
+			Some(&(_, value)) => Ok(value),
+			None => Err(ErrorKind::BadArgument),
+		};
+		assert_eq!((desc.pfn)(&env, &mut []), expected, "{}", desc.name);
+		assert_eq!(desc.arity.accepts(0), expected.is_ok(), "{}", desc.name);
 	}
 }
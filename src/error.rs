@@ -0,0 +1,439 @@
+use std::{error, fmt};
+use crate::Arity;
+
+//----------------------------------------------------------------
+
+/// Things that can go wrong.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ErrorKind {
+	/// Expected an operator-like thing.
+	///
+	/// Eg. `12 5`. Expected an operator instead of `5`.
+	ExpectOperator,
+	/// Expected a value-like thing.
+	NaExpression,
+	/// Disallowed unary operator.
+	///
+	/// Only `+` and `-` are allowed as unary operators.
+	DisallowedUnary,
+	/// Something went wrong unexpectedly.
+	///
+	/// This is a bug.
+	InternalError,
+	/// Expression isn’t finished, cannot end with an operator.
+	///
+	/// Eg. `2 +`.
+	UnfinishedExpression,
+	/// Tokenization failed to lex a token.
+	InvalidToken,
+	/// Different number of `(` and `)`.
+	UnbalancedParens,
+	/// Closing bracket doesn’t match the opening bracket.
+	///
+	/// Eg. `(2]`.
+	MismatchedParens,
+	/// Misplaced a comma token outside of a function application.
+	MisplacedComma,
+	/// Misplaced a semicolon token in a single expression.
+	///
+	/// Use [`eval_all`](crate::eval_all) to evaluate multiple expressions.
+	MisplacedSemicolon,
+	/// Misplaced `=` outside of a keyword argument.
+	///
+	/// Eg. `x = 2`, keyword arguments name a function argument as in `log(8, base = 2)`.
+	MisplacedAssign,
+	/// Conditional `?` without its `:` or the other way around.
+	///
+	/// Eg. `1 ? 2` or `(1 ? 2) : 3`.
+	UnbalancedConditional,
+	/// Bad number of arguments.
+	BadArgument,
+	/// A function was called with a number of arguments it doesn’t accept.
+	///
+	/// Eg. `log(8)`. Reported when the [`Env::arity`](crate::Env::arity) of the function is known,
+	/// [`Error::diagnostic`] renders the name of the function.
+	ArgumentCount {
+		/// Number of arguments accepted by the function.
+		expected: Arity,
+		/// Number of arguments supplied.
+		got: usize,
+	},
+	/// Keyword argument not among the [`Env::params`](crate::Env::params) of the function.
+	///
+	/// Eg. `log(8, bass = 2)`.
+	UnknownKeyword,
+	/// Keyword argument for an argument already given.
+	///
+	/// Eg. `log(8, x = 2)` or `log(base = 2, base = 3)`.
+	DuplicateKeyword,
+	/// Positional argument following a keyword argument.
+	///
+	/// Eg. `log(x = 8, 2)`.
+	PositionalAfterKeyword,
+	/// The expression exceeds the configured [`Limits`](crate::Limits).
+	LimitExceeded,
+	/// Division by zero in strict mode.
+	///
+	/// See [`Expr::set_strict`](crate::Expr::set_strict).
+	DivisionByZero,
+	/// A function of finite arguments has no finite result in strict mode.
+	///
+	/// Eg. `sqrt(-1)` or `log(-1, 10)`. See [`Expr::set_strict`](crate::Expr::set_strict).
+	MathDomain,
+	/// An integer result doesn’t fit in the integer type.
+	///
+	/// Eg. `2^62 * 4` in [`eval_int`](crate::eval_int).
+	Overflow,
+	/// A literal or result isn’t an integer where one is required.
+	///
+	/// Eg. `1.5` or `7 / 2` in [`eval_int`](crate::eval_int) with [`IntDivision::Exact`](crate::IntDivision::Exact).
+	NotInteger,
+	/// Quantities of different dimensions are combined.
+	///
+	/// Eg. `3 m + 2 s` in `eval_units` with the `units` feature.
+	DimensionMismatch,
+	/// The expression needs more pending fns or values than the fixed capacity.
+	///
+	/// See [`eval_fixed`](crate::eval_fixed), names are not limited.
+	CapacityExceeded,
+	/// A variable or function symbol wasn’t found.
+	EnvErrorNotFound,
+	/// Expected a variable name, found a builtin symbol instead.
+	EnvErrorBuiltinFn,
+}
+impl fmt::Display for ErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let desc = match self {
+			ErrorKind::ExpectOperator => "expected an operator",
+			ErrorKind::NaExpression => "not an expression",
+			ErrorKind::DisallowedUnary => "not an unary operator",
+			ErrorKind::InternalError => "internal corruption",
+			ErrorKind::UnfinishedExpression => "unfinished expression",
+			ErrorKind::InvalidToken => "invalid token",
+			ErrorKind::UnbalancedParens => "unbalanced parens",
+			ErrorKind::MismatchedParens => "mismatched parens",
+			ErrorKind::MisplacedComma => "misplaced comma",
+			ErrorKind::MisplacedSemicolon => "misplaced semicolon",
+			ErrorKind::MisplacedAssign => "misplaced assignment",
+			ErrorKind::UnbalancedConditional => "unbalanced conditional",
+			ErrorKind::BadArgument => "bad argument",
+			ErrorKind::ArgumentCount { expected, got } => return write!(f, "function expects {}, got {}", expected, got),
+			ErrorKind::UnknownKeyword => "unknown keyword",
+			ErrorKind::DuplicateKeyword => "duplicate keyword",
+			ErrorKind::PositionalAfterKeyword => "positional argument after keyword",
+			ErrorKind::LimitExceeded => "limit exceeded",
+			ErrorKind::DivisionByZero => "division by zero",
+			ErrorKind::MathDomain => "math domain error",
+			ErrorKind::Overflow => "integer overflow",
+			ErrorKind::NotInteger => "not an integer",
+			ErrorKind::DimensionMismatch => "dimension mismatch",
+			ErrorKind::CapacityExceeded => "capacity exceeded",
+			ErrorKind::EnvErrorNotFound => "env error not found",
+			ErrorKind::EnvErrorBuiltinFn => "env error builtin",
+		};
+		desc.fmt(f)
+	}
+}
+impl error::Error for ErrorKind {}
+
+//----------------------------------------------------------------
+
+/// Error with the position in the input where it went wrong.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Error {
+	/// What went wrong.
+	pub kind: ErrorKind,
+	/// Byte offset in the input.
+	pub position: usize,
+}
+impl Error {
+	/// Creates a new error.
+	pub const fn new(kind: ErrorKind, position: usize) -> Error {
+		Error { kind, position }
+	}
+	/// Renders the error with the offending line of the input and a caret pointing at the position.
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let input = "2 + (3";
+	/// let error = pupil::eval(&env, input).unwrap_err();
+	/// assert_eq!(error.diagnostic(input).to_string(), "\
+	/// error: unbalanced parens at line 1, column 5
+	/// 2 + (3
+	///     ^");
+	/// ```
+	pub fn diagnostic<'a>(&'a self, input: &'a str) -> Diagnostic<'a> {
+		Diagnostic { error: self, input, color: ColorMode::Never }
+	}
+	/// Renders the error like [`diagnostic`](Error::diagnostic), colored with ANSI escape codes if enabled.
+	///
+	/// The `error:` prefix and the caret are red and the message is bold.
+	pub fn diagnostic_colored<'a>(&'a self, input: &'a str, color: ColorMode) -> Diagnostic<'a> {
+		Diagnostic { error: self, input, color }
+	}
+}
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} at position {}", self.kind, self.position)
+	}
+}
+impl error::Error for Error {}
+
+/// Kinds of suspicious constructs, see [`Warning`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum WarningKind {
+	/// Implicit multiplication.
+	///
+	/// Eg. `2pi` or `(1 + 2)(3)`.
+	ImplicitMul,
+	/// Sign applied to a sign.
+	///
+	/// Eg. `2+--3`.
+	RepeatedSign,
+	/// Variable named like a function which takes arguments.
+	///
+	/// Eg. `sin` without parens, the env may still provide a value for it.
+	FunctionName,
+}
+impl fmt::Display for WarningKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let desc = match self {
+			WarningKind::ImplicitMul => "implicit multiplication",
+			WarningKind::RepeatedSign => "repeated sign",
+			WarningKind::FunctionName => "function used as a variable",
+		};
+		desc.fmt(f)
+	}
+}
+
+/// Legal but suspicious construct, often a typo.
+///
+/// Warnings never stop the evaluation, see [`eval_with_warnings`](crate::eval_with_warnings).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Warning {
+	/// What looks suspicious.
+	pub kind: WarningKind,
+	/// Description of the construct.
+	pub message: String,
+	/// Byte offset in the input.
+	pub position: usize,
+}
+impl Warning {
+	/// Creates a new warning described by its kind.
+	pub fn new(kind: WarningKind, position: usize) -> Warning {
+		Warning { kind, message: kind.to_string(), position }
+	}
+}
+impl fmt::Display for Warning {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} at position {}", self.message, self.position)
+	}
+}
+
+/// Whether diagnostics are colored, see [`Error::diagnostic_colored`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ColorMode {
+	/// Plain text.
+	#[default]
+	Never,
+	/// Colored with ANSI escape codes.
+	Always,
+}
+impl ColorMode {
+	/// Colors when writing to a terminal unless the `NO_COLOR` environment variable is set.
+	pub fn auto(is_terminal: bool) -> ColorMode {
+		let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+		if is_terminal && !no_color { ColorMode::Always } else { ColorMode::Never }
+	}
+}
+
+/// Displays an error with the offending line of the input.
+///
+/// See [`Error::diagnostic`].
+#[derive(Copy, Clone, Debug)]
+pub struct Diagnostic<'a> {
+	error: &'a Error,
+	input: &'a str,
+	color: ColorMode,
+}
+impl<'a> fmt::Display for Diagnostic<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let map = LineMap::new(self.input);
+		let (line, column) = map.position_to_line_col(self.error.position);
+		let text = map.line(line);
+		let (red, bold, reset) = match self.color {
+			ColorMode::Never => ("", "", ""),
+			ColorMode::Always => ("\x1b[1;31m", "\x1b[1m", "\x1b[0m"),
+		};
+		write!(f, "{}error:{} {}", red, reset, bold)?;
+		match self.error.kind {
+			// Name the function, eg. `clamp expects 3 arguments, got 2`
+			ErrorKind::ArgumentCount { expected, got } => {
+				let rest = self.input.get(self.error.position..).unwrap_or("");
+				let name = match rest.find(|chr: char| !chr.is_alphanumeric() && chr != '_') {
+					Some(end) => &rest[..end],
+					None => rest,
+				};
+				let name = if name.is_empty() { "function" } else { name };
+				write!(f, "{} expects {}, got {} at line {}, column {}", name, expected, got, line, column)?;
+			},
+			kind => write!(f, "{} at line {}, column {}", kind, line, column)?,
+		}
+		writeln!(f, "{}", reset)?;
+		writeln!(f, "{}", text)?;
+		// Align the caret with the characters before it
+		for chr in text.chars().take(column as usize - 1) {
+			match chr {
+				'\t' => f.write_str("\t")?,
+				_ => for _ in 0..char_width(chr) { f.write_str(" ")?; },
+			}
+		}
+		write!(f, "{}^{}", red, reset)
+	}
+}
+
+// Approximate number of terminal columns taken by a character.
+fn char_width(chr: char) -> usize {
+	match chr as u32 {
+		// Combining marks and zero width characters
+		0x0300..=0x036F | 0x200B..=0x200F => 0,
+		// East asian wide and fullwidth characters, emoji
+		0x1100..=0x115F | 0x2E80..=0x303E | 0x3041..=0xA4CF | 0xAC00..=0xD7A3 |
+		0xF900..=0xFAFF | 0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 |
+		0x1F300..=0x1F64F | 0x1F900..=0x1F9FF | 0x20000..=0x3FFFD => 2,
+		_ => 1,
+	}
+}
+
+//----------------------------------------------------------------
+
+/// Maps byte offsets in the input to lines and columns.
+#[derive(Clone, Debug)]
+pub struct LineMap<'a> {
+	input: &'a str,
+	// Byte offset of the start of every line
+	lines: Vec<usize>,
+}
+impl<'a> LineMap<'a> {
+	/// Creates a new line map for the input.
+	pub fn new(input: &'a str) -> LineMap<'a> {
+		let mut lines = vec![0];
+		lines.extend(input.match_indices('\n').map(|(pos, _)| pos + 1));
+		LineMap { input, lines }
+	}
+	/// Returns the one-based line and column of a byte offset.
+	///
+	/// The column counts UTF-8 characters, not bytes.
+	/// Positions past the end of the input are clamped to the end of the input.
+	pub fn position_to_line_col(&self, position: usize) -> (u32, u32) {
+		let position = usize::min(position, self.input.len());
+		let index = match self.lines.binary_search(&position) {
+			Ok(index) => index,
+			Err(index) => index - 1,
+		};
+		// Count the characters by skipping the UTF-8 continuation bytes
+		let column = self.input.as_bytes()[self.lines[index]..position].iter()
+			.filter(|&&byte| byte & 0xC0 != 0x80)
+			.count();
+		(index as u32 + 1, column as u32 + 1)
+	}
+	/// Returns the text of the one-based line without the line terminator.
+	pub fn line(&self, line: u32) -> &'a str {
+		let index = line as usize - 1;
+		let start = self.lines[index];
+		let end = self.lines.get(index + 1).map(|&end| end - 1).unwrap_or(self.input.len());
+		let text = &self.input[start..end];
+		text.strip_suffix('\r').unwrap_or(text)
+	}
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn positions() {
+	let input = "1 +\r\n\tπ * (2\n";
+	let map = LineMap::new(input);
+	assert_eq!(map.position_to_line_col(0), (1, 1));
+	assert_eq!(map.position_to_line_col(2), (1, 3));
+	assert_eq!(map.position_to_line_col(6), (2, 2));
+	assert_eq!(map.position_to_line_col(11), (2, 6));
+	assert_eq!(map.position_to_line_col(input.len()), (3, 1));
+	assert_eq!(map.position_to_line_col(1000), (3, 1));
+	assert_eq!(map.line(1), "1 +");
+	assert_eq!(map.line(2), "\tπ * (2");
+	assert_eq!(map.line(3), "");
+
+	let error = Error::new(ErrorKind::UnbalancedParens, 11);
+	assert_eq!(error.diagnostic(input).to_string(), "error: unbalanced parens at line 2, column 6\n\tπ * (2\n\t    ^");
+	let error = Error::new(ErrorKind::InvalidToken, 9);
+	assert_eq!(error.diagnostic("数字 + !").to_string(), "error: invalid token at line 1, column 6\n数字 + !\n       ^");
+}
+#[test]
+fn arguments() {
+	let env = crate::BasicEnv::default();
+	let render = |input| crate::eval(&env, input).unwrap_err().diagnostic(input).to_string();
+	assert_eq!(render("log(8)"), "error: log expects 2 arguments, got 1 at line 1, column 1\nlog(8)\n^");
+	assert_eq!(render("1 +\n sqrt(4, 9)"), "error: sqrt expects 1 argument, got 2 at line 2, column 2\n sqrt(4, 9)\n ^");
+	assert_eq!(render("2 * pi(1)"), "error: pi expects no arguments, got 1 at line 1, column 5\n2 * pi(1)\n    ^");
+	assert_eq!(render("sub(1, 2, 3)"), "error: sub expects 1 to 2 arguments, got 3 at line 1, column 1\nsub(1, 2, 3)\n^");
+	assert_eq!(render("max()"), "error: max expects at least 1 argument, got 0 at line 1, column 1\nmax()\n^");
+	assert_eq!(render("mul(2)"), "error: mul expects at least 2 arguments, got 1 at line 1, column 1\nmul(2)\n^");
+	// Without the input the function isn’t named
+	assert_eq!(crate::eval(&env, "atan2(1)").unwrap_err().to_string(), "function expects 2 arguments, got 1 at position 0");
+}
+#[test]
+fn colored() {
+	let env = crate::BasicEnv::default();
+	let render = |input, color| crate::eval(&env, input).unwrap_err().diagnostic_colored(input, color).to_string();
+	assert_eq!(render("2 + (3", ColorMode::Never), "error: unbalanced parens at line 1, column 5\n2 + (3\n    ^");
+	assert_eq!(render("2 + (3", ColorMode::Always), "\x1b[1;31merror:\x1b[0m \x1b[1munbalanced parens at line 1, column 5\x1b[0m\n2 + (3\n    \x1b[1;31m^\x1b[0m");
+	assert_eq!(render("log(8)", ColorMode::Never), "error: log expects 2 arguments, got 1 at line 1, column 1\nlog(8)\n^");
+	assert_eq!(render("log(8)", ColorMode::Always), "\x1b[1;31merror:\x1b[0m \x1b[1mlog expects 2 arguments, got 1 at line 1, column 1\x1b[0m\nlog(8)\n\x1b[1;31m^\x1b[0m");
+	assert_eq!(ColorMode::auto(false), ColorMode::Never);
+}
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+	let kinds = [
+		ErrorKind::ExpectOperator,
+		ErrorKind::NaExpression,
+		ErrorKind::DisallowedUnary,
+		ErrorKind::InternalError,
+		ErrorKind::UnfinishedExpression,
+		ErrorKind::InvalidToken,
+		ErrorKind::UnbalancedParens,
+		ErrorKind::MismatchedParens,
+		ErrorKind::MisplacedComma,
+		ErrorKind::MisplacedSemicolon,
+		ErrorKind::MisplacedAssign,
+		ErrorKind::UnbalancedConditional,
+		ErrorKind::BadArgument,
+		ErrorKind::ArgumentCount { expected: Arity::between(1, 2), got: 3 },
+		ErrorKind::UnknownKeyword,
+		ErrorKind::DuplicateKeyword,
+		ErrorKind::PositionalAfterKeyword,
+		ErrorKind::LimitExceeded,
+		ErrorKind::DivisionByZero,
+		ErrorKind::MathDomain,
+		ErrorKind::Overflow,
+		ErrorKind::NotInteger,
+		ErrorKind::DimensionMismatch,
+		ErrorKind::CapacityExceeded,
+		ErrorKind::EnvErrorNotFound,
+		ErrorKind::EnvErrorBuiltinFn,
+	];
+	for (position, kind) in kinds.into_iter().enumerate() {
+		let error = Error::new(kind, position);
+		let json = serde_json::to_string(&error).unwrap();
+		assert_eq!(serde_json::from_str::<Error>(&json).unwrap(), error);
+	}
+	assert_eq!(serde_json::to_string(&Error::new(ErrorKind::UnbalancedParens, 3)).unwrap(), r#"{"kind":"UnbalancedParens","position":3}"#);
+	let warning = Warning::new(WarningKind::ImplicitMul, 1);
+	assert_eq!(serde_json::to_string(&warning).unwrap(), r#"{"kind":"ImplicitMul","message":"implicit multiplication","position":1}"#);
+}
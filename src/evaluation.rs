@@ -0,0 +1,76 @@
+use crate::{Error, Value};
+
+/// Result of evaluating an input, serializes as a single object for scripting.
+///
+/// Successes serialize as `{"input":"2+3","ok":true,"value":5.0}`,
+/// failures as `{"input":"(2","ok":false,"error":{"kind":"UnbalancedParens","position":0}}`.
+/// JSON has no infinities or NaN, these values serialize as the strings `"inf"`, `"-inf"` and `"nan"`.
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize)]
+pub struct Evaluation<'a> {
+	/// The evaluated input.
+	pub input: &'a str,
+	/// Whether the evaluation succeeded.
+	pub ok: bool,
+	/// The value on success.
+	#[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_value")]
+	pub value: Option<Value>,
+	/// The error on failure.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<Error>,
+}
+
+impl<'a> Evaluation<'a> {
+	/// Creates the evaluation of the input.
+	pub fn new(input: &'a str, result: &Result<Value, Error>) -> Evaluation<'a> {
+		match *result {
+			Ok(value) => Evaluation { input, ok: true, value: Some(value), error: None },
+			Err(error) => Evaluation { input, ok: false, value: None, error: Some(error) },
+		}
+	}
+	/// Serializes as a single line of JSON, requires the `json` feature.
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let result = pupil::eval(&env, "2 + 3");
+	/// assert_eq!(pupil::Evaluation::new("2 + 3", &result).to_json(), r#"{"input":"2 + 3","ok":true,"value":5.0}"#);
+	/// ```
+	#[cfg(feature = "json")]
+	pub fn to_json(&self) -> String {
+		serde_json::to_string(self).unwrap()
+	}
+}
+
+fn serialize_value<S: serde::Serializer>(value: &Option<Value>, serializer: S) -> Result<S::Ok, S::Error> {
+	match *value {
+		Some(value) => serde::Serialize::serialize(&JsonValue(value), serializer),
+		None => serializer.serialize_none(),
+	}
+}
+
+// Value serialized with the non-finite values as strings.
+pub(crate) struct JsonValue(pub Value);
+
+impl serde::Serialize for JsonValue {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match self.0 {
+			value if value.is_finite() => serializer.serialize_f64(value),
+			value if value.is_nan() => serializer.serialize_str("nan"),
+			value if value > 0.0 => serializer.serialize_str("inf"),
+			_ => serializer.serialize_str("-inf"),
+		}
+	}
+}
+
+#[test]
+fn evaluations() {
+	let env = crate::BasicEnv::default();
+	let json = |input: &str| serde_json::to_string(&Evaluation::new(input, &crate::eval(&env, input))).unwrap();
+	assert_eq!(json("2 + 3"), r#"{"input":"2 + 3","ok":true,"value":5.0}"#);
+	assert_eq!(json("-0.5"), r#"{"input":"-0.5","ok":true,"value":-0.5}"#);
+	assert_eq!(json("2 + (3"), r#"{"input":"2 + (3","ok":false,"error":{"kind":"UnbalancedParens","position":4}}"#);
+	assert_eq!(json("\"1\""), r#"{"input":"\"1\"","ok":false,"error":{"kind":"InvalidToken","position":0}}"#);
+	// Non-finite values are strings
+	assert_eq!(json("1/0"), r#"{"input":"1/0","ok":true,"value":"inf"}"#);
+	assert_eq!(json("-1/0"), r#"{"input":"-1/0","ok":true,"value":"-inf"}"#);
+	assert_eq!(json("0/0"), r#"{"input":"0/0","ok":true,"value":"nan"}"#);
+}
@@ -1,229 +1,594 @@
+use std::{fmt, mem};
 use crate::*;
+use crate::parser::{self, Parser, Sink};
+use crate::storage::{Alloc, Fixed, Heap, Storage};
 
-// Consider this a finite state automaton of some kind.
-// At any point while parsing an expression, it is either expecting a value or operator-like thing.
+// Evaluates the parsed expression on the fly.
+struct Eval<'a, V: Num, A: Alloc = Heap> {
+	env: &'a dyn Env<V>,
+	vals: A::Vals<V>,
+	// Whether the condition was true for each nested conditional
+	// Lazy fns have an entry to skip their remaining arguments
+	conds: A::Fns<bool>,
+	// Index of the conditional in conds whose branch not taken is being parsed
+	// While skipping the tokens are parsed but neither looked up nor evaluated
+	skip: Option<usize>,
+	// Whether non-finite results of finite arguments are errors
+	strict: bool,
+	tracer: Option<Box<dyn FnMut(TraceEvent<V>) + 'a>>,
+	// Names of the functions called while tracing
+	names: Vec<String>,
+	// Parameters named by the keyword arguments of the pending fns
+	keys: A::Vals<usize>,
+	// Whether warnings are collected, see Expr::set_warnings
+	collect: bool,
+	warnings: Vec<Warning>,
+	// Full precision value of the literal being parsed
+	literal: Option<V>,
+}
+
+// Fn waiting for its arguments.
+#[derive(Copy, Clone)]
+struct EvalFn<'a, V: Num> {
+	pfn: BuiltinFn<V>,
+	// Whether it divides by its last argument
+	divides: bool,
+	// Number of arguments accepted if known
+	arity: Option<Arity>,
+	// Parameter names of calls, none for operators and grouping brackets
+	params: Option<&'a [&'a str]>,
+	// Number of keyword arguments
+	keywords: usize,
+	// Lazy fns know which arguments decide the result and their index in conds
+	lazy: Option<(bool, usize)>,
+	name: TraceName,
+}
+
+// Name of the fn reported to the tracer.
+#[derive(Copy, Clone)]
+enum TraceName {
+	// Grouping brackets and fns applied while not tracing
+	None,
+	Static(&'static str),
+	// Index into the names
+	Call(usize),
+}
+
+impl<'a, V: Num> EvalFn<'a, V> {
+	fn new(pfn: BuiltinFn<V>) -> EvalFn<'a, V> {
+		EvalFn { pfn, divides: false, arity: None, params: None, keywords: 0, lazy: None, name: TraceName::None }
+	}
+}
+
+/// Application of an operator or function, see [`Expr::set_tracer`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEvent<V = Value> {
+	/// Name of the function, operators are named after the equivalent builtin.
+	///
+	/// Eg. `mul` for both `2*3` and `mul(2, 3)`, and `percent` for the percentage `5%`.
+	pub name: String,
+	/// Values of the arguments.
+	pub args: Vec<V>,
+	/// The result.
+	pub result: V,
+	/// Byte offset of the operator or function in the input.
+	pub position: usize,
+}
+
+impl<V: fmt::Display> fmt::Display for TraceEvent<V> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}(", self.name)?;
+		for (i, arg) in self.args.iter().enumerate() {
+			if i > 0 {
+				f.write_str(", ")?;
+			}
+			write!(f, "{}", arg)?;
+		}
+		write!(f, ") = {}", self.result)
+	}
+}
+
+impl<'a, V: Num, A: Alloc> Eval<'a, V, A> {
+	fn new(env: &'a dyn Env<V>) -> Eval<'a, V, A> {
+		Eval {
+			env,
+			vals: Default::default(),
+			conds: Default::default(),
+			skip: None,
+			strict: false,
+			tracer: None,
+			names: Vec::new(),
+			keys: Default::default(),
+			collect: false,
+			warnings: Vec::new(),
+			literal: None,
+		}
+	}
+	// Skip the branch of the conditional at this index unless already skipping.
+	fn skip_start(&mut self, index: usize) {
+		if self.skip.is_none() {
+			self.skip = Some(index);
+		}
+	}
+	// Stop skipping if the conditional at this index started it.
+	fn skip_end(&mut self, index: usize) {
+		if self.skip == Some(index) {
+			self.skip = None;
+		}
+	}
+}
+
+impl<'a, V: Num, A: Alloc> Sink for Eval<'a, V, A> {
+	type Fn = EvalFn<'a, V>;
+	type Alloc = A;
+	fn lit(&mut self, val: Value, _pos: usize) -> Result<(), ErrorKind> {
+		let val = self.literal.take().unwrap_or_else(|| V::from_f64(val));
+		self.vals.push(val)
+	}
+	#[cfg(feature = "complex")]
+	fn imag(&mut self, val: Value, _pos: usize) -> Result<(), ErrorKind> {
+		let val = V::imaginary(val).ok_or(ErrorKind::InvalidToken)?;
+		self.vals.push(val)
+	}
+	fn var(&mut self, name: &str, pos: usize) -> Result<(), ErrorKind> {
+		let val = if self.skip.is_some() { V::from_f64(Value::NAN) } else { self.env.get_value(name)? };
+		// Constants are builtins without arguments
+		if A::WARNINGS && self.collect && self.skip.is_none() && self.env.builtin(name).is_ok() && self.env.arity(name).map(|arity| !arity.accepts(0)).unwrap_or(false) {
+			let message = format!("{} is a function used as a variable", name);
+			self.warnings.push(Warning { kind: WarningKind::FunctionName, message, position: pos });
+		}
+		self.vals.push(val)
+	}
+	fn call(&mut self, name: &str) -> Result<EvalFn<'a, V>, ErrorKind> {
+		if self.skip.is_some() {
+			let params = if name.is_empty() { None } else { Some(&[][..]) };
+			return Ok(EvalFn { params, ..EvalFn::new(V::identity()) });
+		}
+		let pfn = self.env.builtin(name)?;
+		// Grouping parentheses report a bad argument and aren’t traced
+		if name.is_empty() {
+			return Ok(EvalFn::new(pfn));
+		}
+		let traced = if self.tracer.is_some() {
+			self.names.push(name.into());
+			TraceName::Call(self.names.len() - 1)
+		}
+		else {
+			TraceName::None
+		};
+		let lazy = match crate::builtins::lazy(name) {
+			Some(decides) => {
+				self.conds.push(true)?;
+				Some((decides, self.conds.len() - 1))
+			},
+			None => None,
+		};
+		Ok(EvalFn {
+			pfn,
+			divides: self.env.divides(name),
+			arity: self.env.arity(name),
+			params: Some(self.env.params(name).unwrap_or(&[])),
+			keywords: 0,
+			lazy,
+			name: traced,
+		})
+	}
+	fn op(&mut self, op: Operator) -> EvalFn<'a, V> {
+		EvalFn {
+			divides: matches!(op, Operator::Div | Operator::Rem | Operator::FloorDiv),
+			name: TraceName::Static(op.desc().name),
+			..EvalFn::new(V::operator(op))
+		}
+	}
+	fn percent(&mut self) -> EvalFn<'a, V> {
+		EvalFn { name: TraceName::Static("percent"), ..EvalFn::new(V::percent()) }
+	}
+	fn group(&mut self) -> EvalFn<'a, V> {
+		EvalFn::new(V::identity())
+	}
+	fn apply(&mut self, f: EvalFn<'a, V>, nargs: usize, pos: usize) -> Result<(), ErrorKind> {
+		// Find its arguments
+		if nargs > self.vals.len() {
+			// This should never happen... Panic instead?
+			// Indicates a logic error when manipulating the nargs.
+			return Err(ErrorKind::InternalError);
+		}
+		let args = self.vals.len() - nargs..;
+		let keys = self.keys.len().checked_sub(f.keywords).ok_or(ErrorKind::InternalError)?;
+		// Lazy fns skipping their remaining arguments were decided
+		let decided = match f.lazy {
+			Some((decides, index)) => {
+				self.conds.pop();
+				let decided = self.skip == Some(index);
+				self.skip_end(index);
+				decided.then_some(V::from_f64(if decides { 1.0 } else { 0.0 }))
+			},
+			None => None,
+		};
+		// Apply the fn unless in a branch not taken
+		let result = if let Some(val) = decided { val }
+		else if self.skip.is_some() { V::from_f64(Value::NAN) }
+		else {
+			if let Some(arity) = f.arity {
+				arity.check(nargs)?;
+			}
+			let vals = &mut self.vals[args.clone()];
+			parser::place_keywords(nargs, &mut self.keys[keys..], |i, j| vals.swap(i, j))?;
+			let finite = self.strict && vals.iter().all(|val| val.is_finite());
+			let by_zero = f.divides && vals.last().is_some_and(|val| val.is_zero());
+			// Copy the arguments before the fn gets to modify them
+			let traced = self.tracer.as_ref().map(|_| vals.to_vec());
+			let result = (f.pfn)(self.env, vals)?;
+			if finite && !result.is_finite() {
+				return Err(if by_zero { ErrorKind::DivisionByZero } else { ErrorKind::MathDomain });
+			}
+			if let (Some(tracer), Some(args)) = (&mut self.tracer, traced) {
+				let name = match f.name {
+					TraceName::None => None,
+					TraceName::Static(name) => Some(String::from(name)),
+					TraceName::Call(index) => self.names.get(index).cloned(),
+				};
+				if let Some(name) = name {
+					tracer(TraceEvent { name, args, result: result.clone(), position: pos });
+				}
+			}
+			result
+		};
+		// Pop vals and push result
+		self.keys.truncate(keys);
+		self.vals.truncate(args.start);
+		self.vals.push(result)
+	}
+	fn keyword(&mut self, f: &mut EvalFn<'a, V>, index: usize, name: &str, _pos: usize) -> Result<(), ErrorKind> {
+		let params = f.params.ok_or(ErrorKind::MisplacedAssign)?;
+		// Keywords are resolved while skipping but not looked up
+		let param = if self.skip.is_some() { index }
+		else {
+			let keys = self.keys.len().checked_sub(f.keywords).ok_or(ErrorKind::InternalError)?;
+			parser::resolve_keyword(params, name, index, &self.keys[keys..])?
+		};
+		self.keys.push(param)?;
+		f.keywords += 1;
+		Ok(())
+	}
+	fn next_arg(&mut self, f: &mut EvalFn<'a, V>) -> Result<(), ErrorKind> {
+		if let Some((decides, index)) = f.lazy {
+			let val = self.vals.last().ok_or(ErrorKind::InternalError)?;
+			if val.truthy() == decides {
+				self.skip_start(index);
+			}
+		}
+		Ok(())
+	}
+	fn warn(&mut self, kind: WarningKind, pos: usize) {
+		if A::WARNINGS && self.collect {
+			self.warnings.push(Warning::new(kind, pos));
+		}
+	}
+	fn cond(&mut self) -> Result<(), ErrorKind> {
+		let cond = self.vals.pop().ok_or(ErrorKind::InternalError)?;
+		let taken = cond.truthy();
+		self.conds.push(taken)?;
+		if !taken {
+			self.skip_start(self.conds.len() - 1);
+		}
+		Ok(())
+	}
+	fn cond_else(&mut self) -> Result<(), ErrorKind> {
+		let &taken = self.conds.last().ok_or(ErrorKind::InternalError)?;
+		let index = self.conds.len() - 1;
+		if taken {
+			self.skip_start(index);
+		}
+		else {
+			// Discard the value of the skipped then branch
+			self.vals.pop();
+			self.skip_end(index);
+		}
+		Ok(())
+	}
+	fn cond_end(&mut self, _pos: usize) -> Result<(), ErrorKind> {
+		let taken = self.conds.pop().ok_or(ErrorKind::InternalError)?;
+		if taken {
+			// Discard the value of the skipped else branch
+			self.vals.pop();
+		}
+		self.skip_end(self.conds.len());
+		Ok(())
+	}
+}
+
+/// Limits on the complexity of expressions.
+///
+/// Protects against untrusted input exhausting resources, see [`eval_with_limits`].
+/// Exceeding a limit fails with [`ErrorKind::LimitExceeded`] at the offending token.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum State { Val, Op }
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limits {
+	/// Maximum number of tokens.
+	pub max_tokens: usize,
+	/// Maximum number of pending operators, functions and brackets.
+	pub max_depth: usize,
+	/// Maximum number of arguments to a function.
+	pub max_args: usize,
+}
 
-struct FnVal {
-	pfn: BuiltinFn,
-	pre: Order,
-	nargs: u8,
+impl Default for Limits {
+	/// No limits.
+	fn default() -> Limits {
+		Limits {
+			max_tokens: usize::MAX,
+			max_depth: usize::MAX,
+			max_args: usize::MAX,
+		}
+	}
 }
 
 /// The expression context.
-pub struct Expr<'a> {
-	env: &'a dyn Env,
-	fns: Vec<FnVal>,
-	vals: Vec<Value>,
-	next: State,
+///
+/// Evaluates in the [`Num`] type of its environment, `f64` unless bound to eg. a [`BasicEnv32`].
+pub struct Expr<'a, V: Num = Value> {
+	parser: Parser<Eval<'a, V>>,
+	// Total length of the input fed so far
+	offset: usize,
+	// Trailing input of which the tokens may continue in the next chunk
+	chunk: String,
+	options: LexerOptions,
 }
 
-impl<'a> Expr<'a> {
+impl<'a, V: Num> Expr<'a, V> {
 	/// Creates a new expression and binds it to the environment.
-	pub fn new(env: &'a dyn Env) -> Expr<'a> {
+	pub fn new(env: &'a dyn Env<V>) -> Expr<'a, V> {
 		Expr {
-			env,
-			fns: Vec::new(),
-			vals: Vec::new(),
-			next: State::Val,
+			parser: Parser::new(Eval::new(env)),
+			offset: 0,
+			chunk: String::new(),
+			options: LexerOptions::default(),
 		}
 	}
+	/// Configures the tokenizer used to feed input.
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let mut expr = pupil::Expr::new(&env);
+	/// expr.set_lexer_options(pupil::LexerOptions { locale: pupil::Locale::DecimalComma });
+	/// expr.feed("max(1,5; 2,5)").unwrap();
+	/// assert_eq!(expr.result(), Ok(2.5));
+	/// ```
+	pub fn set_lexer_options(&mut self, options: LexerOptions) {
+		self.options = options;
+	}
+	/// Configures the limits on the complexity of the expression.
+	pub fn set_limits(&mut self, limits: Limits) {
+		self.parser.limits = limits;
+	}
+	/// Configures strict arithmetic, off by default.
+	///
+	/// When strict, an operator or function of finite arguments without a finite result fails
+	/// with [`ErrorKind::DivisionByZero`] when dividing by zero or [`ErrorKind::MathDomain`] otherwise.
+	/// Infinities and NaN in the arguments propagate as usual.
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let mut expr = pupil::Expr::new(&env);
+	/// expr.set_strict(true);
+	/// expr.feed("2 + 1 / 0").unwrap();
+	/// assert_eq!(expr.result(), Err(pupil::Error::new(pupil::ErrorKind::DivisionByZero, 6)));
+	/// assert_eq!(pupil::eval(&env, "2 + 1 / 0"), Ok(f64::INFINITY));
+	/// ```
+	pub fn set_strict(&mut self, strict: bool) {
+		self.parser.sink.strict = strict;
+	}
+	/// Calls the tracer with every operator and function applied, in order of evaluation.
+	///
+	/// Grouping brackets and branches not taken are not traced, see also [`explain`].
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let mut trace = Vec::new();
+	/// let mut expr = pupil::Expr::new(&env);
+	/// expr.set_tracer(|event| trace.push(event.to_string()));
+	/// expr.feed("max(2, 3) * 4").unwrap();
+	/// assert_eq!(expr.result(), Ok(12.0));
+	/// assert_eq!(trace, ["max(2, 3) = 3", "mul(3, 4) = 12"]);
+	/// ```
+	pub fn set_tracer<F: FnMut(TraceEvent<V>) + 'a>(&mut self, tracer: F) {
+		self.parser.sink.tracer = Some(Box::new(tracer));
+	}
+	/// Configures collecting warnings about suspicious constructs, off by default.
+	///
+	/// See [`take_warnings`](Self::take_warnings) and [`eval_with_warnings`].
+	pub fn set_warnings(&mut self, collect: bool) {
+		self.parser.sink.collect = collect;
+	}
 	/// Parses a token.
+	///
+	/// Errors are reported at the token’s position or the position of the operator or function which failed.
 	pub fn parse(&mut self, tok: Token) -> Result<(), Error> {
-		match self.next {
-			State::Op => self.parse_op(tok),
-			State::Val => self.parse_val(tok),
-		}
+		self.parser.parse(tok)
 	}
 	/// Feeds new input to be parsed and evaluated.
+	///
+	/// The end of the input is a token boundary, eg. feeding `12` then `.5` parses as two literals.
+	/// See [`feed_chunked`](Self::feed_chunked) for input which may split tokens.
+	///
+	/// Positions are relative to the start of the first input fed to this expression.
 	pub fn feed(&mut self, input: &str) -> Result<(), Error> {
-		// Tokenize and parse the input
-		for tok in tokenize(input) {
-			// Dispatch based on a simple state machine:
-			//  expect either an operator or value like token.
-			self.parse(tok)?;
-		}
-		Ok(())
+		self.flush()?;
+		self.feed_str(input)
+	}
+	/// Feeds a chunk of a larger input to be parsed and evaluated.
+	///
+	/// Tokens may be split across chunks, eg. feeding `si` then `n(1)` parses as `sin(1)`.
+	/// Trailing input which may continue in the next chunk is retained until more input arrives
+	/// or the expression is finalized with [`result`](Self::result).
+	pub fn feed_chunked(&mut self, chunk: &str) -> Result<(), Error> {
+		self.chunk.push_str(chunk);
+		// Tokens never span whitespace or these delimiters
+		let delimiters = match self.options.locale {
+			Locale::DecimalPoint => "()[]{},;",
+			Locale::DecimalComma => "()[]{};",
+		};
+		let end = match self.chunk.char_indices().rev().find(|&(_, chr)| chr.is_whitespace() || delimiters.contains(chr)) {
+			Some((pos, chr)) => pos + chr.len_utf8(),
+			None => return Ok(()),
+		};
+		let mut chunk = mem::take(&mut self.chunk);
+		let result = self.feed_str(&chunk[..end]);
+		// Keep the remainder and reuse the allocation
+		chunk.drain(..end);
+		self.chunk = chunk;
+		result
 	}
 	/// Finalizes the expression and calculates the final result.
-	pub fn result(mut self) -> Result<Value, Error> {
-		// Must end at a value like token
-		if self.next == State::Val {
-			return Err(Error::UnfinishedExpression);
-		}
-		// Evaluate all pending operators
-		self.eval_gt(Order::FnBarrier)?;
-		// Expect exactly one result
-		if self.vals.len() != 1 || self.fns.len() != 0 {
-			return Err(Error::UnbalancedParens);
+	pub fn result(mut self) -> Result<V, Error> {
+		self.result_and_clear()
+	}
+	/// Finalizes the expression and calculates the final result, then clears the expression for reuse.
+	///
+	/// The expression is cleared even if there was an error.
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let mut expr = pupil::Expr::new(&env);
+	/// for (input, result) in [("2 + 3", 5.0), ("max(1, 2)", 2.0)] {
+	///     expr.feed(input).unwrap();
+	///     assert_eq!(expr.result_and_clear(), Ok(result));
+	/// }
+	/// ```
+	pub fn result_and_clear(&mut self) -> Result<V, Error> {
+		let result = self.finish();
+		self.clear();
+		result
+	}
+	/// Takes the warnings about suspicious constructs in the input parsed so far.
+	///
+	/// Warnings are only collected when enabled with [`set_warnings`](Self::set_warnings)
+	/// and are discarded when the expression is cleared.
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let mut expr = pupil::Expr::new(&env);
+	/// expr.set_warnings(true);
+	/// expr.feed("2pi").unwrap();
+	/// let warnings = expr.take_warnings();
+	/// assert_eq!(warnings[0].kind, pupil::WarningKind::ImplicitMul);
+	/// assert_eq!(warnings[0].position, 1);
+	/// ```
+	pub fn take_warnings(&mut self) -> Vec<Warning> {
+		mem::take(&mut self.parser.sink.warnings)
+	}
+	/// Discards the input fed so far to start a new expression.
+	///
+	/// Keeps the allocations and the configuration, discards the warnings.
+	pub fn clear(&mut self) {
+		self.parser.clear();
+		let sink = &mut self.parser.sink;
+		sink.vals.clear();
+		sink.conds.clear();
+		sink.skip = None;
+		sink.names.clear();
+		sink.keys.clear();
+		sink.warnings.clear();
+		self.offset = 0;
+		self.chunk.clear();
+	}
+	/// Clears the expression and binds it to another environment.
+	///
+	/// The tracer is removed as it may borrow from the old environment’s lifetime.
+	///
+	/// ```
+	/// let env = pupil::BasicEnv { ans: 2.0, ..Default::default() };
+	/// let mut expr = pupil::Expr::new(&env);
+	/// expr.feed("ans * 3").unwrap();
+	/// let other = pupil::BasicEnv { ans: 5.0, ..Default::default() };
+	/// let mut expr = expr.rebind(&other);
+	/// expr.feed("ans * 3").unwrap();
+	/// assert_eq!(expr.result(), Ok(15.0));
+	/// ```
+	pub fn rebind<'b>(mut self, env: &'b dyn Env<V>) -> Expr<'b, V> {
+		self.clear();
+		Expr {
+			parser: self.parser.map_sink(|sink| Eval {
+				env,
+				vals: sink.vals,
+				conds: sink.conds,
+				skip: None,
+				strict: sink.strict,
+				tracer: None,
+				names: sink.names,
+				keys: sink.keys,
+				collect: sink.collect,
+				warnings: sink.warnings,
+				literal: None,
+			}),
+			offset: 0,
+			chunk: self.chunk,
+			options: self.options,
 		}
-		// Return the result
-		Ok(self.vals[0])
 	}
 }
 
 //----------------------------------------------------------------
 
 // Implementation details go here.
-impl<'a> Expr<'a> {
-	fn parse_val(&mut self, tok: Token) -> Result<(), Error> {
-		match tok {
-			Token::Unk(_) => {
-				Err(Error::InvalidToken)
-			},
-			Token::Lit(val) => {
-				// Push on the value stack
-				self.vals.push(val);
-				// Followed by an operator
-				self.next = State::Op;
-				Ok(())
-			},
-			Token::Op(op) => {
-				// Unary operators have high precedence
-				let desc = op.desc();
-				if desc.unary {
-					self.fns.push(FnVal {
-						pfn: desc.pfn,
-						pre: Order::Unary,
-						nargs: 1,
-					});
-					// Followed by a value
-					self.next = State::Val;
-					Ok(())
-				}
-				else {
-					Err(Error::DisallowedUnary)
-				}
-			},
-			Token::Var(name) => {
-				// Lookup the symbol variable
-				let result = self.env.get_value(name)?;
-				// And push the resulting value
-				self.vals.push(result);
-				// Followed by an operator
-				self.next = State::Op;
-				Ok(())
-			},
-			Token::Open(name) => {
-				let pfn = self.env.builtin(name)?;
-				let pre = Order::FnBarrier; // Very low precedence acts as a barrier
-				let nargs = 1;
-				self.fns.push(FnVal { pfn, pre, nargs });
-				// Followed by its arguments
-				self.next = State::Val;
-				Ok(())
-			},
-			Token::Comma => {
-				Err(Error::NaExpression)
-			},
-			Token::Close => {
-				// This should catch function calls with empty argument list...
-				// Eg. `add()` or `pi()`. For constants just leave the parens out.
-				if self.fns.last().map(|f| f.pre == Order::FnBarrier && f.nargs == 1).unwrap_or(false) {
-					Err(Error::BadArgument)
-				}
-				else {
-					Err(Error::NaExpression)
-				}
-			},
-		}
+impl<'a, V: Num> Expr<'a, V> {
+	fn is_empty(&self) -> bool {
+		self.parser.is_empty()
 	}
-	fn parse_op(&mut self, tok: Token) -> Result<(), Error> {
-		match tok {
-			Token::Unk(_) => {
-				Err(Error::InvalidToken)
-			},
-			Token::Lit(_) => {
-				Err(Error::ExpectOperator)
-			},
-			Token::Op(op) => {
-				// Get relevant operator descriptor
-				let desc = op.desc();
-				// Evaluate all lower precedence fns
-				match desc.assoc {
-					Assoc::Left => self.eval_ge(desc.pre)?,
-					Assoc::Right => self.eval_gt(desc.pre)?,
-					// Assoc::None => return Err(Error::InternalError),
-				};
-				// Push operator as fn, always takes two arguments
-				self.fns.push(FnVal {
-					pfn: desc.pfn,
-					pre: desc.pre,
-					nargs: 2,
-				});
-				// Followed by a value
-				self.next = State::Val;
-				Ok(())
-			},
-			Token::Var(_) => {
-				// Insert implicit multiplication token
-				self.parse_op(Token::Op(Operator::IMul))?;
-				// Retry inserting this token
-				self.parse_val(tok)
-			},
-			Token::Open(_) => {
-				// Insert implicit multiplication token
-				self.parse_op(Token::Op(Operator::IMul))?;
-				// Retry inserting this token
-				self.parse_val(tok)
-			},
-			Token::Comma => {
-				// Eval until an fn barier
-				self.eval_gt(Order::FnBarrier)?;
-				// Increment nargs for that fn
-				self.fns.last_mut().ok_or(Error::MisplacedComma)?.nargs += 1;
-				// Followed by a value
-				self.next = State::Val;
-				Ok(())
-			},
-			Token::Close => {
-				// Eval everything until the fn barrier and push past it
-				self.eval_gt(Order::FnBarrier)?;
-				self.eval_apply()?;
-				// Followed by an operator
-				self.next = State::Op;
-				Ok(())
-			},
-		}
+	fn feed_str(&mut self, input: &str) -> Result<(), Error> {
+		let offset = self.offset;
+		self.offset += input.len();
+		self.parser.feed_in(input, offset, self.options)
+	}
+	fn finish(&mut self) -> Result<V, Error> {
+		// Parse any retained input
+		self.flush()?;
+		self.parser.result_at(self.offset)
 	}
-	// Eval all fns with higher or equal precedence.
-	fn eval_ge(&mut self, pre: Order) -> Result<(), Error> {
-		while self.fns.last().map(|f| f.pre >= pre).unwrap_or(false) {
-			self.eval_apply()?;
+	fn flush(&mut self) -> Result<(), Error> {
+		if self.chunk.is_empty() {
+			return Ok(());
 		}
-		Ok(())
+		let mut chunk = mem::take(&mut self.chunk);
+		let result = self.feed_str(&chunk);
+		chunk.clear();
+		self.chunk = chunk;
+		result
 	}
-	// Eval all fns with strictly higher precedence.
-	fn eval_gt(&mut self, pre: Order) -> Result<(), Error> {
-		while self.fns.last().map(|f| f.pre > pre).unwrap_or(false) {
-			self.eval_apply()?;
+}
+
+// Evaluation shared by the storages.
+impl<'a, V: Num, A: Alloc> Parser<Eval<'a, V, A>> {
+	// Tokenizes and parses the input starting at the offset.
+	fn feed_in(&mut self, input: &str, offset: usize, options: LexerOptions) -> Result<(), Error> {
+		let exact = matches!(V::from_literal("0"), Ok(Some(_)));
+		let mut tokens = tokenize_num(input, options, V::imaginary(1.0).is_some(), V::quoted_names()).peekable();
+		while let Some(tok) = tokens.next() {
+			self.parse_in(input, tok, tokens.peek(), offset, exact, options.locale)?;
 		}
 		Ok(())
 	}
-	// Pop and eval a single fn.
-	fn eval_apply(&mut self) -> Result<(), Error> {
-		if let Some(f) = self.fns.pop() {
-			// Find its arguments
-			if f.nargs as usize > self.vals.len() {
-				// This should never happen... Panic instead?
-				// Indicates a logic error when manipulating the nargs.
-				return Err(Error::InternalError);
+	// Parses a token of the input followed by the next token, literals at full precision if exact.
+	fn parse_in(&mut self, input: &str, tok: Token, next: Option<&Token>, offset: usize, exact: bool, locale: Locale) -> Result<(), Error> {
+		if exact && matches!(tok.kind, TokenKind::Lit(_)) {
+			if let Some(text) = literal_text(&input[tok.position..], locale) {
+				let position = tok.position + offset;
+				self.sink.literal = V::from_literal(&text.replace(',', ".")).map_err(|kind| Error::new(kind, position))?;
 			}
-			let args = self.vals.len() - f.nargs as usize..;
-			// Apply the fn
-			let result = {
-				let vals = &mut self.vals[args.clone()];
-				(f.pfn)(self.env, vals)?
-			};
-			// Pop vals and push result
-			let _ = self.vals.drain(args.clone());
-			self.vals.push(result);
-			Ok(())
 		}
-		else {
-			// You tried to apply an fn when there are no more fns to apply
-			Err(Error::UnbalancedParens)
+		let tok = Token { position: tok.position + offset, ..tok };
+		let result = if A::LOOKAHEAD { self.parse_ahead(tok, next) } else { self.parse(tok) };
+		self.sink.literal = None;
+		result
+	}
+	// Finishes parsing at the end and takes the result.
+	fn result_at(&mut self, end: usize) -> Result<V, Error> {
+		self.finish(end)?;
+		// Expect exactly one result
+		match self.sink.vals[..] {
+			[ref val] => Ok(val.clone()),
+			_ => Err(self.error(ErrorKind::UnbalancedParens)),
 		}
 	}
 }
@@ -235,12 +600,148 @@ impl<'a> Expr<'a> {
 /// let result = pupil::eval(&env, "2 + 3");
 /// assert_eq!(result, Ok(5.0));
 /// ```
-pub fn eval(env: &dyn Env, input: &str) -> Result<Value, Error> {
+pub fn eval<V: Num>(env: &dyn Env<V>, input: &str) -> Result<V, Error> {
+	let mut expr = Expr::new(env);
+	expr.feed(input)?;
+	expr.result()
+}
+
+/// Evaluates the input and collects the warnings about suspicious constructs.
+///
+/// The warnings are collected even if the evaluation fails.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// let (result, warnings) = pupil::eval_with_warnings(&env, "2+--3");
+/// assert_eq!(result, Ok(5.0));
+/// assert_eq!(warnings[0].kind, pupil::WarningKind::RepeatedSign);
+/// assert_eq!(warnings[0].position, 3);
+/// ```
+pub fn eval_with_warnings<V: Num>(env: &dyn Env<V>, input: &str) -> (Result<V, Error>, Vec<Warning>) {
+	let mut expr = Expr::new(env);
+	expr.set_warnings(true);
+	let result = expr.feed(input).and_then(|_| expr.finish());
+	(result, expr.take_warnings())
+}
+
+/// Evaluates the input within the limits.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// let limits = pupil::Limits { max_tokens: 3, ..Default::default() };
+/// assert_eq!(pupil::eval_with_limits(&env, "1 + 2", &limits), Ok(3.0));
+/// assert_eq!(pupil::eval_with_limits(&env, "1 + 2 + 3", &limits).map_err(|e| e.kind), Err(pupil::ErrorKind::LimitExceeded));
+/// ```
+pub fn eval_with_limits<V: Num>(env: &dyn Env<V>, input: &str, limits: &Limits) -> Result<V, Error> {
 	let mut expr = Expr::new(env);
+	expr.set_limits(*limits);
 	expr.feed(input)?;
 	expr.result()
 }
 
+/// Evaluates the input and returns the trace of the operators and functions applied.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// let (result, trace) = pupil::explain(&env, "2 * 3 + 4").unwrap();
+/// assert_eq!(result, 10.0);
+/// assert_eq!(trace[0].to_string(), "mul(2, 3) = 6");
+/// assert_eq!(trace[1].to_string(), "add(6, 4) = 10");
+/// ```
+pub fn explain<V: Num>(env: &dyn Env<V>, input: &str) -> Result<(V, Vec<TraceEvent<V>>), Error> {
+	let mut trace = Vec::new();
+	let mut expr = Expr::new(env);
+	expr.set_tracer(|event| trace.push(event));
+	expr.feed(input)?;
+	let result = expr.result()?;
+	Ok((result, trace))
+}
+
+/// Evaluates many inputs in order, reusing the allocations between them.
+///
+/// An error only fails its own input.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// let results = pupil::eval_many(&env, &["2 + 3", "1 +", "max(1, 2)"]);
+/// assert_eq!(results[0], Ok(5.0));
+/// assert!(results[1].is_err());
+/// assert_eq!(results[2], Ok(2.0));
+/// ```
+pub fn eval_many<V: Num>(env: &dyn Env<V>, inputs: &[&str]) -> Vec<Result<V, Error>> {
+	let mut expr = Expr::new(env);
+	inputs.iter().map(|input| eval_reuse(&mut expr, input)).collect()
+}
+
+/// Evaluates many inputs in parallel, see [`eval_many`].
+///
+/// The results are in the order of the inputs.
+#[cfg(feature = "rayon")]
+pub fn par_eval_many(env: &(dyn Env + Sync), inputs: &[&str]) -> Vec<Result<Value, Error>> {
+	use rayon::prelude::*;
+	inputs.par_iter().map_init(|| Expr::new(env), |expr, input| eval_reuse(expr, input)).collect()
+}
+
+// Evaluates the input with an expression which is cleared afterwards.
+fn eval_reuse<V: Num>(expr: &mut Expr<V>, input: &str) -> Result<V, Error> {
+	let result = expr.feed(input).and_then(|_| expr.result_and_clear());
+	if result.is_err() {
+		expr.clear();
+	}
+	result
+}
+
+/// Evaluates multiple expressions separated by semicolons.
+///
+/// Empty expressions, such as a trailing semicolon, are skipped.
+/// Stops at the first error.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// let result = pupil::eval_all(&env, "2 + 3; max(1, 2);");
+/// assert_eq!(result, Ok(vec![5.0, 2.0]));
+/// ```
+pub fn eval_all<V: Num>(env: &dyn Env<V>, input: &str) -> Result<Vec<V>, Error> {
+	let mut results = Vec::new();
+	let mut expr = Expr::new(env);
+	let exact = matches!(V::from_literal("0"), Ok(Some(_)));
+	let mut tokens = tokenize_num(input, LexerOptions::default(), V::imaginary(1.0).is_some(), V::quoted_names()).peekable();
+	while let Some(tok) = tokens.next() {
+		if tok.kind == TokenKind::Semi {
+			if !expr.is_empty() {
+				// Errors at the end of the expression point at the semicolon
+				expr.offset = tok.position;
+				results.push(expr.result_and_clear()?);
+			}
+		}
+		else {
+			expr.parser.parse_in(input, tok, tokens.peek(), 0, exact, Locale::DecimalPoint)?;
+		}
+	}
+	if !expr.is_empty() {
+		expr.offset = input.len();
+		results.push(expr.result()?);
+	}
+	Ok(results)
+}
+
+/// Evaluates the input without allocating, for embedded targets.
+///
+/// Up to `F` operators and functions may be pending and up to `V` values, exceeding either fails with [`ErrorKind::CapacityExceeded`].
+/// Names are borrowed from the input and may be of any length, warnings are not collected.
+///
+/// ```
+/// let env = pupil::BasicEnv::default();
+/// assert_eq!(pupil::eval_fixed::<16, 16, _>(&env, "2 * (3 + 4)"), Ok(14.0));
+/// let result = pupil::eval_fixed::<2, 16, _>(&env, "(((1)))");
+/// assert_eq!(result.map_err(|e| e.kind), Err(pupil::ErrorKind::CapacityExceeded));
+/// ```
+pub fn eval_fixed<const F: usize, const V: usize, N: Num>(env: &dyn Env<N>, input: &str) -> Result<N, Error> {
+	let mut parser = Parser::new(Eval::<N, Fixed<F, V>>::new(env));
+	parser.feed_in(input, 0, LexerOptions::default())?;
+	parser.result_at(input.len())
+}
+
 #[test]
 fn basics() {
 	let env = crate::BasicEnv::default();
@@ -258,20 +759,601 @@ fn funcs() {
 	assert_eq!(eval(&env, "mul(2,add(3,4))"), Ok(14.0));
 }
 #[test]
+fn brackets() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(eval(&env, "[ (1+2) * { 5 - 1 } ]"), Ok(12.0));
+	assert_eq!(eval(&env, "{[2]}[3]"), Ok(6.0));
+	assert_eq!(eval(&env, "max([1], {2})"), Ok(2.0));
+	assert_eq!(eval(&env, "( 1 ]").map_err(|e| e.kind), Err(ErrorKind::MismatchedParens));
+	assert_eq!(eval(&env, "[ 1 )").map_err(|e| e.kind), Err(ErrorKind::MismatchedParens));
+	assert_eq!(eval(&env, "{ (1 } )").map_err(|e| e.kind), Err(ErrorKind::MismatchedParens));
+	assert_eq!(eval(&env, "max(1, 2]").map_err(|e| e.kind), Err(ErrorKind::MismatchedParens));
+	assert_eq!(eval(&env, "[1").map_err(|e| e.kind), Err(ErrorKind::UnbalancedParens));
+	assert_eq!(eval(&env, "1}").map_err(|e| e.kind), Err(ErrorKind::UnbalancedParens));
+	assert_eq!(eval(&env, "[]").map_err(|e| e.kind), Err(ErrorKind::BadArgument));
+	assert_eq!(eval(&env, "(]").map_err(|e| e.kind), Err(ErrorKind::MismatchedParens));
+	// Function calls must use round parens
+	assert_eq!(eval(&env, "sin[0]").map_err(|e| e.kind), Err(ErrorKind::EnvErrorBuiltinFn));
+}
+#[test]
+fn unicode() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(eval(&env, "2×π"), Ok(std::f64::consts::TAU));
+	assert_eq!(eval(&env, "√9 + 1"), Ok(4.0));
+	assert_eq!(eval(&env, "2√9"), Ok(6.0));
+	assert_eq!(eval(&env, "−√(8×2)"), Ok(-4.0));
+	assert_eq!(eval(&env, "3² + 4²"), Ok(25.0));
+	assert_eq!(eval(&env, "2·3").map_err(|e| e.kind), Err(ErrorKind::InvalidToken));
+	assert_eq!(eval(&env, "-2²"), Ok(-4.0));
+	assert_eq!(eval(&env, "2^3²"), Ok(512.0));
+	assert_eq!(eval(&env, "(1+1)³ ÷ 4"), Ok(2.0));
+	assert_eq!(eval(&env, "pi² - pi * pi"), Ok(0.0));
+	let mut env = env;
+	env.ans = 2.0;
+	assert_eq!(eval(&env, "ans³ + ans² + √ans²"), Ok(14.0));
+	assert_eq!(eval(&env, "²").map_err(|e| e.kind), Err(ErrorKind::DisallowedUnary));
+	assert_eq!(eval(&env, "2 −").map_err(|e| e.kind), Err(ErrorKind::UnfinishedExpression));
+}
+#[test]
+fn single() {
+	// The suite evaluated in single precision agrees with double precision
+	let env = crate::BasicEnv { ans: 5.0, ..Default::default() };
+	let env32 = crate::BasicEnv32 { ans: 5.0, ..Default::default() };
+	let inputs = [
+		"2 + 3", "2-3*4", "2*3+4", "3^2-2", "2+---2", "-1",
+		"2*(3+4)", "mul(2,add(3,4))", "[ (1+2) * { 5 - 1 } ]", "{[2]}[3]", "max([1], {2})",
+		"2×π", "√9 + 1", "2√9", "−√(8×2)", "3² + 4²", "-2²", "2^3²", "(1+1)³ ÷ 4",
+		"120 * 15%", "50% + 1", "-50%", "(10 + 40)%", "7 % 3", "7 % pi", "50 % -2",
+		"2**10", "7 // 2", "-7 // 2", "7 // -2", "1 + 9 // 2 * 3",
+		"1 + 1 && 0", "0 && 1 + 1", "2 - 2 || 3", "1 || 0 && 0", "0 || 0 || 5", "all(1, 2) && any(0, 3)", "not(nan)",
+		"2(3 + 4)", "2sqrt(9)", "2ans", "1/2(4)", "2^(2)3", "(10)%3",
+		"max(1, 3, 2,)", "sin(0,)", "log(8, base = 2)", "atan2(x = 1, y = 0) + sub(x = 5, y = 3,)", "pow(exp = [1 + 1], base = 3) * e",
+		"1 ? 2 : 3", "0 ? 1 : 0 ? 2 : 3", "max(0 ? 1 : 5, 1 ? 2 : 3) * 2", "ans - 5 ? 1 / 0 : 2",
+		"mod(-7, 3) + rem(-7, 3) + clamp(7, 1, 5)", "exp(1) - ln(e) + log2(8) + log10(1000)",
+		"sin(1) + cos(2) * tan(0.5) - hypot(3, 4) + cbrt(27) + deg(pi) + rad(180)",
+		"asin(0.5) + acos(0.5) + atan(1) + sinh(1) - cosh(1) + tanh(0.5)", "floor(-2.5) + ceil(2.5) + round(2.5) + abs(-1)",
+		"1 / 0", "0 ? log() : 1", "1 + ", "2 3", "sin[0]", "hello(5)", "log(x = 8, 2)", "1 ? 2",
+	];
+	for input in inputs {
+		let expected = eval(&env, input).map(|val| val as f32);
+		let result = eval(&env32, input);
+		match (result, expected) {
+			(Ok(val), Ok(expected)) => assert!(val == expected || (val - expected).abs() <= 1e-5 * expected.abs().max(1.0), "{:?}: {} != {}", input, val, expected),
+			(result, expected) => assert_eq!(result, expected, "{:?}", input),
+		}
+	}
+	// Functions missing from the single precision builtins
+	assert_eq!(eval(&env32, "mean(1, 2)"), Err(Error::new(ErrorKind::EnvErrorNotFound, 0)));
+	assert_eq!(eval(&env32, "round(2.5, 1)"), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::exactly(1), got: 2 }, 0)));
+	// Rounds in single precision
+	assert_eq!(eval(&env32, "16777216 + 1"), Ok(16777216.0));
+	assert_eq!(explain(&env32, "0.1 * 3").unwrap().1[0].result, 0.1f32 * 3.0);
+	let mut expr = Expr::new(&env32);
+	expr.set_strict(true);
+	expr.feed("1 // 0").unwrap();
+	assert_eq!(expr.result(), Err(Error::new(ErrorKind::DivisionByZero, 2)));
+}
+#[test]
+fn chunked() {
+	let env = crate::BasicEnv::default();
+	let inputs = [
+		"12.5 + sin(1) * 2e3 && max(1, 2) ^ 2",
+		"1e+5 - [ans]*{pi} + 4%",
+		"√2×π − mean(1,2,3)²",
+		"2ans(3) + 50 % (7)",
+		"cos(2 + 1",
+		"sin 2 ! 3",
+		"0 ? log() : 1 ? 2 : 3",
+	];
+	for input in inputs {
+		let expected = eval(&env, input);
+		for (i, _) in input.char_indices() {
+			let mut expr = Expr::new(&env);
+			let result = expr.feed_chunked(&input[..i])
+				.and_then(|_| expr.feed_chunked(&input[i..]))
+				.and_then(|_| expr.result());
+			assert_eq!(result, expected, "{:?} split at {}", input, i);
+		}
+	}
+	// Split into individual characters
+	let mut expr = Expr::new(&env);
+	for chr in "10.25 * sin(0) + sqrt(16)".chars() {
+		expr.feed_chunked(chr.encode_utf8(&mut [0; 4])).unwrap();
+	}
+	assert_eq!(expr.result(), Ok(4.0));
+	// Plain feed treats the end of input as a token boundary
+	let mut expr = Expr::new(&env);
+	expr.feed("12").unwrap();
+	assert_eq!(expr.feed(".5"), Err(Error::new(ErrorKind::ExpectOperator, 2)));
+}
+#[test]
+fn locale() {
+	let env = crate::BasicEnv::default();
+	let eval = |input: &str| {
+		let mut expr = Expr::new(&env);
+		expr.set_lexer_options(LexerOptions { locale: Locale::DecimalComma });
+		expr.feed(input)?;
+		expr.result()
+	};
+	assert_eq!(eval("2,5 * 2"), Ok(5.0));
+	assert_eq!(eval("max(1,5; 2,5)"), Ok(2.5));
+	assert_eq!(eval("max(1;2;3)"), Ok(3.0));
+	assert_eq!(eval("1,2,3"), Err(Error::new(ErrorKind::ExpectOperator, 3)));
+	assert_eq!(eval("1,5;"), Err(Error::new(ErrorKind::MisplacedComma, 3)));
+	assert_eq!(eval("3.14"), Err(Error::new(ErrorKind::InvalidToken, 1)));
+	// Chunked feeding keeps the decimal comma with its literal
+	let mut expr = Expr::new(&env);
+	expr.set_lexer_options(LexerOptions { locale: Locale::DecimalComma });
+	expr.feed_chunked("max(2;3,").unwrap();
+	expr.feed_chunked("5)").unwrap();
+	assert_eq!(expr.result(), Ok(3.5));
+}
+#[test]
+fn multiple() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(eval_all(&env, "1 + 1; 2 * 3; max(1, 4)"), Ok(vec![2.0, 6.0, 4.0]));
+	assert_eq!(eval_all(&env, ";;1;;2;"), Ok(vec![1.0, 2.0]));
+	assert_eq!(eval_all(&env, "50%; 7 % 2"), Ok(vec![0.5, 1.0]));
+	assert_eq!(eval_all(&env, ""), Ok(vec![]));
+	assert_eq!(eval_all(&env, " ; "), Ok(vec![]));
+	assert_eq!(eval_all(&env, "1; 2 +; 3"), Err(Error::new(ErrorKind::UnfinishedExpression, 6)));
+	assert_eq!(eval_all(&env, "1; -"), Err(Error::new(ErrorKind::UnfinishedExpression, 4)));
+	assert_eq!(eval_all(&env, "max(1; 2)"), Err(Error::new(ErrorKind::UnbalancedParens, 0)));
+	assert_eq!(eval_all(&env, "1; 2)"), Err(Error::new(ErrorKind::UnbalancedParens, 4)));
+	assert_eq!(eval(&env, "1; 2"), Err(Error::new(ErrorKind::MisplacedSemicolon, 1)));
+	assert_eq!(eval(&env, ";"), Err(Error::new(ErrorKind::MisplacedSemicolon, 0)));
+}
+#[test]
+fn strict() {
+	let env = crate::BasicEnv { ans: f64::INFINITY, ..Default::default() };
+	let strict = |input| {
+		let mut expr = Expr::new(&env);
+		expr.set_strict(true);
+		expr.feed(input)?;
+		expr.result()
+	};
+	assert_eq!(strict("1 / 0"), Err(Error::new(ErrorKind::DivisionByZero, 2)));
+	assert_eq!(strict("2 * (0 / 0)"), Err(Error::new(ErrorKind::DivisionByZero, 7)));
+	assert_eq!(strict("1 + 7 % 0"), Err(Error::new(ErrorKind::DivisionByZero, 6)));
+	assert_eq!(strict("1 // 0"), Err(Error::new(ErrorKind::DivisionByZero, 2)));
+	assert_eq!(strict("div(1, 0)"), Err(Error::new(ErrorKind::DivisionByZero, 0)));
+	assert_eq!(strict("2 rem(1, 0)"), Err(Error::new(ErrorKind::DivisionByZero, 2)));
+	assert_eq!(strict("mod(1, 0)"), Err(Error::new(ErrorKind::DivisionByZero, 0)));
+	assert_eq!(strict("sqrt(-1)"), Err(Error::new(ErrorKind::MathDomain, 0)));
+	assert_eq!(strict("√-1"), Err(Error::new(ErrorKind::MathDomain, 0)));
+	assert_eq!(strict("log(-1, 10)"), Err(Error::new(ErrorKind::MathDomain, 0)));
+	assert_eq!(strict("1 + ln(0)"), Err(Error::new(ErrorKind::MathDomain, 4)));
+	assert_eq!(strict("10 ^ 400"), Err(Error::new(ErrorKind::MathDomain, 3)));
+	assert_eq!(strict("0 ^ -1"), Err(Error::new(ErrorKind::MathDomain, 2)));
+	assert_eq!(strict("1 / 0%"), Err(Error::new(ErrorKind::DivisionByZero, 2)));
+	// Legitimate results
+	assert_eq!(strict("1 / 2"), Ok(0.5));
+	assert_eq!(strict("0 / 1"), Ok(0.0));
+	assert_eq!(strict("sqrt(4) + ln(1)"), Ok(2.0));
+	// Infinities from the inputs propagate
+	assert_eq!(strict("ans + 1"), Ok(f64::INFINITY));
+	assert_eq!(strict("1 / ans"), Ok(0.0));
+	assert!(strict("ans - ans").unwrap().is_nan());
+	// Branches not taken are not evaluated
+	assert_eq!(strict("0 ? 1 / 0 : 2"), Ok(2.0));
+	// User builtins declare whether they divide
+	let builtins = [BuiltinDesc { name: "ratio", pfn: crate::builtins::builtin_div, pure: true, divides: true, arity: Arity::exactly(2), params: &[], category: Category::Arithmetic, desc: "" }];
+	let user = crate::BasicEnv { builtins: &builtins, ..Default::default() };
+	let mut expr = Expr::new(&user);
+	expr.set_strict(true);
+	assert_eq!(expr.feed("ratio(1, 0)").and_then(|_| expr.result()), Err(Error::new(ErrorKind::DivisionByZero, 0)));
+	// Permissive by default
+	assert_eq!(eval(&env, "1 / 0"), Ok(f64::INFINITY));
+	assert!(eval(&env, "sqrt(-1)").unwrap().is_nan());
+}
+#[test]
+fn many() {
+	let env = crate::BasicEnv::default();
+	let inputs = ["2 + 3", "(1 +", "", "max(1, 2", "4 * 5", "1 2", "sqrt(9)"];
+	let expected = inputs.iter().map(|input| eval(&env, input)).collect::<Vec<_>>();
+	assert_eq!(eval_many(&env, &inputs), expected);
+	assert_eq!(eval_many(&env, &[]), vec![]);
+	#[cfg(feature = "rayon")]
+	{
+		let inputs = inputs.repeat(100);
+		let expected = inputs.iter().map(|input| eval(&env, input)).collect::<Vec<_>>();
+		assert_eq!(par_eval_many(&env, &inputs), expected);
+	}
+}
+#[test]
+fn trace() {
+	let env = crate::BasicEnv::default();
+	let trace = |input| explain(&env, input).map(|(_, trace)| trace.iter().map(|event| event.to_string()).collect::<Vec<_>>());
+	assert_eq!(trace("2*3+4"), Ok(vec!["mul(2, 3) = 6".into(), "add(6, 4) = 10".into()]));
+	assert_eq!(explain(&env, "2*3+4").unwrap().1[1], TraceEvent { name: "add".into(), args: vec![6.0, 4.0], result: 10.0, position: 3 });
+	assert_eq!(trace("-[2](3) + 50%"), Ok(vec!["sub(2) = -2".into(), "mul(-2, 3) = -6".into(), "percent(50) = 0.5".into(), "add(-6, 0.5) = -5.5".into()]));
+	// Arguments before the fn modified them
+	assert_eq!(trace("median(3, 1, 2)"), Ok(vec!["median(3, 1, 2) = 2".into()]));
+	// Branches not taken
+	assert_eq!(trace("0 || 1 ? sqrt(4) : max(1, 2)"), Ok(vec!["any(0, 1) = 1".into(), "sqrt(4) = 2".into()]));
+	assert_eq!(trace("0 ? 1 : 2"), Ok(vec![]));
+	assert_eq!(trace("1 +"), Err(Error::new(ErrorKind::UnfinishedExpression, 3)));
+}
+#[test]
+fn reuse() {
+	let env = crate::BasicEnv::default();
+	let mut expr = Expr::new(&env);
+	expr.set_limits(Limits { max_tokens: 5, ..Default::default() });
+	// Errors clear the expression
+	expr.feed("(1 +").unwrap();
+	assert_eq!(expr.result_and_clear(), Err(Error::new(ErrorKind::UnfinishedExpression, 4)));
+	expr.feed_chunked("0 ? 1").unwrap();
+	assert_eq!(expr.result_and_clear(), Err(Error::new(ErrorKind::UnbalancedConditional, 2)));
+	// Positions start over after clearing
+	expr.feed("2 + 3").unwrap();
+	expr.clear();
+	expr.feed_chunked("max(1,").unwrap();
+	expr.feed_chunked(" 2)").unwrap();
+	assert_eq!(expr.result_and_clear(), Ok(2.0));
+	assert_eq!(expr.feed("1 + 2 + 3 + 4"), Err(Error::new(ErrorKind::LimitExceeded, 10)));
+	// Rebinding keeps the options
+	let other = crate::BasicEnv { ans: 4.0, ..Default::default() };
+	let mut expr = expr.rebind(&other);
+	expr.feed("2ans +").unwrap();
+	assert_eq!(expr.result_and_clear(), Err(Error::new(ErrorKind::UnfinishedExpression, 6)));
+	expr.feed("0 ? 1 : ans").unwrap();
+	assert_eq!(expr.result(), Ok(4.0));
+}
+#[test]
+fn percent() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(eval(&env, "120 * 15%"), Ok(18.0));
+	assert_eq!(eval(&env, "50% + 1"), Ok(1.5));
+	assert_eq!(eval(&env, "50%"), Ok(0.5));
+	assert_eq!(eval(&env, "-50%"), Ok(-0.5));
+	assert_eq!(eval(&env, "(10 + 40)%"), Ok(0.5));
+	assert_eq!(eval(&env, "max(50%, 20)"), Ok(20.0));
+	assert_eq!(eval(&env, "7 % 3"), Ok(1.0));
+	assert_eq!(eval(&env, "7%3"), Ok(1.0));
+	assert_eq!(eval(&env, "7 % pi"), Ok(7.0 % std::f64::consts::PI));
+	assert_eq!(eval(&env, "50 % ( 2 )"), Ok(0.0));
+	assert_eq!(eval(&env, "50 % -2"), Ok(-1.5));
+	assert_eq!(eval(&env, "5 % !").map_err(|e| e.kind), Err(ErrorKind::InvalidToken));
+}
+#[test]
+fn spellings() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(eval(&env, "2**10"), Ok(1024.0));
+	assert_eq!(eval(&env, "2**3**2"), Ok(512.0));
+	assert_eq!(eval(&env, "2^3^2"), Ok(512.0));
+	assert_eq!(eval(&env, "7 // 2"), Ok(3.0));
+	assert_eq!(eval(&env, "-7 // 2"), Ok(-4.0));
+	assert_eq!(eval(&env, "7 // -2"), Ok(-4.0));
+	assert_eq!(eval(&env, "-7 // -2"), Ok(3.0));
+	assert_eq!(eval(&env, "1 + 9 // 2 * 3"), Ok(13.0));
+	assert_eq!(eval(&env, "7 / 2"), Ok(3.5));
+	assert_eq!(eval(&env, "2 * * 3").map_err(|e| e.kind), Err(ErrorKind::DisallowedUnary));
+}
+#[test]
+fn logic() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(eval(&env, "1 + 1 && 0"), Ok(0.0));
+	assert_eq!(eval(&env, "0 && 1 + 1"), Ok(0.0));
+	assert_eq!(eval(&env, "2 - 2 || 3"), Ok(1.0));
+	assert_eq!(eval(&env, "1 || 0 && 0"), Ok(1.0));
+	assert_eq!(eval(&env, "(1 || 0) && 0"), Ok(0.0));
+	assert_eq!(eval(&env, "0 || 0 || 5"), Ok(1.0));
+	assert_eq!(eval(&env, "all(1, 2) && any(0, 3)"), Ok(1.0));
+	assert_eq!(eval(&env, "&& 1").map_err(|e| e.kind), Err(ErrorKind::DisallowedUnary));
+}
+#[test]
+fn warnings() {
+	let env = crate::BasicEnv::default();
+	let kinds = |input: &str| {
+		let (_, warnings) = eval_with_warnings(&env, input);
+		warnings.iter().map(|warning| (warning.kind, warning.position)).collect::<Vec<_>>()
+	};
+	assert_eq!(kinds("2pi + 2 * pi"), [(WarningKind::ImplicitMul, 1)]);
+	assert_eq!(kinds("(1)(2) + (3)4 + 2√4"), [(WarningKind::ImplicitMul, 3), (WarningKind::ImplicitMul, 12), (WarningKind::ImplicitMul, 17)]);
+	assert_eq!(kinds("2+--3 - -3 + -√4"), [(WarningKind::RepeatedSign, 3)]);
+	assert_eq!(kinds("max(1, 2) + 1"), []);
+	// Collected even if the evaluation fails
+	assert_eq!(eval_with_warnings(&env, "2x").0, Err(Error::new(ErrorKind::EnvErrorNotFound, 1)));
+	assert_eq!(kinds("2x"), [(WarningKind::ImplicitMul, 1)]);
+
+	// Variables named like a function
+	struct Vars<'a>(crate::BasicEnv<'a>);
+	impl<'a> Env for Vars<'a> {
+		fn builtin(&self, name: &str) -> Result<BuiltinFn, ErrorKind> {
+			self.0.builtin(name)
+		}
+		fn get_value(&self, name: &str) -> Result<Value, ErrorKind> {
+			match name {
+				"sin" => Ok(2.0),
+				_ => self.0.get_value(name),
+			}
+		}
+		fn set_value(&mut self, name: &str, value: Value) -> Result<(), ErrorKind> {
+			self.0.set_value(name, value)
+		}
+		fn arity(&self, name: &str) -> Option<Arity> {
+			self.0.arity(name)
+		}
+	}
+	let env = Vars(env);
+	let (result, warnings) = eval_with_warnings(&env, "pi * sin + sin(0) + (0 ? sin : 1)");
+	assert_eq!(result, Ok(std::f64::consts::TAU + 1.0));
+	assert_eq!(warnings, [Warning { kind: WarningKind::FunctionName, message: "sin is a function used as a variable".into(), position: 5 }]);
+	// Only collected when enabled
+	let mut expr = Expr::new(&env);
+	expr.feed("2sin").unwrap();
+	assert_eq!(expr.take_warnings(), []);
+	// Discarded when cleared
+	expr.set_warnings(true);
+	expr.clear();
+	expr.feed("sin").unwrap();
+	assert_eq!(expr.result_and_clear(), Ok(2.0));
+	expr.feed("2sin").unwrap();
+	assert_eq!(expr.take_warnings().len(), 2);
+	assert_eq!(expr.take_warnings(), []);
+	expr.clear();
+	for _ in 0..3 {
+		assert_eq!(eval_reuse(&mut expr, "2sin"), Ok(4.0));
+	}
+	assert_eq!(expr.take_warnings(), []);
+}
+#[test]
+fn lazy() {
+	let builtins = [
+		BuiltinDesc { name: "and", pfn: crate::builtins::builtin_all, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "" },
+		BuiltinDesc { name: "fail", pfn: |_, _| Err(ErrorKind::BadArgument), pure: true, divides: false, arity: Arity::at_least(0), params: &[], category: Category::Logic, desc: "" },
+		BuiltinDesc { name: "or", pfn: crate::builtins::builtin_any, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Logic, desc: "" },
+	];
+	let env = crate::BasicEnv { ans: 0.0, builtins: &builtins };
+	let inputs = [
+		("and(ans, 1, fail())", Ok(0.0)),
+		("or(1, ans, fail(), x)", Ok(1.0)),
+		("or(ans, and(1, ans, fail()), 2)", Ok(1.0)),
+		("and(1, or(ans, ans), fail() + 1)", Ok(0.0)),
+		("and(1, 2, ans ? fail() : 3,)", Ok(1.0)),
+		("ans ? and(fail()) : or(ans, 1 ? 0 : fail())", Ok(0.0)),
+		("and(1, 2, fail())", Err(Error::new(ErrorKind::BadArgument, 10))),
+		("or(ans, fail(), 1)", Err(Error::new(ErrorKind::BadArgument, 8))),
+		("and()", Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::at_least(1), got: 0 }, 0))),
+	];
+	for (input, result) in inputs {
+		assert_eq!(eval(&env, input), result, "{:?}", input);
+		assert_eq!(crate::parse(input).unwrap().eval(&env), result, "{:?}", input);
+		let compiled = crate::compile(&env, input);
+		let vars = compiled.as_ref().map(|program| vec![0.0; program.var_names().len()]).unwrap_or_default();
+		assert_eq!(compiled.map_err(|e| e.kind).and_then(|program| program.eval(&vars)), result.map_err(|e| e.kind), "{:?}", input);
+	}
+	// Same results as the eager versions when all arguments are evaluated
+	let env = crate::BasicEnv::default();
+	for args in ["1, 2, 3", "1, 0, 3", "0, 0", "nan, 1", "1, nan", "5"] {
+		assert_eq!(eval(&env, &format!("and({})", args)), eval(&env, &format!("all({})", args)), "{:?}", args);
+		assert_eq!(eval(&env, &format!("or({})", args)), eval(&env, &format!("any({})", args)), "{:?}", args);
+	}
+}
+#[test]
+fn empty_call() {
+	let builtins = [
+		BuiltinDesc { name: "add", pfn: crate::builtins::builtin_add, pure: true, divides: false, arity: Arity::at_least(1), params: &[], category: Category::Arithmetic, desc: "" },
+		BuiltinDesc { name: "answer", pfn: |_, vals| if vals.is_empty() { Ok(42.0) } else { Err(ErrorKind::BadArgument) }, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "" },
+		BuiltinDesc { name: "pi", pfn: crate::builtins::builtin_pi, pure: true, divides: false, arity: Arity::exactly(0), params: &[], category: Category::Constant, desc: "" },
+	];
+	let env = crate::BasicEnv { ans: 0.0, builtins: &builtins };
+	assert_eq!(eval(&env, "answer()"), Ok(42.0));
+	assert_eq!(eval(&env, "2answer() + 1"), Ok(85.0));
+	assert_eq!(eval(&env, "add(answer(), pi())"), Ok(42.0 + std::f64::consts::PI));
+	assert_eq!(eval(&env, "answer(1)"), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::exactly(0), got: 1 }, 0)));
+	assert_eq!(eval(&env, "2 * add()"), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::at_least(1), got: 0 }, 4)));
+	assert_eq!(eval(&env, "answer(]"), Err(Error::new(ErrorKind::MismatchedParens, 7)));
+	assert_eq!(crate::parse("answer()").and_then(|ast| ast.eval(&env)), Ok(42.0));
+	assert_eq!(crate::compile(&env, "answer()").unwrap().eval(&[]), Ok(42.0));
+}
+#[test]
+fn implicit_mul() {
+	let env = crate::BasicEnv { ans: 5.0, ..Default::default() };
+	assert_eq!(eval(&env, "2(3 + 4)"), Ok(14.0));
+	assert_eq!(eval(&env, "2sqrt(9)"), Ok(6.0));
+	assert_eq!(eval(&env, "2ans"), Ok(10.0));
+	assert_eq!(eval(&env, "(2)3"), Ok(6.0));
+	assert_eq!(eval(&env, "(2)(3)"), Ok(6.0));
+	assert_eq!(eval(&env, "[2]{3}4"), Ok(24.0));
+	assert_eq!(eval(&env, "sqrt(4)3"), Ok(6.0));
+	assert_eq!(eval(&env, "(2)3^2"), Ok(18.0));
+	assert_eq!(eval(&env, "1/2(4)"), Ok(0.125));
+	assert_eq!(eval(&env, "1/(2)4"), Ok(0.125));
+	assert_eq!(eval(&env, "2^(2)3"), Ok(12.0));
+	assert_eq!(eval(&env, "(10)%3"), Ok(1.0));
+	assert_eq!(eval(&env, "2 3"), Err(Error::new(ErrorKind::ExpectOperator, 2)));
+	assert_eq!(eval(&env, "ans 3"), Err(Error::new(ErrorKind::ExpectOperator, 4)));
+	assert_eq!(eval(&env, "(2)² 3"), Err(Error::new(ErrorKind::ExpectOperator, 6)));
+	assert_eq!(crate::parse("1/(2)3").map(|ast| ast.to_string()), Ok("1 / (2 * 3)".into()));
+}
+#[test]
+fn trailing_comma() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(eval(&env, "max(1, 3, 2,)"), Ok(3.0));
+	assert_eq!(eval(&env, "max(1, 3, 2, ) * 2"), Ok(6.0));
+	assert_eq!(eval(&env, "sin(0,)"), Ok(0.0));
+	assert_eq!(eval(&env, "[1,] + 1"), Ok(2.0));
+	assert_eq!(eval(&env, "max(,)"), Err(Error::new(ErrorKind::NaExpression, 4)));
+	assert_eq!(eval(&env, "max(1,,2)"), Err(Error::new(ErrorKind::NaExpression, 6)));
+	assert_eq!(eval(&env, "max(1,,)"), Err(Error::new(ErrorKind::NaExpression, 6)));
+	assert_eq!(eval(&env, "max(1, 2 +)"), Err(Error::new(ErrorKind::NaExpression, 10)));
+	assert_eq!(eval(&env, "max(1, 2,]"), Err(Error::new(ErrorKind::MismatchedParens, 9)));
+	assert_eq!(eval(&env, "1,"), Err(Error::new(ErrorKind::MisplacedComma, 1)));
+	assert_eq!(crate::parse("max(x, y,)").map(|ast| ast.to_string()), Ok("max(x, y)".into()));
+}
+#[test]
+fn keywords() {
+	let env = crate::BasicEnv::default();
+	assert_eq!(eval(&env, "log(8, base = 2)"), Ok(3.0));
+	assert_eq!(eval(&env, "log(base = 2, x = 8)"), Ok(3.0));
+	assert_eq!(eval(&env, "atan2(x = 1, y = 0) + sub(x = 5, y = 3,)"), Ok(2.0));
+	assert_eq!(eval(&env, "pow(exp = [1 + 1], base = 3) * e"), eval(&env, "9e"));
+	assert_eq!(eval(&env, "log(x = log(base = 2, x = 4), base = 2)"), Ok(1.0));
+	assert_eq!(eval(&env, "0 ? log(bass = 2) : 1"), Ok(1.0));
+	// Variables starting an argument are still positional
+	assert_eq!(eval(&env, "max(pi, e) + (ans)"), Ok(std::f64::consts::PI));
+	assert_eq!(eval(&env, "log(8, bass = 2)"), Err(Error::new(ErrorKind::UnknownKeyword, 7)));
+	assert_eq!(eval(&env, "max(a = 1)"), Err(Error::new(ErrorKind::UnknownKeyword, 4)));
+	assert_eq!(eval(&env, "log(8, x = 2)"), Err(Error::new(ErrorKind::DuplicateKeyword, 7)));
+	assert_eq!(eval(&env, "log(base = 8, base = 2)"), Err(Error::new(ErrorKind::DuplicateKeyword, 14)));
+	assert_eq!(eval(&env, "log(x = 8, 2)"), Err(Error::new(ErrorKind::PositionalAfterKeyword, 11)));
+	assert_eq!(eval(&env, "log(x = 8, pi)"), Err(Error::new(ErrorKind::PositionalAfterKeyword, 11)));
+	assert_eq!(eval(&env, "sub(y = 1)"), Err(Error::new(ErrorKind::BadArgument, 0)));
+	assert_eq!(eval(&env, "log(base = 2)"), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::exactly(2), got: 1 }, 0)));
+	assert_eq!(eval(&env, "log(8, base =)"), Err(Error::new(ErrorKind::NaExpression, 13)));
+	assert_eq!(eval(&env, "(x = 2)"), Err(Error::new(ErrorKind::MisplacedAssign, 1)));
+	assert_eq!(eval(&env, "pi = 2"), Err(Error::new(ErrorKind::MisplacedAssign, 3)));
+	assert_eq!(eval(&env, "log(8, 2 = 2)"), Err(Error::new(ErrorKind::MisplacedAssign, 9)));
+	// Same results for the expression tree and the compiled program
+	for input in ["log(base = 2, x = 8)", "atan2(x = 1, y = 0)", "log(8, bass = 2)", "sub(y = 1)"] {
+		let result = eval(&env, input).map_err(|e| e.kind);
+		assert_eq!(crate::parse(input).unwrap().eval(&env).map_err(|e| e.kind), result, "{:?}", input);
+		assert_eq!(crate::compile(&env, input).map_err(|e| e.kind).and_then(|program| program.eval(&[])), result, "{:?}", input);
+	}
+}
+#[test]
+fn many_args() {
+	let env = crate::BasicEnv::default();
+	for n in [255, 256, 300] {
+		let input = format!("add({})", vec!["1"; n].join(", "));
+		assert_eq!(eval(&env, &input), Ok(n as Value));
+	}
+}
+#[test]
+fn spill() {
+	let env = crate::BasicEnv::default();
+	// More pending fns and values than kept inline
+	for n in [7, 8, 9, 40] {
+		let input = (1..=n).map(|i| format!("{} + max(1, {} * (", i, i)).collect::<String>() + "1" + &"))".repeat(n);
+		let expected = (1..=n).rev().fold(1.0, |acc, i| i as Value + (i as Value * acc).max(1.0));
+		assert_eq!(eval(&env, &input), Ok(expected));
+	}
+	// Reusing the spilled stacks
+	let mut expr = Expr::new(&env);
+	let input = "-".repeat(20) + "2" + &"^1".repeat(20);
+	for _ in 0..3 {
+		expr.feed(&input).unwrap();
+		assert_eq!(expr.result_and_clear(), Ok(2.0));
+	}
+}
+#[test]
+fn limits() {
+	let env = crate::BasicEnv::default();
+	let limits = Limits { max_tokens: 1000, max_depth: 100, max_args: 10 };
+	// Lots of tokens
+	let input = "1+".repeat(50000) + "1";
+	assert_eq!(eval(&env, &input), Ok(50001.0));
+	assert_eq!(eval_with_limits(&env, &input, &limits), Err(Error::new(ErrorKind::LimitExceeded, 1000)));
+	// Deeply nested
+	let input = "(".repeat(10000) + "1" + &")".repeat(10000);
+	assert_eq!(eval_with_limits(&env, &input, &limits), Err(Error::new(ErrorKind::LimitExceeded, 100)));
+	let input = "-".repeat(10000) + "1";
+	assert_eq!(eval_with_limits(&env, &input, &limits), Err(Error::new(ErrorKind::LimitExceeded, 100)));
+	let input = "2^".repeat(1000) + "1";
+	assert_eq!(eval_with_limits(&env, &input, &limits), Err(Error::new(ErrorKind::LimitExceeded, 201)));
+	// Many arguments
+	let input = format!("add({})", ["1"; 11].join(","));
+	assert_eq!(eval_with_limits(&env, &input, &limits), Err(Error::new(ErrorKind::LimitExceeded, 23)));
+	let input = format!("add({})", ["1"; 10].join(","));
+	assert_eq!(eval_with_limits(&env, &input, &limits), Ok(10.0));
+	// Within the limits
+	assert_eq!(eval_with_limits(&env, "max((((1))), 2, -(-3)) ^ 2", &limits), Ok(9.0));
+}
+#[test]
+fn conditional() {
+	let mut env = crate::BasicEnv::default();
+	assert_eq!(eval(&env, "1 ? 2 : 3"), Ok(2.0));
+	assert_eq!(eval(&env, "0 ? 2 : 3"), Ok(3.0));
+	assert_eq!(eval(&env, "1 - 1 ? 2 : 3 + 4"), Ok(7.0));
+	assert_eq!(eval(&env, "0 || 2 ? 10 : 20"), Ok(10.0));
+	// Right associative
+	assert_eq!(eval(&env, "0 ? 1 : 0 ? 2 : 3"), Ok(3.0));
+	assert_eq!(eval(&env, "0 ? 1 : 1 ? 2 : 3"), Ok(2.0));
+	assert_eq!(eval(&env, "1 ? 0 ? 1 : 2 : 3"), Ok(2.0));
+	assert_eq!(eval(&env, "0 ? 0 ? 1 : 2 : 3"), Ok(3.0));
+	assert_eq!(eval(&env, "max(0 ? 1 : 5, 1 ? 2 : 3) * 2"), Ok(10.0));
+	assert_eq!(eval(&env, "4(0 ? 2 : 3)"), Ok(12.0));
+	assert_eq!(eval(&env, "50% ? 1 : 2"), Ok(1.0));
+	// Only the chosen branch is evaluated
+	env.ans = 0.0;
+	assert_eq!(eval(&env, "ans ? 1 / ans : 0"), Ok(0.0));
+	assert_eq!(eval(&env, "1 ? 2 : log()"), Ok(2.0));
+	assert_eq!(eval(&env, "0 ? log() : 3"), Ok(3.0));
+	assert_eq!(eval(&env, "0 ? sqrt(1, 2) + nope(x) : 3"), Ok(3.0));
+	assert_eq!(eval(&env, "1 ? 2 : 1 ? log() : max(0 ? 1 : pi())"), Ok(2.0));
+	assert_eq!(eval(&env, "0 ? 2 : log()"), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::exactly(2), got: 0 }, 8)));
+	assert_eq!(eval(&env, "1 ? nope : 3"), Err(Error::new(ErrorKind::EnvErrorNotFound, 4)));
+	// Syntax errors are reported in either branch
+	assert_eq!(eval(&env, "1 ? 2 : 3 +"), Err(Error::new(ErrorKind::UnfinishedExpression, 11)));
+	assert_eq!(eval(&env, "1 ? 2 : (3"), Err(Error::new(ErrorKind::UnbalancedParens, 8)));
+	assert_eq!(eval(&env, "1 ? 2"), Err(Error::new(ErrorKind::UnbalancedConditional, 2)));
+	assert_eq!(eval(&env, "1 : 2"), Err(Error::new(ErrorKind::UnbalancedConditional, 2)));
+	assert_eq!(eval(&env, "(1 ? 2) : 3"), Err(Error::new(ErrorKind::UnbalancedConditional, 3)));
+	assert_eq!(eval(&env, "max(1 ? 2, 3 : 4)"), Err(Error::new(ErrorKind::UnbalancedConditional, 6)));
+	assert_eq!(eval(&env, "1 ? : 2"), Err(Error::new(ErrorKind::NaExpression, 4)));
+}
+#[test]
 fn errors() {
 	let env = crate::BasicEnv::default();
-	assert_eq!(eval(&env, ""), Err(Error::UnfinishedExpression));
-	assert_eq!(eval(&env, "12 5"), Err(Error::ExpectOperator));
-	assert_eq!(eval(&env, ","), Err(Error::NaExpression));
-	assert_eq!(eval(&env, ")"), Err(Error::NaExpression));
-	assert_eq!(eval(&env, "*2"), Err(Error::DisallowedUnary));
-	assert_eq!(eval(&env, "2 +"), Err(Error::UnfinishedExpression));
-	assert_eq!(eval(&env, "!&"), Err(Error::InvalidToken));
-	assert_eq!(eval(&env, "(2"), Err(Error::UnbalancedParens));
-	assert_eq!(eval(&env, "(3))"), Err(Error::UnbalancedParens));
-	assert_eq!(eval(&env, "2,"), Err(Error::MisplacedComma));
-	assert_eq!(eval(&env, "pi()"), Err(Error::BadArgument));
-	assert_eq!(eval(&env, "mean"), Err(Error::EnvErrorBuiltinFn));
-	assert_eq!(eval(&env, "hello(5)"), Err(Error::EnvErrorNotFound));
-	assert_eq!(eval(&env, "hi"), Err(Error::EnvErrorNotFound));
+	assert_eq!(eval(&env, ""), Err(Error::new(ErrorKind::UnfinishedExpression, 0)));
+	assert_eq!(eval(&env, "12 5"), Err(Error::new(ErrorKind::ExpectOperator, 3)));
+	assert_eq!(eval(&env, ","), Err(Error::new(ErrorKind::NaExpression, 0)));
+	assert_eq!(eval(&env, ")"), Err(Error::new(ErrorKind::NaExpression, 0)));
+	assert_eq!(eval(&env, "*2"), Err(Error::new(ErrorKind::DisallowedUnary, 0)));
+	assert_eq!(eval(&env, "2 +"), Err(Error::new(ErrorKind::UnfinishedExpression, 3)));
+	assert_eq!(eval(&env, "!&"), Err(Error::new(ErrorKind::InvalidToken, 0)));
+	assert_eq!(eval(&env, "1 + (2"), Err(Error::new(ErrorKind::UnbalancedParens, 4)));
+	assert_eq!(eval(&env, "(3))"), Err(Error::new(ErrorKind::UnbalancedParens, 3)));
+	assert_eq!(eval(&env, "2,"), Err(Error::new(ErrorKind::MisplacedComma, 1)));
+	assert_eq!(eval(&env, "1 + add()"), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::at_least(1), got: 0 }, 4)));
+	assert_eq!(eval(&env, "1 + sqrt(1, 2)"), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::exactly(1), got: 2 }, 4)));
+	assert_eq!(eval(&env, "mean"), Err(Error::new(ErrorKind::EnvErrorBuiltinFn, 0)));
+	assert_eq!(eval(&env, "hello(5)"), Err(Error::new(ErrorKind::EnvErrorNotFound, 0)));
+	assert_eq!(eval(&env, "2 * hi"), Err(Error::new(ErrorKind::EnvErrorNotFound, 4)));
+	assert_eq!(eval(&env, "[1 + 2)"), Err(Error::new(ErrorKind::MismatchedParens, 6)));
+	assert_eq!(eval(&env, "√π × 3 % ·"), Err(Error::new(ErrorKind::InvalidToken, 13)));
+	// Positions are global across multiple feeds
+	let mut expr = Expr::new(&env);
+	expr.feed("1 + ").unwrap();
+	expr.feed("(2").unwrap();
+	assert_eq!(expr.result(), Err(Error::new(ErrorKind::UnbalancedParens, 4)));
+	let mut expr = Expr::new(&env);
+	expr.feed_chunked("1 + 2 ").unwrap();
+	assert_eq!(expr.feed_chunked("* sq").and_then(|_| expr.feed_chunked("rt(1, 2) + #")), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::exactly(1), got: 2 }, 8)));
+}
+#[test]
+fn fixed() {
+	let env = crate::BasicEnv { ans: 5.0, ..Default::default() };
+	let inputs = [
+		"2 + 3", "2-3*4", "2*3+4", "3^2-2", "2+---2", "-1",
+		"2*(3+4)", "mul(2,add(3,4))", "[ (1+2) * { 5 - 1 } ]", "max([1], {2})", "√9 + 1", "3² + 4²",
+		"log(8, base = 2)", "0 ? 1 : 0 ? 2 : 3", "ans - 5 ? 1 / 0 : 2", "0 ? log() : 1",
+		"", "1 + ", "2 3", "hello(5)", "(3))", "1 + (2", "sqrt(1, 2)",
+		"sin(x=ans%,hianshi", "max(1, hello", "log(8, base = 2, hello",
+	];
+	for input in inputs {
+		assert_eq!(eval_fixed::<8, 8, _>(&env, input), eval(&env, input), "{:?}", input);
+	}
+	// Exceeds the pending fns at the parenthesis which doesn't fit
+	let input = "(".repeat(9) + "1" + &")".repeat(9);
+	assert_eq!(eval_fixed::<9, 8, _>(&env, &input), Ok(1.0));
+	assert_eq!(eval_fixed::<8, 8, _>(&env, &input), Err(Error::new(ErrorKind::CapacityExceeded, 8)));
+	// Exceeds the values at the argument which doesn't fit
+	let input = format!("mean({})", ["1"; 9].join(","));
+	assert_eq!(eval_fixed::<8, 9, _>(&env, &input), Ok(1.0));
+	assert_eq!(eval_fixed::<8, 8, _>(&env, &input), Err(Error::new(ErrorKind::CapacityExceeded, 21)));
+	// Names of any length are borrowed from the input
+	let name = "x".repeat(100);
+	assert_eq!(eval_fixed::<8, 8, _>(&env, &format!("max({}, 1)", name)), Err(Error::new(ErrorKind::EnvErrorNotFound, 4)));
+	let input = format!("max(ans, {}, 1)", name);
+	assert_eq!(eval_fixed::<8, 8, _>(&env, &input), eval(&env, &input));
+	let input = format!("log({}=8, base=2)", "x".repeat(100));
+	assert_eq!(eval_fixed::<8, 8, _>(&env, &input), eval(&env, &input));
+	let input = "clamp(ans, hi = 4, lo = 0) + log(8, base = 2) + max(e, 1)";
+	assert_eq!(eval_fixed::<8, 8, _>(&env, input), Ok(7.0 + std::f64::consts::E));
+	let input = "clamp(ans, hi = 4, 0)";
+	assert_eq!(eval_fixed::<8, 8, _>(&env, input), eval(&env, input));
+	// Without capacity nothing fits
+	assert_eq!(eval_fixed::<0, 0, _>(&env, "1"), Err(Error::new(ErrorKind::CapacityExceeded, 0)));
 }
@@ -0,0 +1,199 @@
+use std::str::FromStr;
+use crate::{approximate_fraction, Value};
+
+/// Notation used to display values, see [`format_value`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Notation {
+	/// Shortest representation which round trips, eg. `1234.5`.
+	#[default]
+	Auto,
+	/// Fixed number of decimal places, eg. `1234.50` with 2 places.
+	Fixed(usize),
+	/// Scientific notation with one digit before the decimal point, eg. `1.2345e3`.
+	Sci,
+	/// Engineering notation with the exponent a multiple of three, eg. `1.2345e3` or `500e-3`.
+	Eng,
+	/// Shortest representation next to the closest fraction, eg. `0.75 = 3/4`.
+	Frac,
+}
+
+/// Parses `auto`, `fixed:N`, `sci`, `eng` or `frac`.
+impl FromStr for Notation {
+	type Err = ();
+	fn from_str(s: &str) -> Result<Notation, ()> {
+		match s {
+			"auto" => Ok(Notation::Auto),
+			"sci" => Ok(Notation::Sci),
+			"eng" => Ok(Notation::Eng),
+			"frac" => Ok(Notation::Frac),
+			_ => {
+				let places = s.strip_prefix("fixed:").ok_or(())?;
+				places.parse().map(Notation::Fixed).map_err(|_| ())
+			},
+		}
+	}
+}
+
+/// Options for [`format_value`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct FormatOptions {
+	/// Notation of the value.
+	pub notation: Notation,
+	/// Number of significant digits, `None` for the shortest representation which round trips.
+	///
+	/// The value is rounded before applying the notation, trailing zeros are not shown.
+	/// Ignored by [`Notation::Fixed`] which has its own number of decimal places.
+	pub precision: Option<usize>,
+}
+
+/// Formats a value for display.
+///
+/// Infinities and NaN are always displayed as `inf`, `-inf` and `NaN`.
+///
+/// ```
+/// use pupil::{format_value, FormatOptions, Notation};
+/// let options = FormatOptions { precision: Some(15), ..Default::default() };
+/// assert_eq!(format_value(0.1 + 0.2, &options), "0.3");
+/// let options = FormatOptions { notation: Notation::Eng, precision: Some(3) };
+/// assert_eq!(format_value(0.012345, &options), "12.3e-3");
+/// ```
+pub fn format_value(value: Value, options: &FormatOptions) -> String {
+	if !value.is_finite() {
+		return value.to_string();
+	}
+	// Round through the decimal representation, rounding up the largest values overflows when parsed
+	let sci = sci_digits(value, options.precision);
+	let rounded: Option<Value> = sci.parse().ok().filter(|rounded: &Value| rounded.is_finite());
+	match options.notation {
+		Notation::Auto => match rounded {
+			Some(rounded) => rounded.to_string(),
+			None => sci,
+		},
+		Notation::Fixed(places) => format!("{:.*}", places, value),
+		Notation::Sci => sci,
+		Notation::Eng => {
+			// Shift the decimal point of the scientific notation to avoid rounding errors
+			let (mantissa, exp) = sci.split_once('e').unwrap();
+			let exp: i32 = exp.parse().unwrap();
+			let shift = exp.rem_euclid(3) as usize;
+			let (sign, mantissa) = match mantissa.strip_prefix('-') {
+				Some(mantissa) => ("-", mantissa),
+				None => ("", mantissa),
+			};
+			let mut digits = mantissa.replace('.', "");
+			while digits.len() < shift + 1 {
+				digits.push('0');
+			}
+			let (int, frac) = digits.split_at(shift + 1);
+			let dot = if frac.is_empty() { "" } else { "." };
+			format!("{}{}{}{}e{}", sign, int, dot, frac, exp - shift as i32)
+		},
+		Notation::Frac => {
+			let Some(rounded) = rounded else {
+				return sci;
+			};
+			match approximate_fraction(rounded, 1000000) {
+				Some((num, den)) if den > 1 => {
+					let rel = if num as Value / den as Value == rounded { "=" } else { "≈" };
+					format!("{} {} {}/{}", rounded, rel, num, den)
+				},
+				_ => rounded.to_string(),
+			}
+		},
+	}
+}
+
+// Scientific notation rounded to significant digits without trailing zeros, zero digits are treated as one.
+fn sci_digits(value: Value, precision: Option<usize>) -> String {
+	let Some(digits) = precision else {
+		return format!("{:e}", value);
+	};
+	let sci = format!("{:.*e}", digits.max(1) - 1, value);
+	let (mantissa, exp) = sci.split_once('e').unwrap();
+	let mantissa = if mantissa.contains('.') { mantissa.trim_end_matches('0').trim_end_matches('.') } else { mantissa };
+	format!("{}e{}", mantissa, exp)
+}
+
+#[test]
+fn notations() {
+	assert_eq!("auto".parse(), Ok(Notation::Auto));
+	assert_eq!("fixed:2".parse(), Ok(Notation::Fixed(2)));
+	assert_eq!("sci".parse(), Ok(Notation::Sci));
+	assert_eq!("eng".parse(), Ok(Notation::Eng));
+	assert_eq!("frac".parse(), Ok(Notation::Frac));
+	assert_eq!("fixed".parse::<Notation>(), Err(()));
+	assert_eq!("fixed:-1".parse::<Notation>(), Err(()));
+	assert_eq!("hex".parse::<Notation>(), Err(()));
+
+	let display = |notation, val| format_value(val, &FormatOptions { notation, precision: None });
+	assert_eq!(display(Notation::Auto, 0.1 + 0.2), "0.30000000000000004");
+	assert_eq!(display(Notation::Fixed(2), 1.23456), "1.23");
+	assert_eq!(display(Notation::Fixed(0), 2.5), "2");
+	assert_eq!(display(Notation::Fixed(3), -1.0), "-1.000");
+	assert_eq!(display(Notation::Sci, 1234.5), "1.2345e3");
+	assert_eq!(display(Notation::Sci, -0.00012), "-1.2e-4");
+	assert_eq!(display(Notation::Sci, 0.0), "0e0");
+
+	let table = [
+		(1.0, "1e0"),
+		(12.0, "12e0"),
+		(123.0, "123e0"),
+		(1234.5, "1.2345e3"),
+		(-12345.0, "-12.345e3"),
+		(123456.0, "123.456e3"),
+		(1e6, "1e6"),
+		(0.5, "500e-3"),
+		(0.012, "12e-3"),
+		(0.0015, "1.5e-3"),
+		(0.0001, "100e-6"),
+		(0.0, "0e0"),
+		(f64::NEG_INFINITY, "-inf"),
+		(f64::NAN, "NaN"),
+	];
+	for &(val, expected) in &table {
+		assert_eq!(display(Notation::Eng, val), expected, "{}", val);
+	}
+
+	assert_eq!(display(Notation::Frac, 0.75), "0.75 = 3/4");
+	assert_eq!(display(Notation::Frac, -0.125), "-0.125 = -1/8");
+	assert_eq!(display(Notation::Frac, 1.0 / 3.0), "0.3333333333333333 = 1/3");
+	assert_eq!(display(Notation::Frac, std::f64::consts::PI), "3.141592653589793 ≈ 3126535/995207");
+	assert_eq!(display(Notation::Frac, 2.0), "2");
+	assert_eq!(display(Notation::Frac, 1e-9), "0.000000001");
+	assert_eq!(display(Notation::Frac, f64::NAN), "NaN");
+}
+
+#[test]
+fn precision() {
+	let display = |notation, precision, val| format_value(val, &FormatOptions { notation, precision: Some(precision) });
+	let table = [
+		(0.1 + 0.2, 15, "0.3"),
+		(0.1 + 0.2, 17, "0.30000000000000004"),
+		(2.0 / 3.0, 3, "0.667"),
+		(-2.0 / 3.0, 1, "-0.7"),
+		(123456.0, 2, "120000"),
+		(999.96, 4, "1000"),
+		(1.0, 0, "1"),
+		(1.26e-7, 2, "0.00000013"),
+		(-0.0, 3, "-0"),
+		(0.0, 3, "0"),
+		(f64::INFINITY, 3, "inf"),
+		(f64::NEG_INFINITY, 3, "-inf"),
+		(f64::NAN, 3, "NaN"),
+	];
+	for &(val, precision, expected) in &table {
+		assert_eq!(display(Notation::Auto, precision, val), expected, "{} at {}", val, precision);
+	}
+	assert_eq!(display(Notation::Auto, 17, f64::MAX), format!("17976931348623157{}", "0".repeat(292)));
+	assert_eq!(display(Notation::Auto, 3, f64::MAX), "1.8e308");
+	// Very large and very small values are best read in scientific notation
+	assert_eq!(display(Notation::Sci, 3, f64::MAX), "1.8e308");
+	assert_eq!(display(Notation::Sci, 3, 5e-324), "4.94e-324");
+	assert_eq!(display(Notation::Sci, 3, 1.23456e-300), "1.23e-300");
+	assert_eq!(display(Notation::Sci, 2, -0.0), "-0e0");
+	assert_eq!(display(Notation::Eng, 3, 123456.0), "123e3");
+	assert_eq!(display(Notation::Eng, 2, 999.0), "1e3");
+	assert_eq!(display(Notation::Frac, 3, 0.33333), "0.333 = 333/1000");
+	// Fixed notation has its own places
+	assert_eq!(display(Notation::Fixed(2), 1, 1.23456), "1.23");
+}
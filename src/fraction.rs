@@ -0,0 +1,87 @@
+use crate::Value;
+
+/// Returns the best rational approximation of `x` with a denominator up to `max_den`.
+///
+/// The continued fraction of `x` is expanded until the denominator would exceed `max_den`,
+/// then the closest of the last convergent and the best semiconvergent is returned.
+/// The numerator carries the sign and the fraction is in lowest terms.
+///
+/// Returns `None` if `x` is not finite, `max_den` is zero or the numerator does not fit.
+///
+/// ```
+/// assert_eq!(pupil::approximate_fraction(0.75, 1000000), Some((3, 4)));
+/// assert_eq!(pupil::approximate_fraction(std::f64::consts::PI, 1000), Some((355, 113)));
+/// ```
+pub fn approximate_fraction(x: Value, max_den: u64) -> Option<(i64, u64)> {
+	if !x.is_finite() || max_den == 0 || x.abs() >= i64::MAX as Value {
+		return None;
+	}
+	let target = x.abs();
+	let max_den = max_den as u128;
+	// Previous two convergents, starting with 0/1 and 1/0
+	let (mut p0, mut q0, mut p1, mut q1) = (0u128, 1u128, 1u128, 0u128);
+	let mut r = target;
+	loop {
+		let a = r.floor();
+		// Float to int casts saturate, huge terms always exceed the denominator
+		let q2 = (a as u128).checked_mul(q1).and_then(|aq| aq.checked_add(q0));
+		match q2 {
+			Some(q2) if q2 <= max_den => {
+				let p2 = (a as u128) * p1 + p0;
+				(p0, q0, p1, q1) = (p1, q1, p2, q2);
+			},
+			_ => {
+				// The largest semiconvergent within the denominator may be closer than the last convergent
+				let k = (max_den - q0) / q1;
+				let (ps, qs) = (k * p1 + p0, k * q1 + q0);
+				let error = |p: u128, q: u128| (target - p as Value / q as Value).abs();
+				if error(ps, qs) < error(p1, q1) {
+					(p1, q1) = (ps, qs);
+				}
+				break;
+			},
+		}
+		let frac = r - a;
+		if frac == 0.0 {
+			break;
+		}
+		r = frac.recip();
+	}
+	let p = i64::try_from(p1).ok()?;
+	Some((if x < 0.0 { -p } else { p }, q1 as u64))
+}
+
+#[test]
+fn fractions() {
+	// Exact dyadics
+	assert_eq!(approximate_fraction(0.0, 1000000), Some((0, 1)));
+	assert_eq!(approximate_fraction(0.5, 1000000), Some((1, 2)));
+	assert_eq!(approximate_fraction(0.75, 1000000), Some((3, 4)));
+	assert_eq!(approximate_fraction(2.0, 1000000), Some((2, 1)));
+	assert_eq!(approximate_fraction(0.8125, 1000000), Some((13, 16)));
+	assert_eq!(approximate_fraction(1.0 / 1024.0, 1000000), Some((1, 1024)));
+	// Repeating decimals
+	assert_eq!(approximate_fraction(1.0 / 3.0, 1000000), Some((1, 3)));
+	assert_eq!(approximate_fraction(2.0 / 7.0, 1000000), Some((2, 7)));
+	assert_eq!(approximate_fraction(0.1 + 0.2, 1000000), Some((3, 10)));
+	// Convergents and semiconvergents of pi
+	let pi = std::f64::consts::PI;
+	assert_eq!(approximate_fraction(pi, 1), Some((3, 1)));
+	assert_eq!(approximate_fraction(pi, 10), Some((22, 7)));
+	assert_eq!(approximate_fraction(pi, 100), Some((311, 99)));
+	assert_eq!(approximate_fraction(pi, 1000), Some((355, 113)));
+	assert_eq!(approximate_fraction(pi, 1000000), Some((3126535, 995207)));
+	// Negatives
+	assert_eq!(approximate_fraction(-0.75, 1000000), Some((-3, 4)));
+	assert_eq!(approximate_fraction(-pi, 1000), Some((-355, 113)));
+	assert_eq!(approximate_fraction(-2.5, 1), Some((-2, 1)));
+	// Rounds to the nearest with small denominators
+	assert_eq!(approximate_fraction(0.9, 1), Some((1, 1)));
+	assert_eq!(approximate_fraction(1e-9, 1000000), Some((0, 1)));
+	assert_eq!(approximate_fraction(1e15 + 0.5, 2), Some((2000000000000001, 2)));
+	// Out of range
+	assert_eq!(approximate_fraction(f64::NAN, 1000), None);
+	assert_eq!(approximate_fraction(f64::INFINITY, 1000), None);
+	assert_eq!(approximate_fraction(0.5, 0), None);
+	assert_eq!(approximate_fraction(1e19, 1000), None);
+}
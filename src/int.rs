@@ -0,0 +1,199 @@
+use std::cell::Cell;
+use crate::*;
+
+/// Integer division in [`eval_int_with`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum IntDivision {
+	/// Dividing with a remainder is an error, eg. `7 / 2` fails with [`ErrorKind::NotInteger`].
+	#[default]
+	Exact,
+	/// Rounds the quotient towards negative infinity, eg. `-7 / 2` is `-4`.
+	Floor,
+}
+
+thread_local! {
+	static DIVISION: Cell<IntDivision> = const { Cell::new(IntDivision::Exact) };
+}
+
+// The division of the innermost eval_int_with on the thread.
+pub(crate) fn division() -> IntDivision {
+	DIVISION.with(Cell::get)
+}
+
+// Restores the previous division when dropped.
+struct Division(IntDivision);
+impl Division {
+	fn set(division: IntDivision) -> Division {
+		Division(DIVISION.with(|cell| cell.replace(division)))
+	}
+}
+impl Drop for Division {
+	fn drop(&mut self) {
+		DIVISION.with(|cell| cell.set(self.0));
+	}
+}
+
+impl Num for i64 {
+	/// Converts booleans, saturates out of range values.
+	#[inline]
+	fn from_f64(val: f64) -> i64 {
+		val as i64
+	}
+	#[inline]
+	fn truthy(&self) -> bool {
+		*self != 0
+	}
+	#[inline]
+	fn is_finite(&self) -> bool {
+		true
+	}
+	#[inline]
+	fn is_zero(&self) -> bool {
+		*self == 0
+	}
+	fn operator(op: Operator) -> BuiltinFn<i64> {
+		use crate::builtins_int::*;
+		match op {
+			Operator::Add => builtin_add,
+			Operator::Sub => builtin_sub,
+			Operator::Mul | Operator::IMul => builtin_mul,
+			Operator::Div => builtin_div,
+			Operator::Rem => builtin_rem,
+			Operator::Pow => builtin_pow,
+			Operator::And => builtin_all,
+			Operator::Or => builtin_any,
+			Operator::Sqrt => builtin_sqrt,
+			Operator::Sqr => builtin_sqr,
+			Operator::Cube => builtin_cube,
+			Operator::FloorDiv => builtin_floordiv,
+		}
+	}
+	fn percent() -> BuiltinFn<i64> {
+		crate::builtins_int::builtin_percent
+	}
+	fn identity() -> BuiltinFn<i64> {
+		crate::builtins_int::builtin_id
+	}
+	/// Parses integer literals exactly, eg. `1.5e1` is `15` but `1.5` is not an integer.
+	fn from_literal(text: &str) -> Result<Option<i64>, ErrorKind> {
+		let parse = |digits: &str, radix| i64::from_str_radix(digits, radix).map_err(|err| match err.kind() {
+			std::num::IntErrorKind::PosOverflow => ErrorKind::Overflow,
+			_ => ErrorKind::NotInteger,
+		});
+		if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+			return parse(hex, 16).map(Some);
+		}
+		let (mantissa, exp) = match text.split_once(['e', 'E']) {
+			Some((mantissa, exp)) => (mantissa, exp.parse::<i32>().map_err(|_| ErrorKind::NotInteger)?),
+			None => (text, 0),
+		};
+		let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+		if !(int.bytes().chain(frac.bytes())).all(|b| b.is_ascii_digit()) {
+			// Infinity and NaN
+			return Err(ErrorKind::NotInteger);
+		}
+		// Shift the decimal point to the end of the digits
+		let digits = format!("{}{}", int, frac);
+		let scale = exp - frac.len() as i32;
+		let digits = if scale < 0 {
+			let (digits, fraction) = digits.split_at(digits.len().saturating_sub(-scale as usize));
+			if fraction.bytes().any(|b| b != b'0') {
+				return Err(ErrorKind::NotInteger);
+			}
+			digits
+		}
+		else {
+			&digits[..]
+		};
+		let val = if digits.is_empty() { 0 } else { parse(digits, 10)? };
+		if val == 0 || scale <= 0 {
+			return Ok(Some(val));
+		}
+		10i64.checked_pow(scale as u32).and_then(|pow| val.checked_mul(pow)).map(Some).ok_or(ErrorKind::Overflow)
+	}
+}
+
+/// Evaluates the input in integers with [`IntDivision::Exact`], see [`eval_int_with`].
+///
+/// ```
+/// let env = pupil::IntEnv::default();
+/// assert_eq!(pupil::eval_int(&env, "2^53 + 1"), Ok(9007199254740993));
+/// assert_eq!(pupil::eval_int(&env, "2^62 * 4"), Err(pupil::Error::new(pupil::ErrorKind::Overflow, 5)));
+/// ```
+pub fn eval_int(env: &dyn Env<i64>, input: &str) -> Result<i64, Error> {
+	eval_int_with(env, input, IntDivision::Exact)
+}
+
+/// Evaluates the input in integers.
+///
+/// Literals must be integers and every operation which overflows fails with [`ErrorKind::Overflow`].
+/// Dividing with a remainder either fails or floors depending on the division.
+///
+/// ```
+/// use pupil::{IntDivision, IntEnv};
+/// assert_eq!(pupil::eval_int_with(&IntEnv::default(), "-7 / 2", IntDivision::Floor), Ok(-4));
+/// ```
+pub fn eval_int_with(env: &dyn Env<i64>, input: &str, division: IntDivision) -> Result<i64, Error> {
+	let _division = Division::set(division);
+	eval(env, input)
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn overflow() {
+	let env = crate::IntEnv::default();
+	assert_eq!(eval_int(&env, "2^62 * 4"), Err(Error::new(ErrorKind::Overflow, 5)));
+	assert_eq!(eval_int(&env, "2^62 + 2^62"), Err(Error::new(ErrorKind::Overflow, 5)));
+	assert_eq!(eval_int(&env, "2^62 - 1 + 2^62"), Ok(i64::MAX));
+	assert_eq!(eval_int(&env, "9223372036854775807 + 1"), Err(Error::new(ErrorKind::Overflow, 20)));
+	assert_eq!(eval_int(&env, "-9223372036854775807 - 1"), Ok(i64::MIN));
+	assert_eq!(eval_int(&env, "abs(-9223372036854775807 - 1)"), Err(Error::new(ErrorKind::Overflow, 0)));
+	assert_eq!(eval_int(&env, "2^63"), Err(Error::new(ErrorKind::Overflow, 1)));
+	assert_eq!(eval_int(&env, "3037000500²"), Err(Error::new(ErrorKind::Overflow, 10)));
+	assert_eq!(eval_int(&env, "1^100000000000 + (-1)^100000000001"), Ok(0));
+	// Exact beyond double precision
+	assert_eq!(eval_int(&env, "2^53 + 1"), Ok(9007199254740993));
+	assert_eq!(eval_int(&env, "9007199254740993 - 9007199254740992"), Ok(1));
+}
+#[test]
+fn divide() {
+	let env = crate::IntEnv::default();
+	assert_eq!(eval_int(&env, "8 / 2"), Ok(4));
+	assert_eq!(eval_int(&env, "7 / 2"), Err(Error::new(ErrorKind::NotInteger, 2)));
+	assert_eq!(eval_int(&env, "1 + 1 / 0"), Err(Error::new(ErrorKind::DivisionByZero, 6)));
+	assert_eq!(eval_int(&env, "(-9223372036854775807 - 1) / -1"), Err(Error::new(ErrorKind::Overflow, 27)));
+	assert_eq!(eval_int(&env, "-7 // 2"), Ok(-4));
+	assert_eq!(eval_int(&env, "-7 % 2"), Ok(-1));
+	assert_eq!(eval_int(&env, "mod(-7, 3)"), Ok(2));
+	assert_eq!(eval_int(&env, "50%"), Err(Error::new(ErrorKind::NotInteger, 2)));
+	assert_eq!(eval_int(&env, "2^-1"), Err(Error::new(ErrorKind::NotInteger, 1)));
+	assert_eq!(eval_int(&env, "sqrt(16) + √17"), Err(Error::new(ErrorKind::NotInteger, 11)));
+	let floor = |input| eval_int_with(&env, input, IntDivision::Floor);
+	assert_eq!(floor("7 / 2"), Ok(3));
+	assert_eq!(floor("-7 / 2"), Ok(-4));
+	assert_eq!(floor("250%"), Ok(2));
+	assert_eq!(floor("2^-1 + (-2)^-3"), Ok(-1));
+	assert_eq!(floor("√17"), Ok(4));
+	assert_eq!(floor("1 / 0"), Err(Error::new(ErrorKind::DivisionByZero, 2)));
+	// Restored after the evaluation
+	assert_eq!(eval(&env, "7 / 2"), Err(Error::new(ErrorKind::NotInteger, 2)));
+}
+#[test]
+fn literals() {
+	let env = crate::IntEnv::default();
+	assert_eq!(eval_int(&env, "1 + 2.5"), Err(Error::new(ErrorKind::NotInteger, 4)));
+	assert_eq!(eval_int(&env, "max(1, 0.5)"), Err(Error::new(ErrorKind::NotInteger, 7)));
+	assert_eq!(eval_int(&env, "1e-1"), Err(Error::new(ErrorKind::NotInteger, 0)));
+	assert_eq!(eval_int(&env, "2 * inf"), Err(Error::new(ErrorKind::NotInteger, 4)));
+	assert_eq!(eval_int(&env, "9223372036854775808"), Err(Error::new(ErrorKind::Overflow, 0)));
+	assert_eq!(eval_int(&env, "1e19"), Err(Error::new(ErrorKind::Overflow, 0)));
+	assert_eq!(eval_int(&env, "1e3 + 1.5e1 + 2.50e1 + 3.0"), Ok(1043));
+	assert_eq!(eval_int(&env, "0x7fffffffffffffff"), Ok(i64::MAX));
+	assert_eq!(eval_int(&env, "0.0 + 00"), Ok(0));
+	assert_eq!(eval_int(&env, "gcd(12, 18, 0) + min(3, -2) * max(1, 5)"), Ok(-4));
+	assert_eq!(eval_int(&env, "0 ? 1 / 0 : 2"), Ok(2));
+	assert_eq!(eval_int(&env, "sin(1)"), Err(Error::new(ErrorKind::EnvErrorNotFound, 0)));
+	let env = crate::IntEnv { ans: 41, ..Default::default() };
+	assert_eq!(eval_int(&env, "ans + 1"), Ok(42));
+}
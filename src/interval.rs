@@ -0,0 +1,211 @@
+use std::fmt;
+use crate::*;
+
+/// Closed interval of values, see [`eval_interval`].
+///
+/// Operations round outwards so the result contains every value the operation takes on the arguments,
+/// an invalid operation such as `sqrt([-2, -1])` is NaN in both bounds.
+/// Literals are their `f64` values.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Interval {
+	/// Lower bound.
+	pub lo: Value,
+	/// Upper bound.
+	pub hi: Value,
+}
+
+impl Interval {
+	/// Interval of all values.
+	pub const ENTIRE: Interval = Interval { lo: Value::NEG_INFINITY, hi: Value::INFINITY };
+	/// Result of an invalid operation.
+	pub const NAN: Interval = Interval { lo: Value::NAN, hi: Value::NAN };
+
+	/// Creates an interval from its bounds in any order.
+	pub fn new(a: Value, b: Value) -> Interval {
+		if a.is_nan() || b.is_nan() { Interval::NAN }
+		else { Interval { lo: a.min(b), hi: a.max(b) } }
+	}
+	/// Creates an interval from a value and its uncertainty, eg. `1.5 ± 0.1`.
+	pub fn around(val: Value, delta: Value) -> Interval {
+		Interval::outward(val - delta.abs(), val + delta.abs())
+	}
+	// Widens by an ulp for the rounding of the bounds.
+	pub(crate) fn outward(lo: Value, hi: Value) -> Interval {
+		if lo.is_nan() || hi.is_nan() { Interval::NAN }
+		else { Interval { lo: lo.next_down(), hi: hi.next_up() } }
+	}
+	/// Whether the value lies within the bounds.
+	pub fn contains(self, val: Value) -> bool {
+		self.lo <= val && val <= self.hi
+	}
+	/// Whether the bounds are equal.
+	pub fn is_point(self) -> bool {
+		self.lo == self.hi
+	}
+	/// Whether the interval is the result of an invalid operation.
+	pub fn is_nan(self) -> bool {
+		self.lo.is_nan()
+	}
+	/// Distance between the bounds.
+	pub fn width(self) -> Value {
+		self.hi - self.lo
+	}
+	/// Midpoint between the bounds.
+	pub fn mid(self) -> Value {
+		self.lo * 0.5 + self.hi * 0.5
+	}
+	/// Smallest interval containing both intervals.
+	pub fn hull(self, other: Interval) -> Interval {
+		if self.is_nan() || other.is_nan() { Interval::NAN }
+		else { Interval { lo: self.lo.min(other.lo), hi: self.hi.max(other.hi) } }
+	}
+	// Smallest and largest of the products of the bounds, zero times infinity is zero.
+	pub(crate) fn corners(self, other: Interval, f: impl Fn(Value, Value) -> Value) -> Interval {
+		if self.is_nan() || other.is_nan() {
+			return Interval::NAN;
+		}
+		let vals = [f(self.lo, other.lo), f(self.lo, other.hi), f(self.hi, other.lo), f(self.hi, other.hi)];
+		let vals = vals.map(|val| if val.is_nan() { 0.0 } else { val });
+		Interval::outward(vals.iter().copied().fold(Value::INFINITY, Value::min), vals.iter().copied().fold(Value::NEG_INFINITY, Value::max))
+	}
+}
+
+impl From<Value> for Interval {
+	fn from(val: Value) -> Interval {
+		Interval { lo: val, hi: val }
+	}
+}
+
+impl fmt::Display for Interval {
+	/// Formats like `[1.5, 2]`.
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "[{}, {}]", self.lo, self.hi)
+	}
+}
+
+impl Num for Interval {
+	#[inline]
+	fn from_f64(val: f64) -> Interval {
+		Interval::from(val)
+	}
+	/// Whether no value in the interval is zero.
+	#[inline]
+	fn truthy(&self) -> bool {
+		!self.is_nan() && !self.contains(0.0)
+	}
+	#[inline]
+	fn is_finite(&self) -> bool {
+		self.lo.is_finite() && self.hi.is_finite()
+	}
+	#[inline]
+	fn is_zero(&self) -> bool {
+		self.lo == 0.0 && self.hi == 0.0
+	}
+	fn operator(op: Operator) -> BuiltinFn<Interval> {
+		use crate::builtins_interval::*;
+		match op {
+			Operator::Add => builtin_add,
+			Operator::Sub => builtin_sub,
+			Operator::Mul | Operator::IMul => builtin_mul,
+			Operator::Div => builtin_div,
+			Operator::Rem => builtin_rem,
+			Operator::Pow => builtin_pow,
+			Operator::And => builtin_all,
+			Operator::Or => builtin_any,
+			Operator::Sqrt => builtin_sqrt,
+			Operator::Sqr => builtin_sqr,
+			Operator::Cube => builtin_cube,
+			Operator::FloorDiv => builtin_floordiv,
+		}
+	}
+	fn percent() -> BuiltinFn<Interval> {
+		crate::builtins_interval::builtin_percent
+	}
+	fn identity() -> BuiltinFn<Interval> {
+		crate::builtins_interval::builtin_id
+	}
+}
+
+/// Environment of intervals with the [`builtins_interval`](crate::builtins_interval) and variables.
+///
+/// ```
+/// use pupil::{Env, Interval, IntervalEnv};
+/// let mut env = IntervalEnv::default();
+/// env.set_value("r", Interval::around(2.0, 0.1)).unwrap();
+/// let area = pupil::eval_interval(&env, "pi * r^2").unwrap();
+/// assert!(area.contains(3.61 * std::f64::consts::PI) && area.contains(4.41 * std::f64::consts::PI));
+/// ```
+pub type IntervalEnv<'a> = VarEnv<'a, Interval>;
+
+/// Evaluates the input in intervals.
+///
+/// The result contains the result of evaluating the input in `f64` for every choice of values within the intervals of the variables.
+///
+/// ```
+/// let env = pupil::IntervalEnv::default();
+/// let third = pupil::eval_interval(&env, "1 / 3").unwrap();
+/// assert!(third.lo < third.hi && third.contains(1.0 / 3.0));
+/// ```
+pub fn eval_interval(env: &dyn Env<Interval>, input: &str) -> Result<Interval, Error> {
+	eval(env, input)
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn containment() {
+	let mut env = IntervalEnv::default();
+	let bounds = [("x", 0.5, 2.0), ("y", -1.0, 1.5), ("z", 1.0, 4.0)];
+	for &(name, lo, hi) in &bounds {
+		env.set_value(name, Interval::new(lo, hi)).unwrap();
+	}
+	let basic = crate::BasicEnv::default();
+	let rng = Rng::new(1);
+	let inputs = [
+		"x * y - z / (x + 3)",
+		"sqrt(x) + exp(-y) * ln(z)",
+		"sin(x * y) - cos(z)^2",
+		"(x - y)^3 + x^2 - y^2",
+		"abs(x - 1) / (1 + y^2) + z % x",
+		"y / z - 2^y + z^x + cbrt(y)",
+		"max(x, y, 1) * min(-z, y) + atan(z // x)",
+		"log10(z) + log2(x) - tanh(y) + 10%",
+		"(x + y + z) / (x * z) - e^-z + pi",
+	];
+	for input in inputs {
+		let result = eval_interval(&env, input).unwrap();
+		assert!(result.is_finite(), "{}: {}", input, result);
+		let program = crate::compile(&basic, input).unwrap();
+		for _ in 0..1000 {
+			let vars = program.var_names().iter().map(|name| {
+				let &(_, lo, hi) = bounds.iter().find(|it| it.0 == name).unwrap();
+				lo + (hi - lo) * rng.next_value()
+			}).collect::<Vec<_>>();
+			let val = program.eval(&vars).unwrap();
+			assert!(result.contains(val), "{}: {} not in {} at {:?}", input, val, result, vars);
+		}
+		// The bounds themselves
+		let lo = program.var_names().iter().map(|name| bounds.iter().find(|it| it.0 == name).unwrap().1).collect::<Vec<_>>();
+		let hi = program.var_names().iter().map(|name| bounds.iter().find(|it| it.0 == name).unwrap().2).collect::<Vec<_>>();
+		assert!(result.contains(program.eval(&lo).unwrap()) && result.contains(program.eval(&hi).unwrap()), "{}: {}", input, result);
+	}
+}
+#[test]
+fn arithmetic() {
+	let env = IntervalEnv::default();
+	let bounds = |input| eval_interval(&env, input).map(|val| (val.lo, val.hi));
+	assert_eq!(bounds("interval(1, 2) + interval(-3, 4)").map(|(lo, hi)| (lo.round(), hi.round())), Ok((-2.0, 6.0)));
+	assert_eq!(bounds("interval(-2, 3)^2").map(|(lo, hi)| (lo, hi.round())), Ok((0.0, 9.0)));
+	assert_eq!(bounds("interval(-2, 3)^3").map(|(lo, hi)| (lo.round(), hi.round())), Ok((-8.0, 27.0)));
+	assert_eq!(bounds("-interval(1, 2)"), Ok((-2.0, -1.0)));
+	assert_eq!(bounds("1 / interval(-1, 1)"), Ok((Value::NEG_INFINITY, Value::INFINITY)));
+	assert_eq!(bounds("sin(interval(0, 7))"), Ok((-1.0, 1.0)));
+	assert_eq!(bounds("lo(interval(1, 2)) + hi(interval(3, 4))"), Ok((5.0f64.next_down(), 5.0f64.next_up())));
+	assert!(eval_interval(&env, "sqrt(interval(-2, -1))").unwrap().is_nan());
+	let pi = eval_interval(&env, "pi").unwrap();
+	assert!(pi.lo < pi.hi && pi.contains(std::f64::consts::PI));
+	// Conditionals need the whole interval on one side of zero
+	assert_eq!(bounds("interval(1, 2) ? 1 : 2"), Ok((1.0, 1.0)));
+	assert_eq!(bounds("interval(-1, 2) ? 1 : 2"), Ok((2.0, 2.0)));
+	assert_eq!(Interval::new(2.0, 1.5).to_string(), "[1.5, 2]");
+}
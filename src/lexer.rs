@@ -1,11 +1,24 @@
-use std::str;
+use std::{fmt, str};
 use crate::*;
 
 //----------------------------------------------------------------
 
+/// Bracket flavors.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Paren {
+	/// Round parentheses `(` and `)`.
+	Round,
+	/// Square brackets `[` and `]`.
+	Square,
+	/// Curly braces `{` and `}`.
+	Curly,
+}
+
 /// Token types.
 #[derive(Clone, Debug, PartialEq)]
-pub enum Token<'a> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TokenKind<'a> {
 	/// Unknown token.
 	///
 	/// It’s the caller’s responsibility to handle this with an error of some kind.
@@ -14,8 +27,13 @@ pub enum Token<'a> {
 	Unk(&'a str),
 	/// Value literal token.
 	///
-	/// Negative literals are `Token::Op(Operator::Sub)` followed by a positive literal.
+	/// Negative literals are `TokenKind::Op(Operator::Sub)` followed by a positive literal.
 	Lit(Value),
+	/// Imaginary literal token, eg. `4i`.
+	///
+	/// Only lexed by expressions evaluating a [`Num`](crate::Num) type with an imaginary unit, otherwise `4i` is a literal followed by a variable.
+	#[cfg(feature = "complex")]
+	Imag(Value),
 	/// Operator token.
 	Op(Operator),
 	/// Variable token.
@@ -26,12 +44,255 @@ pub enum Token<'a> {
 	///
 	/// Alphanumeric characters only. Implicitly followed by a `(`.
 	Open(&'a str),
+	/// Grouping token `[` or `{`.
+	///
+	/// Behaves like a nameless `(` but must be closed by the matching bracket.
+	/// Function calls always use round parentheses.
+	Group(Paren),
 	/// Comma token `,`.
 	///
 	/// Used to provide multiple arguments to a function.
 	Comma,
-	/// Function closing token `)`.
-	Close,
+	/// Closing token `)`, `]` or `}`.
+	Close(Paren),
+	/// Semicolon token `;`.
+	///
+	/// Separates multiple expressions, see [`eval_all`](crate::eval_all).
+	/// Not available with [`Locale::DecimalComma`] where it separates function arguments.
+	Semi,
+	/// Conditional token `?`.
+	///
+	/// Evaluates only the chosen branch of `cond ? a : b`.
+	Question,
+	/// Conditional else token `:`.
+	Colon,
+	/// Keyword argument token `=`.
+	///
+	/// Names an argument of a function call, eg. `log(8, base = 2)`.
+	Assign,
+}
+
+/// Number formatting conventions.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Locale {
+	/// Numbers use `.` as the decimal separator and `,` separates function arguments.
+	#[default]
+	DecimalPoint,
+	/// Numbers use `,` as the decimal separator and `;` separates function arguments.
+	///
+	/// A `.` is not accepted in numbers.
+	/// Every literal has at most one decimal separator, eg. `1,2,3` lexes as the literals `1,2` and `,3`.
+	DecimalComma,
+}
+
+/// Options to configure the tokenizer.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LexerOptions {
+	/// Number formatting conventions.
+	pub locale: Locale,
+}
+
+/// Token with its position in the input.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token<'a> {
+	/// The token type.
+	#[cfg_attr(feature = "serde", serde(borrow))]
+	pub kind: TokenKind<'a>,
+	/// Byte offset in the input.
+	pub position: usize,
+}
+
+/// Run of invalid characters reported by [`tokenize_checked`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TokenError<'a> {
+	/// The invalid characters.
+	pub text: &'a str,
+	/// Byte offset in the input.
+	pub position: usize,
+}
+
+impl<'a> TokenError<'a> {
+	/// Returns the byte range of the invalid characters in the input.
+	pub fn span(&self) -> std::ops::Range<usize> {
+		self.position..self.position + self.text.len()
+	}
+}
+impl<'a> From<TokenError<'a>> for Error {
+	fn from(error: TokenError<'a>) -> Error {
+		Error::new(ErrorKind::InvalidToken, error.position)
+	}
+}
+
+/// Token types owning their strings.
+///
+/// Mirrors [`TokenKind`] to store tokens independently of the input.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedTokenKind {
+	/// See [`TokenKind::Unk`].
+	Unk(String),
+	/// See [`TokenKind::Lit`].
+	Lit(Value),
+	/// See [`TokenKind::Imag`].
+	#[cfg(feature = "complex")]
+	Imag(Value),
+	/// See [`TokenKind::Op`].
+	Op(Operator),
+	/// See [`TokenKind::Var`].
+	Var(String),
+	/// See [`TokenKind::Open`].
+	Open(String),
+	/// See [`TokenKind::Group`].
+	Group(Paren),
+	/// See [`TokenKind::Comma`].
+	Comma,
+	/// See [`TokenKind::Close`].
+	Close(Paren),
+	/// See [`TokenKind::Semi`].
+	Semi,
+	/// See [`TokenKind::Question`].
+	Question,
+	/// See [`TokenKind::Colon`].
+	Colon,
+	/// See [`TokenKind::Assign`].
+	Assign,
+}
+
+/// Token owning its strings with its position in the input.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedToken {
+	/// The token type.
+	pub kind: OwnedTokenKind,
+	/// Byte offset in the input.
+	pub position: usize,
+}
+
+impl OwnedTokenKind {
+	/// Borrows as a token kind.
+	pub fn as_kind(&self) -> TokenKind<'_> {
+		match *self {
+			OwnedTokenKind::Unk(ref s) => TokenKind::Unk(s),
+			OwnedTokenKind::Lit(val) => TokenKind::Lit(val),
+			#[cfg(feature = "complex")]
+			OwnedTokenKind::Imag(val) => TokenKind::Imag(val),
+			OwnedTokenKind::Op(op) => TokenKind::Op(op),
+			OwnedTokenKind::Var(ref name) => TokenKind::Var(name),
+			OwnedTokenKind::Open(ref name) => TokenKind::Open(name),
+			OwnedTokenKind::Group(paren) => TokenKind::Group(paren),
+			OwnedTokenKind::Comma => TokenKind::Comma,
+			OwnedTokenKind::Close(paren) => TokenKind::Close(paren),
+			OwnedTokenKind::Semi => TokenKind::Semi,
+			OwnedTokenKind::Question => TokenKind::Question,
+			OwnedTokenKind::Colon => TokenKind::Colon,
+			OwnedTokenKind::Assign => TokenKind::Assign,
+		}
+	}
+}
+impl OwnedToken {
+	/// Borrows as a token.
+	pub fn as_token(&self) -> Token<'_> {
+		Token { kind: self.kind.as_kind(), position: self.position }
+	}
+}
+impl<'a> From<TokenKind<'a>> for OwnedTokenKind {
+	fn from(kind: TokenKind<'a>) -> OwnedTokenKind {
+		match kind {
+			TokenKind::Unk(s) => OwnedTokenKind::Unk(s.into()),
+			TokenKind::Lit(val) => OwnedTokenKind::Lit(val),
+			#[cfg(feature = "complex")]
+			TokenKind::Imag(val) => OwnedTokenKind::Imag(val),
+			TokenKind::Op(op) => OwnedTokenKind::Op(op),
+			TokenKind::Var(name) => OwnedTokenKind::Var(name.into()),
+			TokenKind::Open(name) => OwnedTokenKind::Open(name.into()),
+			TokenKind::Group(paren) => OwnedTokenKind::Group(paren),
+			TokenKind::Comma => OwnedTokenKind::Comma,
+			TokenKind::Close(paren) => OwnedTokenKind::Close(paren),
+			TokenKind::Semi => OwnedTokenKind::Semi,
+			TokenKind::Question => OwnedTokenKind::Question,
+			TokenKind::Colon => OwnedTokenKind::Colon,
+			TokenKind::Assign => OwnedTokenKind::Assign,
+		}
+	}
+}
+impl<'a> From<Token<'a>> for OwnedToken {
+	fn from(tok: Token<'a>) -> OwnedToken {
+		OwnedToken { kind: tok.kind.into(), position: tok.position }
+	}
+}
+
+impl<'a> fmt::Display for TokenKind<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			TokenKind::Unk(s) => f.write_str(s),
+			TokenKind::Lit(val) => {
+				// Shortest representation which round-trips, long numbers in scientific notation
+				let plain = val.to_string();
+				let sci = format!("{:e}", val);
+				f.write_str(if sci.len() < plain.len() { &sci } else { &plain })
+			},
+			#[cfg(feature = "complex")]
+			TokenKind::Imag(val) => write!(f, "{}i", TokenKind::Lit(val)),
+			TokenKind::Op(op) => op.fmt(f),
+			TokenKind::Var(name) => f.write_str(name),
+			TokenKind::Open(name) => write!(f, "{}(", name),
+			TokenKind::Group(Paren::Round) => f.write_str("("),
+			TokenKind::Group(Paren::Square) => f.write_str("["),
+			TokenKind::Group(Paren::Curly) => f.write_str("{"),
+			TokenKind::Comma => f.write_str(","),
+			TokenKind::Close(Paren::Round) => f.write_str(")"),
+			TokenKind::Close(Paren::Square) => f.write_str("]"),
+			TokenKind::Close(Paren::Curly) => f.write_str("}"),
+			TokenKind::Semi => f.write_str(";"),
+			TokenKind::Question => f.write_str("?"),
+			TokenKind::Colon => f.write_str(":"),
+			TokenKind::Assign => f.write_str("="),
+		}
+	}
+}
+impl<'a> fmt::Display for Token<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.kind.fmt(f)
+	}
+}
+
+/// Turns tokens back into text.
+///
+/// Whitespace is only inserted where needed to keep adjacent tokens apart,
+/// such that tokenizing the result reproduces the same token kinds.
+/// This holds for tokens produced by [`tokenize`], tokens such as negative literals or implicit multiplication cannot be reproduced.
+///
+/// ```
+/// let tokens = pupil::tokenize("2 x + sin( 1e300 )").collect::<Vec<_>>();
+/// assert_eq!(pupil::detokenize(&tokens), "2x+sin(1e300)");
+/// ```
+pub fn detokenize(tokens: &[Token]) -> String {
+	// Adjacent tokens may merge with up to this many tokens before them, eg. `1` `e` `-` `5`
+	const WINDOW: usize = 4;
+	#[cfg(feature = "complex")]
+	let imaginary = tokens.iter().any(|tok| matches!(tok.kind, TokenKind::Imag(_)));
+	#[cfg(not(feature = "complex"))]
+	let imaginary = false;
+	let mut string = String::new();
+	let mut starts = Vec::with_capacity(tokens.len());
+	for (i, tok) in tokens.iter().enumerate() {
+		let start = string.len();
+		string.push_str(&tok.kind.to_string());
+		// Check if the tokens in the window still lex the same
+		let first = i.saturating_sub(WINDOW);
+		let window = starts.get(first).map(|&start| &string[start..]).unwrap_or("");
+		if i > 0 && !tokenize_num(window, LexerOptions::default(), imaginary, false).map(|tok| tok.kind).eq(tokens[first..=i].iter().map(|tok| tok.kind.clone())) {
+			string.insert(start, ' ');
+			starts.push(start + 1);
+		}
+		else {
+			starts.push(start);
+		}
+	}
+	string
 }
 
 //----------------------------------------------------------------
@@ -39,9 +300,20 @@ pub enum Token<'a> {
 #[derive(Clone, Debug)]
 struct TokenIterator<'a> {
 	string: &'a str,
+	// Length of the whole input to derive the positions
+	len: usize,
+	options: LexerOptions,
+	// Whether literals may have an imaginary suffix
+	#[cfg_attr(not(feature = "complex"), allow(dead_code))]
+	imaginary: bool,
+	// Whether names may be quoted, eg. `"mph"`
+	quoted: bool,
 }
 
 impl<'a> TokenIterator<'a> {
+	fn offset(&self) -> usize {
+		self.len - self.string.len()
+	}
 	fn skip_whitespace(&mut self) -> bool {
 		// Use Clones instead of Peekable...
 		let mut iter = self.string.chars();
@@ -52,38 +324,73 @@ impl<'a> TokenIterator<'a> {
 			// Overwrite with previous iterator
 			self.string = iter.as_str();
 		}
-		return false;
+		false
 	}
-	fn lex_lit(&mut self) -> Option<Token<'a>> {
-		strtod(self.string).map(|(num, tail_s)| {
+	fn lex_lit(&mut self) -> Option<TokenKind<'a>> {
+		strtod(self.string, self.options.locale).map(|(num, tail_s)| {
 			// Update the iterator to right after the number
 			self.string = tail_s;
-			Token::Lit(num)
+			// An imaginary suffix is not the start of an identifier, eg. `2in`
+			#[cfg(feature = "complex")]
+			if self.imaginary && tail_s.starts_with('i') && !tail_s[1..].starts_with(|chr: char| chr.is_alphanumeric() || chr == '_') {
+				self.string = &tail_s[1..];
+				return TokenKind::Imag(num);
+			}
+			TokenKind::Lit(num)
 		})
 	}
-	fn lex_op(&mut self) -> Option<Token<'a>> {
+	fn lex_op(&mut self) -> Option<TokenKind<'a>> {
 		let mut iter = self.string.chars();
 		iter.next().and_then(|chr| {
 			let tok = match chr {
-				'+' => Token::Op(Operator::Add),
-				'-' => Token::Op(Operator::Sub),
-				'*' => Token::Op(Operator::Mul),
-				'/' => Token::Op(Operator::Div),
-				'%' => Token::Op(Operator::Rem),
-				'^' => Token::Op(Operator::Pow),
-				',' => Token::Comma,
-				')' => Token::Close,
+				'+' => TokenKind::Op(Operator::Add),
+				'-' => TokenKind::Op(Operator::Sub),
+				// Python style `**` and `//` operators
+				'*' if iter.clone().next() == Some('*') => { iter.next(); TokenKind::Op(Operator::Pow) },
+				'/' if iter.clone().next() == Some('/') => { iter.next(); TokenKind::Op(Operator::FloorDiv) },
+				'*' => TokenKind::Op(Operator::Mul),
+				'/' => TokenKind::Op(Operator::Div),
+				'%' => TokenKind::Op(Operator::Rem),
+				'^' => TokenKind::Op(Operator::Pow),
+				'&' if iter.next() == Some('&') => TokenKind::Op(Operator::And),
+				'|' if iter.next() == Some('|') => TokenKind::Op(Operator::Or),
+				// Unicode math symbols copied from documents
+				'×' => TokenKind::Op(Operator::Mul),
+				'÷' => TokenKind::Op(Operator::Div),
+				'−' => TokenKind::Op(Operator::Sub),
+				'√' => TokenKind::Op(Operator::Sqrt),
+				'²' => TokenKind::Op(Operator::Sqr),
+				'³' => TokenKind::Op(Operator::Cube),
+				'π' => TokenKind::Var("pi"),
+				',' if self.options.locale == Locale::DecimalPoint => TokenKind::Comma,
+				';' if self.options.locale == Locale::DecimalComma => TokenKind::Comma,
+				';' => TokenKind::Semi,
+				'?' => TokenKind::Question,
+				':' => TokenKind::Colon,
+				'=' => TokenKind::Assign,
+				')' => TokenKind::Close(Paren::Round),
+				'[' => TokenKind::Group(Paren::Square),
+				']' => TokenKind::Close(Paren::Square),
+				'{' => TokenKind::Group(Paren::Curly),
+				'}' => TokenKind::Close(Paren::Curly),
 				_ => return None,
 			};
 			self.string = iter.as_str();
 			Some(tok)
 		})
 	}
-	fn lex_id(&mut self) -> Option<Token<'a>> {
+	fn lex_id(&mut self) -> Option<TokenKind<'a>> {
 		let s = self.string;
-		// Scan for a non-alphanumeric character, take whole string otherwise
+		// Quoted names are variables, an unterminated quote is an unknown token
+		if let Some(quoted) = s.strip_prefix('"').filter(|_| self.quoted) {
+			let (name, s_rem) = quoted.split_once('"')?;
+			self.string = s_rem;
+			return Some(TokenKind::Var(name));
+		}
+		// Scan for a non-alphanumeric character except underscore, take whole string otherwise
+		// Superscripts are alphanumeric but end the name, eg. `x²`
 		let end = s.char_indices()
-			.find(|&(_, chr)| !chr.is_alphanumeric())
+			.find(|&(_, chr)| !chr.is_alphanumeric() && chr != '_' || matches!(chr, '¹' | '²' | '³' | '⁰'..='⁹'))
 			.map(|(pos, _)| pos)
 			.unwrap_or(s.len());
 		// Slice the identifier
@@ -93,52 +400,127 @@ impl<'a> TokenIterator<'a> {
 		// Parenthesis means a function begin
 		if paren_it.next() == Some('(') {
 			self.string = paren_it.as_str();
-			Some(Token::Open(s_id))
+			Some(TokenKind::Open(s_id))
 		}
 		// Otherwise is a variable
 		else {
 			// Variables can’t have length zero
-			if s_id.len() == 0 {
+			if s_id.is_empty() {
 				None
 			}
 			else {
 				self.string = s_rem;
-				Some(Token::Var(s_id))
+				Some(TokenKind::Var(s_id))
 			}
 		}
 	}
-	fn lex_unk(&mut self) -> Option<Token<'a>> {
+	fn lex_unk(&mut self) -> Option<TokenKind<'a>> {
 		// Unknown tokens handled upstream
 		// Set the iterator to finish on next() otherwise it would never end
 		let s_rem = self.string;
 		self.string = "";
-		Some(Token::Unk(s_rem))
-	}
-}
-
-fn strtod(s: &str) -> Option<(f64, &str)> {
-	// Yeah let’s go `strtod`!
-	// ...
-	// Fun fact: Rust strings aren’t zero-terminated, but `strtod` cares...
-	// To ‘fix’ this, copy at most 31 bytes form input and zero terminate it.
-	// Alternatively malloc some memory with CString but are you mad? It’s just a few bytes.
-	// A test was added, I guess that means it’s all good :)
-	use std::{mem, ptr};
-	unsafe {
-		let mut s_num: [libc::c_char; 32] = [0; 32];
-		let s_len = usize::min(s.len(), 31);
-		(&mut s_num[..s_len]).clone_from_slice(mem::transmute(&s.as_bytes()[..s_len]));
-		s_num[s_len] = 0;
-		let mut s_end: *mut libc::c_char = ptr::null_mut();
-		let num = libc::strtod(s_num.as_ptr(), &mut s_end);
-		let read = s_end as usize - s_num.as_ptr() as usize;
-		if read != 0 {
-			Some((num as f64, &s[read..]))
+		Some(TokenKind::Unk(s_rem))
+	}
+}
+
+// Parses the longest prefix which is a number, like C’s `strtod` with the decimal separator of the locale.
+// Accepts decimal and hexadecimal literals, and `inf`, `infinity` and `nan` in any case.
+// Text of the literal starting the string, eg. `1.5e3` of `1.5e3 + x`.
+pub(crate) fn literal_text(s: &str, locale: Locale) -> Option<&str> {
+	strtod(s, locale).map(|(_, tail)| &s[..s.len() - tail.len()])
+}
+
+fn strtod(s: &str, locale: Locale) -> Option<(f64, &str)> {
+	let sep = match locale {
+		Locale::DecimalPoint => b'.',
+		Locale::DecimalComma => b',',
+	};
+	let bytes = s.as_bytes();
+	for (name, num) in [("infinity", f64::INFINITY), ("inf", f64::INFINITY), ("nan", f64::NAN)] {
+		if bytes.len() >= name.len() && bytes[..name.len()].eq_ignore_ascii_case(name.as_bytes()) {
+			return Some((num, &s[name.len()..]));
+		}
+	}
+	if bytes.len() > 2 && bytes[0] == b'0' && (bytes[1] == b'x' || bytes[1] == b'X') {
+		if let Some((num, read)) = strtod_hex(&bytes[2..], sep) {
+			return Some((num, &s[2 + read..]));
+		}
+	}
+	let digits = |start: usize| bytes[start..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+	let int = digits(0);
+	let mut read = int;
+	let mut frac = 0;
+	if bytes.get(read) == Some(&sep) {
+		frac = digits(read + 1);
+		read += 1 + frac;
+	}
+	if int + frac == 0 {
+		return None;
+	}
+	// The exponent is only part of the number if it has digits
+	if matches!(bytes.get(read), Some(b'e' | b'E')) {
+		let sign = matches!(bytes.get(read + 1), Some(b'+' | b'-')) as usize;
+		let exp = digits(read + 1 + sign);
+		if exp != 0 {
+			read += 1 + sign + exp;
+		}
+	}
+	// Only a decimal comma needs a copy to parse
+	let num = match sep {
+		b'.' => s[..read].parse().ok()?,
+		_ => s[..read].replace(sep as char, ".").parse().ok()?,
+	};
+	Some((num, &s[read..]))
+}
+
+// Parses the digits of a hexadecimal literal after the `0x` prefix with an optional binary exponent, eg. `1.8p3`.
+fn strtod_hex(bytes: &[u8], sep: u8) -> Option<(f64, usize)> {
+	let mut mantissa = 0u64;
+	let mut exp = 0i32;
+	let (mut read, mut count, mut frac) = (0, 0, false);
+	while let Some(&byte) = bytes.get(read) {
+		if let Some(digit) = (byte as char).to_digit(16) {
+			// Keep the leading digits which fit, the others only scale the value
+			if mantissa >> 60 == 0 {
+				mantissa = mantissa * 16 + digit as u64;
+				exp -= if frac { 4 } else { 0 };
+			}
+			else if !frac {
+				exp += 4;
+			}
+			count += 1;
+		}
+		else if byte == sep && !frac {
+			frac = true;
 		}
 		else {
-			None
+			break;
 		}
+		read += 1;
 	}
+	if count == 0 {
+		return None;
+	}
+	if matches!(bytes.get(read), Some(b'p' | b'P')) {
+		let negative = bytes.get(read + 1) == Some(&b'-');
+		let sign = matches!(bytes.get(read + 1), Some(b'+' | b'-')) as usize;
+		let digits = bytes[read + 1 + sign..].iter().take_while(|byte| byte.is_ascii_digit());
+		let (mut bin_exp, mut len) = (0i32, 0);
+		for &digit in digits {
+			bin_exp = i32::min(bin_exp * 10 + (digit - b'0') as i32, 100000);
+			len += 1;
+		}
+		if len != 0 {
+			exp += if negative { -bin_exp } else { bin_exp };
+			read += 1 + sign + len;
+		}
+	}
+	if mantissa == 0 {
+		return Some((0.0, read));
+	}
+	// Scale in two steps so the intermediate powers of two don’t overflow
+	let half = exp / 2;
+	Some((mantissa as f64 * 2f64.powi(half) * 2f64.powi(exp - half), read))
 }
 
 impl<'a> Iterator for TokenIterator<'a> {
@@ -146,11 +528,13 @@ impl<'a> Iterator for TokenIterator<'a> {
 	fn next(&mut self) -> Option<Token<'a>> {
 		// Start by skipping over the whitespace
 		if self.skip_whitespace() {
+			let position = self.offset();
 			// Try lexing as various tokens
 			self.lex_op()
 				.or_else(|| self.lex_lit())
 				.or_else(|| self.lex_id())
 				.or_else(|| self.lex_unk())
+				.map(|kind| Token { kind, position })
 		}
 		// End of string
 		else {
@@ -161,28 +545,264 @@ impl<'a> Iterator for TokenIterator<'a> {
 
 /// Creates an iterator over the tokens in a string.
 pub fn tokenize<'a>(string: &'a str) -> impl 'a + Iterator<Item = Token<'a>> {
-	TokenIterator { string }
+	tokenize_with(string, LexerOptions::default())
+}
+
+/// Creates an iterator over the tokens in a string with custom options.
+pub fn tokenize_with<'a>(string: &'a str, options: LexerOptions) -> impl 'a + Iterator<Item = Token<'a>> {
+	tokenize_num(string, options, false, false)
+}
+
+// Tokenizes for the number type, imaginary literals only if it has an imaginary unit and quoted names only if it has named units.
+pub(crate) fn tokenize_num<'a>(string: &'a str, options: LexerOptions, imaginary: bool, quoted: bool) -> impl 'a + Iterator<Item = Token<'a>> {
+	TokenIterator { string, len: string.len(), options, imaginary, quoted }
+}
+
+//----------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+struct CheckedTokenIterator<'a> {
+	inner: TokenIterator<'a>,
+}
+
+impl<'a> CheckedTokenIterator<'a> {
+	fn lex_invalid(&mut self) -> TokenError<'a> {
+		let position = self.inner.offset();
+		let start = self.inner.string;
+		// Skip characters until one which starts a valid token
+		let mut iter = self.inner.string.chars();
+		iter.next();
+		loop {
+			let mut probe = TokenIterator { string: iter.as_str(), len: 0, options: self.inner.options, imaginary: false, quoted: false };
+			let valid = match iter.clone().next() {
+				Some(chr) => chr.is_whitespace() || probe.lex_op().or_else(|| probe.lex_lit()).or_else(|| probe.lex_id()).is_some(),
+				None => true,
+			};
+			if valid {
+				break;
+			}
+			iter.next();
+		}
+		self.inner.string = iter.as_str();
+		TokenError { text: &start[..start.len() - iter.as_str().len()], position }
+	}
+}
+
+impl<'a> Iterator for CheckedTokenIterator<'a> {
+	type Item = Result<Token<'a>, TokenError<'a>>;
+	fn next(&mut self) -> Option<Result<Token<'a>, TokenError<'a>>> {
+		if self.inner.skip_whitespace() {
+			let position = self.inner.offset();
+			let kind = self.inner.lex_op()
+				.or_else(|| self.inner.lex_lit())
+				.or_else(|| self.inner.lex_id());
+			Some(kind.map(|kind| Token { kind, position }).ok_or_else(|| self.lex_invalid()))
+		}
+		else {
+			None
+		}
+	}
+}
+
+/// Creates an iterator over the tokens in a string reporting invalid tokens as errors.
+///
+/// Unlike [`tokenize`] which yields a [`TokenKind::Unk`] swallowing the remainder of the input,
+/// every run of invalid characters produces a [`TokenError`] spanning the run after which lexing resumes.
+/// It converts into an [`ErrorKind::InvalidToken`] error at the start of the run.
+pub fn tokenize_checked<'a>(string: &'a str) -> impl 'a + Iterator<Item = Result<Token<'a>, TokenError<'a>>> {
+	tokenize_checked_with(string, LexerOptions::default())
+}
+
+/// Creates an iterator over the tokens in a string with custom options reporting invalid tokens as errors.
+pub fn tokenize_checked_with<'a>(string: &'a str, options: LexerOptions) -> impl 'a + Iterator<Item = Result<Token<'a>, TokenError<'a>>> {
+	CheckedTokenIterator { inner: TokenIterator { string, len: string.len(), options, imaginary: false, quoted: false } }
 }
 
 #[test]
 fn units() {
-	use crate::Token::*;
+	use crate::TokenKind::*;
 	use crate::Operator::*;
+	use crate::Paren::*;
 	// Literals, RIP "inf" support
-	assert_eq!(tokenize("12.4 45 -0.111").collect::<Vec<_>>(),
+	assert_eq!(tokenize("12.4 45 -0.111").map(|tok| tok.kind).collect::<Vec<_>>(),
 		vec![Lit(12.4), Lit(45.0), Op(Sub), Lit(0.111)]);
 	// Functions and Variables
-	assert_eq!(tokenize("fn(12, (2ans))-pi").collect::<Vec<_>>(),
-		vec![Open("fn"), Lit(12.0), Comma, Open(""), Lit(2.0), Var("ans"), Close, Close, Op(Sub), Var("pi")]);
+	assert_eq!(tokenize("fn(12, (2ans))-pi").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Open("fn"), Lit(12.0), Comma, Open(""), Lit(2.0), Var("ans"), Close(Round), Close(Round), Op(Sub), Var("pi")]);
+	// Underscores in identifiers
+	assert_eq!(tokenize("wrap_angle(_x) x_1").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Open("wrap_angle"), Var("_x"), Close(Round), Var("x_1")]);
+	// Brackets
+	assert_eq!(tokenize("[(1)*{2}]").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Group(Square), Open(""), Lit(1.0), Close(Round), Op(Mul), Group(Curly), Lit(2.0), Close(Curly), Close(Square)]);
 	// All Operators
-	assert_eq!(tokenize("1%2+3-5*-4/2^1").collect::<Vec<_>>(),
+	assert_eq!(tokenize("1%2+3-5*-4/2^1").map(|tok| tok.kind).collect::<Vec<_>>(),
 		vec![Lit(1.0), Op(Rem), Lit(2.0), Op(Add), Lit(3.0), Op(Sub), Lit(5.0), Op(Mul), Op(Sub), Lit(4.0), Op(Div), Lit(2.0), Op(Pow), Lit(1.0)]);
+	// Alternative spellings
+	assert_eq!(tokenize("2**3//4*/5").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(2.0), Op(Pow), Lit(3.0), Op(FloorDiv), Lit(4.0), Op(Mul), Op(Div), Lit(5.0)]);
+	assert_eq!(tokenize("2***3").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(2.0), Op(Pow), Op(Mul), Lit(3.0)]);
+	// Logical operators
+	assert_eq!(tokenize("1&&0||2").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(1.0), Op(And), Lit(0.0), Op(Or), Lit(2.0)]);
+	assert_eq!(tokenize("1 & 2").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(1.0), Unk("& 2")]);
+	// Conditional
+	assert_eq!(tokenize("x?1:y").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Var("x"), Question, Lit(1.0), Colon, Var("y")]);
+	// Keyword arguments
+	assert_eq!(tokenize("log(8,base=2)").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Open("log"), Lit(8.0), Comma, Var("base"), Assign, Lit(2.0), Close(Round)]);
+	// Unicode math symbols
+	assert_eq!(tokenize("2×π÷√3²−1³").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(2.0), Op(Mul), Var("pi"), Op(Div), Op(Sqrt), Lit(3.0), Op(Sqr), Op(Sub), Lit(1.0), Op(Cube)]);
+	assert_eq!(tokenize("2πr").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(2.0), Var("pi"), Var("r")]);
+	assert_eq!(tokenize("x²+pi³-f(x)²").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Var("x"), Op(Sqr), Op(Add), Var("pi"), Op(Cube), Op(Sub), Open("f"), Var("x"), Close(Round), Op(Sqr)]);
 	// Unknown
-	assert_eq!(tokenize("2 + 3 * !èè&").collect::<Vec<_>>(),
+	assert_eq!(tokenize("2 + 3 * !èè&").map(|tok| tok.kind).collect::<Vec<_>>(),
 		vec![Lit(2.0), Op(Add), Lit(3.0), Op(Mul), Unk("!èè&")]);
 }
 #[test]
+fn checked() {
+	use crate::TokenKind::*;
+	use crate::Operator::*;
+	fn invalid(text: &str, position: usize) -> Result<TokenKind<'_>, TokenError<'_>> {
+		Err(TokenError { text, position })
+	}
+	assert_eq!(tokenize_checked("2 + $ 3 @#! * 4").map(|tok| tok.map(|tok| tok.kind)).collect::<Vec<_>>(),
+		vec![Ok(Lit(2.0)), Ok(Op(Add)), invalid("$", 4), Ok(Lit(3.0)), invalid("@#!", 8), Ok(Op(Mul)), Ok(Lit(4.0))]);
+	assert_eq!(tokenize_checked("1&2#").map(|tok| tok.map(|tok| tok.kind)).collect::<Vec<_>>(),
+		vec![Ok(Lit(1.0)), invalid("&", 1), Ok(Lit(2.0)), invalid("#", 3)]);
+	assert_eq!(tokenize_checked("!!x(").map(|tok| tok.map(|tok| tok.kind)).collect::<Vec<_>>(),
+		vec![invalid("!!", 0), Ok(Open("x"))]);
+	// Spans in bytes of multibyte characters
+	let error = tokenize_checked("1 + €€ 2").find_map(Result::err).unwrap();
+	assert_eq!((error.span(), Error::from(error)), (4..10, Error::new(ErrorKind::InvalidToken, 4)));
+	// Custom options
+	let options = LexerOptions { locale: Locale::DecimalComma };
+	assert_eq!(tokenize_checked_with("1,5 $ 2", options).map(|tok| tok.map(|tok| tok.kind)).collect::<Vec<_>>(),
+		vec![Ok(Lit(1.5)), invalid("$", 4), Ok(Lit(2.0))]);
+	// Without errors it matches the unchecked tokenizer
+	let input = "fn(12, [2ans]) && -pi²";
+	assert_eq!(tokenize_checked(input).collect::<Result<Vec<_>, _>>(), Ok(tokenize(input).collect::<Vec<_>>()));
+}
+#[test]
+fn positions() {
+	assert_eq!(tokenize(" 12 + sin(π)² ").map(|tok| tok.position).collect::<Vec<_>>(),
+		vec![1, 4, 6, 10, 12, 13]);
+	assert_eq!(tokenize("×÷ !").map(|tok| tok.position).collect::<Vec<_>>(),
+		vec![0, 2, 5]);
+}
+#[test]
+fn locale() {
+	use crate::TokenKind::*;
+	let options = LexerOptions { locale: Locale::DecimalComma };
+	assert_eq!(tokenize_with("max(1,5; 2,5e1)", options).map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Open("max"), Lit(1.5), Comma, Lit(25.0), Close(Paren::Round)]);
+	// At most one decimal separator per literal
+	assert_eq!(tokenize_with("1,2,3", options).map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(1.2), Lit(0.3)]);
+	assert_eq!(tokenize_with("1, 2", options).map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(1.0), Lit(2.0)]);
+	// The decimal point is rejected
+	assert_eq!(tokenize_with("3.14", options).map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(3.0), Unk(".14")]);
+	// And the default remains unchanged
+	assert_eq!(tokenize("1,5;").map(|tok| tok.kind).collect::<Vec<_>>(),
+		vec![Lit(1.0), Comma, Lit(5.0), Semi]);
+}
+#[test]
+fn display() {
+	let inputs = [
+		"12.4 45 -0.111",
+		"fn(12, (2ans))-pi",
+		"[(1)*{2}]",
+		"1%2+3-5*-4/2^1",
+		"2**3//4*/5 ** * 2",
+		"1&&0||2",
+		"2×π÷√3²−1³",
+		"1e-5 1 e-5 1 e -5 0 x1 0x1p3 1e300 1e-300 0.1 100 inf x (y) z[w] sin (1)",
+		"1; 2;;x",
+		"x?1:y ? (2):-3",
+		"f(x = 1, y=-2)",
+		"2 + 3 * !èè&",
+	];
+	for input in inputs {
+		let tokens = tokenize(input).collect::<Vec<_>>();
+		let string = detokenize(&tokens);
+		assert!(tokenize(&string).map(|tok| tok.kind).eq(tokens.iter().map(|tok| tok.kind.clone())), "{:?} -> {:?}", input, string);
+	}
+	assert_eq!(detokenize(&tokenize("1 e-5 2 3 x y").collect::<Vec<_>>()), "1e- 5 2 3x y");
+	assert_eq!(detokenize(&tokenize("x (y) sin (z)").collect::<Vec<_>>()), "x (y)sin (z)");
+	assert_eq!(TokenKind::Lit(1e21).to_string(), "1e21");
+	assert_eq!(TokenKind::Lit(0.1 + 0.2).to_string(), "0.30000000000000004");
+	assert_eq!(TokenKind::Open("max").to_string(), "max(");
+	assert_eq!(TokenKind::Op(Operator::FloorDiv).to_string(), "//");
+}
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+	let input = "max(1.5, x) + [2]² && !";
+	let tokens = tokenize(input).collect::<Vec<_>>();
+	let json = serde_json::to_string(&tokens).unwrap();
+	// Borrowed tokens deserialize directly from the json
+	assert_eq!(serde_json::from_str::<Vec<Token>>(&json).unwrap(), tokens);
+	// Owned tokens have the same representation
+	let owned = tokens.iter().cloned().map(OwnedToken::from).collect::<Vec<_>>();
+	assert_eq!(serde_json::from_str::<Vec<OwnedToken>>(&json).unwrap(), owned);
+	assert_eq!(serde_json::to_string(&owned).unwrap(), json);
+	assert!(owned.iter().map(OwnedToken::as_token).eq(tokens.iter().cloned()));
+	// Escaped strings require owned tokens
+	let json = r#"{"kind":{"Var":"a\u0062"},"position":3}"#;
+	assert_eq!(serde_json::from_str::<OwnedToken>(json).unwrap(), OwnedToken { kind: OwnedTokenKind::Var("ab".into()), position: 3 });
+	assert!(serde_json::from_str::<Token>(json).is_err());
+}
+#[test]
 fn regressions() {
 	// Regression test: fixed `strtod` from reading past the real input
-	assert_eq!(strtod(&"1234"[..2]), Some((12.0, "")));
+	assert_eq!(strtod(&"1234"[..2], Locale::DecimalPoint), Some((12.0, "")));
+	// Long literals aren’t truncated
+	let long = "12345678901234567890123456789012345";
+	assert_eq!(strtod(long, Locale::DecimalPoint), Some((long.parse().unwrap(), "")));
+}
+#[test]
+fn literals() {
+	let table = [
+		("1e5", Some((1e5, ""))),
+		("1E-2x", Some((0.01, "x"))),
+		(".5", Some((0.5, ""))),
+		("5.", Some((5.0, ""))),
+		("1.2.3", Some((1.2, ".3"))),
+		("00012", Some((12.0, ""))),
+		("1e", Some((1.0, "e"))),
+		("1e+", Some((1.0, "e+"))),
+		("1_000", Some((1.0, "_000"))),
+		("1e400", Some((f64::INFINITY, ""))),
+		("1e-400", Some((0.0, ""))),
+		("0x10", Some((16.0, ""))),
+		("0x1p3", Some((8.0, ""))),
+		("0X1.8P1", Some((3.0, ""))),
+		("0x1p-1074", Some((5e-324, ""))),
+		("0xffffffffffffffffff", Some((4.722366482869645e21, ""))),
+		("0x", Some((0.0, "x"))),
+		("0xg", Some((0.0, "xg"))),
+		("0x1p", Some((1.0, "p"))),
+		("INF", Some((f64::INFINITY, ""))),
+		("infinity", Some((f64::INFINITY, ""))),
+		(".", None),
+		("e5", None),
+		("٣", None),
+	];
+	for (input, expected) in table {
+		assert_eq!(strtod(input, Locale::DecimalPoint), expected, "{:?}", input);
+	}
+	assert!(strtod("nanx", Locale::DecimalPoint).is_some_and(|(num, rest)| num.is_nan() && rest == "x"));
+	assert_eq!(strtod("1,5", Locale::DecimalPoint), Some((1.0, ",5")));
+	assert_eq!(strtod("1,5", Locale::DecimalComma), Some((1.5, "")));
+	assert_eq!(strtod("1.5", Locale::DecimalComma), Some((1.0, ".5")));
+	assert_eq!(strtod("0x1,8", Locale::DecimalComma), Some((1.5, "")));
+	assert_eq!(strtod(",5", Locale::DecimalComma), Some((0.5, "")));
 }
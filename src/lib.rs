@@ -14,16 +14,74 @@ assert_eq!(result, Ok(5.0));
 ```
 */
 
+mod ast;
+mod bench;
+mod cache;
+#[cfg(feature = "bigfloat")]
+mod bigfloat;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod check;
+mod constant;
+#[cfg(feature = "complex")]
+mod complex;
 mod env;
+mod error;
+#[cfg(feature = "serde")]
+mod evaluation;
 mod expr;
+mod format;
+mod fraction;
+mod int;
+mod interval;
 mod lexer;
+mod math;
+mod num;
 mod op;
+mod parser;
+mod program;
+mod storage;
+#[cfg(feature = "units")]
+mod units;
 pub mod builtins;
+pub mod builtins32;
+#[cfg(feature = "bigfloat")]
+pub mod builtins_big;
+#[cfg(feature = "complex")]
+pub mod builtins_complex;
+pub mod builtins_int;
+pub mod builtins_interval;
+#[cfg(feature = "units")]
+pub mod builtins_units;
+#[cfg(feature = "repl")]
+pub mod repl;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+pub use ast::*;
+pub use bench::*;
+pub use cache::*;
+#[cfg(feature = "bigfloat")]
+pub use bigfloat::*;
+pub use check::*;
+pub use constant::*;
+#[cfg(feature = "complex")]
+pub use complex::*;
 pub use env::*;
+pub use error::*;
+#[cfg(feature = "serde")]
+pub use evaluation::*;
 pub use expr::*;
+pub use format::*;
+pub use fraction::*;
+pub use int::*;
+pub use interval::*;
 pub use lexer::*;
+pub use num::*;
 pub use op::*;
+pub use program::*;
+#[cfg(feature = "units")]
+pub use units::*;
 
 #[cfg(doc)]
 #[doc = include_str!("../readme.md")]
@@ -0,0 +1,126 @@
+/*!
+Transcendental functions of the builtins.
+
+With the `deterministic` feature these are the pure Rust implementations of the `libm` crate which give bit-identical results on every platform,
+otherwise the std implementations which may differ by an ulp between platforms and C library versions.
+The square root is correctly rounded either way.
+
+The complex, interval and arbitrary precision backends always use their own implementations.
+*/
+
+macro_rules! functions {
+	($ty:ty; $($name:ident($($arg:ident),*) => $libm:ident;)*) => {$(
+		#[cfg(feature = "deterministic")]
+		#[inline]
+		pub(crate) fn $name(x: $ty $(, $arg: $ty)*) -> $ty {
+			libm::$libm(x $(, $arg)*)
+		}
+		#[cfg(not(feature = "deterministic"))]
+		#[inline]
+		pub(crate) fn $name(x: $ty $(, $arg: $ty)*) -> $ty {
+			x.$name($($arg),*)
+		}
+	)*};
+}
+
+functions! { f64;
+	sin() => sin;
+	cos() => cos;
+	tan() => tan;
+	asin() => asin;
+	acos() => acos;
+	atan() => atan;
+	atan2(x) => atan2;
+	sinh() => sinh;
+	cosh() => cosh;
+	tanh() => tanh;
+	asinh() => asinh;
+	acosh() => acosh;
+	atanh() => atanh;
+	exp() => exp;
+	exp2() => exp2;
+	exp_m1() => expm1;
+	ln() => log;
+	ln_1p() => log1p;
+	log2() => log2;
+	log10() => log10;
+	powf(n) => pow;
+	cbrt() => cbrt;
+	hypot(y) => hypot;
+}
+
+/// Raises to an integer power.
+#[inline]
+pub(crate) fn powi(x: f64, n: i32) -> f64 {
+	if cfg!(feature = "deterministic") { powf(x, n as f64) }
+	else { x.powi(n) }
+}
+/// Logarithm in a base.
+#[inline]
+pub(crate) fn log(x: f64, base: f64) -> f64 {
+	ln(x) / ln(base)
+}
+/// Sine and cosine.
+#[inline]
+pub(crate) fn sin_cos(x: f64) -> (f64, f64) {
+	(sin(x), cos(x))
+}
+
+/// Single precision functions.
+pub(crate) mod single {
+	functions! { f32;
+		sin() => sinf;
+		cos() => cosf;
+		tan() => tanf;
+		asin() => asinf;
+		acos() => acosf;
+		atan() => atanf;
+		atan2(x) => atan2f;
+		sinh() => sinhf;
+		cosh() => coshf;
+		tanh() => tanhf;
+		exp() => expf;
+		ln() => logf;
+		log2() => log2f;
+		log10() => log10f;
+		powf(n) => powf;
+		cbrt() => cbrtf;
+		hypot(y) => hypotf;
+	}
+
+	/// Logarithm in a base.
+	#[inline]
+	pub(crate) fn log(x: f32, base: f32) -> f32 {
+		ln(x) / ln(base)
+	}
+}
+
+//----------------------------------------------------------------
+
+#[cfg(feature = "deterministic")]
+#[test]
+fn golden() {
+	let inputs = [
+		"sin(1) + cos(2) * tan(3)",
+		"asin(0.3) - acos(0.7) + atan(5)",
+		"atan2(1.5, -2.5) + hypot(3.1, 4.2)",
+		"sinh(0.5) * cosh(1.5) / tanh(2.5)",
+		"exp(1.1) - ln(7) + log2(10) * log10(3)",
+		"2^0.5 + 10^-3.3 + 1.0001^10000",
+		"cbrt(17) + sqrt(2) + log(100, 7)",
+		"sin(1e10) + cos(-1e5) + exp(-700)",
+		"sin(pi / 3) * cos(tau / 7) + e^pi",
+	];
+	// Bit patterns of the results in both precisions hashed with FNV-1a
+	let env = crate::BasicEnv::default();
+	let env32 = crate::BasicEnv32::default();
+	let mut hash = 0xcbf29ce484222325u64;
+	for input in inputs {
+		let bits = crate::eval(&env, input).unwrap().to_bits() ^ crate::eval(&env32, input).unwrap().to_bits() as u64;
+		for byte in bits.to_le_bytes() {
+			hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+		}
+	}
+	// Regenerate when deliberately changing the implementations
+	assert_eq!(hash, 1831936114374249821);
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+use crate::*;
+
+/// Number type in which expressions are evaluated.
+///
+/// Implemented for `f64`, the default [`Value`] with the default builtins,
+/// and `f32` with the [`builtins32`](crate::builtins32) in a [`BasicEnv32`].
+/// Also for `i64` in an [`IntEnv`], `Interval` in an [`IntervalEnv`], with the `complex` feature for `Complex` in a `ComplexEnv`,
+/// and with the `bigfloat` feature for `BigValue` in a `PreciseEnv`.
+///
+/// Literals are parsed as `f64` and converted, so single precision literals may round twice.
+pub trait Num: Clone + PartialEq + fmt::Debug + fmt::Display + 'static {
+	/// Converts a literal, boolean results convert `1.0` for true and `0.0` for false.
+	fn from_f64(val: f64) -> Self;
+	/// Whether the value counts as true in conditionals and logic operators.
+	fn truthy(&self) -> bool;
+	/// Whether the value is neither infinite nor NaN, see [`Expr::set_strict`].
+	fn is_finite(&self) -> bool;
+	/// Whether dividing by the value is a division by zero.
+	fn is_zero(&self) -> bool;
+	/// Returns the builtin implementing the operator.
+	fn operator(op: Operator) -> BuiltinFn<Self>;
+	/// Returns the builtin implementing the postfix percentage.
+	fn percent() -> BuiltinFn<Self>;
+	/// Returns the builtin returning its single argument, used for grouping brackets.
+	fn identity() -> BuiltinFn<Self>;
+	/// Parses the text of a literal at full precision, eg. `0.1` or `1e-40`.
+	///
+	/// The text uses `.` as the decimal separator. Number types returning `Ok(None)` convert the literal from `f64`,
+	/// an error rejects the literal, eg. `1.5` as an integer.
+	fn from_literal(_text: &str) -> Result<Option<Self>, ErrorKind> {
+		Ok(None)
+	}
+	/// Converts an imaginary literal, eg. `4i`.
+	///
+	/// Number types without an imaginary unit return `None` and `4i` is lexed as a literal followed by a variable.
+	/// Imaginary literals are only lexed with the `complex` feature.
+	fn imaginary(_val: f64) -> Option<Self> {
+		None
+	}
+	/// Whether names may be quoted, eg. the unit in `to(x, "mph")`.
+	///
+	/// Quoted names are lexed as variables, otherwise a quote is an invalid token.
+	fn quoted_names() -> bool {
+		false
+	}
+}
+
+impl Num for f64 {
+	#[inline]
+	fn from_f64(val: f64) -> f64 {
+		val
+	}
+	#[inline]
+	fn truthy(&self) -> bool {
+		crate::builtins::truthy(*self)
+	}
+	#[inline]
+	fn is_finite(&self) -> bool {
+		f64::is_finite(*self)
+	}
+	#[inline]
+	fn is_zero(&self) -> bool {
+		*self == 0.0
+	}
+	fn operator(op: Operator) -> BuiltinFn {
+		op.desc().pfn
+	}
+	fn percent() -> BuiltinFn {
+		crate::builtins::builtin_percent
+	}
+	fn identity() -> BuiltinFn {
+		crate::builtins::builtin_id
+	}
+}
+
+impl Num for f32 {
+	#[inline]
+	fn from_f64(val: f64) -> f32 {
+		val as f32
+	}
+	#[inline]
+	fn truthy(&self) -> bool {
+		// NaN compares unequal to everything, explicitly reject it
+		*self != 0.0 && !self.is_nan()
+	}
+	#[inline]
+	fn is_finite(&self) -> bool {
+		f32::is_finite(*self)
+	}
+	#[inline]
+	fn is_zero(&self) -> bool {
+		*self == 0.0
+	}
+	fn operator(op: Operator) -> BuiltinFn<f32> {
+		use crate::builtins32::*;
+		match op {
+			Operator::Add => builtin_add,
+			Operator::Sub => builtin_sub,
+			Operator::Mul | Operator::IMul => builtin_mul,
+			Operator::Div => builtin_div,
+			Operator::Rem => builtin_rem,
+			Operator::Pow => builtin_pow,
+			Operator::And => builtin_all,
+			Operator::Or => builtin_any,
+			Operator::Sqrt => builtin_sqrt,
+			Operator::Sqr => builtin_sqr,
+			Operator::Cube => builtin_cube,
+			Operator::FloorDiv => builtin_floordiv,
+		}
+	}
+	fn percent() -> BuiltinFn<f32> {
+		crate::builtins32::builtin_percent
+	}
+	fn identity() -> BuiltinFn<f32> {
+		crate::builtins32::builtin_id
+	}
+}
@@ -1,5 +1,6 @@
+use std::fmt;
 use crate::BuiltinFn;
-use crate::builtins::{builtin_add, builtin_sub, builtin_mul, builtin_div, builtin_rem, builtin_pow};
+use crate::builtins::{builtin_add, builtin_sub, builtin_mul, builtin_div, builtin_rem, builtin_pow, builtin_all, builtin_any, builtin_sqrt, builtin_sqr, builtin_cube, builtin_floordiv};
 
 /// Operator precedence.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
@@ -10,12 +11,23 @@ pub(crate) enum Order {
 	/// Prevents precedence rules from pushing past a function application.
 	/// Only an explicit closing `)` can push past it.
 	FnBarrier,
+	/// Conditional `?:` precedence.
+	///
+	/// Never evaluated by precedence rules, only by the matching `:` or the end of its enclosing group.
+	Cond,
+	/// Logical or precedence.
+	Or,
+	/// Logical and precedence.
+	And,
 	/// Addition and subtraction precedence.
 	AddSub,
 	/// Multiplication and division precedence.
 	MulDiv,
 	/// Implicit multiplication precedence.
 	///
+	/// Implicit multiplication is inserted before a variable, function or bracket following a value, eg. `2x`, `2sin(x)`, `2(3)` and `(2)(3)`,
+	/// and before a literal following a closing bracket, eg. `(2)3`. Adjacent literals such as `2 3` are an error.
+	///
 	/// This makes implicit mul bind tightly under division allowing `1/2ans` to be evaulated as `1/(2*ans)`.
 	/// But not high enough to overpower exponentiation so `2ans^3` will be evaluated as `2*(ans^3)`.
 	IMul,
@@ -39,6 +51,7 @@ pub(crate) enum Assoc {
 
 /// Supported operator types.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Operator {
 	/// `+`
@@ -57,26 +70,58 @@ pub enum Operator {
 	///
 	/// Created on the fly as needed by the parser.
 	IMul,
-	/// `^`
+	/// `^` or `**`
 	Pow,
+	/// `&&`
+	///
+	/// Logical and, see [`builtin_all`](crate::builtins::builtin_all) for the truthiness rules.
+	And,
+	/// `||`
+	///
+	/// Logical or, see [`builtin_any`](crate::builtins::builtin_any) for the truthiness rules.
+	Or,
+	/// `√`
+	///
+	/// Prefix square root, only valid as an unary operator.
+	Sqrt,
+	/// `²`
+	///
+	/// Postfix square, applies to the value right before it.
+	Sqr,
+	/// `³`
+	///
+	/// Postfix cube, applies to the value right before it.
+	Cube,
+	/// `//`
+	///
+	/// Floored division, rounds the quotient towards negative infinity.
+	FloorDiv,
 }
 
 /// Descriptor for an operator’s builtin, precedence, associativity and if available as unary operator.
 pub(crate) struct OpDesc {
+	// Name of the equivalent builtin
+	pub name: &'static str,
 	pub pfn: BuiltinFn,
 	pub pre: Order,
 	pub assoc: Assoc,
 	pub unary: bool,
 }
 
-static OP_DESC: [OpDesc; 7] = [
-	OpDesc { pfn: builtin_add, pre: Order::AddSub, assoc: Assoc::Left, unary: true },
-	OpDesc { pfn: builtin_sub, pre: Order::AddSub, assoc: Assoc::Left, unary: true },
-	OpDesc { pfn: builtin_mul, pre: Order::MulDiv, assoc: Assoc::Left, unary: false },
-	OpDesc { pfn: builtin_div, pre: Order::MulDiv, assoc: Assoc::Left, unary: false },
-	OpDesc { pfn: builtin_rem, pre: Order::MulDiv, assoc: Assoc::Left, unary: false },
-	OpDesc { pfn: builtin_mul, pre: Order::IMul, assoc: Assoc::Left, unary: false },
-	OpDesc { pfn: builtin_pow, pre: Order::Pow, assoc: Assoc::Right, unary: false },
+static OP_DESC: [OpDesc; 13] = [
+	OpDesc { name: "add", pfn: builtin_add, pre: Order::AddSub, assoc: Assoc::Left, unary: true },
+	OpDesc { name: "sub", pfn: builtin_sub, pre: Order::AddSub, assoc: Assoc::Left, unary: true },
+	OpDesc { name: "mul", pfn: builtin_mul, pre: Order::MulDiv, assoc: Assoc::Left, unary: false },
+	OpDesc { name: "div", pfn: builtin_div, pre: Order::MulDiv, assoc: Assoc::Left, unary: false },
+	OpDesc { name: "rem", pfn: builtin_rem, pre: Order::MulDiv, assoc: Assoc::Left, unary: false },
+	OpDesc { name: "mul", pfn: builtin_mul, pre: Order::IMul, assoc: Assoc::Left, unary: false },
+	OpDesc { name: "pow", pfn: builtin_pow, pre: Order::Pow, assoc: Assoc::Right, unary: false },
+	OpDesc { name: "all", pfn: builtin_all, pre: Order::And, assoc: Assoc::Left, unary: false },
+	OpDesc { name: "any", pfn: builtin_any, pre: Order::Or, assoc: Assoc::Left, unary: false },
+	OpDesc { name: "sqrt", pfn: builtin_sqrt, pre: Order::Unary, assoc: Assoc::Right, unary: true },
+	OpDesc { name: "sqr", pfn: builtin_sqr, pre: Order::Unary, assoc: Assoc::Left, unary: false },
+	OpDesc { name: "cube", pfn: builtin_cube, pre: Order::Unary, assoc: Assoc::Left, unary: false },
+	OpDesc { name: "floordiv", pfn: builtin_floordiv, pre: Order::MulDiv, assoc: Assoc::Left, unary: false },
 ];
 
 impl Operator {
@@ -86,3 +131,25 @@ impl Operator {
 		&OP_DESC[self as usize]
 	}
 }
+
+impl fmt::Display for Operator {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let s = match self {
+			Operator::Add => "+",
+			Operator::Sub => "-",
+			Operator::Mul => "*",
+			Operator::Div => "/",
+			Operator::Rem => "%",
+			// Implicit multiplication has no symbol of its own
+			Operator::IMul => "*",
+			Operator::Pow => "^",
+			Operator::And => "&&",
+			Operator::Or => "||",
+			Operator::Sqrt => "√",
+			Operator::Sqr => "²",
+			Operator::Cube => "³",
+			Operator::FloorDiv => "//",
+		};
+		f.write_str(s)
+	}
+}
@@ -0,0 +1,691 @@
+use crate::*;
+use crate::storage::{Alloc, Storage};
+
+/// Receives the parsed expression in postfix order.
+///
+/// The parser resolves precedence and brackets, the sink decides what to do with the result.
+/// Errors returned by the sink are reported at the position of the token being parsed,
+/// except for `apply` which is reported at the position of the fn.
+pub(crate) trait Sink {
+	/// Handle to an fn waiting for its arguments.
+	type Fn;
+	/// Storage of the stacks of the parser and sink.
+	type Alloc: Alloc;
+	fn lit(&mut self, val: Value, pos: usize) -> Result<(), ErrorKind>;
+	// Imaginary literals are only lexed for number types with an imaginary unit
+	#[cfg(feature = "complex")]
+	fn imag(&mut self, _val: Value, _pos: usize) -> Result<(), ErrorKind> {
+		Err(ErrorKind::InvalidToken)
+	}
+	fn var(&mut self, name: &str, pos: usize) -> Result<(), ErrorKind>;
+	fn call(&mut self, name: &str) -> Result<Self::Fn, ErrorKind>;
+	fn op(&mut self, op: Operator) -> Self::Fn;
+	fn percent(&mut self) -> Self::Fn;
+	fn group(&mut self) -> Self::Fn;
+	fn apply(&mut self, f: Self::Fn, nargs: usize, pos: usize) -> Result<(), ErrorKind>;
+	// The argument at this index of the fn is named, the keyword arguments follow the positional arguments
+	fn keyword(&mut self, f: &mut Self::Fn, index: usize, name: &str, pos: usize) -> Result<(), ErrorKind>;
+	// The last value is an argument of the fn followed by another argument
+	fn next_arg(&mut self, f: &mut Self::Fn) -> Result<(), ErrorKind>;
+	fn warn(&mut self, kind: WarningKind, pos: usize);
+	// The last value is the condition of a conditional
+	fn cond(&mut self) -> Result<(), ErrorKind>;
+	// The last value is the then branch of the innermost conditional
+	fn cond_else(&mut self) -> Result<(), ErrorKind>;
+	// The last value is the else branch of the innermost conditional
+	fn cond_end(&mut self, pos: usize) -> Result<(), ErrorKind>;
+}
+
+// Consider this a finite state automaton of some kind.
+// At any point while parsing an expression, it is either expecting a value or operator-like thing.
+// After a `%` the next token decides whether it was a binary remainder or a postfix percent.
+// After a variable starting an argument the next token decides whether it was a keyword.
+// When the next token is known ahead the keyword is resolved right away and its `=` is expected.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum State { Val, Op, Rem(usize), Key(usize), Assign }
+
+// The previous token where it affects the meaning of the next token.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Prev {
+	Other,
+	// Closing bracket, eg. `(2)3`
+	Close,
+	// Opening bracket or comma starting an argument
+	Arg,
+	// Keyword argument, eg. `base =`
+	Assign,
+	// Unary `+` or `-`
+	Sign,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Tag {
+	None,
+	// Which bracket opened this fn barrier, must match the closing bracket
+	Paren(Paren),
+	// Branches of a conditional
+	Then,
+	Else,
+}
+
+struct FnVal<F> {
+	// The conditional branches have no fn
+	func: Option<F>,
+	pre: Order,
+	nargs: usize,
+	tag: Tag,
+	// Whether any of the arguments is a keyword argument
+	keywords: bool,
+	// Position of the token which pushed this fn
+	pos: usize,
+}
+
+/// Shunting-yard parser feeding its output to a sink.
+pub(crate) struct Parser<S: Sink> {
+	pub sink: S,
+	pub limits: Limits,
+	fns: <S::Alloc as Alloc>::Fns<FnVal<S::Fn>>,
+	next: State,
+	prev: Prev,
+	// Name of the variable which may be a keyword
+	key: <S::Alloc as Alloc>::Name,
+	// Whether the next token is a `=` if known ahead
+	ahead: Option<bool>,
+	// Number of tokens parsed
+	tokens: usize,
+	// Position of the token being parsed
+	position: usize,
+}
+
+impl<S: Sink> Parser<S> {
+	pub fn new(sink: S) -> Parser<S> {
+		Parser {
+			sink,
+			limits: Limits::default(),
+			fns: Default::default(),
+			next: State::Val,
+			prev: Prev::Other,
+			key: Default::default(),
+			ahead: None,
+			tokens: 0,
+			position: 0,
+		}
+	}
+	pub fn parse(&mut self, tok: Token) -> Result<(), Error> {
+		self.position = tok.position;
+		self.tokens += 1;
+		if self.tokens > self.limits.max_tokens {
+			return Err(self.error(ErrorKind::LimitExceeded));
+		}
+		let prev = match tok.kind {
+			TokenKind::Close(_) => Prev::Close,
+			TokenKind::Open(_) | TokenKind::Comma => Prev::Arg,
+			TokenKind::Assign => Prev::Assign,
+			TokenKind::Op(Operator::Add | Operator::Sub) if self.next == State::Val => Prev::Sign,
+			_ => Prev::Other,
+		};
+		// Dispatch based on a simple state machine:
+		//  expect either an operator or value like token.
+		let result = match self.next {
+			State::Op => self.parse_op(tok.kind),
+			State::Val => self.parse_val(tok.kind),
+			State::Rem(pos) => self.parse_rem(tok.kind, pos),
+			State::Key(pos) => self.parse_key(tok.kind, pos),
+			State::Assign => self.parse_assign(tok.kind),
+		};
+		self.prev = prev;
+		result
+	}
+	/// Parses the token given the token after it, none at the end of the input.
+	///
+	/// A variable starting an argument is resolved right away instead of copying its name until the next token.
+	pub fn parse_ahead(&mut self, tok: Token, next: Option<&Token>) -> Result<(), Error> {
+		self.ahead = Some(matches!(next, Some(Token { kind: TokenKind::Assign, .. })));
+		let result = self.parse(tok);
+		self.ahead = None;
+		result
+	}
+	/// Finishes parsing at the end position, leaving a single value in the sink.
+	pub fn finish(&mut self, end: usize) -> Result<(), Error> {
+		self.position = end;
+		self.flush_key()?;
+		// Must end at a value like token
+		if self.next == State::Val {
+			return Err(self.error(ErrorKind::UnfinishedExpression));
+		}
+		// Trailing `%` is a percentage
+		if let State::Rem(pos) = self.next {
+			self.eval_percent(pos)?;
+		}
+		// Evaluate all pending operators
+		self.eval_gt(Order::FnBarrier)?;
+		// Any fn left is an unclosed bracket
+		if let Some(f) = self.fns.last() {
+			return Err(Error::new(ErrorKind::UnbalancedParens, f.pos));
+		}
+		Ok(())
+	}
+	/// Resolves the variable starting an argument as a positional argument when no token follows it.
+	pub fn flush_key(&mut self) -> Result<(), Error> {
+		if let State::Key(pos) = self.next {
+			let key = std::mem::take(&mut self.key);
+			let result = key_name(&key, pos).and_then(|name| self.resolve_key(name, pos, false));
+			self.key = key;
+			result?;
+			self.next = State::Op;
+		}
+		Ok(())
+	}
+	/// Resets the parser to parse a new expression, keeping its allocations.
+	pub fn clear(&mut self) {
+		self.fns.clear();
+		self.next = State::Val;
+		self.prev = Prev::Other;
+		self.key.clear();
+		self.tokens = 0;
+		self.position = 0;
+	}
+	/// Replaces the sink of the cleared parser.
+	pub fn map_sink<T: Sink<Alloc = S::Alloc>>(self, f: impl FnOnce(S) -> T) -> Parser<T> {
+		Parser {
+			sink: f(self.sink),
+			limits: self.limits,
+			fns: Default::default(),
+			next: self.next,
+			prev: self.prev,
+			key: self.key,
+			ahead: self.ahead,
+			tokens: self.tokens,
+			position: self.position,
+		}
+	}
+	/// Returns the number of open brackets.
+	pub fn brackets(&self) -> usize {
+		self.fns.iter().filter(|f| f.pre == Order::FnBarrier).count()
+	}
+	/// Resumes parsing after an error at a comma or closing bracket of the innermost brackets.
+	///
+	/// Given the number of brackets open before the token was parsed, the token may have closed its bracket before failing.
+	/// The pending fns within the brackets are discarded without telling the sink, mismatched brackets are closed anyway.
+	/// Returns whether parsing resumed, it doesn’t outside of brackets.
+	pub fn resync(&mut self, tok: &Token, brackets: usize) -> Result<bool, Error> {
+		self.position = tok.position;
+		if let TokenKind::Close(_) = tok.kind {
+			if self.brackets() < brackets {
+				self.next = State::Op;
+				self.prev = Prev::Close;
+				return Ok(true);
+			}
+		}
+		let Some(index) = self.fns.iter().rposition(|f| f.pre == Order::FnBarrier)
+		else {
+			return Ok(false);
+		};
+		match tok.kind {
+			TokenKind::Comma => {
+				self.fns.truncate(index + 1);
+				self.fns[index].nargs += 1;
+				self.next = State::Val;
+				self.prev = Prev::Arg;
+				Ok(true)
+			},
+			TokenKind::Close(_) => {
+				self.fns.truncate(index + 1);
+				self.next = State::Op;
+				self.prev = Prev::Close;
+				self.eval_apply()?;
+				Ok(true)
+			},
+			_ => Ok(false),
+		}
+	}
+	pub fn error(&self, kind: ErrorKind) -> Error {
+		Error::new(kind, self.position)
+	}
+	pub fn is_empty(&self) -> bool {
+		self.next == State::Val && self.fns.is_empty()
+	}
+	fn parse_val(&mut self, tok: TokenKind) -> Result<(), Error> {
+		// Keyword arguments must be followed by keyword arguments
+		if self.prev == Prev::Arg && !matches!(tok, TokenKind::Var(_) | TokenKind::Close(_)) && self.fns.last().map(|f| f.keywords).unwrap_or(false) {
+			return Err(self.error(ErrorKind::PositionalAfterKeyword));
+		}
+		// Starts another argument after a comma, eg. lazy fns may skip it
+		if self.prev == Prev::Arg && !matches!(tok, TokenKind::Close(_)) {
+			if let Some(func) = self.fns.last_mut().filter(|f| f.nargs > 1).and_then(|f| f.func.as_mut()) {
+				self.sink.next_arg(func).map_err(|kind| Error::new(kind, self.position))?;
+			}
+		}
+		match tok {
+			TokenKind::Unk(_) => {
+				Err(self.error(ErrorKind::InvalidToken))
+			},
+			TokenKind::Lit(val) => {
+				// Push on the value stack
+				self.sink.lit(val, self.position).map_err(|kind| self.error(kind))?;
+				// Followed by an operator
+				self.next = State::Op;
+				Ok(())
+			},
+			#[cfg(feature = "complex")]
+			TokenKind::Imag(val) => {
+				self.sink.imag(val, self.position).map_err(|kind| self.error(kind))?;
+				self.next = State::Op;
+				Ok(())
+			},
+			TokenKind::Op(op) => {
+				// Unary operators have high precedence
+				let desc = op.desc();
+				if desc.unary {
+					if self.prev == Prev::Sign && matches!(op, Operator::Add | Operator::Sub) {
+						self.sink.warn(WarningKind::RepeatedSign, self.position);
+					}
+					let func = Some(self.sink.op(op));
+					self.push(FnVal {
+						func,
+						pre: Order::Unary,
+						nargs: 1,
+						tag: Tag::None,
+						keywords: false,
+						pos: self.position,
+					})?;
+					// Followed by a value
+					self.next = State::Val;
+					Ok(())
+				}
+				else {
+					Err(self.error(ErrorKind::DisallowedUnary))
+				}
+			},
+			TokenKind::Var(name) if self.prev == Prev::Arg => {
+				// The next token is known to tell whether it names the argument
+				if let Some(assign) = self.ahead {
+					self.resolve_key(name, self.position, assign)?;
+					self.next = if assign { State::Assign } else { State::Op };
+					return Ok(());
+				}
+				// Defer until the next token tells whether it names the argument
+				self.key.clear();
+				for &byte in name.as_bytes() {
+					self.key.push(byte).map_err(|kind| self.error(kind))?;
+				}
+				self.next = State::Key(self.position);
+				Ok(())
+			},
+			TokenKind::Var(name) => {
+				// Lookup the symbol variable and push the resulting value
+				self.sink.var(name, self.position).map_err(|kind| self.error(kind))?;
+				// Followed by an operator
+				self.next = State::Op;
+				Ok(())
+			},
+			TokenKind::Open(name) => {
+				let func = Some(self.sink.call(name).map_err(|kind| self.error(kind))?);
+				let pre = Order::FnBarrier; // Very low precedence acts as a barrier
+				let nargs = 1;
+				let tag = Tag::Paren(Paren::Round);
+				let pos = self.position;
+				self.push(FnVal { func, pre, nargs, tag, keywords: false, pos })?;
+				// Followed by its arguments
+				self.next = State::Val;
+				Ok(())
+			},
+			TokenKind::Group(paren) => {
+				// Grouping brackets are an fn barrier around the identity fn
+				let func = Some(self.sink.group());
+				let pre = Order::FnBarrier;
+				let nargs = 1;
+				let tag = Tag::Paren(paren);
+				let pos = self.position;
+				self.push(FnVal { func, pre, nargs, tag, keywords: false, pos })?;
+				// Followed by its contents
+				self.next = State::Val;
+				Ok(())
+			},
+			TokenKind::Comma | TokenKind::Question | TokenKind::Colon => {
+				Err(self.error(ErrorKind::NaExpression))
+			},
+			TokenKind::Semi => {
+				Err(self.error(ErrorKind::MisplacedSemicolon))
+			},
+			TokenKind::Assign => {
+				Err(self.error(ErrorKind::MisplacedAssign))
+			},
+			// Keyword without its value, eg. `log(8, base =)`
+			TokenKind::Close(_) if self.prev == Prev::Assign => {
+				Err(self.error(ErrorKind::NaExpression))
+			},
+			TokenKind::Close(paren) => {
+				// Closes a function call with empty argument list, eg. `rand()`.
+				if self.fns.last().map(|f| f.pre == Order::FnBarrier && f.tag != Tag::Paren(paren)).unwrap_or(false) {
+					Err(self.error(ErrorKind::MismatchedParens))
+				}
+				else if let Some(f) = self.fns.last_mut().filter(|f| f.pre == Order::FnBarrier && f.nargs == 1) {
+					// Call with an empty argument list, the fn decides whether that’s allowed
+					f.nargs = 0;
+					self.eval_apply()?;
+					// Followed by an operator
+					self.next = State::Op;
+					Ok(())
+				}
+				else if let Some(f) = self.fns.last_mut().filter(|f| f.pre == Order::FnBarrier) {
+					// Trailing comma, eg. `max(1, 2,)`, the comma didn’t start another argument
+					f.nargs -= 1;
+					self.eval_apply()?;
+					self.next = State::Op;
+					Ok(())
+				}
+				else {
+					Err(self.error(ErrorKind::NaExpression))
+				}
+			},
+		}
+	}
+	fn parse_op(&mut self, tok: TokenKind) -> Result<(), Error> {
+		match tok {
+			TokenKind::Unk(_) => {
+				Err(self.error(ErrorKind::InvalidToken))
+			},
+			ref lit if is_lit(lit) && self.prev == Prev::Close => {
+				// Insert implicit multiplication token, eg. `(2)3`
+				self.parse_op(TokenKind::Op(Operator::IMul))?;
+				// Retry inserting this token
+				self.parse_val(tok)
+			},
+			// Adjacent literals are most likely a typo, eg. `12 5`
+			TokenKind::Lit(_) => Err(self.error(ErrorKind::ExpectOperator)),
+			#[cfg(feature = "complex")]
+			TokenKind::Imag(_) => Err(self.error(ErrorKind::ExpectOperator)),
+			TokenKind::Op(Operator::Rem) => {
+				// Defer until the next token disambiguates
+				self.next = State::Rem(self.position);
+				Ok(())
+			},
+			TokenKind::Op(Operator::Sqrt) => {
+				// Prefix only operator, insert implicit multiplication token
+				self.parse_op(TokenKind::Op(Operator::IMul))?;
+				// Retry inserting this token
+				self.parse_val(tok)
+			},
+			TokenKind::Op(op @ (Operator::Sqr | Operator::Cube)) => {
+				// Postfix operators apply to the last value right away
+				let func = Some(self.sink.op(op));
+				self.push(FnVal {
+					func,
+					pre: Order::Unary,
+					nargs: 1,
+					tag: Tag::None,
+					keywords: false,
+					pos: self.position,
+				})?;
+				self.eval_apply()
+			},
+			TokenKind::Op(op) => {
+				if op == Operator::IMul {
+					self.sink.warn(WarningKind::ImplicitMul, self.position);
+				}
+				// Get relevant operator descriptor
+				let desc = op.desc();
+				// Evaluate all lower precedence fns
+				match desc.assoc {
+					Assoc::Left => self.eval_ge(desc.pre)?,
+					Assoc::Right => self.eval_gt(desc.pre)?,
+					// Assoc::None => return Err(self.error(ErrorKind::InternalError)),
+				};
+				// Push operator as fn, always takes two arguments
+				let func = Some(self.sink.op(op));
+				self.push(FnVal {
+					func,
+					pre: desc.pre,
+					nargs: 2,
+					tag: Tag::None,
+					keywords: false,
+					pos: self.position,
+				})?;
+				// Followed by a value
+				self.next = State::Val;
+				Ok(())
+			},
+			TokenKind::Var(_) => {
+				// Insert implicit multiplication token
+				self.parse_op(TokenKind::Op(Operator::IMul))?;
+				// Retry inserting this token
+				self.parse_val(tok)
+			},
+			TokenKind::Open(_) | TokenKind::Group(_) => {
+				// Insert implicit multiplication token
+				self.parse_op(TokenKind::Op(Operator::IMul))?;
+				// Retry inserting this token
+				self.parse_val(tok)
+			},
+			TokenKind::Comma => {
+				// Eval until an fn barier
+				self.eval_gt(Order::FnBarrier)?;
+				// Increment nargs for that fn
+				let err = self.error(ErrorKind::MisplacedComma);
+				let f = self.fns.last_mut().ok_or(err)?;
+				if f.nargs >= self.limits.max_args {
+					return Err(self.error(ErrorKind::LimitExceeded));
+				}
+				f.nargs += 1;
+				// Followed by a value
+				self.next = State::Val;
+				Ok(())
+			},
+			TokenKind::Close(paren) => {
+				// Eval everything until the fn barrier and push past it
+				self.eval_gt(Order::FnBarrier)?;
+				// The closing bracket must match the opening bracket
+				if self.fns.last().map(|f| f.tag != Tag::Paren(paren)).unwrap_or(false) {
+					return Err(self.error(ErrorKind::MismatchedParens));
+				}
+				self.eval_apply()?;
+				// Followed by an operator
+				self.next = State::Op;
+				Ok(())
+			},
+			TokenKind::Semi => {
+				Err(self.error(ErrorKind::MisplacedSemicolon))
+			},
+			TokenKind::Assign => {
+				Err(self.error(ErrorKind::MisplacedAssign))
+			},
+			TokenKind::Question => {
+				// Evaluate the condition, nested conditionals are right associative
+				self.eval_gt(Order::Cond)?;
+				self.sink.cond().map_err(|kind| self.error(kind))?;
+				self.push(FnVal {
+					func: None,
+					pre: Order::Cond,
+					nargs: 0,
+					tag: Tag::Then,
+					keywords: false,
+					pos: self.position,
+				})?;
+				// Followed by the then branch
+				self.next = State::Val;
+				Ok(())
+			},
+			TokenKind::Colon => {
+				// Finish the then branch including any nested conditionals, eg. `a ? b ? c : d : e`
+				self.eval_gt(Order::Cond)?;
+				while self.fns.last().map(|f| f.tag == Tag::Else).unwrap_or(false) {
+					self.eval_apply()?;
+				}
+				match self.fns.last_mut() {
+					Some(f) if f.tag == Tag::Then => f.tag = Tag::Else,
+					_ => return Err(self.error(ErrorKind::UnbalancedConditional)),
+				}
+				self.sink.cond_else().map_err(|kind| self.error(kind))?;
+				// Followed by the else branch
+				self.next = State::Val;
+				Ok(())
+			},
+		}
+	}
+	fn parse_rem(&mut self, tok: TokenKind, pos: usize) -> Result<(), Error> {
+		match tok {
+			// Followed by a value, the `%` was the remainder operator
+			ref val if is_lit(val) || matches!(val, TokenKind::Unk(_) | TokenKind::Var(_) | TokenKind::Open(_) | TokenKind::Group(_)) => {
+				let desc = Operator::Rem.desc();
+				self.eval_ge(desc.pre)?;
+				let func = Some(self.sink.op(Operator::Rem));
+				self.push(FnVal {
+					func,
+					pre: desc.pre,
+					nargs: 2,
+					tag: Tag::None,
+					keywords: false,
+					pos,
+				})?;
+				self.next = State::Val;
+				self.parse_val(tok)
+			},
+			// Followed by an operator-like thing, the `%` was a postfix percentage
+			_ => {
+				self.eval_percent(pos)?;
+				self.next = State::Op;
+				self.parse_op(tok)
+			},
+		}
+	}
+	fn parse_key(&mut self, tok: TokenKind, pos: usize) -> Result<(), Error> {
+		let assign = matches!(tok, TokenKind::Assign);
+		// Taken while resolved, keeping its allocation
+		let key = std::mem::take(&mut self.key);
+		let result = key_name(&key, pos).and_then(|name| self.resolve_key(name, pos, assign));
+		self.key = key;
+		result?;
+		if assign {
+			self.next = State::Val;
+			Ok(())
+		}
+		else {
+			self.next = State::Op;
+			self.parse_op(tok)
+		}
+	}
+	// Expects the `=` of the keyword resolved ahead.
+	fn parse_assign(&mut self, tok: TokenKind) -> Result<(), Error> {
+		match tok {
+			TokenKind::Assign => {
+				self.next = State::Val;
+				Ok(())
+			},
+			_ => Err(self.error(ErrorKind::InternalError)),
+		}
+	}
+	// Resolves the variable starting an argument as a keyword if followed by a `=`, otherwise as a positional argument.
+	fn resolve_key(&mut self, name: &str, pos: usize, assign: bool) -> Result<(), Error> {
+		let err = Error::new(ErrorKind::InternalError, pos);
+		let f = self.fns.last_mut().ok_or(err)?;
+		if assign {
+			let func = f.func.as_mut().ok_or(err)?;
+			self.sink.keyword(func, f.nargs - 1, name, pos).map_err(|kind| Error::new(kind, pos))?;
+			f.keywords = true;
+		}
+		else {
+			if f.keywords {
+				return Err(Error::new(ErrorKind::PositionalAfterKeyword, pos));
+			}
+			self.sink.var(name, pos).map_err(|kind| Error::new(kind, pos))?;
+		}
+		Ok(())
+	}
+	// Push a pending fn.
+	fn push(&mut self, f: FnVal<S::Fn>) -> Result<(), Error> {
+		if self.fns.len() >= self.limits.max_depth {
+			return Err(self.error(ErrorKind::LimitExceeded));
+		}
+		self.fns.push(f).map_err(|kind| self.error(kind))
+	}
+	// Apply the postfix percentage to the last value.
+	fn eval_percent(&mut self, pos: usize) -> Result<(), Error> {
+		let func = Some(self.sink.percent());
+		self.push(FnVal {
+			func,
+			pre: Order::Unary,
+			nargs: 1,
+			tag: Tag::None,
+			keywords: false,
+			pos,
+		})?;
+		self.eval_apply()
+	}
+	// Eval all fns with higher or equal precedence.
+	fn eval_ge(&mut self, pre: Order) -> Result<(), Error> {
+		while self.fns.last().map(|f| f.pre >= pre).unwrap_or(false) {
+			self.eval_apply()?;
+		}
+		Ok(())
+	}
+	// Eval all fns with strictly higher precedence.
+	fn eval_gt(&mut self, pre: Order) -> Result<(), Error> {
+		while self.fns.last().map(|f| f.pre > pre).unwrap_or(false) {
+			self.eval_apply()?;
+		}
+		Ok(())
+	}
+	// Pop and eval a single fn.
+	fn eval_apply(&mut self) -> Result<(), Error> {
+		if let Some(f) = self.fns.pop() {
+			let result = match (f.tag, f.func) {
+				// Never reached its `:`
+				(Tag::Then, _) => Err(ErrorKind::UnbalancedConditional),
+				(Tag::Else, _) => self.sink.cond_end(f.pos),
+				(_, Some(func)) => self.sink.apply(func, f.nargs, f.pos),
+				(_, None) => Err(ErrorKind::InternalError),
+			};
+			result.map_err(|kind| Error::new(kind, f.pos))
+		}
+		else {
+			// You tried to apply an fn when there are no more fns to apply
+			Err(self.error(ErrorKind::UnbalancedParens))
+		}
+	}
+}
+
+//----------------------------------------------------------------
+
+// Whether the token is a literal, imaginary literals only exist with the complex feature.
+fn is_lit(tok: &TokenKind) -> bool {
+	match tok {
+		TokenKind::Lit(_) => true,
+		#[cfg(feature = "complex")]
+		TokenKind::Imag(_) => true,
+		_ => false,
+	}
+}
+
+// Name of the variable which may be a keyword, the bytes were copied from a str.
+fn key_name(key: &[u8], pos: usize) -> Result<&str, Error> {
+	std::str::from_utf8(key).map_err(|_| Error::new(ErrorKind::InternalError, pos))
+}
+
+// Resolves the keyword naming the argument at the index to the index of its parameter.
+// The keywords of the fn resolved so far are given, the arguments before them are positional.
+pub(crate) fn resolve_keyword(params: &[&str], name: &str, index: usize, keys: &[usize]) -> Result<usize, ErrorKind> {
+	let param = params.iter().position(|&param| param == name).ok_or(ErrorKind::UnknownKeyword)?;
+	let npos = index.checked_sub(keys.len()).ok_or(ErrorKind::InternalError)?;
+	if param < npos || keys.contains(&param) {
+		return Err(ErrorKind::DuplicateKeyword);
+	}
+	Ok(param)
+}
+
+// Moves the keyword arguments at the end of the arguments to their parameters by swapping pairs of arguments.
+pub(crate) fn place_keywords(nargs: usize, keys: &mut [usize], mut swap: impl FnMut(usize, usize)) -> Result<(), ErrorKind> {
+	let npos = nargs.checked_sub(keys.len()).ok_or(ErrorKind::InternalError)?;
+	// Arguments missing before a keyword argument, eg. `sub(y = 1)`
+	if keys.iter().any(|&param| param >= nargs) {
+		return Err(ErrorKind::BadArgument);
+	}
+	for i in 0..keys.len() {
+		while keys[i] != npos + i {
+			let param = keys[i];
+			swap(npos + i, param);
+			keys.swap(i, param - npos);
+		}
+	}
+	Ok(())
+}
@@ -0,0 +1,431 @@
+use crate::*;
+use crate::parser::{self, Parser, Sink};
+use crate::storage::Heap;
+
+#[derive(Copy, Clone, Debug)]
+enum Instr {
+	Lit(Value),
+	// Index into the variables
+	Var(usize),
+	// Index into the names looked up in the environment when evaluated
+	Load(usize),
+	// Applies the fn to this many values on the stack
+	Call(BuiltinFn, usize),
+	// Swaps the values at these distances from the top of the stack
+	Swap(usize, usize),
+	// Pops the argument of a lazy fn and jumps to the instruction with its result if it decides the result
+	Lazy(bool, usize),
+	// Pops the condition and jumps to the instruction if false
+	JumpIfNot(usize),
+	Jump(usize),
+}
+
+/// Compiled expression.
+///
+/// Functions and pure constants such as `pi` are resolved when compiled.
+/// Other values of the environment such as `ans` are looked up when evaluated,
+/// names not found in the environment are variables provided when evaluated.
+#[derive(Clone)]
+pub struct Program<'a> {
+	env: &'a dyn Env,
+	instrs: Vec<Instr>,
+	names: Vec<String>,
+	// Names looked up in the environment
+	loads: Vec<String>,
+	// Maximum depth of the value stack
+	depth: usize,
+}
+
+impl<'a> Program<'a> {
+	/// Evaluates the program with the values of its variables.
+	///
+	/// The values are given in the order of [`var_names`](Self::var_names).
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let program = pupil::compile(&env, "x^2 + y * pi").unwrap();
+	/// assert_eq!(program.var_names(), ["x", "y"]);
+	/// assert_eq!(program.eval(&[3.0, 0.0]), Ok(9.0));
+	/// assert_eq!(program.eval(&[2.0, 2.0]), Ok(4.0 + std::f64::consts::TAU));
+	/// ```
+	pub fn eval(&self, vars: &[Value]) -> Result<Value, ErrorKind> {
+		if vars.len() != self.names.len() {
+			return Err(ErrorKind::BadArgument);
+		}
+		self.run(&mut Vec::with_capacity(self.depth), |index| vars[index])
+	}
+	/// Evaluates the program for every row of the columns of its variables.
+	///
+	/// The columns are given in the order of [`var_names`](Self::var_names) and must have as many rows as the output,
+	/// otherwise it fails at row 0 with [`ErrorKind::BadArgument`].
+	/// Stops at the first row which fails and returns its index with the error, the rows before have their results.
+	///
+	/// ```
+	/// let env = pupil::BasicEnv::default();
+	/// let program = pupil::compile(&env, "x * y + 1").unwrap();
+	/// let mut out = [0.0; 3];
+	/// program.eval_batch(&[&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]], &mut out).unwrap();
+	/// assert_eq!(out, [5.0, 11.0, 19.0]);
+	/// ```
+	pub fn eval_batch(&self, inputs: &[&[Value]], out: &mut [Value]) -> Result<(), (usize, ErrorKind)> {
+		if inputs.len() != self.names.len() || inputs.iter().any(|column| column.len() != out.len()) {
+			return Err((0, ErrorKind::BadArgument));
+		}
+		// Reuse the stack for every row
+		let mut stack = Vec::with_capacity(self.depth);
+		for (row, result) in out.iter_mut().enumerate() {
+			stack.clear();
+			*result = self.run(&mut stack, |index| inputs[index][row]).map_err(|kind| (row, kind))?;
+		}
+		Ok(())
+	}
+	// Runs the instructions on the empty stack with the value of each variable by index.
+	fn run(&self, stack: &mut Vec<Value>, var: impl Fn(usize) -> Value) -> Result<Value, ErrorKind> {
+		let mut pc = 0;
+		while let Some(&instr) = self.instrs.get(pc) {
+			pc += 1;
+			match instr {
+				Instr::Lit(val) => stack.push(val),
+				Instr::Var(index) => stack.push(var(index)),
+				Instr::Load(index) => {
+					let name = self.loads.get(index).ok_or(ErrorKind::InternalError)?;
+					stack.push(self.env.get_value(name)?);
+				},
+				Instr::Call(pfn, nargs) => {
+					let start = stack.len().checked_sub(nargs).ok_or(ErrorKind::InternalError)?;
+					let result = pfn(self.env, &mut stack[start..])?;
+					stack.truncate(start);
+					stack.push(result);
+				},
+				Instr::Swap(i, j) => {
+					let len = stack.len();
+					match (len.checked_sub(i), len.checked_sub(j)) {
+						(Some(i), Some(j)) => stack.swap(i, j),
+						_ => return Err(ErrorKind::InternalError),
+					}
+				},
+				Instr::Lazy(decides, target) => {
+					let arg = stack.pop().ok_or(ErrorKind::InternalError)?;
+					if crate::builtins::truthy(arg) == decides {
+						stack.push(if decides { 1.0 } else { 0.0 });
+						pc = target;
+					}
+				},
+				Instr::JumpIfNot(target) => {
+					let cond = stack.pop().ok_or(ErrorKind::InternalError)?;
+					if !crate::builtins::truthy(cond) {
+						pc = target;
+					}
+				},
+				Instr::Jump(target) => pc = target,
+			}
+		}
+		stack.pop().ok_or(ErrorKind::InternalError)
+	}
+	/// Returns the names of the variables in order of first appearance.
+	pub fn var_names(&self) -> &[String] {
+		&self.names
+	}
+}
+
+/// Compiles the input for repeated evaluation.
+///
+/// See [`Program`] for how names are resolved.
+pub fn compile<'a>(env: &'a dyn Env, input: &str) -> Result<Program<'a>, Error> {
+	let mut parser = Parser::new(Compile {
+		env,
+		instrs: Vec::new(),
+		names: Vec::new(),
+		loads: Vec::new(),
+		jumps: Vec::new(),
+		keys: Vec::new(),
+		depth: 0,
+		max_depth: 0,
+	});
+	for tok in tokenize(input) {
+		parser.parse(tok)?;
+	}
+	parser.finish(input.len())?;
+	let Compile { instrs, names, loads, max_depth, .. } = parser.sink;
+	Ok(Program { env, instrs, names, loads, depth: max_depth })
+}
+
+//----------------------------------------------------------------
+
+// Compiles the parsed expression to instructions.
+struct Compile<'a> {
+	env: &'a dyn Env,
+	instrs: Vec<Instr>,
+	names: Vec<String>,
+	loads: Vec<String>,
+	// Jump instructions of the nested conditionals waiting for their target
+	jumps: Vec<usize>,
+	// Parameters named by the keyword arguments of the pending fns
+	keys: Vec<usize>,
+	depth: usize,
+	max_depth: usize,
+}
+
+// Fn waiting for its arguments.
+#[derive(Copy, Clone)]
+struct CompileFn<'a> {
+	pfn: BuiltinFn,
+	// Number of arguments accepted if known
+	arity: Option<Arity>,
+	// Parameter names of calls, none for operators
+	params: Option<&'a [&'a str]>,
+	// Number of keyword arguments
+	keywords: usize,
+	// Lazy fns know which arguments decide the result
+	lazy: Option<bool>,
+	// Number of arguments popped by lazy jumps
+	popped: usize,
+}
+
+impl<'a> CompileFn<'a> {
+	fn new(pfn: BuiltinFn) -> CompileFn<'a> {
+		CompileFn { pfn, arity: None, params: None, keywords: 0, lazy: None, popped: 0 }
+	}
+}
+
+// Returns the index of the name, appended if not yet known.
+fn intern(names: &mut Vec<String>, name: &str) -> usize {
+	match names.iter().position(|known| known == name) {
+		Some(index) => index,
+		None => {
+			names.push(name.into());
+			names.len() - 1
+		},
+	}
+}
+
+impl<'a> Compile<'a> {
+	fn push(&mut self, instr: Instr) {
+		self.depth += 1;
+		self.max_depth = usize::max(self.max_depth, self.depth);
+		self.instrs.push(instr);
+	}
+	// Jumps to the next instruction.
+	fn patch(&mut self) -> Result<(), ErrorKind> {
+		let target = self.instrs.len();
+		match self.jumps.pop().and_then(|index| self.instrs.get_mut(index)) {
+			Some(Instr::JumpIfNot(jump) | Instr::Jump(jump) | Instr::Lazy(_, jump)) => *jump = target,
+			_ => return Err(ErrorKind::InternalError),
+		}
+		Ok(())
+	}
+}
+
+impl<'a> Sink for Compile<'a> {
+	// Grouping brackets have no fn
+	type Fn = Option<CompileFn<'a>>;
+	type Alloc = Heap;
+	fn lit(&mut self, val: Value, _pos: usize) -> Result<(), ErrorKind> {
+		self.push(Instr::Lit(val));
+		Ok(())
+	}
+	fn var(&mut self, name: &str, _pos: usize) -> Result<(), ErrorKind> {
+		let instr = match self.env.get_value(name) {
+			// Only pure constants are evaluated ahead of time
+			Ok(val) if self.env.is_pure(name) => Instr::Lit(val),
+			Ok(_) => Instr::Load(intern(&mut self.loads, name)),
+			Err(ErrorKind::EnvErrorNotFound) => Instr::Var(intern(&mut self.names, name)),
+			Err(kind) => return Err(kind),
+		};
+		self.push(instr);
+		Ok(())
+	}
+	fn call(&mut self, name: &str) -> Result<Self::Fn, ErrorKind> {
+		// Parentheses without a name are for grouping
+		if name.is_empty() {
+			return Ok(None);
+		}
+		Ok(Some(CompileFn {
+			pfn: self.env.builtin(name)?,
+			arity: self.env.arity(name),
+			params: Some(self.env.params(name).unwrap_or(&[])),
+			keywords: 0,
+			lazy: crate::builtins::lazy(name),
+			popped: 0,
+		}))
+	}
+	fn op(&mut self, op: Operator) -> Self::Fn {
+		Some(CompileFn::new(op.desc().pfn))
+	}
+	fn percent(&mut self) -> Self::Fn {
+		Some(CompileFn::new(crate::builtins::builtin_percent))
+	}
+	fn group(&mut self) -> Self::Fn {
+		// Grouping brackets compile to nothing
+		None
+	}
+	fn apply(&mut self, f: Self::Fn, nargs: usize, _pos: usize) -> Result<(), ErrorKind> {
+		match f {
+			Some(f) => {
+				// Wrong number of arguments is a compile error
+				if let Some(arity) = f.arity {
+					arity.check(nargs)?;
+				}
+				// Keyword arguments are moved to their parameters before the call
+				let keys = self.keys.len().checked_sub(f.keywords).ok_or(ErrorKind::InternalError)?;
+				let mut keys = self.keys.split_off(keys);
+				let instrs = &mut self.instrs;
+				parser::place_keywords(nargs, &mut keys, |i, j| instrs.push(Instr::Swap(nargs - i, nargs - j)))?;
+				// Lazy fns are called with their last argument unless decided by a lazy jump
+				let nargs = nargs - f.popped;
+				self.depth -= nargs;
+				self.push(Instr::Call(f.pfn, nargs));
+				for _ in 0..f.popped {
+					self.patch()?;
+				}
+			},
+			None if nargs != 1 => return Err(ErrorKind::BadArgument),
+			None => (),
+		}
+		Ok(())
+	}
+	fn keyword(&mut self, f: &mut Self::Fn, index: usize, name: &str, _pos: usize) -> Result<(), ErrorKind> {
+		let f = f.as_mut().ok_or(ErrorKind::MisplacedAssign)?;
+		let params = f.params.ok_or(ErrorKind::MisplacedAssign)?;
+		let keys = self.keys.len().checked_sub(f.keywords).ok_or(ErrorKind::InternalError)?;
+		let param = parser::resolve_keyword(params, name, index, &self.keys[keys..])?;
+		self.keys.push(param);
+		f.keywords += 1;
+		Ok(())
+	}
+	fn next_arg(&mut self, f: &mut Self::Fn) -> Result<(), ErrorKind> {
+		if let Some(f) = f.as_mut() {
+			if let Some(decides) = f.lazy {
+				self.depth -= 1;
+				self.jumps.push(self.instrs.len());
+				self.instrs.push(Instr::Lazy(decides, 0));
+				f.popped += 1;
+			}
+		}
+		Ok(())
+	}
+	fn warn(&mut self, _kind: WarningKind, _pos: usize) {}
+	fn cond(&mut self) -> Result<(), ErrorKind> {
+		// The condition is popped by the jump
+		self.depth -= 1;
+		self.jumps.push(self.instrs.len());
+		self.instrs.push(Instr::JumpIfNot(0));
+		Ok(())
+	}
+	fn cond_else(&mut self) -> Result<(), ErrorKind> {
+		// The else branch starts where the then branch started
+		self.depth -= 1;
+		let jump = self.instrs.len();
+		self.instrs.push(Instr::Jump(0));
+		self.patch()?;
+		self.jumps.push(jump);
+		Ok(())
+	}
+	fn cond_end(&mut self, _pos: usize) -> Result<(), ErrorKind> {
+		self.patch()
+	}
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn program() {
+	let env = crate::BasicEnv::default();
+	let program = compile(&env, "max(a, b, 1) * -c + a").unwrap();
+	assert_eq!(program.var_names(), ["a", "b", "c"]);
+	assert_eq!(program.eval(&[1.0, 2.0, 3.0]), Ok(-5.0));
+	assert_eq!(program.eval(&[5.0, 2.0, 0.5]), Ok(2.5));
+	assert_eq!(program.eval(&[1.0, 2.0]), Err(ErrorKind::BadArgument));
+	// Matches the regular evaluation
+	let inputs = [
+		"2 + 3 * 4",
+		"(1 + 2) * [3] - {4}",
+		"√9 + 3² + 2³ + π - ans",
+		"120 * 15% + 7 % 4 // 2",
+		"0 ? 1 : 1 ? 2 ? 3 : 4 : 5",
+		"1 ? 2 : 1 / 0",
+		"0 ? ln(0) : 2",
+		"mean(1, 0 ? 5 : 6, [1 ? 7 : 8]) || 0",
+	];
+	for input in inputs {
+		let program = compile(&env, input).unwrap();
+		assert_eq!(program.eval(&[]).ok(), crate::eval(&env, input).ok(), "{:?}", input);
+	}
+	// Compile errors
+	assert_eq!(compile(&env, "2 * mean").map(|_| ()), Err(Error::new(ErrorKind::EnvErrorBuiltinFn, 4)));
+	assert_eq!(compile(&env, "0 ? 2 : log(1)").map(|_| ()), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::exactly(2), got: 1 }, 8)));
+	assert_eq!(compile(&env, "hello(x)").map(|_| ()), Err(Error::new(ErrorKind::EnvErrorNotFound, 0)));
+	assert_eq!(compile(&env, "1 + add()").map(|_| ()), Err(Error::new(ErrorKind::ArgumentCount { expected: Arity::at_least(1), got: 0 }, 4)));
+	assert_eq!(compile(&env, "(1, 2)").map(|_| ()), Err(Error::new(ErrorKind::BadArgument, 0)));
+	assert_eq!(compile(&env, "[1, 2]").map(|_| ()), Err(Error::new(ErrorKind::BadArgument, 0)));
+	assert_eq!(compile(&env, "x +").map(|_| ()), Err(Error::new(ErrorKind::UnfinishedExpression, 3)));
+}
+#[test]
+fn batch() {
+	// Column of the variable x
+	struct Column<'a>(crate::BasicEnv<'a>, Value);
+	impl<'a> Env for Column<'a> {
+		fn builtin(&self, name: &str) -> Result<BuiltinFn, ErrorKind> {
+			self.0.builtin(name)
+		}
+		fn get_value(&self, name: &str) -> Result<Value, ErrorKind> {
+			match name {
+				"x" => Ok(self.1),
+				_ => self.0.get_value(name),
+			}
+		}
+		fn set_value(&mut self, name: &str, value: Value) -> Result<(), ErrorKind> {
+			self.0.set_value(name, value)
+		}
+	}
+	let env = crate::BasicEnv::default();
+	let xs = (0..1000).map(|i| i as Value * 0.37 - 100.0).collect::<Vec<_>>();
+	let mut out = vec![0.0; xs.len()];
+	for input in ["x^2 / 3 + sin(x) * 2 - max(x, 0.5)", "x % 2 ? sqrt(x) : -x", "and(x, 1 / x) + x % 7", "pi"] {
+		let program = compile(&env, input).unwrap();
+		let columns: &[&[Value]] = if program.var_names().is_empty() { &[] } else { &[&xs] };
+		program.eval_batch(columns, &mut out).unwrap();
+		for (&x, &y) in xs.iter().zip(&out) {
+			assert_eq!(y.to_bits(), crate::eval(&Column(crate::BasicEnv::default(), x), input).unwrap().to_bits(), "{:?} {}", input, x);
+		}
+	}
+	// Mismatched columns
+	let program = compile(&env, "x + y").unwrap();
+	assert_eq!(program.eval_batch(&[&xs, &xs[1..]], &mut out), Err((0, ErrorKind::BadArgument)));
+	assert_eq!(program.eval_batch(&[&xs], &mut out), Err((0, ErrorKind::BadArgument)));
+	assert_eq!(program.eval_batch(&[&[], &[]], &mut []), Ok(()));
+	// Stops at the failing row
+	let program = compile(&env, "fac(x)").unwrap();
+	let mut out = [0.0; 3];
+	assert_eq!(program.eval_batch(&[&[3.0, 0.5, 2.0]], &mut out), Err((1, ErrorKind::BadArgument)));
+	assert_eq!(out, [6.0, 0.0, 0.0]);
+}
+#[test]
+fn loads() {
+	use std::cell::Cell;
+	// Answer which changes while the program borrows the env
+	struct Answer<'a>(crate::BasicEnv<'a>, Cell<Value>);
+	impl<'a> Env for Answer<'a> {
+		fn builtin(&self, name: &str) -> Result<BuiltinFn, ErrorKind> {
+			self.0.builtin(name)
+		}
+		fn get_value(&self, name: &str) -> Result<Value, ErrorKind> {
+			match name {
+				"ans" => Ok(self.1.get()),
+				_ => self.0.get_value(name),
+			}
+		}
+		fn set_value(&mut self, name: &str, value: Value) -> Result<(), ErrorKind> {
+			self.0.set_value(name, value)
+		}
+		fn is_pure(&self, name: &str) -> bool {
+			self.0.is_pure(name)
+		}
+	}
+	let env = Answer(crate::BasicEnv::default(), Cell::new(1.0));
+	let program = compile(&env, "ans * x + pi").unwrap();
+	assert_eq!(program.var_names(), ["x"]);
+	assert_eq!(program.eval(&[2.0]), Ok(2.0 + std::f64::consts::PI));
+	env.1.set(3.0);
+	assert_eq!(program.eval(&[2.0]), Ok(6.0 + std::f64::consts::PI));
+}
@@ -0,0 +1,855 @@
+/*!
+Interactive read-eval-print loop.
+
+Shared by the `pupil` binary, requires the `repl` feature.
+*/
+
+use std::borrow::Cow;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+use crate::*;
+
+/// Environment of an interactive session.
+///
+/// The default builtins with variables assigned by the user, see [`VarEnv`].
+pub type Session<'a> = VarEnv<'a, Value>;
+
+/// What a line of input did.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reply {
+	/// The line was empty.
+	Empty,
+	/// Evaluated an expression, which is also the new `ans`.
+	Value(Value),
+	/// Assigned a variable, eg. `x = 41`.
+	Assign(String, Value),
+	/// Listed the variables with `:vars`.
+	Vars(Vec<(String, Value)>),
+	/// Removed all variables with `:clear`.
+	Clear,
+	/// Help text for `:help` or `:help name`.
+	Help(String),
+}
+
+impl<'a> Session<'a> {
+	/// Executes a line of input.
+	///
+	/// Either an expression, an assignment `name = expr` or a command `:vars`, `:clear`, `:help` or `:help name`.
+	/// Positions of errors and warnings are relative to the line.
+	pub fn exec(&mut self, line: &str) -> (Result<Reply, Error>, Vec<Warning>) {
+		let cmd = line.trim();
+		match cmd {
+			"" => return (Ok(Reply::Empty), Vec::new()),
+			":vars" => return (Ok(Reply::Vars(self.vars.iter().map(|(name, &val)| (name.clone(), val)).collect())), Vec::new()),
+			":clear" => {
+				self.vars.clear();
+				return (Ok(Reply::Clear), Vec::new());
+			},
+			":help" => return (Ok(Reply::Help(help(self.env.builtins))), Vec::new()),
+			_ if cmd.starts_with(":help ") => {
+				let name = cmd[":help ".len()..].trim();
+				let result = help_topic(self.env.builtins, name)
+					.map(Reply::Help)
+					.ok_or_else(|| Error::new(ErrorKind::EnvErrorNotFound, line.rfind(name).unwrap_or(0)));
+				return (result, Vec::new());
+			},
+			_ if cmd.starts_with(':') => {
+				let position = line.len() - line.trim_start().len();
+				return (Err(Error::new(ErrorKind::InvalidToken, position)), Vec::new());
+			},
+			_ => (),
+		}
+		let Some((name, offset)) = split_assign(line) else {
+			// Positions in the continued line are past the injected `ans `
+			let input = continue_with_ans(line);
+			let prefix = input.len() - line.len();
+			let (result, mut warnings) = eval_with_warnings(self, &input);
+			for warning in &mut warnings {
+				warning.position = warning.position.saturating_sub(prefix);
+			}
+			let result = result.map_err(|e| Error::new(e.kind, e.position.saturating_sub(prefix)));
+			if let Ok(val) = result {
+				self.env.ans = val;
+			}
+			return (result.map(Reply::Value), warnings);
+		};
+		if self.env.reserves(name) {
+			let position = line.len() - line.trim_start().len();
+			return (Err(Error::new(ErrorKind::EnvErrorBuiltinFn, position)), Vec::new());
+		}
+		let (result, mut warnings) = eval_with_warnings(self, &line[offset..]);
+		for warning in &mut warnings {
+			warning.position += offset;
+		}
+		let result = result
+			.map_err(|e| Error::new(e.kind, e.position + offset))
+			.and_then(|val| {
+				self.set_value(name, val).map_err(|kind| Error::new(kind, 0))?;
+				Ok(Reply::Assign(name.to_string(), val))
+			});
+		(result, warnings)
+	}
+	/// Writes the variables as `name = value` lines, see [`load`](Session::load).
+	///
+	/// The values are written with full precision so they are restored exactly.
+	pub fn save(&self, out: &mut dyn Write) -> io::Result<()> {
+		for (name, val) in &self.vars {
+			writeln!(out, "{} = {}", name, val)?;
+		}
+		Ok(())
+	}
+	/// Executes every line of the input, returns the errors with their one-based line numbers.
+	///
+	/// Restores the variables written by [`save`](Session::save), blank lines and comments starting with `#` are skipped.
+	pub fn load(&mut self, input: &mut dyn BufRead) -> io::Result<Vec<(usize, Error)>> {
+		let mut errors = Vec::new();
+		for (index, line) in input.lines().enumerate() {
+			let line = line?;
+			if line.trim_start().starts_with('#') {
+				continue;
+			}
+			if let Err(e) = self.exec(&line).0 {
+				errors.push((index + 1, e));
+			}
+		}
+		Ok(errors)
+	}
+}
+
+/// Continues the previous answer when the input starts with a binary operator, eg. `* 2` becomes `ans * 2`.
+///
+/// Operators which are only binary or postfix always continue, eg. `* / % ^ && || ²`.
+/// The `+` and `-` signs only continue when followed by whitespace, `- 2` is `ans - 2` while `-2` stays negative.
+///
+/// ```
+/// use pupil::repl::continue_with_ans;
+/// assert_eq!(continue_with_ans("* 2"), "ans * 2");
+/// assert_eq!(continue_with_ans("-2"), "-2");
+/// ```
+pub fn continue_with_ans(input: &str) -> Cow<'_, str> {
+	let Some(Token { kind: TokenKind::Op(op), position }) = tokenize(input).next() else {
+		return Cow::Borrowed(input);
+	};
+	let continues = match op {
+		Operator::Add | Operator::Sub => input[position..].chars().nth(1).is_some_and(char::is_whitespace),
+		_ => !op.desc().unary,
+	};
+	if continues {
+		Cow::Owned(format!("ans {}", input))
+	}
+	else {
+		Cow::Borrowed(input)
+	}
+}
+
+// Splits `name = expr` into the name and the offset of the expression.
+fn split_assign(line: &str) -> Option<(&str, usize)> {
+	let (lhs, _) = line.split_once('=')?;
+	let name = lhs.trim();
+	if !is_name(name) {
+		return None;
+	}
+	Some((name, lhs.len() + 1))
+}
+
+// Variable names start with a letter or underscore followed by letters, digits or underscores.
+fn is_name(name: &str) -> bool {
+	let mut chars = name.chars();
+	matches!(chars.next(), Some(first) if first.is_alphabetic() || first == '_') && chars.all(|chr| chr.is_alphanumeric() || chr == '_')
+}
+
+/// Read-eval-print loop over a session.
+pub struct Repl<'a> {
+	pub session: Session<'a>,
+	/// Prints prompts when at a console.
+	pub interactive: bool,
+	/// How the values are displayed, the precision is changed with `:precision N` or `:precision full`.
+	pub format: FormatOptions,
+	/// Prints the results and errors of expressions and assignments and the variables of `:vars` as JSON, requires the `json` feature.
+	pub json: bool,
+	/// Colors the diagnostics and dims the warnings.
+	pub color: ColorMode,
+	/// Prints how long each line took, toggled with `:time`.
+	pub time: bool,
+	/// Number of evaluations of `:bench expr`.
+	pub iterations: usize,
+}
+
+/// Default number of evaluations of `:bench expr`.
+pub const BENCH_ITERATIONS: usize = 10000;
+
+impl<'a> Repl<'a> {
+	/// Creates a loop displaying the shortest representation of the values.
+	pub fn new(session: Session<'a>, interactive: bool) -> Repl<'a> {
+		Repl { session, interactive, format: FormatOptions::default(), json: false, color: ColorMode::Never, time: false, iterations: BENCH_ITERATIONS }
+	}
+	/// Executes a line of input and prints the reply.
+	pub fn line(&mut self, line: &str, out: &mut dyn Write, err: &mut dyn Write) -> io::Result<()> {
+		if let Some(result) = self.command(line.trim(), out, err) {
+			return result;
+		}
+		let start = Instant::now();
+		let (result, warnings) = self.session.exec(line);
+		let elapsed = start.elapsed();
+		print_warnings(err, &warnings, self.color == ColorMode::Always)?;
+		if let Some(json) = self.json_reply(line, &result) {
+			return write!(out, "{}", json);
+		}
+		match result {
+			Ok(reply) => self.reply(reply, out)?,
+			Err(e) => writeln!(err, "{}", e.diagnostic_colored(line, self.color))?,
+		}
+		if self.time {
+			writeln!(out, "time: {:?}", elapsed)?;
+		}
+		Ok(())
+	}
+	/// Evaluates every line of the input, returns the number of lines which failed.
+	///
+	/// Prints `line: result` for expressions and assignments, errors and warnings print `line:col: message`.
+	/// Blank lines and comments starting with `#` are skipped, the session carries over between lines.
+	pub fn batch(&mut self, input: &mut dyn BufRead, out: &mut dyn Write, err: &mut dyn Write) -> io::Result<usize> {
+		let mut failed = 0;
+		for (index, line) in input.lines().enumerate() {
+			let line = line?;
+			let number = index + 1;
+			let cmd = line.trim();
+			if cmd.is_empty() || cmd.starts_with('#') {
+				continue;
+			}
+			if let Some(result) = self.command(cmd, out, err) {
+				result?;
+				continue;
+			}
+			let start = Instant::now();
+			let (result, warnings) = self.session.exec(&line);
+			let elapsed = start.elapsed();
+			let column = |position| LineMap::new(&line).position_to_line_col(position).1;
+			for warning in &warnings {
+				writeln!(err, "{}:{}: warning: {}", number, column(warning.position), warning.message)?;
+			}
+			if let Some(json) = self.json_reply(&line, &result) {
+				failed += result.is_err() as usize;
+				write!(out, "{}", json)?;
+				continue;
+			}
+			match result {
+				Ok(reply @ (Reply::Value(_) | Reply::Assign(..))) => {
+					write!(out, "{}: ", number)?;
+					self.reply(reply, out)?;
+				},
+				Ok(reply) => self.reply(reply, out)?,
+				Err(e) => {
+					writeln!(err, "{}:{}: error: {}", number, column(e.position), e.kind)?;
+					failed += 1;
+				},
+			}
+			if self.time {
+				writeln!(out, "{}: time: {:?}", number, elapsed)?;
+			}
+		}
+		Ok(failed)
+	}
+	// Handles the commands of the loop rather than the session, `None` if not one of them.
+	fn command(&mut self, cmd: &str, out: &mut dyn Write, err: &mut dyn Write) -> Option<io::Result<()>> {
+		if let Some(arg) = cmd.strip_prefix(":precision") {
+			return Some(match parse_precision(arg.trim()) {
+				Some(precision) => {
+					self.format.precision = precision;
+					Ok(())
+				},
+				None => writeln!(err, "error: expected :precision N with N at least 1, or :precision full"),
+			});
+		}
+		if let Some(path) = cmd.strip_prefix(":save ") {
+			let path = path.trim();
+			let result = fs::File::create(path).and_then(|mut file| self.session.save(&mut file));
+			return Some(match result {
+				Ok(()) => Ok(()),
+				Err(e) => writeln!(err, "error: cannot save {}: {}", path, e),
+			});
+		}
+		if let Some(path) = cmd.strip_prefix(":load ") {
+			let path = path.trim();
+			let result = fs::File::open(path).and_then(|file| self.session.load(&mut io::BufReader::new(file)));
+			return Some(match result {
+				Ok(errors) => errors.iter().try_for_each(|(line, e)| writeln!(err, "{}:{}: error: {}", path, line, e.kind)),
+				Err(e) => writeln!(err, "error: cannot load {}: {}", path, e),
+			});
+		}
+		if cmd == ":time" {
+			self.time = !self.time;
+			return Some(writeln!(out, "time: {}", if self.time { "on" } else { "off" }));
+		}
+		let input = cmd.strip_prefix(":bench ")?;
+		Some(match bench_eval(&self.session, input, self.iterations) {
+			Ok(bench) => writeln!(out, "{} in {} iterations, min: {:?}, mean: {:?}, max: {:?}",
+				format_value(bench.value, &self.format), bench.iterations, bench.min, bench.mean, bench.max),
+			Err(e) => writeln!(err, "{}", e.diagnostic_colored(input, self.color)),
+		})
+	}
+	// Line of JSON for the results and errors of expressions and assignments when enabled.
+	fn json_reply(&self, line: &str, result: &Result<Reply, Error>) -> Option<String> {
+		if !self.json {
+			return None;
+		}
+		let result = match *result {
+			Ok(Reply::Value(val) | Reply::Assign(_, val)) => Ok(val),
+			Ok(Reply::Vars(ref vars)) => return json_vars(line, vars),
+			Ok(_) => return None,
+			Err(e) => Err(e),
+		};
+		json_line(line, &result)
+	}
+	fn reply(&self, reply: Reply, out: &mut dyn Write) -> io::Result<()> {
+		match reply {
+			Reply::Empty | Reply::Clear => Ok(()),
+			Reply::Help(text) => write!(out, "{}", text),
+			Reply::Value(val) => writeln!(out, "{}", format_value(val, &self.format)),
+			Reply::Assign(name, val) => writeln!(out, "{} = {}", name, format_value(val, &self.format)),
+			Reply::Vars(vars) => {
+				for (name, val) in vars {
+					writeln!(out, "{} = {}", name, format_value(val, &self.format))?;
+				}
+				Ok(())
+			},
+		}
+	}
+	/// Runs the loop until the input ends.
+	pub fn run(&mut self, input: &mut dyn BufRead, out: &mut dyn Write, err: &mut dyn Write) -> io::Result<()> {
+		loop {
+			// If user is at a console, print a nice REPL
+			if self.interactive {
+				write!(out, ">>> ")?;
+				out.flush()?;
+			}
+			// Read input
+			let mut line = String::new();
+			if input.read_line(&mut line).is_err() {
+				return Ok(());
+			}
+			// Not sure how to handle ctrl-c events, Rust’s read_line is a bit weird in this regard
+			// I basically get an empty string as opposed to a newline when you just press enter.
+			if line.is_empty() {
+				return Ok(());
+			}
+			// If you press enter without any input, just retry without evaluating.
+			self.line(line.trim_end(), out, err)?;
+		}
+	}
+}
+
+/// Syntax summary printed at the top of the help.
+pub const SYNTAX: &str = "\
+Syntax:
+  +-*/^   : Operators with correct precedence, also × ÷ − √ ² ³ and π.
+  && ||   : Logical operators, non-zero is true, and(...) or(...) short-circuit.
+  c?x:y   : Conditional, only the chosen branch is evaluated.
+  ** //   : Alternative power and floored division operators.
+  x%      : Percentage when not followed by a value, eg. 120 * 15%.
+  2x 2(x) : Implicit multiplication, also (2)3 but not 2 3.
+  k = v   : Keyword argument, eg. log(8, base = 2).
+  (expr)  : Group expression with parentheses, [expr] and {expr} work too.
+  ans     : Use answer from previous expression, also * 2 or - 2 continue it.
+  x = v   : Assign a variable, :vars lists them and :clear removes them.
+  :help f : Describe the builtin f.
+  :precision N : Display N significant digits, :precision full shows all.
+  :save file   : Save the variables to the file, :load file restores them.
+  :time        : Toggle printing how long each line takes.
+  :bench expr  : Time many evaluations, prints the min and mean.
+";
+
+/// Returns the help listing the syntax and the builtins by category.
+pub fn help(builtins: &[BuiltinDesc]) -> String {
+	let mut text = String::from(SYNTAX);
+	for category in Category::ALL {
+		let mut line = String::from(" ");
+		let mut names = builtins.iter().filter(|desc| desc.category == category && !desc.name.is_empty()).peekable();
+		if names.peek().is_none() {
+			continue;
+		}
+		text.push_str(&format!("\n{}:\n", category));
+		for desc in names {
+			let sig = desc.signature();
+			// Wrap the signatures at 80 columns including the separator
+			if line.len() > 1 && line.len() + sig.len() + 3 > 80 {
+				text.push_str(&line);
+				text.push_str(",\n");
+				line = String::from(" ");
+			}
+			else if line.len() > 1 {
+				line.push(',');
+			}
+			line.push(' ');
+			line.push_str(&sig);
+		}
+		text.push_str(&line);
+		text.push('\n');
+	}
+	text
+}
+
+/// Returns the help describing a single builtin, `None` if not found.
+pub fn help_topic(builtins: &[BuiltinDesc], name: &str) -> Option<String> {
+	let desc = builtins.iter().find(|desc| desc.name == name && !name.is_empty())?;
+	Some(format!("{}\n  {}\n  Takes {}.\n", desc.signature(), desc.desc, desc.arity))
+}
+
+/// Command line arguments of the `pupil` binary.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Args {
+	/// How the result is displayed, `--format` and `--precision`.
+	pub format: FormatOptions,
+	/// Prints only the bare result, `--quiet` or `-q`.
+	pub quiet: bool,
+	/// Words of the expression to evaluate, empty to run the loop.
+	pub expr: Vec<String>,
+	/// File of expressions to evaluate one per line, `--file`.
+	pub file: Option<String>,
+	/// Prints a line of JSON per expression, `--json`, requires the `json` feature.
+	pub json: bool,
+	/// Variables defined with `-D name=value` or `--define name=value`.
+	pub defines: Vec<(String, Value)>,
+}
+
+/// Result of evaluating the expression on the command line.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Outcome {
+	/// Text for the standard output.
+	pub stdout: String,
+	/// Text for the standard error, warnings and diagnostics.
+	pub stderr: String,
+	/// Exit code, 0 on success and 1 if the evaluation failed.
+	pub code: i32,
+}
+
+/// Exit code for invalid command line arguments.
+pub const USAGE_ERROR: i32 = 2;
+
+impl Args {
+	/// Parses the arguments without the program name.
+	///
+	/// Options come before the expression, everything after `--` is part of the expression.
+	/// Returns the message for a usage error, which should exit with [`USAGE_ERROR`].
+	pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+		let mut result = Args::default();
+		let mut iter = args.into_iter();
+		while let Some(arg) = iter.next() {
+			// Everything after -- is the expression
+			if arg == "--" {
+				result.expr.extend(iter.by_ref());
+				break;
+			}
+			if arg == "--quiet" || arg == "-q" {
+				result.quiet = true;
+				continue;
+			}
+			if arg == "-D" {
+				let define = iter.next().ok_or("Invalid option -D, expected a definition name=value.")?;
+				result.defines.push(parse_define(&define)?);
+				continue;
+			}
+			if arg == "--json" {
+				if cfg!(not(feature = "json")) {
+					return Err("Invalid option --json, requires the json feature.".into());
+				}
+				result.json = true;
+				continue;
+			}
+			let (option, value) = match arg.split_once('=') {
+				Some((option, value)) if option.starts_with("--") => (option.to_string(), Some(value.to_string())),
+				_ if arg.starts_with("--") => (arg, iter.next()),
+				_ => { result.expr.push(arg); continue },
+			};
+			match (option.as_str(), value.as_deref()) {
+				("--format", Some(value)) => match value.parse() {
+					Ok(notation) => result.format.notation = notation,
+					Err(()) => return Err("Invalid format, expected one of fixed:N, sci, eng, frac or auto.".into()),
+				},
+				("--precision", Some(value)) => match parse_precision(value) {
+					Some(precision) => result.format.precision = precision,
+					None => return Err("Invalid precision, expected a number of significant digits or full.".into()),
+				},
+				("--file", Some(value)) => result.file = Some(value.to_string()),
+				("--define", Some(value)) => result.defines.push(parse_define(value)?),
+				_ => return Err(format!("Invalid option {}, expected --format, --precision, --file or --define with a value, or --quiet.", option)),
+			}
+		}
+		if result.file.is_some() && !result.expr.is_empty() {
+			return Err("Invalid arguments, expected either an expression or --file.".into());
+		}
+		Ok(result)
+	}
+	/// Creates the session with the defined variables.
+	///
+	/// Returns the message for a usage error if a definition names a builtin.
+	pub fn session<'a>(&self, env: BasicEnv<'a>) -> Result<Session<'a>, String> {
+		let mut session = Session::new(env);
+		for (name, value) in &self.defines {
+			if session.set_value(name, *value).is_err() {
+				return Err(format!("Invalid definition {}, cannot redefine a builtin.", name));
+			}
+		}
+		Ok(session)
+	}
+	/// Evaluates the expression and decides the output and exit code.
+	///
+	/// Prints `Ok: value` unless quiet, in which case only the value is printed.
+	/// Errors print the diagnostic to the standard error and exit with 1.
+	pub fn eval(&self, env: &dyn Env, color: ColorMode) -> Outcome {
+		let input = self.expr.join(" ");
+		let (result, warnings) = eval_with_warnings(env, &input);
+		let mut stderr = Vec::new();
+		print_warnings(&mut stderr, &warnings, color == ColorMode::Always).unwrap();
+		let mut outcome = Outcome { stderr: String::from_utf8(stderr).unwrap(), ..Outcome::default() };
+		if let Some(json) = json_line(&input, &result).filter(|_| self.json) {
+			outcome.stdout = json;
+			outcome.code = result.is_err() as i32;
+			return outcome;
+		}
+		match result {
+			Ok(val) => {
+				let val = format_value(val, &self.format);
+				outcome.stdout = if self.quiet { format!("{}\n", val) } else { format!("Ok: {}\n", val) };
+			},
+			Err(e) => {
+				outcome.stderr.push_str(&format!("{}\n", e.diagnostic_colored(&input, color)));
+				outcome.code = 1;
+			},
+		}
+		outcome
+	}
+}
+
+// Serializes the result as a line of JSON, `None` without the `json` feature.
+#[cfg(feature = "json")]
+fn json_line(input: &str, result: &Result<Value, Error>) -> Option<String> {
+	Some(format!("{}\n", Evaluation::new(input, result).to_json()))
+}
+#[cfg(not(feature = "json"))]
+fn json_line(_input: &str, _result: &Result<Value, Error>) -> Option<String> {
+	None
+}
+// Serializes the variables as a line of JSON, `None` without the `json` feature.
+#[cfg(feature = "json")]
+fn json_vars(input: &str, vars: &[(String, Value)]) -> Option<String> {
+	let vars = vars.iter().map(|&(ref name, val)| (name.clone(), serde_json::to_value(crate::evaluation::JsonValue(val)).unwrap()));
+	let vars = vars.collect::<serde_json::Map<_, _>>();
+	Some(format!("{}\n", serde_json::json!({ "input": input, "ok": true, "vars": vars })))
+}
+#[cfg(not(feature = "json"))]
+fn json_vars(_input: &str, _vars: &[(String, Value)]) -> Option<String> {
+	None
+}
+
+/// Parses a definition `name=value` of the command line.
+///
+/// The name is a variable name and the value a literal as in expressions, optionally signed.
+/// Returns the message for a usage error if malformed.
+pub fn parse_define(s: &str) -> Result<(String, Value), String> {
+	let (name, value) = s.split_once('=').ok_or_else(|| format!("Invalid definition {}, expected name=value.", s))?;
+	let name = name.trim();
+	if !is_name(name) {
+		return Err(format!("Invalid definition {}, expected a variable name before =.", s));
+	}
+	let tokens = tokenize(value).map(|tok| tok.kind).collect::<Vec<_>>();
+	let value = match tokens[..] {
+		[TokenKind::Lit(value)] | [TokenKind::Op(Operator::Add), TokenKind::Lit(value)] => value,
+		[TokenKind::Op(Operator::Sub), TokenKind::Lit(value)] => -value,
+		_ => return Err(format!("Invalid definition {}, expected a number after =.", s)),
+	};
+	Ok((name.to_string(), value))
+}
+
+/// Parses the number of significant digits, `full` is `Some(None)` for the shortest round trip representation.
+pub fn parse_precision(s: &str) -> Option<Option<usize>> {
+	match s {
+		"full" => Some(None),
+		_ => s.parse().ok().filter(|&digits| digits >= 1).map(Some),
+	}
+}
+
+/// Prints the warnings, dimmed if colored.
+pub fn print_warnings(err: &mut dyn Write, warnings: &[Warning], dim: bool) -> io::Result<()> {
+	for warning in warnings {
+		if dim {
+			writeln!(err, "\x1b[2mwarning: {}\x1b[0m", warning)?;
+		}
+		else {
+			writeln!(err, "warning: {}", warning)?;
+		}
+	}
+	Ok(())
+}
+
+#[test]
+fn session() {
+	let mut session = Session::default();
+	assert_eq!(session.exec("x = 41").0, Ok(Reply::Assign("x".into(), 41.0)));
+	assert_eq!(session.exec("x + 1").0, Ok(Reply::Value(42.0)));
+	assert_eq!(session.exec("ans * 2").0, Ok(Reply::Value(84.0)));
+	// Assignments leave the answer alone
+	assert_eq!(session.exec("  long_name_2 = x / 2 ").0, Ok(Reply::Assign("long_name_2".into(), 20.5)));
+	assert_eq!(session.exec("ans").0, Ok(Reply::Value(84.0)));
+	assert_eq!(session.exec("ans = 1").0, Ok(Reply::Assign("ans".into(), 1.0)));
+	assert_eq!(session.env.ans, 1.0);
+	assert_eq!(session.exec("x = x + 1").0, Ok(Reply::Assign("x".into(), 42.0)));
+	assert_eq!(session.exec("log(8, base = 2)").0, Ok(Reply::Value(3.0)));
+	assert_eq!(session.exec(":vars").0, Ok(Reply::Vars(vec![("long_name_2".into(), 20.5), ("x".into(), 42.0)])));
+	assert_eq!(session.exec("   ").0, Ok(Reply::Empty));
+
+	// Errors are positioned in the line
+	assert_eq!(session.exec("pi = 3").0, Err(Error::new(ErrorKind::EnvErrorBuiltinFn, 0)));
+	assert_eq!(session.exec(" sqrt = 3").0, Err(Error::new(ErrorKind::EnvErrorBuiltinFn, 1)));
+	assert_eq!(session.exec("d = 3").0, Ok(Reply::Assign("d".into(), 3.0)));
+	assert_eq!(session.exec("y = d + d(1)").0, Ok(Reply::Assign("y".into(), 4.0)));
+	assert_eq!(session.exec("y = 2 +").0, Err(Error::new(ErrorKind::UnfinishedExpression, 7)));
+	assert_eq!(session.exec("y = z").0, Err(Error::new(ErrorKind::EnvErrorNotFound, 4)));
+	assert_eq!(session.exec("2 = 3").0, Err(Error::new(ErrorKind::MisplacedAssign, 2)));
+	assert_eq!(session.exec(":quit").0, Err(Error::new(ErrorKind::InvalidToken, 0)));
+	let (_, warnings) = session.exec("y = 2 3%");
+	assert!(warnings.iter().all(|warning| warning.position >= 4));
+
+	assert_eq!(session.exec(":clear").0, Ok(Reply::Clear));
+	assert_eq!(session.exec(":vars").0, Ok(Reply::Vars(Vec::new())));
+	assert_eq!(session.exec("x").0, Err(Error::new(ErrorKind::EnvErrorNotFound, 0)));
+	assert_eq!(session.exec("ans").0, Ok(Reply::Value(3.0)));
+}
+
+#[test]
+fn repl() {
+	let mut repl = Repl::new(Session::default(), false);
+	let mut input = "x = 41\nx + 1\n\n:vars\n:clear\nx\n".as_bytes();
+	let (mut out, mut err) = (Vec::new(), Vec::new());
+	repl.run(&mut input, &mut out, &mut err).unwrap();
+	assert_eq!(String::from_utf8(out).unwrap(), "x = 41\n42\nx = 41\n");
+	assert!(String::from_utf8(err).unwrap().contains("env error not found"));
+	assert_eq!(repl.session.env.ans, 42.0);
+
+	let mut input = "0.1 + 0.2\n:precision 15\nans\n:precision 0\n:precision full\nans\n".as_bytes();
+	let (mut out, mut err) = (Vec::new(), Vec::new());
+	repl.run(&mut input, &mut out, &mut err).unwrap();
+	assert_eq!(String::from_utf8(out).unwrap(), "0.30000000000000004\n0.3\n0.30000000000000004\n");
+	assert!(String::from_utf8(err).unwrap().contains(":precision full"));
+	assert_eq!(parse_precision("3"), Some(Some(3)));
+	assert_eq!(parse_precision("full"), Some(None));
+	assert_eq!(parse_precision("0"), None);
+	assert_eq!(parse_precision("-1"), None);
+}
+
+#[test]
+fn helps() {
+	let env = BasicEnv::default();
+	let text = help(env.builtins);
+	// Every named builtin is listed once with its signature
+	for desc in env.builtins.iter().filter(|desc| !desc.name.is_empty()) {
+		let sig = desc.signature();
+		let found = text.matches(&format!(" {},", sig)).count() + text.matches(&format!(" {}\n", sig)).count();
+		assert_eq!(found, 1, "{}", sig);
+		assert!(!desc.desc.is_empty(), "{}", desc.name);
+		assert!(help_topic(env.builtins, desc.name).unwrap().contains(desc.desc));
+	}
+	assert!(text.lines().all(|line| line.chars().count() <= 80), "{}", text);
+	assert!(text.contains("\nTrigonometry and geometry:\n  acos(x), "));
+	assert_eq!(help_topic(env.builtins, "round").as_deref(), Some("round(x[, digits])\n  Rounds half away from zero, optionally at decimal places.\n  Takes 1 to 2 arguments.\n"));
+	assert_eq!(help_topic(env.builtins, "nope"), None);
+	assert_eq!(help_topic(env.builtins, ""), None);
+
+	let mut session = Session::default();
+	assert_eq!(session.exec(":help").0, Ok(Reply::Help(text)));
+	assert_eq!(session.exec(":help  sin ").0, Ok(Reply::Help("sin(x)\n  Sine of an angle in radians.\n  Takes 1 argument.\n".into())));
+	assert_eq!(session.exec(":help nope").0, Err(Error::new(ErrorKind::EnvErrorNotFound, 6)));
+}
+
+#[test]
+fn args() {
+	let parse = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+	let args = parse(&["-q", "--precision", "3", "--format=sci", "2", "/", "3"]).unwrap();
+	assert_eq!(args, Args {
+		format: FormatOptions { notation: Notation::Sci, precision: Some(3) },
+		quiet: true,
+		expr: vec!["2".into(), "/".into(), "3".into()],
+		..Args::default()
+	});
+	assert_eq!(parse(&["--", "--quiet", "-1"]).unwrap().expr, ["--quiet", "-1"]);
+	assert_eq!(parse(&["-1", "+", "2"]).unwrap().expr, ["-1", "+", "2"]);
+	assert_eq!(parse(&[]), Ok(Args::default()));
+	assert!(parse(&["--format", "hex", "1"]).is_err());
+	assert!(parse(&["--precision"]).is_err());
+	assert!(parse(&["--verbose", "1"]).is_err());
+
+	let env = BasicEnv::default();
+	let eval = |args: &[&str]| parse(args).unwrap().eval(&env, ColorMode::Never);
+	assert_eq!(eval(&["2+2"]), Outcome { stdout: "Ok: 4\n".into(), stderr: String::new(), code: 0 });
+	assert_eq!(eval(&["-q", "2+2"]), Outcome { stdout: "4\n".into(), stderr: String::new(), code: 0 });
+	assert_eq!(eval(&["-q", "--precision", "3", "2/3"]).stdout, "0.667\n");
+	let outcome = eval(&["-q", "2", "+"]);
+	assert_eq!((outcome.stdout.as_str(), outcome.code), ("", 1));
+	assert!(outcome.stderr.contains("2 +\n   ^"), "{}", outcome.stderr);
+	let outcome = eval(&["-q", "2pi"]);
+	assert_eq!((outcome.stdout.as_str(), outcome.code), ("6.283185307179586\n", 0));
+	assert!(outcome.stderr.starts_with("warning: "));
+}
+
+#[test]
+fn batch() {
+	let mut repl = Repl::new(Session::default(), false);
+	let mut input = "\
+# Formulas
+1 + 2
+
+ans * 2
+  # Indented comment
+x = ans / 3
+2 + (x
+sqrt(x * 8)
+2pi
+".as_bytes();
+	let (mut out, mut err) = (Vec::new(), Vec::new());
+	assert_eq!(repl.batch(&mut input, &mut out, &mut err).unwrap(), 1);
+	assert_eq!(String::from_utf8(out).unwrap(), "2: 3\n4: 6\n6: x = 2\n8: 4\n9: 6.283185307179586\n");
+	assert_eq!(String::from_utf8(err).unwrap(), "7:5: error: unbalanced parens\n9:2: warning: implicit multiplication\n");
+	assert_eq!(repl.session.env.ans, 2.0 * std::f64::consts::PI);
+
+	let parse = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+	assert_eq!(parse(&["--file", "formulas.txt"]).unwrap().file.as_deref(), Some("formulas.txt"));
+	assert!(parse(&["--file=formulas.txt", "1"]).is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json() {
+	let parse = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+	let env = BasicEnv::default();
+	let outcome = parse(&["--json", "2", "+", "3"]).eval(&env, ColorMode::Never);
+	assert_eq!(outcome, Outcome { stdout: "{\"input\":\"2 + 3\",\"ok\":true,\"value\":5.0}\n".into(), stderr: String::new(), code: 0 });
+	let outcome = parse(&["--json", "(2"]).eval(&env, ColorMode::Never);
+	assert_eq!(outcome.stdout, "{\"input\":\"(2\",\"ok\":false,\"error\":{\"kind\":\"UnbalancedParens\",\"position\":0}}\n");
+	assert_eq!(outcome.code, 1);
+
+	let mut repl = Repl::new(Session::default(), false);
+	repl.json = true;
+	let mut input = "# Comment\nx = 1/0\n:vars\n2 +\n".as_bytes();
+	let (mut out, mut err) = (Vec::new(), Vec::new());
+	assert_eq!(repl.batch(&mut input, &mut out, &mut err).unwrap(), 1);
+	assert_eq!(String::from_utf8(out).unwrap(), "\
+{\"input\":\"x = 1/0\",\"ok\":true,\"value\":\"inf\"}
+{\"input\":\":vars\",\"ok\":true,\"vars\":{\"x\":\"inf\"}}
+{\"input\":\"2 +\",\"ok\":false,\"error\":{\"kind\":\"UnfinishedExpression\",\"position\":3}}
+");
+	assert!(err.is_empty());
+}
+
+#[test]
+fn timing() {
+	let mut repl = Repl::new(Session::default(), false);
+	repl.iterations = 10;
+	let mut input = "x = 3\n:bench x * 2\n:bench 2 +\n:time\nx\n:time\nx\n".as_bytes();
+	let (mut out, mut err) = (Vec::new(), Vec::new());
+	repl.run(&mut input, &mut out, &mut err).unwrap();
+	let out = String::from_utf8(out).unwrap();
+	let lines = out.lines().collect::<Vec<_>>();
+	assert_eq!(lines.len(), 7, "{}", out);
+	assert_eq!(lines[0], "x = 3");
+	assert!(lines[1].starts_with("6 in 10 iterations, min: "), "{}", lines[1]);
+	assert!(lines[1].contains(", mean: ") && lines[1].contains(", max: "), "{}", lines[1]);
+	assert_eq!(lines[2..4], ["time: on", "3"]);
+	assert!(lines[4].starts_with("time: ") && lines[4] != "time: off", "{}", lines[4]);
+	assert_eq!(lines[5..], ["time: off", "3"]);
+	assert_eq!(String::from_utf8(err).unwrap(), "error: unfinished expression at line 1, column 4\n2 +\n   ^\n");
+}
+
+#[test]
+fn defines() {
+	assert_eq!(parse_define("x=3"), Ok(("x".into(), 3.0)));
+	assert_eq!(parse_define("half=0.5e0"), Ok(("half".into(), 0.5)));
+	assert_eq!(parse_define(" rate_2 = -7e-2 "), Ok(("rate_2".into(), -0.07)));
+	assert_eq!(parse_define("y=+0x10"), Ok(("y".into(), 16.0)));
+	// Missing =
+	assert!(parse_define("x").is_err());
+	assert!(parse_define("").is_err());
+	// Empty or invalid name
+	assert!(parse_define("=3").is_err());
+	assert!(parse_define("2x=3").is_err());
+	assert!(parse_define("a b=3").is_err());
+	// Non-numeric value
+	assert!(parse_define("x=").is_err());
+	assert!(parse_define("x=abc").is_err());
+	assert!(parse_define("x=1+2").is_err());
+	assert!(parse_define("x=--1").is_err());
+
+	let parse = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+	let args = parse(&["-D", "x=3", "--define", "rate=0.07", "--define=y=1", "x * (1+rate) + y"]).unwrap();
+	assert_eq!(args.defines, [("x".into(), 3.0), ("rate".into(), 0.07), ("y".into(), 1.0)]);
+	let session = args.session(BasicEnv::default()).unwrap();
+	assert_eq!(args.eval(&session, ColorMode::Never).stdout, "Ok: 4.21\n");
+	assert!(parse(&["-D"]).is_err());
+	assert!(parse(&["-D", "x", "1"]).is_err());
+	assert!(parse(&["-D", "pi=3", "1"]).unwrap().session(BasicEnv::default()).is_err());
+}
+
+#[test]
+fn save_load() {
+	let mut session = Session::default();
+	let values = [1.0 / 3.0, -0.0, 0.1 + 0.2, 1e300, -5e-324, f64::MAX, f64::INFINITY, f64::NEG_INFINITY, 123456789.0];
+	for (i, &val) in values.iter().enumerate() {
+		session.set_value(&format!("v{}", i), val).unwrap();
+	}
+	let mut saved = Vec::new();
+	session.save(&mut saved).unwrap();
+	let vars = session.vars.clone();
+
+	assert_eq!(session.exec(":clear").0, Ok(Reply::Clear));
+	assert_eq!(session.load(&mut &saved[..]).unwrap(), []);
+	assert_eq!(session.vars.len(), vars.len());
+	for (name, val) in &vars {
+		assert_eq!(session.vars[name].to_bits(), val.to_bits(), "{}", name);
+	}
+
+	// Errors are reported per line and loading continues
+	let mut input = "# Saved\na = 1\nb = 2 +\n\npi = 3\nc = a + 1\n".as_bytes();
+	let mut session = Session::default();
+	let errors = session.load(&mut input).unwrap();
+	assert_eq!(errors, [(3, Error::new(ErrorKind::UnfinishedExpression, 7)), (5, Error::new(ErrorKind::EnvErrorBuiltinFn, 0))]);
+	assert_eq!(session.exec(":vars").0, Ok(Reply::Vars(vec![("a".into(), 1.0), ("c".into(), 2.0)])));
+}
+
+#[test]
+fn continue_ans() {
+	let table = [
+		("* 2", "ans * 2"),
+		("/2", "ans /2"),
+		("  ^ 2", "ans   ^ 2"),
+		("** 2", "ans ** 2"),
+		("// 2", "ans // 2"),
+		("% 3", "ans % 3"),
+		("&& 1", "ans && 1"),
+		("²", "ans ²"),
+		("+ 5", "ans + 5"),
+		("- 2", "ans - 2"),
+		("− 2", "ans − 2"),
+		// Signs without whitespace stay unary
+		("-2", "-2"),
+		("+2", "+2"),
+		("-", "-"),
+		("√4", "√4"),
+		("2 * 3", "2 * 3"),
+		("x", "x"),
+		("", ""),
+	];
+	for (input, expected) in table {
+		assert_eq!(continue_with_ans(input), expected, "{:?}", input);
+	}
+	assert!(matches!(continue_with_ans("-2"), Cow::Borrowed(_)));
+
+	let mut session = Session::default();
+	assert_eq!(session.exec("3").0, Ok(Reply::Value(3.0)));
+	assert_eq!(session.exec("* 2").0, Ok(Reply::Value(6.0)));
+	assert_eq!(session.exec("- 1").0, Ok(Reply::Value(5.0)));
+	assert_eq!(session.exec("-1").0, Ok(Reply::Value(-1.0)));
+	assert_eq!(session.exec("^ 2").0, Ok(Reply::Value(1.0)));
+	// Positions are relative to the line as typed
+	assert_eq!(session.exec("* (2").0, Err(Error::new(ErrorKind::UnbalancedParens, 2)));
+	assert_eq!(session.exec("* 2pi").1[0].position, 3);
+}
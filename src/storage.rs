@@ -0,0 +1,141 @@
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+use std::{ptr, slice};
+use crate::ErrorKind;
+
+/// Stack keeping its first elements inline, most expressions never allocate.
+#[cfg(feature = "smallvec")]
+pub(crate) type Stack<T> = smallvec::SmallVec<[T; 8]>;
+#[cfg(not(feature = "smallvec"))]
+pub(crate) type Stack<T> = Vec<T>;
+
+/// Stack used by the parser and evaluator.
+pub(crate) trait Storage<T>: Default + Deref<Target = [T]> + DerefMut {
+	/// Pushes the value, fails with [`ErrorKind::CapacityExceeded`] if full.
+	fn push(&mut self, val: T) -> Result<(), ErrorKind>;
+	fn pop(&mut self) -> Option<T>;
+	fn truncate(&mut self, len: usize);
+	fn clear(&mut self) {
+		self.truncate(0);
+	}
+}
+
+impl<T> Storage<T> for Stack<T> {
+	fn push(&mut self, val: T) -> Result<(), ErrorKind> {
+		Stack::push(self, val);
+		Ok(())
+	}
+	fn pop(&mut self) -> Option<T> {
+		Stack::pop(self)
+	}
+	fn truncate(&mut self, len: usize) {
+		Stack::truncate(self, len)
+	}
+}
+
+/// Storage of the stacks.
+pub(crate) trait Alloc {
+	/// Pending fns and the state kept per fn.
+	type Fns<T>: Storage<T>;
+	/// Values and the parameters of their keyword arguments.
+	type Vals<T>: Storage<T>;
+	/// Name of the variable which may be a keyword.
+	type Name: Storage<u8>;
+	/// Whether warnings are collected, their messages are allocated.
+	const WARNINGS: bool;
+	/// Whether the whole input is parsed at once and each token is parsed knowing the next, names are then never copied.
+	const LOOKAHEAD: bool;
+}
+
+/// Stacks on the heap.
+pub(crate) struct Heap;
+impl Alloc for Heap {
+	type Fns<T> = Stack<T>;
+	type Vals<T> = Stack<T>;
+	type Name = Stack<u8>;
+	const WARNINGS: bool = true;
+	const LOOKAHEAD: bool = false;
+}
+
+/// Stacks of fixed capacity which never allocate.
+pub(crate) struct Fixed<const F: usize, const V: usize>;
+impl<const F: usize, const V: usize> Alloc for Fixed<F, V> {
+	type Fns<T> = FixedStack<T, F>;
+	type Vals<T> = FixedStack<T, V>;
+	// Names are resolved by looking ahead and borrowed from the input
+	type Name = FixedStack<u8, 0>;
+	const WARNINGS: bool = false;
+	const LOOKAHEAD: bool = true;
+}
+
+/// Stack of up to `N` elements stored inline.
+pub(crate) struct FixedStack<T, const N: usize> {
+	items: [MaybeUninit<T>; N],
+	// The items before are initialized
+	len: usize,
+}
+
+impl<T, const N: usize> Default for FixedStack<T, N> {
+	fn default() -> FixedStack<T, N> {
+		FixedStack { items: [const { MaybeUninit::uninit() }; N], len: 0 }
+	}
+}
+impl<T, const N: usize> Deref for FixedStack<T, N> {
+	type Target = [T];
+	fn deref(&self) -> &[T] {
+		// SAFETY: The first len items are initialized
+		unsafe { slice::from_raw_parts(self.items.as_ptr().cast(), self.len) }
+	}
+}
+impl<T, const N: usize> DerefMut for FixedStack<T, N> {
+	fn deref_mut(&mut self) -> &mut [T] {
+		// SAFETY: The first len items are initialized
+		unsafe { slice::from_raw_parts_mut(self.items.as_mut_ptr().cast(), self.len) }
+	}
+}
+impl<T, const N: usize> Storage<T> for FixedStack<T, N> {
+	fn push(&mut self, val: T) -> Result<(), ErrorKind> {
+		let item = self.items.get_mut(self.len).ok_or(ErrorKind::CapacityExceeded)?;
+		item.write(val);
+		self.len += 1;
+		Ok(())
+	}
+	fn pop(&mut self) -> Option<T> {
+		self.len = self.len.checked_sub(1)?;
+		// SAFETY: The item was initialized and is no longer part of the stack
+		Some(unsafe { self.items[self.len].assume_init_read() })
+	}
+	fn truncate(&mut self, len: usize) {
+		if len < self.len {
+			let count = self.len - len;
+			// Forget the items before dropping them in case a drop panics
+			self.len = len;
+			// SAFETY: The items were initialized and are no longer part of the stack
+			unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.items.as_mut_ptr().add(len).cast::<T>(), count)) }
+		}
+	}
+}
+impl<T, const N: usize> Drop for FixedStack<T, N> {
+	fn drop(&mut self) {
+		self.truncate(0);
+	}
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn fixed_stack() {
+	use std::rc::Rc;
+	let item = Rc::new(());
+	let mut stack = FixedStack::<Rc<()>, 3>::default();
+	for _ in 0..3 {
+		stack.push(item.clone()).unwrap();
+	}
+	assert_eq!(stack.push(item.clone()), Err(ErrorKind::CapacityExceeded));
+	assert_eq!((stack.len(), Rc::strong_count(&item)), (3, 4));
+	assert!(stack.pop().is_some());
+	stack.truncate(1);
+	assert_eq!((stack.len(), Rc::strong_count(&item)), (1, 2));
+	drop(stack);
+	assert_eq!(Rc::strong_count(&item), 1);
+}
@@ -0,0 +1,348 @@
+use std::fmt;
+use crate::*;
+
+/// Exponents of the SI base units, see [`Dimension::SYMBOLS`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Dimension(pub [i8; 7]);
+
+impl Dimension {
+	/// Symbols of the base units in order.
+	pub const SYMBOLS: [&'static str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+	/// Dimensionless.
+	pub const NONE: Dimension = Dimension([0; 7]);
+	/// Length in metres.
+	pub const LENGTH: Dimension = Dimension([1, 0, 0, 0, 0, 0, 0]);
+	/// Mass in kilograms.
+	pub const MASS: Dimension = Dimension([0, 1, 0, 0, 0, 0, 0]);
+	/// Time in seconds.
+	pub const TIME: Dimension = Dimension([0, 0, 1, 0, 0, 0, 0]);
+	/// Velocity in metres per second.
+	pub const VELOCITY: Dimension = Dimension([1, 0, -1, 0, 0, 0, 0]);
+
+	/// Whether all exponents are zero.
+	pub fn is_none(self) -> bool {
+		self == Dimension::NONE
+	}
+	/// Dimension of a product.
+	pub fn checked_mul(self, other: Dimension) -> Option<Dimension> {
+		self.zip(other, i8::checked_add)
+	}
+	/// Dimension of a quotient.
+	pub fn checked_div(self, other: Dimension) -> Option<Dimension> {
+		self.zip(other, i8::checked_sub)
+	}
+	/// Dimension of a power, none if an exponent isn't an integer.
+	pub fn pow(self, exp: Value) -> Option<Dimension> {
+		let mut dim = Dimension::NONE;
+		for (d, &e) in dim.0.iter_mut().zip(self.0.iter()) {
+			let val = e as Value * exp;
+			if val.fract() != 0.0 || !(i8::MIN as Value..=i8::MAX as Value).contains(&val) {
+				return None;
+			}
+			*d = val as i8;
+		}
+		Some(dim)
+	}
+	/// Dimension of a root, none if an exponent isn't divisible.
+	pub fn root(self, n: i8) -> Option<Dimension> {
+		let mut dim = Dimension::NONE;
+		for (d, &e) in dim.0.iter_mut().zip(self.0.iter()) {
+			if e % n != 0 {
+				return None;
+			}
+			*d = e / n;
+		}
+		Some(dim)
+	}
+	fn zip(self, other: Dimension, f: impl Fn(i8, i8) -> Option<i8>) -> Option<Dimension> {
+		let mut dim = Dimension::NONE;
+		for i in 0..7 {
+			dim.0[i] = f(self.0[i], other.0[i])?;
+		}
+		Some(dim)
+	}
+}
+
+impl fmt::Display for Dimension {
+	/// Formats the base units like `kg*m^2/s^2`, negative exponents only if there is no numerator, eg. `s^-1`.
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let base = |f: &mut fmt::Formatter, sym, exp: i8| {
+			if exp == 1 { f.write_str(sym) }
+			else { write!(f, "{}^{}", sym, exp) }
+		};
+		let numerator = self.0.iter().any(|&e| e > 0);
+		let mut first = true;
+		// Order the mass before the length like the derived units are usually written
+		for i in [1, 0, 2, 3, 4, 5, 6] {
+			let (sym, exp) = (Dimension::SYMBOLS[i], self.0[i]);
+			if exp > 0 || (exp < 0 && !numerator) {
+				if !first {
+					f.write_str("*")?;
+				}
+				base(f, sym, exp)?;
+				first = false;
+			}
+		}
+		if numerator {
+			for i in [1, 0, 2, 3, 4, 5, 6] {
+				if self.0[i] < 0 {
+					f.write_str("/")?;
+					base(f, Dimension::SYMBOLS[i], -self.0[i])?;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Named unit of measure.
+#[derive(Debug, PartialEq)]
+pub struct Unit {
+	/// Symbol of the unit, eg. `km`.
+	pub name: &'static str,
+	/// Value of one unit in the SI base units.
+	pub factor: Value,
+	/// Dimension of the unit.
+	pub dim: Dimension,
+}
+
+const fn dim(m: i8, kg: i8, s: i8) -> Dimension {
+	Dimension([m, kg, s, 0, 0, 0, 0])
+}
+
+/// Units available in a [`UnitEnv`], sorted by name.
+pub static UNITS: [Unit; 28] = [
+	Unit { name: "A", factor: 1.0, dim: Dimension([0, 0, 0, 1, 0, 0, 0]) },
+	Unit { name: "Hz", factor: 1.0, dim: dim(0, 0, -1) },
+	Unit { name: "J", factor: 1.0, dim: dim(2, 1, -2) },
+	Unit { name: "K", factor: 1.0, dim: Dimension([0, 0, 0, 0, 1, 0, 0]) },
+	Unit { name: "L", factor: 1e-3, dim: dim(3, 0, 0) },
+	Unit { name: "N", factor: 1.0, dim: dim(1, 1, -2) },
+	Unit { name: "Pa", factor: 1.0, dim: dim(-1, 1, -2) },
+	Unit { name: "W", factor: 1.0, dim: dim(2, 1, -3) },
+	Unit { name: "cd", factor: 1.0, dim: Dimension([0, 0, 0, 0, 0, 0, 1]) },
+	Unit { name: "cm", factor: 1e-2, dim: Dimension::LENGTH },
+	Unit { name: "day", factor: 86400.0, dim: Dimension::TIME },
+	Unit { name: "ft", factor: 0.3048, dim: Dimension::LENGTH },
+	Unit { name: "g", factor: 1e-3, dim: Dimension::MASS },
+	Unit { name: "h", factor: 3600.0, dim: Dimension::TIME },
+	Unit { name: "in", factor: 0.0254, dim: Dimension::LENGTH },
+	Unit { name: "kg", factor: 1.0, dim: Dimension::MASS },
+	Unit { name: "km", factor: 1e3, dim: Dimension::LENGTH },
+	Unit { name: "kph", factor: 1.0 / 3.6, dim: Dimension::VELOCITY },
+	Unit { name: "lb", factor: 0.45359237, dim: Dimension::MASS },
+	Unit { name: "m", factor: 1.0, dim: Dimension::LENGTH },
+	Unit { name: "mi", factor: 1609.344, dim: Dimension::LENGTH },
+	Unit { name: "min", factor: 60.0, dim: Dimension::TIME },
+	Unit { name: "mm", factor: 1e-3, dim: Dimension::LENGTH },
+	Unit { name: "mol", factor: 1.0, dim: Dimension([0, 0, 0, 0, 0, 1, 0]) },
+	Unit { name: "mph", factor: 0.44704, dim: Dimension::VELOCITY },
+	Unit { name: "ms", factor: 1e-3, dim: Dimension::TIME },
+	Unit { name: "s", factor: 1.0, dim: Dimension::TIME },
+	Unit { name: "yd", factor: 0.9144, dim: Dimension::LENGTH },
+];
+
+/// Looks up a unit by its symbol.
+pub fn unit(name: &str) -> Option<&'static Unit> {
+	UNITS.binary_search_by_key(&name, |unit| unit.name).ok().map(|index| &UNITS[index])
+}
+
+/// Value with a dimension, see [`eval_units`].
+///
+/// The value is in the SI base units, the unit only affects how the quantity is displayed.
+#[derive(Copy, Clone, Debug)]
+pub struct Quantity {
+	/// Value in the SI base units.
+	pub value: Value,
+	/// Dimension of the value.
+	pub dim: Dimension,
+	/// Unit to display the value in, the SI base units if none.
+	pub unit: Option<&'static Unit>,
+}
+
+impl Quantity {
+	/// Quantity of a number of units.
+	pub fn new(val: Value, unit: &'static Unit) -> Quantity {
+		Quantity { value: val * unit.factor, dim: unit.dim, unit: Some(unit) }
+	}
+	/// Value in the display unit.
+	pub fn magnitude(&self) -> Value {
+		match self.unit {
+			Some(unit) => self.value / unit.factor,
+			None => self.value,
+		}
+	}
+}
+
+impl From<Value> for Quantity {
+	fn from(val: Value) -> Quantity {
+		Quantity { value: val, dim: Dimension::NONE, unit: None }
+	}
+}
+
+impl PartialEq for Quantity {
+	/// Equal values and dimensions, regardless of the display unit.
+	fn eq(&self, other: &Quantity) -> bool {
+		self.value == other.value && self.dim == other.dim
+	}
+}
+
+impl fmt::Display for Quantity {
+	/// Formats like `18.6 mph` or `8.3 m/s`.
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.unit {
+			Some(unit) => write!(f, "{} {}", self.magnitude(), unit.name),
+			None if self.dim.is_none() => write!(f, "{}", self.value),
+			None => write!(f, "{} {}", self.value, self.dim),
+		}
+	}
+}
+
+impl Num for Quantity {
+	#[inline]
+	fn from_f64(val: f64) -> Quantity {
+		Quantity::from(val)
+	}
+	#[inline]
+	fn truthy(&self) -> bool {
+		self.value != 0.0 && !self.value.is_nan()
+	}
+	#[inline]
+	fn is_finite(&self) -> bool {
+		self.value.is_finite()
+	}
+	#[inline]
+	fn is_zero(&self) -> bool {
+		self.value == 0.0
+	}
+	fn operator(op: Operator) -> BuiltinFn<Quantity> {
+		use crate::builtins_units::*;
+		match op {
+			Operator::Add => builtin_add,
+			Operator::Sub => builtin_sub,
+			Operator::Mul | Operator::IMul => builtin_mul,
+			Operator::Div => builtin_div,
+			Operator::Rem => builtin_rem,
+			Operator::Pow => builtin_pow,
+			Operator::And => builtin_all,
+			Operator::Or => builtin_any,
+			Operator::Sqrt => builtin_sqrt,
+			Operator::Sqr => builtin_sqr,
+			Operator::Cube => builtin_cube,
+			Operator::FloorDiv => builtin_floordiv,
+		}
+	}
+	fn percent() -> BuiltinFn<Quantity> {
+		crate::builtins_units::builtin_percent
+	}
+	fn identity() -> BuiltinFn<Quantity> {
+		crate::builtins_units::builtin_id
+	}
+	/// Units are quoted in conversions, eg. `to(60 km / h, "mph")`.
+	fn quoted_names() -> bool {
+		true
+	}
+}
+
+/// Environment of quantities with the [`builtins_units`](crate::builtins_units) and the [`UNITS`] as variables.
+///
+/// Units follow a value with implicit multiplication, eg. `3 m` or `60 km / 2 h`.
+///
+/// ```
+/// let env = pupil::UnitEnv::default();
+/// assert_eq!(pupil::eval_units(&env, "3 m + 40 cm").unwrap().to_string(), "3.4 m");
+/// ```
+#[derive(Clone, Default)]
+pub struct UnitEnv<'a> {
+	pub env: NumEnv<'a, Quantity>,
+}
+
+impl<'a> Env<Quantity> for UnitEnv<'a> {
+	fn builtin(&self, name: &str) -> Result<BuiltinFn<Quantity>, ErrorKind> {
+		self.env.builtin(name)
+	}
+	fn get_value(&self, name: &str) -> Result<Quantity, ErrorKind> {
+		match unit(name) {
+			Some(unit) => Ok(Quantity::new(1.0, unit)),
+			None => self.env.get_value(name),
+		}
+	}
+	fn set_value(&mut self, name: &str, value: Quantity) -> Result<(), ErrorKind> {
+		self.env.set_value(name, value)
+	}
+	fn is_pure(&self, name: &str) -> bool {
+		self.env.is_pure(name)
+	}
+	fn divides(&self, name: &str) -> bool {
+		self.env.divides(name)
+	}
+	fn arity(&self, name: &str) -> Option<Arity> {
+		self.env.arity(name)
+	}
+	fn params(&self, name: &str) -> Option<&[&str]> {
+		self.env.params(name)
+	}
+	fn random(&self) -> Option<Value> {
+		self.env.random()
+	}
+}
+
+/// Evaluates the input in quantities with dimensions.
+///
+/// Adding, comparing or converting quantities of different dimensions fails with [`ErrorKind::DimensionMismatch`] at the operator.
+/// Use `to(x, "unit")` to display the result in a unit.
+///
+/// ```
+/// let env = pupil::UnitEnv::default();
+/// let speed = pupil::eval_units(&env, r#"to(60 km / 2 h, "mph")"#).unwrap();
+/// assert!((speed.magnitude() - 18.64).abs() < 0.01);
+/// ```
+pub fn eval_units(env: &dyn Env<Quantity>, input: &str) -> Result<Quantity, Error> {
+	eval(env, input)
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn speed() {
+	let env = UnitEnv::default();
+	let speed = eval_units(&env, "60 km / 2 h").unwrap();
+	assert_eq!(speed.dim, Dimension::VELOCITY);
+	assert!((speed.value - 8.333333).abs() < 1e-6);
+	assert_eq!(speed.to_string(), "8.333333333333334 m/s");
+	let mph = eval_units(&env, r#"to(60 km / 2 h, "mph")"#).unwrap();
+	assert!((mph.magnitude() - 18.6411).abs() < 1e-4, "{}", mph);
+	assert!(mph.to_string().ends_with(" mph"));
+	assert_eq!(eval_units(&env, "100 m / 10 s * 2 min").unwrap().to_string(), "1200 m");
+	assert_eq!(eval_units(&env, "2 kg * 3 m / s^2").unwrap().to_string(), "6 kg*m/s^2");
+	assert_eq!(eval_units(&env, "sqrt(9 m^2) + 1 m").unwrap().to_string(), "4 m");
+	assert_eq!(eval_units(&env, "1 / 2 s").unwrap().to_string(), "0.5 s^-1");
+	assert_eq!(eval_units(&env, "10 m / 5 m"), Ok(Quantity::from(2.0)));
+}
+#[test]
+fn mismatch() {
+	let env = UnitEnv::default();
+	assert_eq!(eval_units(&env, "3 m + 2 s"), Err(Error::new(ErrorKind::DimensionMismatch, 4)));
+	assert_eq!(eval_units(&env, "1 + 2 m * 3 kg - 4"), Err(Error::new(ErrorKind::DimensionMismatch, 2)));
+	assert_eq!(eval_units(&env, "sin(2 m)"), Err(Error::new(ErrorKind::DimensionMismatch, 0)));
+	assert_eq!(eval_units(&env, "2^(3 s)"), Err(Error::new(ErrorKind::DimensionMismatch, 1)));
+	assert_eq!(eval_units(&env, "sqrt(2 m)"), Err(Error::new(ErrorKind::DimensionMismatch, 0)));
+	assert_eq!(eval_units(&env, r#"to(3 m, "s")"#), Err(Error::new(ErrorKind::DimensionMismatch, 0)));
+	assert_eq!(eval_units(&env, r#"to(3 m, "parsec")"#), Err(Error::new(ErrorKind::EnvErrorNotFound, 8)));
+	assert_eq!(eval_units(&env, "max(1 m, 2 s)"), Err(Error::new(ErrorKind::DimensionMismatch, 0)));
+}
+#[test]
+fn round_trip() {
+	let env = UnitEnv::default();
+	assert!(UNITS.windows(2).all(|pair| pair[0].name < pair[1].name));
+	for unit in &UNITS {
+		// Through the first unit of the same dimension
+		let other = UNITS.iter().find(|it| it.dim == unit.dim).unwrap();
+		let input = format!(r#"to(to(2.5 {}, "{}"), "{}")"#, unit.name, other.name, unit.name);
+		let result = eval_units(&env, &input).unwrap();
+		assert_eq!(result.unit, Some(unit), "{}", input);
+		assert!((result.magnitude() - 2.5).abs() < 1e-12, "{}: {}", input, result);
+	}
+	assert_eq!(eval_units(&env, r#"to(to(5 km, "mi"), "km")"#).unwrap().to_string(), "5 km");
+	assert_eq!(eval_units(&env, r#"to(1 mi, "ft")"#).unwrap().to_string(), "5280 ft");
+}
@@ -0,0 +1,133 @@
+/*!
+WebAssembly bindings.
+
+Build the `ffi` crate with `wasm-pack build ffi --target web -- --features wasm`, then from JavaScript:
+
+```js
+import init, { WasmEvaluator } from "./ffi/pkg/pupil_ffi.js";
+await init();
+const calc = new WasmEvaluator();
+calc.set_var("r", 2);
+console.log(calc.eval("pi * r^2"));
+try {
+  calc.eval("2 + (3");
+}
+catch (err) {
+  console.log(err.kind, err.position);
+  console.log(err.diagnostic);
+}
+for (const tok of calc.tokenize("sin(x) + 1")) {
+  console.log(tok.kind, tok.text, tok.position);
+}
+```
+*/
+
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+use crate::*;
+
+// Name of the token kind for highlighting.
+fn kind_name(kind: &TokenKind) -> &'static str {
+	match kind {
+		TokenKind::Unk(_) => "unknown",
+		TokenKind::Lit(_) => "literal",
+		#[cfg(feature = "complex")]
+		TokenKind::Imag(_) => "imaginary",
+		TokenKind::Op(_) => "operator",
+		TokenKind::Var(_) => "variable",
+		TokenKind::Open(_) => "function",
+		TokenKind::Group(_) => "group",
+		TokenKind::Comma => "comma",
+		TokenKind::Close(_) => "close",
+		TokenKind::Semi => "semi",
+		TokenKind::Question => "question",
+		TokenKind::Colon => "colon",
+		TokenKind::Assign => "assign",
+	}
+}
+
+// Tokens with their kind name and text, the text extends to the next token without the whitespace in between.
+fn spans(input: &str) -> Vec<(&'static str, &str, usize)> {
+	let tokens = tokenize(input).collect::<Vec<_>>();
+	tokens.iter().enumerate().map(|(i, tok)| {
+		let end = tokens.get(i + 1).map_or(input.len(), |next| next.position);
+		(kind_name(&tok.kind), input[tok.position..end].trim_end(), tok.position)
+	}).collect()
+}
+
+fn object(fields: &[(&str, JsValue)]) -> JsValue {
+	let obj = Object::new();
+	for (key, value) in fields {
+		// Setting a property on a fresh object can't fail
+		let _ = Reflect::set(&obj, &JsValue::from_str(key), value);
+	}
+	obj.into()
+}
+
+/// Evaluator with variables for JavaScript.
+#[wasm_bindgen]
+pub struct WasmEvaluator {
+	env: VarEnv<'static, Value>,
+}
+
+#[wasm_bindgen]
+impl WasmEvaluator {
+	/// Creates an evaluator with the default builtins and no variables.
+	#[wasm_bindgen(constructor)]
+	#[allow(clippy::new_without_default)]
+	pub fn new() -> WasmEvaluator {
+		WasmEvaluator { env: VarEnv::default() }
+	}
+	/// Evaluates the input and stores the result in `ans`.
+	///
+	/// Throws an object with the `kind` of error, its `position` and the rendered `diagnostic`.
+	pub fn eval(&mut self, input: &str) -> Result<f64, JsValue> {
+		let result = eval(&self.env, input).map_err(|err| object(&[
+			("kind", JsValue::from_str(&err.kind.to_string())),
+			("position", JsValue::from(err.position as u32)),
+			("diagnostic", JsValue::from_str(&err.diagnostic(input).to_string())),
+		]))?;
+		let _ = self.env.set_value("ans", result);
+		Ok(result)
+	}
+	/// Sets a variable, throws the kind of error if the name is a builtin.
+	pub fn set_var(&mut self, name: &str, value: f64) -> Result<(), JsValue> {
+		self.env.set_value(name, value).map_err(|kind| JsValue::from_str(&kind.to_string()))
+	}
+	/// Tokenizes for syntax highlighting, an array of `{kind, text, position}` objects.
+	pub fn tokenize(&self, input: &str) -> JsValue {
+		spans(input).into_iter().map(|(kind, text, position)| object(&[
+			("kind", JsValue::from_str(kind)),
+			("text", JsValue::from_str(text)),
+			("position", JsValue::from(position as u32)),
+		])).collect::<Array>().into()
+	}
+}
+
+//----------------------------------------------------------------
+
+#[test]
+fn highlight() {
+	assert_eq!(spans("sin(x) +  1.5"), [
+		("function", "sin(", 0),
+		("variable", "x", 4),
+		("close", ")", 5),
+		("operator", "+", 7),
+		("literal", "1.5", 10),
+	]);
+	assert_eq!(spans("2 $"), [("literal", "2", 0), ("unknown", "$", 2)]);
+}
+#[cfg(all(test, target_arch = "wasm32"))]
+#[wasm_bindgen_test::wasm_bindgen_test]
+fn evaluator() {
+	let mut calc = WasmEvaluator::new();
+	calc.set_var("x", 2.0).unwrap();
+	assert_eq!(calc.eval("x * 3"), Ok(6.0));
+	assert_eq!(calc.eval("ans + 1"), Ok(7.0));
+	assert!(calc.set_var("sin", 1.0).is_err());
+	assert!(calc.set_var("d", 1.0).is_ok());
+	let err = calc.eval("2 + (3").unwrap_err();
+	assert_eq!(Reflect::get(&err, &JsValue::from_str("position")).unwrap(), JsValue::from(4u32));
+	assert!(Reflect::get(&err, &JsValue::from_str("diagnostic")).unwrap().as_string().unwrap().contains('^'));
+	assert_eq!(Array::from(&calc.tokenize("1 + x")).length(), 3);
+}
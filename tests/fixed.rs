@@ -0,0 +1,41 @@
+//! The fixed capacity evaluation never allocates.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use pupil::*;
+
+struct Counting;
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.alloc(layout)
+	}
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[test]
+fn no_allocations() {
+	let env = BasicEnv { ans: 5.0, ..Default::default() };
+	let inputs = [
+		"2 * (3 + 4) - sin(pi / 2)",
+		"mean(1, 2, 3, ans) ^ 2 + 50%",
+		"clamp(ans * 2, lo = 0, hi = 8) ? √16 : 0",
+		"max(1, 2, [3 + { 4 }]) + 1 / 0",
+		"1 + (2",
+		"log(ans, base = 2) + max(ans, a_variable_name_longer_than_thirty_two_bytes)",
+	];
+	for input in inputs {
+		let expected = eval(&env, input);
+		let before = ALLOCATIONS.load(Ordering::Relaxed);
+		let result = eval_fixed::<16, 16, _>(&env, input);
+		assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), before, "{:?}", input);
+		assert_eq!(result, expected, "{:?}", input);
+	}
+}
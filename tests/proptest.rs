@@ -0,0 +1,95 @@
+//! Invariants of the evaluation of random input.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use proptest::prelude::*;
+use pupil::*;
+
+// Fragments of valid and invalid expressions, without the random builtins which evaluate differently every time.
+const VOCABULARY: &[&str] = &[
+	"0", "1", "2.5", "1e3", ".5", "1e", "ans", "pi", "x", "e", "i", "nan",
+	"sin(", "max(", "mean(", "atan2(", "and(", "or(", "clamp(", "x=", "lo=", "√",
+	"(", ")", "[", "]", "{", "}", ",", ";", "?", ":", "=",
+	"+", "-", "*", "/", "//", "^", "%", "!", "<", "==", "&&", "$", " ",
+];
+
+fn tokens() -> impl Strategy<Value = String> {
+	prop::collection::vec(prop::sample::select(VOCABULARY), 0..32).prop_map(|fragments| fragments.concat())
+}
+
+// Mostly valid expressions, random tokens rarely get past the first few.
+fn expressions() -> impl Strategy<Value = String> {
+	let leaf = prop::sample::select(&["0", "1", "2.5", "1e3", "1e400", "ans", "pi", "e", "nan", "-1"][..]).prop_map(String::from);
+	leaf.prop_recursive(6, 48, 4, |inner| prop_oneof![
+		(inner.clone(), prop::sample::select(&["+", "-", "*", "/", "//", "^", "%", " "][..]), inner.clone()).prop_map(|(a, op, b)| format!("{}{}{}", a, op, b)),
+		(prop::sample::select(&["-", "+", "!", "√"][..]), inner.clone()).prop_map(|(op, a)| format!("{}{}", op, a)),
+		(inner.clone(), prop::sample::select(&["%", "!", "²"][..])).prop_map(|(a, op)| format!("{}{}", a, op)),
+		inner.clone().prop_map(|a| format!("({})", a)),
+		(prop::sample::select(&["sin", "max", "mean", "atan2", "and", "or", "clamp", "gcd", "fac", "kth"][..]), prop::collection::vec(inner.clone(), 0..4))
+			.prop_map(|(f, args)| format!("{}({})", f, args.join(", "))),
+		(inner.clone(), inner.clone()).prop_map(|(a, b)| format!("clamp({}, lo = {}, hi = 1)", a, b)),
+		(inner.clone(), inner.clone(), inner).prop_map(|(a, b, c)| format!("{} ? {} : {}", a, b, c)),
+	])
+}
+
+// Valid expressions with a fragment inserted or removed.
+fn mutations() -> impl Strategy<Value = String> {
+	(expressions(), any::<prop::sample::Index>(), prop::option::of(prop::sample::select(VOCABULARY))).prop_map(|(mut input, index, fragment)| {
+		let mut pos = index.index(input.len() + 1);
+		while !input.is_char_boundary(pos) {
+			pos -= 1;
+		}
+		match fragment {
+			Some(fragment) => input.insert_str(pos, fragment),
+			None if pos < input.len() => { input.remove(pos); },
+			None => (),
+		}
+		input
+	})
+}
+
+// Evaluates the tokens one by one instead of feeding the input.
+fn eval_tokens(env: &BasicEnv, input: &str) -> Result<Value, Error> {
+	let mut expr = Expr::new(env);
+	for tok in tokenize(input) {
+		expr.parse(tok)?;
+	}
+	expr.result()
+}
+
+fn check(input: &str) -> Result<(), TestCaseError> {
+	let env = BasicEnv { ans: 2.5, ..Default::default() };
+	let result = eval(&env, input);
+	if let Err(err) = result {
+		prop_assert_ne!(err.kind, ErrorKind::InternalError, "{:?}", input);
+		prop_assert!(err.position <= input.len(), "{:?} {:?}", input, err);
+	}
+	// The tokens don't know where the input ends
+	match (result, eval_tokens(&env, input)) {
+		(Ok(a), Ok(b)) => prop_assert!(a.to_bits() == b.to_bits() || a.is_nan() && b.is_nan(), "{:?}", input),
+		(Err(a), Err(b)) => prop_assert!(a.kind == b.kind && (a.position == b.position || a.position == input.len()), "{:?} {:?} {:?}", input, a, b),
+		(a, b) => prop_assert!(false, "{:?} {:?} {:?}", input, a, b),
+	}
+	Ok(())
+}
+
+proptest! {
+	#![proptest_config(ProptestConfig { cases: 2000, failure_persistence: None, ..Default::default() })]
+
+	#[test]
+	fn random_tokens(input in tokens()) {
+		check(&input)?;
+	}
+	#[test]
+	fn random_expressions(input in expressions()) {
+		check(&input)?;
+	}
+	#[test]
+	fn random_mutations(input in mutations()) {
+		check(&input)?;
+	}
+	#[test]
+	fn random_ascii(input in "[ -~]{0,40}") {
+		check(&input)?;
+	}
+}